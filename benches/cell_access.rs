@@ -0,0 +1,33 @@
+use brainfuckers::*;
+
+use std::time::Instant;
+
+/*
+ * Measures the average cost of a single step for programs dominated by
+ * one kind of operation, to see how much cell access costs on its own.
+ * Run with `cargo bench --bench cell_access`.
+ */
+
+const ITERATIONS: u32 = 5;
+
+const PROGRAMS: &[(&str, &str)] = &[
+    ("movement", "-[>-[>>>>+<<<<-]<-]"),
+    ("arithmetic", "-[>-[>+<-]<-]"),
+    ("comparisons", "-[>-[>>-[+[-]]<<-]<-]"),
+    ("negative-tape", "<<<<-[>-[>+<-]<-]"),
+];
+
+fn main() {
+    for (name, code) in PROGRAMS {
+        let mut best_step_time = f64::MAX;
+        for _ in 0..ITERATIONS {
+            let mut state = new_bf_state(std::hint::black_box(code));
+            capture_output(&mut state);
+            let started = Instant::now();
+            let report = run_bf(&mut state, None, None).ok().unwrap();
+            let step_time = started.elapsed().as_secs_f64() * 1e9 / report.steps as f64;
+            best_step_time = best_step_time.min(step_time);
+        }
+        println!("{name:<16} {best_step_time:>8.3} ns/step");
+    }
+}
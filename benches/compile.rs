@@ -0,0 +1,65 @@
+use brainfuckers::*;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/*
+ * Compares compiling programs one by one with `new_program`, which creates an arena for each compilation,
+ * and in a row with a `CompileArena`, counting the allocations of each compilation (reallocations included).
+ * Run with `cargo bench --bench compile --features arena`.
+ */
+
+const ITERATIONS: u32 = 200;
+
+const PROGRAMS: &[(&str, &str)] = &[
+    ("hanoi", include_str!("programs/hanoi.b")),
+    ("factor", include_str!("programs/factor.b")),
+    ("mandelbrot", include_str!("programs/mandelbrot.b")),
+    ("squares", "++++[>+++++<-]>[<+++++>-]+<+[>[>+>+<<-]++>>[<<+>>-]>>>[-]++>[-]+>>>+[[-]++++++>>>]<<<[[<++++++++<++>>-]+<.<[>----<-]<]<<[>>>>>[>>>[-]+++++++++<[>-<-]+++++++++>[-[<->-]+[<<<]]<[>+<-]>]<<-]<<-]"),
+];
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        return unsafe { System.alloc(layout) };
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(pointer, layout) };
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        return unsafe { System.realloc(pointer, layout, size) };
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn measure(mut compile: impl FnMut() -> usize) -> (f64, f64) {
+    // Microseconds and allocations per compilation
+    compile();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    for _ in 0..ITERATIONS {
+        std::hint::black_box(compile());
+    }
+    let micros = started.elapsed().as_secs_f64() * 1e6 / f64::from(ITERATIONS);
+    return (micros, (ALLOCATIONS.load(Ordering::Relaxed) - allocations) as f64 / f64::from(ITERATIONS));
+}
+
+fn main() {
+    let mut arena = CompileArena::new();
+    println!("{:<12} {:>21} {:>21}", "", "new_program", "CompileArena");
+    for (name, code) in PROGRAMS {
+        let (plain_micros, plain_allocations) = measure(|| new_program(code).bytecode().ops.len());
+        let (arena_micros, arena_allocations) = measure(|| arena.compile(code).bytecode().ops.len());
+        println!("{name:<12} {plain_micros:>8.1} us {plain_allocations:>6.0} allocs {arena_micros:>8.1} us {arena_allocations:>6.0} allocs");
+    }
+}
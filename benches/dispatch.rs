@@ -0,0 +1,119 @@
+use brainfuckers::*;
+
+use std::time::{Duration, Instant};
+
+/*
+ * Compares the bytecode interpreter with the byte-matching interpreter
+ * `step_bf` used to be: it read the current cell on every step
+ * and searched for the matching bracket on every jump.
+ * Run with `cargo bench --bench dispatch`.
+ */
+
+const ITERATIONS: u32 = 5;
+
+const PROGRAMS: &[(&str, &str)] = &[
+    ("nested-loops", "-[>-[>-[-]<-]<-]"),
+    ("multiply", "-[>-[>-[>+++++<-]<-]<-]"),
+    ("commented", "-[>-[ inner loop >-[-] clear <- ]<- outer loop ]"),
+    ("squares", "++++[>+++++<-]>[<+++++>-]+<+[>[>+>+<<-]++>>[<<+>>-]>>>[-]++>[-]+>>>+[[-]++++++>>>]<<<[[<++++++++<++>>-]+<.<[>----<-]<]<<[>>>>>[>>>[-]+++++++++<[>-<-]+++++++++>[-[<->-]+[<<<]]<[>+<-]>]<<-]<<-]"),
+];
+
+fn run_bytecode(code: &str) -> Vec<u8> {
+    let mut state = new_bf_state(code);
+    capture_output(&mut state);
+    return run_bf(&mut state, None, None).ok().unwrap().output;
+}
+
+struct ByteMatchingState {
+    ptape: Vec<u8>,
+    ntape: Vec<u8>,
+    cursor: isize,
+    ip: usize,
+    output: Vec<u8>,
+}
+
+fn get(state: &ByteMatchingState, index: isize) -> u8 {
+    if index >= 0 {
+        return *state.ptape.get(usize::try_from(index).unwrap()).unwrap_or(&0);
+    }
+    return *state.ntape.get(usize::try_from(-1 - index).unwrap()).unwrap_or(&0);
+}
+
+fn set(state: &mut ByteMatchingState, index: isize, value: u8) {
+    if index >= 0 {
+        let index = usize::try_from(index).unwrap();
+        if index >= state.ptape.len() { state.ptape.resize(index + 1, 0); }
+        state.ptape[index] = value;
+    }
+    else {
+        let index = usize::try_from(-1 - index).unwrap();
+        if index >= state.ntape.len() { state.ntape.resize(index + 1, 0); }
+        state.ntape[index] = value;
+    }
+}
+
+fn step_byte_matching(code: &[u8], state: &mut ByteMatchingState) -> bool {
+    if state.ip >= code.len() {
+        return false;
+    }
+    let current_value = get(state, state.cursor);
+    match code[state.ip] {
+        b'+' => set(state, state.cursor, current_value.wrapping_add(1)),
+        b'-' => set(state, state.cursor, current_value.wrapping_sub(1)),
+        b'>' => state.cursor += 1,
+        b'<' => state.cursor -= 1,
+        b'.' => state.output.push(current_value),
+        b'[' if current_value == 0 => {
+            let mut depth = 0;
+            for (i, &opcode) in code.iter().enumerate().skip(state.ip) {
+                match opcode {
+                    b'[' => depth += 1,
+                    b']' => { depth -= 1; if depth == 0 { state.ip = i; break; } },
+                    _ => {}
+                }
+            }
+        },
+        b']' if current_value != 0 => {
+            let mut depth = 0;
+            for (i, &opcode) in code.iter().enumerate().take(state.ip + 1).rev() {
+                match opcode {
+                    b']' => depth += 1,
+                    b'[' => { depth -= 1; if depth == 0 { state.ip = i; break; } },
+                    _ => {}
+                }
+            }
+        },
+        _ => {}
+    }
+    state.ip += 1;
+    return true;
+}
+
+fn run_byte_matching(code: &str) -> Vec<u8> {
+    let mut state = ByteMatchingState { ptape: vec![0; 3000], ntape: Vec::new(), cursor: 0, ip: 0, output: Vec::new() };
+    while step_byte_matching(code.as_bytes(), &mut state) {}
+    return state.output;
+}
+
+fn measure(run: fn(&str) -> Vec<u8>, code: &str) -> (Duration, Vec<u8>) {
+    let mut best = Duration::MAX;
+    let mut output = Vec::new();
+    for _ in 0..ITERATIONS {
+        let started = Instant::now();
+        output = std::hint::black_box(run(std::hint::black_box(code)));
+        best = best.min(started.elapsed());
+    }
+    return (best, output);
+}
+
+fn main() {
+    for (name, code) in PROGRAMS {
+        let (bytecode_time, bytecode_output) = measure(run_bytecode, code);
+        let (byte_matching_time, byte_matching_output) = measure(run_byte_matching, code);
+        assert_eq!(bytecode_output, byte_matching_output, "{name}: outputs differ");
+        println!(
+            "{name:<16} bytecode: {bytecode_time:>12?}  byte matching: {byte_matching_time:>12?}  speedup: {:.2}x",
+            byte_matching_time.as_secs_f64() / bytecode_time.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use brainfuckers::*;
+
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/*
+ * Measures the I/O paths with output-heavy programs, printing into a counting sink.
+ * Every program gets the same in-memory input; only `cat` reads it.
+ * Run with `cargo bench --bench output`.
+ */
+
+const ITERATIONS: u32 = 5;
+
+const INPUT_SIZE: usize = 1 << 24;
+
+const PROGRAMS: &[(&str, &str, bool)] = &[
+    ("cat", ",[.,]", true),
+    ("ascii", "++++++++[>++++++++<-]>+>-[>-[<<.>>-]<-]", false),
+    ("utf8", "+++++++++++++[>+++++++++++++++>+++++++++++++<<-]>>>-[>-[<<<.>.>>-]<-]", false),
+    ("invalid-utf8", "->-[>-[<<.>>-]<-]", false),
+    ("raw", "->-[>-[<<.>>-]<-]", true),
+];
+
+struct CountingWriter(Arc<AtomicUsize>);
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.fetch_add(buf.len(), Ordering::Relaxed);
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return Ok(());
+    }
+}
+
+fn main() {
+    for (name, code, raw_output) in PROGRAMS {
+        let mut best_rate = 0.0_f64;
+        for _ in 0..ITERATIONS {
+            let written = Arc::new(AtomicUsize::new(0));
+            let mut state = new_bf_state(std::hint::black_box(code));
+            set_output_writer(&mut state, Box::new(CountingWriter(written.clone())));
+            set_raw_output(&mut state, *raw_output);
+            set_input_reader(&mut state, Box::new(std::io::Cursor::new(vec![b'x'; INPUT_SIZE])));
+            let started = Instant::now();
+            run_bf(&mut state, None, None).ok().unwrap();
+            let rate = written.load(Ordering::Relaxed) as f64 / started.elapsed().as_secs_f64();
+            best_rate = best_rate.max(rate);
+        }
+        println!("{name:<16} {:>10.2} MB/s", best_rate / 1e6);
+    }
+}
@@ -0,0 +1,28 @@
+use brainfuckers::bench::*;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+
+/*
+ * Runs whole programs, reporting throughput in executed instructions.
+ * Run with `cargo bench --features bench --bench programs`.
+ */
+
+const PROGRAMS: &[(&str, &str)] = &[
+    ("mandelbrot", include_str!("programs/mandelbrot.b")),
+    ("hanoi", include_str!("programs/hanoi.b")),
+    ("factor", include_str!("programs/factor.b")),
+    ("busy_loop", include_str!("programs/busy_loop.b")),
+];
+
+fn programs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("programs");
+    group.sample_size(10);
+    for (name, code) in PROGRAMS {
+        group.throughput(instruction_throughput(code, b"").unwrap());
+        group.bench_function(*name, |b| b.iter(|| run_in_memory(std::hint::black_box(code), b"").unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, programs);
+criterion_main!(benches);
@@ -0,0 +1,4 @@
+Busy loop that does nothing but count down nested loops
+About 8 million iterations of the innermost loop
+
+-[>-[>--[--]<-]<-]
@@ -0,0 +1,49 @@
+Prime factors of every number from 2 to 255 using trial division
+
+[-]++>[-]--[>>>>>>>>>>>[-]++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++>>>[-]<<<<<<<<<<<<<<<[->>>>>>>>>>>
+>>>>+>>>>>>+<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<+>
+>>>>>>>>>>>>>>>>>>>>]<<<<<[-]<<<<[->>>>+>>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>
+>>>>>>]<<<<<<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<<<<<<[-]>[-]>>>>>>>>[-<<<<<<<<
++>>>>>>>>]>[-<<<<<<<<<<+>>>>>>>>>>]<<<<<[-]++++++++++>>[-]<<<<<<[->>>>>>+>>>>>>+
+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<[-]<<<[->>>+>>>>>+<<<<
+<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<<<<[
+-]>[-]>>>>>>[-<<<<<<+>>>>>>]>[-<<<<<<<<+>>>>>>>>]<<<<<<<<<[-]>>>[-]>[-]>[-]>>>>>
+>>[-]<<<<<<<<<<<<<[->>>>>>>>>>>>>+>+<<<<<<<<<<<<<<]>>>>>>>>>>>>>>[-<<<<<<<<<<<<<
+<+>>>>>>>>>>>>>>]<[<<<<<<<<<<<<<++++++++++++++++++++++++++++++++++++++++++++++++
+.>>>>>>[-]+>>>>>>>[-]][-]<<<<<<<<<<<[->>>>>>>>>>>+>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<
+<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<<<[->>>>>>>+>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>
+>]<[<<<<<<<<<<<++++++++++++++++++++++++++++++++++++++++++++++++.>>>>>>>>>>>[-]]<
+<<<<<<<<<++++++++++++++++++++++++++++++++++++++++++++++++.<<<[-]>>[-]>[-]>>>[-][
+-]++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-]<<<<<<<<<<<<[-]<
+<[->>+>>>>>>>>>>>>+<<<<<<<<<<<<<<]>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]
+<<<<<<<<<<<[-]++>>>>[-]++>>>>>>>[-]<<<<<<<<<<<<[->>>>>>>>>>>>+<<<+<<<<<<<<<]>>>>
+>>>>>[-<<<<<<<<<+>>>>>>>>>]>>[-]<<<<<<[->>>>>>+<<+<<<<]>>>>[-<<<<+>>>>]<<<<<[-]+
+>>>>>>>[<[-]+<[-]>>>[-<<<+<+>>>>]<<<<[->>>>+<<<<]>[>[-]<[-]]>[<<<<<<[-]>>>>>>>[-
+]+>[-]+<<[-]]>>-<-]>[-]<<<<<<<<[>>>>>>>>>[-]<<<<<<<<<<<<<[->>>>>>>>>>>>>+<<<+<<<
+<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]>>>>[-]<<<<<<<<<<<<<[->>>>>>>>>>>>>+<<
+<<+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]>>>[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-
+]<<<<<<<<<<<<[-]>[-]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]>[-<<<<<<<<<<<<<<+>>
+>>>>>>>>>>>>]<<<<<<[-]+>[-]<<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>
+>>>>>>>>]<[<[-]>[-]]<[>[-]++++++++++++++++++++++++++++++++.[-]<<<<[-]+++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++>>>>>>[-]<<<<<<<<<<<<[->>>>>>>>>>>>+>>>>>>>+<<<<<<<<<<<<<<<<<<<]>>>>>>>
+>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>]<<<<<<[-]<<<<<<<[->>>>>>>+
+>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<[->-[>+>>
+]>[+[-<+>]>+>>]<<<<<]>[-]<<<[-]>[-]>>>[-<<<+>>>]>[-<<<<<+>>>>>]<<<<<<<<<<[-]++++
+++++++>>>>>>>[-]<[->+>>>>>>>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<[-]<<<<<
+<<<[->>>>>>>>+>>>>>>+<<<<<<<<<<<<<<]>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>
+>]<<<<<<<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<<<[-]<[-]>>>>>>>[-<<<<<<<+>>>>>>>]
+>[-<<<<<<<+>>>>>>>]<<<<[-]<<<<<[-]<[-]>>>>>>>>>>>>>[-]>[-]<<<<<<<<<[->>>>>>>>>+>
++<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<[<<<<<<<<<+++++++++++++++++++++++
++++++++++++++++++++++++++.>>>>>>>>[-]+>[-]][-]<<<<<<<<<<<[->>>>>>>>>>>+>+<<<<<<<
+<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<[->+>+<<]>>[-<<+>>]<[<<<<<<<<<<<
+++++++++++++++++++++++++++++++++++++++++++++++++.>>>>>>>>>>>[-]]<<<<<<<<<<<<++++
+++++++++++++++++++++++++++++++++++++++++++++.>>>[-]<<[-]<[-]>>>>>>>>>>>[-]<<<<<<
+<<<<<<<<<<<<<[-]>>[-<<+>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>[-
+<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<->>>>>>>>>[-]]<<<<<<<<<+>>
+>>>>>>>[-]<<<<<<<<<<[->>>>>>>>>>+<<<+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]>>>>>>>>>>
+>>[-]<<<<<<<<<<<<<<[->>>>>>>>>>>>>>+<<<<<<<<<<<<+<<]>>[-<<+>>]<<<[-]+>>>>>>>>>>>
+>>>>[<<<<<<<<<<<<<[-]+>[-]>>>[-<<<+>+>>]<<[->>+<<]<[<[-]>[-]]<[<<[-]>>>>>>>>>>>>
+>>>[-]+<<<<<<<<<[-]+<<<<[-]]>>>>->>>>>>>>>-]<<<<<<<<<[-]<<<<<<]<<[-]>[-]>>>[-]++
+++++++++.[-]<<<<<<<<+>-]
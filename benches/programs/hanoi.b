@@ -0,0 +1,116 @@
+Towers of Hanoi solutions for 1 to 8 disks
+Each move is computed directly from the move number
+
+>>>>>>>>>>>>>[-]++>[-]+++<<<<<<<<<<<<<<[-]+>[-]++++++++[>>>>>>>>>>>>>>>[-]++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-][-]+++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++.[-][-]+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-][-]+++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++.[-][-]+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++.[-][-]+++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++.[-][-]++++++++++++++++++++++++++++++++.[-]
+>>>>[-]+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++>>>[-]<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>
+>+>>>>>>+<<<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<
+<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>>]<<<<<[-]<<<<[->>>>+>>>>>+<<<<<
+<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<<<<<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<<
+<<<<[-]>[-]>>>>>>>>[-<<<<<<<<+>>>>>>>>]>[-<<<<<<<<<<+>>>>>>>>>>]<<<<<[-]++++++++
+++>>[-]<<<<<<[->>>>>>+>>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>
+>]<<<<<[-]<<<[->>>+>>>>>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<[->-[>+>>]>[
++[-<+>]>+>>]<<<<<]>[-]<<<<<<[-]>[-]>>>>>>[-<<<<<<+>>>>>>]>[-<<<<<<<<+>>>>>>>>]<<
+<<<<<<<[-]>>>[-]>[-]>[-]>>>>>>>[-]<<<<<<<<<<<<<[->>>>>>>>>>>>>+>+<<<<<<<<<<<<<<]
+>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]<[<<<<<<<<<<<<<+++++++++++++++++++
++++++++++++++++++++++++++++++.>>>>>>[-]+>>>>>>>[-]][-]<<<<<<<<<<<[->>>>>>>>>>>+>
++<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<<<[->>>>>>>+>+<<<<<<
+<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[<<<<<<<<<<<++++++++++++++++++++++++++++++++++++
+++++++++++++.>>>>>>>>>>>[-]]<<<<<<<<<<++++++++++++++++++++++++++++++++++++++++++
+++++++.<<<[-]>>[-]>[-]>>>[-][-]++++++++++.[-]<<<<<<<<<<<<<<<<<<<[-]+>[-]<<<<[->>
+>>+>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<
+<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<[>>>[-]<<<<[->>>>+>>>>>>>>>
+>>>>>>+<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>
+>>>>>>>]<<<<<<<<<<<<<<<[-<<<<+>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>
+>[-<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<[-]<<<-]<-<[-]+>[>[-]<<[->>+>>
+>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<
++>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>[-]<<<<<<<<<<<<<<<<<
+<<[->>>>>>>>>>>>>>>>>>>+<+<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<
+<<<+>>>>>>>>>>>>>>>>>>]>>[-]<<<<<<<<<<<[->>>>>>>>>>>+<<+<<<<<<<<<]>>>>>>>>>[-<<<
+<<<<<<+>>>>>>>>>]>[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<<<<<<<<<<<<<<<[-]>[-]>>>>
+>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]>[-<<<<<<<<<<<<<<<<<<<+>>>>>>
+>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<[-]+>>>>>>>>>>>>>[-]<<<<<<<<<<<<<<[->>>>>>>>>>>>>
+>+<+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]>[<<<<<<<<<<<<<[-]>
+>>>>>>>>>>>>[-]]<<<<<<<<<<<<<[<<<<<[-]>>>[-<<<+>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<
+<]>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<+>>>>>>>>>>>
+>>>>>>>[-]<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>+<+<<<<<<<<<<<<<<<<<<]>>>>>>>>
+>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>]>>[-]<<<<<<<<<<<[->>>>>>>>>>>+
+<<+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]>[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<
+<<<<<<<<<<<<<<<<[-]>[-]>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]>[
+-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<[-]+>>>>>>>>>>>>>[-]<<
+<<<<<<<<<<<<[->>>>>>>>>>>>>>+<+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>
+>>>>>>>]>[<<<<<<<<<<<<<[-]>>>>>>>>>>>>>[-]]<<<<<<<<<<<<<]<<<[-]>[-<+>>>>>>>>>>>>
+>>>>+<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>]<<<<<<<<<<
+<<<<<[-]>[-]<<<<[-]>>>>>>>>>>>[-]<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>+>>>>>>>+<<<<<<
+<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>
+>>>>>>]<<<<<<<<<<<<<<<<<[->>>>>>>>>>->>>>>>>+<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>
+[-<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]>[-]<<<<<<<<[->>>>>>>>+<+<<<<<<<]>>>>>>>[-
+<<<<<<<+>>>>>>>]>>[-]<<<<<<<<<<<[->>>>>>>>>>>+<<+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+
+>>>>>>>>>]>[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<<<<<<<<<<<<<<<[-]>[-]>>>>>>>>>>>
+>>>>>>[-<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]>[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>
+>>>>>>]<<<<<<<<<<<<<<<<[-]<<[->>+>>>>>>>>>>>>+<<<<<<<<<<<<<<]>>>>>>>>>>>>>>[-<<<
+<<<<<<<<<<<+>>>>>>>>>>>>>>]<<<<<<<<<<<<+<<<[-]>[-]>>>>>>>[-]<<<<<<<<<<<[-]>>>>>>
+>>>>>>>>>>>>[-]<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>+<+<<<<<<<<<<<<<<<]>>>>>>>>>>>>
+>>>[-<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>]>[<<<<<<<<<<<<[-<<<<<<+>>>>>>>>>>>>>>>>>+<<
+<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]>-]>[-]<<<<<<<<<<<<<<<<<<<[->>>>>
+>>>>>>>>>>>>>>+<+<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<+>>>>>
+>>>>>>>>>>>>>]>>[-]<<<<<<<<<<[->>>>>>>>>>+<<+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>
+>]>[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<<<<<<<<<<<<<<<[-]>>>>[-]>>>>>>>>>>>>>>[-
+<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]>[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>]<<<<<<<
+<<<<<<<<<<<<<<<[-]>>>>>>>[-<<<<<<<+>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<
+<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<<<[-<<<<<<+>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<]>>>>
+>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]>[-]<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>
++<+<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>]
+>>[-]<<<<<<<<<<[->>>>>>>>>>+<<+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]>[->-[>+>>]>
+[+[-<+>]>+>>]<<<<<]>[-]<<<<<<<<<<<<<<<<<[-]>>>>>[-]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+
+>>>>>>>>>>>>>]>[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<[-]<
+<<[-]>>[-]>>>>[-]>>>>>>>>>>>>[-]++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++.[-][-]++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-][-]++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++.[-][-]+++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-][-]+++++++++++++
++++++++++++++++++++.[-][-]++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++.[-][-]+++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[
+-][-]+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++.[-][-]+++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-][-
+]++++++++++++++++++++++++++++++++.[-]<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>[-]+++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++>>>>>[-]<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>+>>>>>>+<<<<<<<<<<<<<<<
+<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>
+>>>>>]<<<<<[-]<<<<<<[->>>>>>+>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>
+>>>]<<<<<<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<[-]<[-]>>>>[-<<<<+>>>>]>[-<<<<+>>>
+>]<<<<<<<<<[-]++++++++++>>>>>>[-]<<[->>+>>>>>>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>
+>>>]<<<<<[-]<<<<<<<[->>>>>>>+>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>
+>>>>>>>]<<<<<<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<<[-]<[-]>>>>>>[-<<<<<<+>>>>>>
+]>[-<<<<<<+>>>>>>]<<<<<[-]<<<[-]<[-]<[-]>>>>>>>>>>>>>[-]<<<<<<<[->>>>>>>+>+<<<<<
+<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<[<<<<<<<+++++++++++++++++++++++++++++++++++++++
++++++++++.<<<<<<[-]+>>>>>>>>>>>>>[-]][-]<<<<<<<<<[->>>>>>>>>+>+<<<<<<<<<<]>>>>>>
+>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<<<<<<<<<<<<<[->>>>>>>>>>>>>+>+<<<<<<<<<<<<<<]>>>>>
+>>>>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>>>>>>>]<[<<<<<<<<<++++++++++++++++++++++++++++
+++++++++++++++++++++.>>>>>>>>>[-]]<<<<<<<<<<++++++++++++++++++++++++++++++++++++
+++++++++++++.>>>[-]<<[-]<[-]<<<[-]<<<<<<<<<<<[-]>>>>>>>>>>>[-]++++++++++++++++++
+++++++++++++++.[-][-]+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++++++++++++.[-][-]++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++.[-][-]+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++.[-][-]+++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[
+-][-]++++++++++++++++++++++++++++++++.[-]<<<<<++++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++.[-]>>>>>[-]++++++++++++++++++++++++++++++++.[-][
+-]++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++.[-][-]+++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.[-]
+[-]++++++++++++++++++++++++++++++++.[-]<<<<+++++++++++++++++++++++++++++++++++++
+++++++++++++++++++++++++++++.[-]>>>>[-]++++++++++.[-]<<<<<<<<<<<<<<+>-]<[-]<<+>-
+]
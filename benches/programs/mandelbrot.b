@@ -0,0 +1,249 @@
+Mandelbrot set rendered as ASCII art
+40 columns by 17 rows with up to 12 iterations per point
+Numbers are stored as a sign and a magnitude with 4 fractional bits
+
+>>>>>>>>>>>>>>>>[-]>[-]+++++++++++++++++[>>>>>>>>>[-]<<<<<<<<<<[->>>>>>>>>>+>>+<
+<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>][-]<<[->>+>+<<<]>>>[-<<<+>>>
+]<<<[-]>>[<<++>>-]<[-]++++++++++++++++>[-]<<[->>+>>>+<<<<<]>>>>>[-<<<<<+>>>>>]<<
+[-]<<[->>+>>+<<<<]>>>>[-<<<<+>>>>]<<<<<<<<[-]+>>>>>>[>[-]+>[-]<<<[->>>+>+<<<<]>>
+>>[-<<<<+>>>>]<[<[-]>[-]]<[<<<<<<<[-]>>>>>>[-]+<[-]+>>[-]]<<->-]<[-]>>[-]<<<<<<<
+[->>>>>>>+<+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]>[<<<<<<<<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>
+>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<]>>>[-<<
+<+>>>]<<[-<<<<<<<<<<<<<<<<<<<<<<<<->>>>>>>>>>>>>>>>>>>>>>>>>>+<<]>>[-<<+>>]<<<<<
+<<<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>>>>>>>>>[-]]<<<<<[-]+>>>>>[-]<<<<<<
+<[->>>>>>>+<+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]>[<<<<<[-]>>>>>[-]]<<<<<[<<<<<<<<<<<<<
+<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>
+>>>>>>>>>>+<<<]>>>[-<<<+>>>]<<<<[-<<<<<<<<<<<<<<<<<<<<<<<->>>>>>>>>>>>>>>>>>>>>>
+>>>>>+<<<<]>>>>[-<<<<+>>>>]<<<<<<<<<<<<<<<<<<<<<<<<<<<<[-]+>>>>>>>>>>>>>>>>>>>>>
+>>[-]]<<[-]>>>[-]>[-]<<<<<<<<<[-]>[-]++++++++++++++++++++++++++++++++++++++++[>>
+>>>>>>[-]++++++++++++++++++++++++++++++++<[-]<<<<<<<<[->>>>>>>>+>>+<<<<<<<<<<]>>
+>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]>>[-]<<<[->>>+<<+<]>[-<+>]<<<<<[-]+>>>>>>>[<[-]+
+<[-]<<[->>+>>>+<<<<<]>>>>>[-<<<<<+>>>>>]<<<[>[-]<[-]]>[<<<<<<[-]>>>>>>>[-]+<<<<[
+-]+>>>[-]]<<<->>>>-]<<<<[-]>>>[-]<<<<<<[->>>>>>+>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>
+>>]<[<<<<<<<<<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<+>>>>>>>
+>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<[
+-<<<<<<<<<<<<<<<<<<<<<<<<<<->>>>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<]>>>[-<<<+>>>]<<<<<
+<<<<<<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>>>>>>>>>>[-]]<<<<[-]+>>>>[-]<<<<
+<<[->>>>>>+>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<[<<<<[-]>>>>[-]]<<<<[<<<<<<<<<<<<
+<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>
+>>>>>>>>>>>>>>>>>>+<<]>>[-<<+>>]<<<<<<<<<<<[-<<<<<<<<<<<<<<<<<->>>>>>>>>>>>>>>>>
+>>>>>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<
+<<<<<<<<<<[-]+>>>>>>>>>>>>>>>>>>>>>>>>>[-]]<<[-]>>>>[-]<<<<<<<<<<<<<<<<<<<<<<<[-
+]>[-]>[-]>[-]>>>>>>>>>>>>>[-]++++++++++++>>[-]<[-]+[>>>[-]++++++++++++++++++++++
++++++++++++>>>[-]<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>+<+<<<<<<<<<<<<<<
+<<<<<<<]>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>]>>>[-
+]<<<<<[->>>>>+<<<+<<]>>[-<<+>>]<<<[-]+>>>>>>[>[-]+<<<<[-]>[-<+>>+<]>[-<+>]<<[>>>
+>[-]<<<<[-]]>>>>[<<<<<<<[-]>>>>>>[-]+<<[-]+>>>[-]]<<<->>-]<<[-]<<<<[-<+>>>>>>>>+
+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<[-]>>>>>>>[-]<<<<<<<<<<<<<<<<<<<<<<<[->>
+>>>>>>>>>>>>>>>>>>>>>+<<<<+<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<
+<<<<<<<+>>>>>>>>>>>>>>>>>>>]>>>[-]<<<<<[->>>>>+<<<+<<]>>[-<<+>>]<<<[-]+>>>>>>[<<
+[-]+<[-]>>>>[-<<<<+>>+>>]<<[->>+<<]<<[>[-]<[-]]>[<<<<[-]>>>>>>[-]+>[-]+<<<[-]]>>
+>-<-]>[-]<<<<<<<[-<+>>>>>+<<<<]>>>>[-<<<<+>>>>]<<<<[-]>>[-]+>>[-]<<<<<[->>>>>+>>
++<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<[<<[-]>>[-]]<<[>>>>>>[-]++++++++++++++++>>[-
+]<<<<<<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>>>>>>>+<+<<<<<<<<<<<<<<<<<<
+<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>
+>>>>>>>>>>>>>>]>>[-]<<<[->>>+<<+<]>[-<+>]>[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<<
+<<<[-]>>[-]>>>>>>[-<<<<<<+>>>>>>]>[-<<<<<<<<<+>>>>>>>>>]<<<[-]<<<<<<<<<<<<<<<<<<
+<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>>>>>>>+<+<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>
+>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>]>>[-
+]<<<[->>>+<<+<]>[-<+>]>[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<<[-]<<<<[-]>>>>>>>>>
+[-<<<<<<<<<+>>>>>>>>>]>[-<<<<<<+>>>>>>]<<<<<<<<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>
+>>>>>>>>[-]>>>>[-]<<<<<[->>>>>+>>>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>
+>>>>>>>>>>>]<<<<<<<[<<[-<<+>>>>>>>>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]
+<<<<<<<-]<<<<[<<<<<<<<<<<<<<<<<<<++++++++++++++++>>>>>>>>>>>>>>>>>>>-][-]>>>>[-]
+<<<<<[->>>>>+>>>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<
+<[<<<<<<[->>+>>>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>
+>]<<<<<<<-]<<<<[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>+<<<<]>>>>[-<<<<+>>>
+>]<<<<[-][-]>>>>[-]<<<[->>>+>>>>>>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>
+]<<<<<<<[<<[-<<+>>>>>>>>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<<<<<<-]<<
+<<[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>+<<<<]>>>>[-<<<<+>>>>]<<<<[-][-]>
+>>>[-]<<<[->>>+>>>>>>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<<<<<<[<<<<
+<<[->>+>>>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<
+<<<-]>[-]<<<<<[->>>>>+>>>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>
+>>]<<<<<<[-]<<<[->>>+>>>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<<<<<<[->-
+[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<[-]>>>>>>>[-]<<<<[->>>>+<<<<]>[-<<<<+>>>>]<<<<[-
+<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<]>>>>>>>>[-<<<<<
+<<<+>>>>>>>>]<<<<<<<<[-]>>>>>>>[-]<<<<<<<<<<<<[-]>>[-]>[-]<<<<[-]>>[-]>>>[-]<<<<
+[-]++++++++++++++++>>>>>>[-]<<<<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>>>
+>>+>>>>>>+<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<
+<<<<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>]<<<<<[-]<<<<<<<[-
+>>>>>>>+>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<[->-[>+
+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<[-]<<<[-]>>>>>>>[-<<<<<<<+>>>>>>>]>[-<<<<<+>>>>>]<
+<<[-]<<<<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>>>>>+>>>>>>+<<<<<<<<<<<<<
+<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<<
+<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>]<<<<<[-]<<<<<<<[->>>>>>>+>>>>>+<<<<<<<<<
+<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]
+>[-]<<<<<<<<[-]>>>>[-]>>>>>[-<<<<<+>>>>>]>[-<<<<<<<<<<+>>>>>>>>>>]<<<<<<<<<<<<<<
+<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>[-]>>>>>>>>>>[-]<<<<<<<<[->>>>>>>>+<<<<<<<+<]>[-
+<+>]>>>>>>>[<<<<<<<<<<<<<[->>>+>>>+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]>>>>>>>-]<<<<<<<
+<<<[<<<<<<<<<<<<<<<<<<++++++++++++++++>>>>>>>>>>>>>>>>>>-][-]>>>>>>>>>>[-]<<<<<<
+<<[->>>>>>>>+<<<<<<<+<]>[-<+>]>>>>>>>[<<<<<<<<<[-<+>>>+<<]>>[-<<+>>]>>>>>>>-]<<<
+<<<<<<<[-<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<]>>>>>>>>>>[-
+<<<<<<<<<<+>>>>>>>>>>]<<<<<<<<<<[-][-]>>>>>>>>>>[-]<<<<<<<<<<<[->>>>>>>>>>>+<<<<
+<<<+<<<<]>>>>[-<<<<+>>>>]>>>>>>>[<<<<<<<<<<<<<[->>>+>>>+<<<<<<]>>>>>>[-<<<<<<+>>
+>>>>]>>>>>>>-]<<<<<<<<<<[-<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<
+<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<<<<<<<<<[-][-]>>>>>>>>>>[-]<<<<<<<<<<<[
+->>>>>>>>>>>+<<<<<<<+<<<<]>>>>[-<<<<+>>>>]>>>>>>>[<<<<<<<<<[-<+>>>+<<]>>[-<<+>>]
+>>>>>>>-]<<<<<<[-]<<<<[->>>>+>>>>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>
+>>>>>]<<<<<<[-]<<<<<<<[->>>>>>>+>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<
+<+>>>>>>>>>>>>>]<<<<<<<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>>>>>[-]<<<<<<<[-]>>>[-
+<<<+>>>]>[->>>+<<<]>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>
+>>+<]>[-<+>]<[-]<<<<<<<[-]<[-]<<<[-]<<[-]>>>>[-]<[-]<<[-]<<<<<<<<<<<<<<[-]<<<<[-
+>>>>+>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<+>>
+>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[->>+>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<]>>>>>>>>>>
+>>>>>>[-<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>]<<<[-]++++++++++++++++++++++++++++++++
++++++++++++++++++++++++++++++++++>>>[-]<<<<<<<<<<<<<<[->>>>>>>>>>>>>>+<+<<<<<<<<
+<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]>>>[-]<<<<<[->>>>>+<<<+<<]>>[-<
+<+>>]<<<<[-]+>>>>>>>[>[-]+<<<<[-]>[-<+>>+<]>[-<+>]<<[>>>>[-]<<<<[-]]>>>>[<<<<<<<
+<[-]>>>>>>>[-]+<<[-]+>>>[-]]<<<->>-]<<[-]<<<<<<<<<<<<<<[-]>>>>>>>>>>>>[-]]<[-]>[
+-]+>>>>>[-]<<<<<<<<[->>>>>>>>+<+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]>[<<<<<[-]>>>>>
+[-]]<<<<<[>>>>>>[-]++++++++++++++++>>[-]<<<<<<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>
+>>>>>>>>>>>>>>>>>>+<+<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<
+<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>]>>[-]<<<[->>>+<<+<]>[-<+>]
+>[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<<[-]<[-]>>>>>>[-<<<<<<+>>>>>>]>[-<<<<<<+>>
+>>>>]<<<[-]<<<<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>>>>>+<+<<<<<<<<<<<<
+<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>
+>>>>>>>>>>>>]>>[-]<<<[->>>+<<+<]>[-<+>]>[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]<<<<<<
+<[-]<[-]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]>[-<<<<<<<<<+>>>>>>>>>]<<<<<<<<<<<<<<<<<<
+<<<<<[-]>>>>>>>>>>>>>>>[-]>>>>[-]<<[->>+>>>>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>
+>>>>>>>>]<<<<<<<[<<<<<[->+>>>>>>>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>
+>>>>>>>>>>]<<<<<<<-]<<<<[<<<<<<<<<<<<<<<++++++++++++++++>>>>>>>>>>>>>>>-][-]>>>>
+[-]<<[->>+>>>>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<<<<<<[<<<<<<[->>+>>
+>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<-]<<<<
+[-<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>+<<<<]>>>>[-<<<<+>>>>]<<<<[-][-]>>>>[-]<<<[
+->>>+>>>>>>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<<<<<<[<<<<<[->+>>>>>
+>>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<<-]<<<<[-<<<<<
+<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>+<<<<]>>>>[-<<<<+>>>>]<<<<[-][-]>>>>[-]<<<[->>>+>>
+>>>>>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<<<<<<[<<<<<<[->>+>>>>>>>>>>
+>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<-]>[-]<<<<<[->
+>>>>+>>>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>>>>>>>]<<<<<<[-]<<<[-
+>>>+>>>>>>+<<<<<<<<<]>>>>>>>>>[-<<<<<<<<<+>>>>>>>>>]<<<<<<<[->-[>+>>]>[+[-<+>]>+
+>>]<<<<<]>[-]<<[-]>>>>>>>[-]<<<<[->>>>+<<<<]>[-<<<<+>>>>]<<<<[-<<<<<<<<<<<<<<<<<
+<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<<[-]>
+>>>>>>[-]<<<<<<<<<[-]<[-]<<[-]<[-]>>[-]>>>[-][-]<<<<<<<<<<<<<<<<<<<<<<<<<<<[->>>
+>>>>>>>>>>>>>>>>>>>>>>>>+<<<+<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>[-
+<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<<[->>>>>>
+>>>>>>>>>>>>>>>>>>>-<<<+<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<
+<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[-]+>>>>>>>>>>>>>>>>[-]>>>[
+-<<<+<<+>>>>>]<<<<<[->>>>>+<<<<<]>>[<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>[-]]>>>[-
+]+<<<[-]<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>+<<+<<<<<<<<<<<<<<]>>>>>>>>>>>>>>[-<<<
+<<<<<<<<<<<+>>>>>>>>>>>>>>]>>[>>>[-]<<<[-]]<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>
+>[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>+>>>]<<<[->>>+<<<]>>>[-]<<<<<<<<<<<<<<<<[
+-]<<[->>+>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<
+<<<+>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[-<<+>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<]
+>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<[-]<<<<<<<<<
+[-]>[-]>>>>>>>>>>>>>>>>>>>>>>[-]<<<<<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>
+>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<
+<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>]<<<<[-]<<<<<<<<<<<<<<[->>>>>>>>>
+>>>>>+>>>>+<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>
+>>>>>>>]<[-<<<->>>>+<]>[-<+>]>[-]+<[-]<<<<[->>>>+>>>>>>>>>+<<<<<<<<<<<<<]>>>>>>>
+>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<[>[-]<[-]]<<<<[-]>>[-]+>>[-]>[-<+>>
+>>>>>>>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<<<[<<[-]>>[-]]>[<<<<<<<<<<<<
+<<<<<<<<<<<<[-]>>>>>>[-<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<]>>>>>>>>
+>>>>>>>>>[-<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<<<<<<<[->>>>
++>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>
+[-<<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<<
+<<[-]>>>>>>[-<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>
+>>[-<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>]>[-]]<<<[>>[-]<<<<<<<<<<<<<<<<<[->>>>>
+>>>>>>>>>>>>+>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>[
+-<<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>]<[-]<<<<<<<<<<<<<<<<<<<
+<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<<<<
+<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<
+<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<[-]+>>>>>>>>>>>
+>>[<<<<<<<[-]+>>>>>>>>[-]<<<<<<<<<<[->>>>>>>>>>+>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<
+<<<<<<+>>>>>>>>>>>]<[<<<<<<<<[-]>>>>>>>>[-]]<<<<<<<<[<<<<<<[-]>>>>>>>>>>>>>[-]+<
+<<<<<<<<[-]+>>[-]]<<->>>>>>>>>-]<<<<<<<<<[-]<<<[-]+>>>>>[-]<<<<<<[->>>>>>+>>>>>>
+>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<[<<<<<[-]>>>>>
+[-]]<<<<<<[<<<<<<<<<<<<<<<<<<<[-]>>>>>>[-<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<
+<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>]<<<<<
+<<<<<<<<<<<<<<<<<<<<<<<<[->>>>->>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<<<<<
+<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>
+>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<<<<<<[-]>>>>>>[-<<<<<<+>>>>>>>>>>>>>>>>>>
+>>>>>>>>+<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<+>>>>>>>
+>>>>>>>>>>>>>]<<<<<<[-]]>[<<<<<<<<<<<<<<<<<<<<[-]<<<<[->>>>+>>>>>>>>>>>>>>>>>>>>
+>>>>>+<<<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<
+<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<[-<<<<<<->>>>>
+>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<
+<+>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>>>>[-<<<<
+<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<]>>>[-<<<+>>>]<<<<<[-]]>[-]]>[
+-]<<<<<<<<<<<<<<<<<[-]>[-]<<<<<[-]>>[-]>>>>>>>>>>>>>>>>>>>[-]<<<<<<<<<<<<<<<<<<<
+[->>>>>>>>>>>>>>>>>>>+>>>+<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<
+<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>]<<<<[-]+>>>>[-]<<<[->>>+>>>>>>>+<<<<<<<<<
+<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>]<<<<<<<[<<<<[-]>>>>[-]]<<<[-]<[->+>>>+<<<<]>
+>>>[-<<<<+>>>>]<<<<[-][-]<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>+>>>>+<<<<<<<
+<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>
+>>>>>>>>>>>>>]<<<[-<->>>>+<<<]>>>[-<<<+>>>]<<<<<[-]+>>>>>[-]<<<<[->>>>+>>>>>>>+<
+<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<[<<<<<[-]>>>>>[-]]<<<<[-]
+<<[-]+>>>>>>[-]<<<<<[->>>>>+>>>>>>>+<<<<<<<<<<<<]>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>
+>>>>>>>>]<<<<<<<[<<<<<<[-]>>>>>>[-]]<<<<<[<<<<<<<<<<<<<<[-]<<<<[->>>>+>>>>>>>>>>
+>>>>>>>>>+<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<
+<<+>>>>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<[->>+>>>>>>>>>>>>>>>>>>>+<<<<<<<
+<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>
+>]<<<<<<<<<<<<<<<<<<<<[-]<<<<[->>>>+>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<<<<<
+<]>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>]<<
+<<<[-]]<[>>>>>>[-]<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>>+>>>>>>>>+<<<<
+<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<
+<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>]<[-]<<<<<<<<<<<<<<<<<<<<<<<<<<<<[->
+>>>>>>>>>>>>>>>>>>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>
+>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<
+<<<[-]+>>>>>>>>>>>[<<<<<<<<<[-]+>>>>>>>>>>[-]<<<<<<<<[->>>>>>>>+>+<<<<<<<<<]>>>>
+>>>>>[-<<<<<<<<<+>>>>>>>>>]<[<<<<<<<<<<[-]>>>>>>>>>>[-]]<<<<<<<<<<[<<[-]>>>>>>>>
+>>>[-]+<<<<<<<[-]+<<[-]]>>->>>>>>>-]<<<<<<<[-]<[-]+<[-]<<[->>+>>>>>>>>>+<<<<<<<<
+<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<<<<<<[>[-]<[-]]<<[<<<<<<<<<<<<<<<[-
+]<<<<[->>>>+>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>[-<<<<<
+<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<[->>->>>>>>>>>>>>>>>>>
++<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>
+>]<<<<<<<<<<<<<<<<<<[-]<<<<[->>>>+>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<<<<]>>>>
+>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>]<<[-]]>>>[<<<<
+<<<<<<<<<<<<<<[-]<<[->>+>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>
+>[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<[->>>>->>>>>>>>>
+>>>>>>>>+<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<+>>>>
+>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<+>>>>
+>>>>>>>>>>>>>>+<]>[-<+>]>[-]]<<<<<[-]]>>>[-]<<<<<<<<<<<<<<<<<<<<<<<<<[-]>[-]>>>>
+>>>>>>>>>>>>>>>>>>>>[-]<<<<<<<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>>>>>
+>>>+>+<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<
+<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>]<<<<[-]<<<<<<<<<<<<<<[->>>>>>
+>>>>>>>>+>>>>+<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<+>>>>>>>>
+>>>>>>>>>>]<[-<<<->>>>+<]>[-<+>]>[-]+<[-]<<<<[->>>>+>>>>>>>>>+<<<<<<<<<<<<<]>>>>
+>>>>>>>>>[-<<<<<<<<<<<<<+>>>>>>>>>>>>>]<<<<<<<<<[>[-]<[-]]<<<<[-]>>[-]+>>[-]>[-<
++>>>>>>>>>+<<<<<<<<]>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<<<[<<[-]>>[-]]>[<<<<<<<<<
+<<<<<<<<<<<<<<<<<[-]>>>>>>>>[-<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<
+<<]>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<<
+<<<<<<<[->>>>+>>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>
+>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+]<<<<<<<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>[-<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<<
+<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>]>[-]]<<
+<[>>[-]<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>+>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<<<<<<<
+<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>
+>>>>>>>]<[-]<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<[->>>>>>>>>>>>>>>>>>>>>>>>>>>>
+>>>>>>>>>>+>+<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>
+>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>
+>>>>>>>>>>>>>]<<<<<<<<<<<<<<[-]+>>>>>>>>>>>>>[<<<<<<<[-]+>>>>>>>>[-]<<<<<<<<<<[-
+>>>>>>>>>>+>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<[<<<<<<<<[-]>>>>>
+>>>[-]]<<<<<<<<[<<<<<<[-]>>>>>>>>>>>>>[-]+<<<<<<<<<[-]+>>[-]]<<->>>>>>>>>-]<<<<<
+<<<<[-]<<<[-]+>>>>>[-]<<<<<<[->>>>>>+>>>>>>>+<<<<<<<<<<<<<]>>>>>>>>>>>>>[-<<<<<<
+<<<<<<<+>>>>>>>>>>>>>]<<<<<<<[<<<<<[-]>>>>>[-]]<<<<<<[<<<<<<<<<<<<<<<<<<<<<[-]>>
+>>>>>>[-<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>
+>>>>[-<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<[->
+>>>->>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>
+>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+]<<<<<<<<<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>[-<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>+
+<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>
+>>>>]<<<<<<[-]]>[<<<<<<<<<<<<<<<<<<<<<<[-]<<<<[->>>>+>>>>>>>>>>>>>>>>>>>>>>>>>>>
++<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<
+<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<[-<<<<<<<<-
+>>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>>>>[-<<<<<<<<<<<
+<<<<<<<<+>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>>>>>
+>>>>>[-<<<<<<<<<<<<<<<<<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<]>>>[-<<<+>>>]<
+<<<<[-]]>[-]]>[-]<<<<<<<<<<<<<<<<<[-]>[-]<<<<<[-]>[-]>[-]>[-]>>>>>>>>>>>>>>[-]]<
+<<<<->>>>>[-]+>>[-]<<<<<[->>>>>+<+<<<<]>>>>[-<<<<+>>>>]>[<<[-]>>[-]]<<<<<<[-]>>>
+>>>[-]<<<<<<<[->>>>>>>+<+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]>[<<<<<<[-]>>>>[-<<<<+>>>>
+>+<]>[-<+>]>[-]]<<[-]<<<<]>>>>[-]+>>[-]<<<<<[->>>>>+<+<<<<]>>>>[-<<<<+>>>>]>[<<[
+-]>>[-]]<<[>>[-]+++++++++++++++++++++++++++++++++++.[-]<<[-]]<<<[>>[-]+++++++++>
+>>[-]<<<<<<<[->>>>>>>+>>>>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>]<<<<
+<[-]<<[->>+>>>>>+<<<<<<<]>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<[-]+>>>[>>[-]+>>>[-]<<
+<<[->>>>+<<+<<]>>[-<<+>>]>>[<<<[-]>>>[-]]<<<[<<<<<[-]>>>[-]+>[-]+>[-]]<-<-]>[-]<
+<[-]+>>>[-]<<<<<[->>>>>+<<+<<<]>>>[-<<<+>>>]>>[<<<[-]>>>[-]]<<<<<[>>>>>[-]++++++
+++++++++++++++++++++++++++.[-]<<<<<[-]]>>[>>>[-]++++++++++++++++++++++++++++++++
++++++++++++.[-]<<<[-]]<[-]<<[-]]<<<<<<<<<<<<<<[-]>[-]>[-]>[-]>>>>>>>>>[-]<<+>-]>
+>>>>>>>>[-]++++++++++.[-]<<<<<<<<<<<<+>-]
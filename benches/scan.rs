@@ -0,0 +1,50 @@
+use brainfuckers::*;
+
+use std::sync::Arc;
+use std::time::Instant;
+
+/*
+ * Measures scan loops (`[>]`, `[<]`) and resetting recycled tapes
+ * on tapes with a few million used cells.
+ * Run with `cargo bench --bench scan`.
+ */
+
+const ITERATIONS: u32 = 5;
+const TAPE_SIZE: usize = 4 << 20;
+
+// The tape is filled with 1s from the origin, ending at or starting from the first cell
+const PROGRAMS: &[(&str, &str, isize)] = &[
+    ("scan-right", "[>]", 0),
+    ("scan-left", "[<]", 1 - TAPE_SIZE as isize),
+    ("scan-by-two", "[>>]", 0),
+];
+
+fn main() {
+    let cells = vec![1; TAPE_SIZE];
+    for (name, code, origin) in PROGRAMS {
+        let program = new_program(code);
+        let mut best_cell_time = f64::MAX;
+        for _ in 0..ITERATIONS {
+            let mut state = new_bf_state_from_program(Arc::clone(&program));
+            load_tape(&mut state, &cells, *origin).unwrap();
+            let started = Instant::now();
+            run_bf(&mut state, None, None).ok().unwrap();
+            let cell_time = started.elapsed().as_secs_f64() * 1e9 / TAPE_SIZE as f64;
+            best_cell_time = best_cell_time.min(cell_time);
+        }
+        println!("{name:<16} {best_cell_time:>8.3} ns/cell");
+    }
+
+    let pool = StatePool::new(1);
+    let program = new_program("");
+    let mut best_reset_time = f64::MAX;
+    for _ in 0..ITERATIONS {
+        let mut state = pool.checkout(Arc::clone(&program));
+        load_tape(&mut state, &cells, 0).unwrap();
+        let started = Instant::now();
+        pool.recycle(state);
+        let cell_time = started.elapsed().as_secs_f64() * 1e9 / TAPE_SIZE as f64;
+        best_reset_time = best_reset_time.min(cell_time);
+    }
+    println!("{:<16} {best_reset_time:>8.3} ns/cell", "recycle");
+}
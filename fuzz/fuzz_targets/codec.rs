@@ -0,0 +1,58 @@
+#![no_main]
+
+/*
+ * Decoding arbitrary hex or base64 input must never panic, only fail with `InvalidData`,
+ * and anything encoded must decode back to the same bytes, however the reads and writes are split.
+ * The first byte chooses the format (lowest bit) and the size of the reads and writes (the others);
+ * the rest is decoded as it is, then encoded and decoded back.
+ */
+
+use brainfuckers::*;
+use libfuzzer_sys::fuzz_target;
+use std::io::{BufReader, Cursor, ErrorKind, Read, Write};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>); // What the encoder wrote
+
+impl Write for Shared {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        return Ok(bytes.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return Ok(());
+    }
+}
+
+fn decode(encoded: &[u8], format: InputFormat, chunk: usize) -> std::io::Result<Vec<u8>> {
+    let inner = BufReader::with_capacity(chunk, Cursor::new(encoded.to_vec()));
+    let mut decoded = Vec::new();
+    DecodingReader::new(Box::new(inner), format).read_to_end(&mut decoded)?;
+    return Ok(decoded);
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, data)) = data.split_first() else {
+        return;
+    };
+    let (input_format, output_format) = match selector & 1 {
+        0 => (InputFormat::Hex, OutputFormat::Hex),
+        _ => (InputFormat::Base64, OutputFormat::Base64),
+    };
+    let chunk = usize::from(selector >> 1) + 1;
+
+    if let Err(error) = decode(data, input_format, chunk) {
+        assert_eq!(error.kind(), ErrorKind::InvalidData, "{error}");
+    }
+
+    let output = Shared::default();
+    let mut writer = EncodingWriter::new(Box::new(output.clone()), output_format);
+    for piece in data.chunks(chunk) {
+        writer.write_all(piece).unwrap();
+    }
+    writer.finish().unwrap();
+    let encoded = output.0.lock().unwrap().clone();
+    assert_eq!(decode(&encoded, input_format, chunk).unwrap(), data);
+});
@@ -0,0 +1,41 @@
+#![no_main]
+
+/*
+ * Running arbitrary code on arbitrary input under arbitrary limits must never panic.
+ * The input starts with a 20 byte header: a cell index and a tape origin (little-endian `i64`s), then 4 bytes
+ * choosing the extensions, limits and preloaded cells. The code follows, then a 0 byte, then what the program reads.
+ */
+
+use brainfuckers::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 20 {
+        return;
+    }
+    let number = |bytes: &[u8]| i64::from_le_bytes(bytes.try_into().unwrap());
+    let (index, origin, limits, rest) = (number(&data[..8]), number(&data[8..16]), &data[16..20], &data[20..]);
+    let split = rest.iter().position(|&c| c == 0).unwrap_or(rest.len());
+    let (code, input) = (&rest[..split], rest.get(split + 1..).unwrap_or_default());
+    let mut state = new_bf_state(code);
+    set_input_reader(&mut state, Box::new(std::io::Cursor::new(input.to_vec())));
+    capture_output(&mut state);
+    if limits[0] & 1 != 0 {
+        enable_env_extension(&mut state, &["HOME"]);
+    }
+    let _ = get_cell(&state, index as isize);
+    if limits[0] & 2 != 0 {
+        // Far from 0 these would only run out of memory
+        set_cell(&mut state, index as isize % 4096, limits[1]);
+        let _ = load_tape(&mut state, &limits[2..], origin as isize % 4096);
+    }
+    let limits = ResourceLimits {
+        max_steps: Some(u64::from(limits[1]) * 100),
+        max_tape_cells: (limits[2] != 0).then_some(usize::from(limits[2])),
+        max_output: (limits[3] != 0).then_some(u64::from(limits[3])),
+        ..ResourceLimits::default()
+    };
+    let _ = run_with_limits(&mut state, &limits);
+    let _ = tape_stats(&state);
+    let _ = export_tape(&state, -16..16);
+});
@@ -0,0 +1,39 @@
+#![no_main]
+
+/*
+ * The optimizer must not change what a program does: the optimized program and the plain one
+ * (every command its own instruction) have to print the same bytes and leave the same tape.
+ * The input is the code, then a 0 byte, then what the program reads.
+ */
+
+use brainfuckers::*;
+use libfuzzer_sys::fuzz_target;
+use std::sync::Arc;
+
+fn run(program: Arc<Program>, input: &[u8], fuel: u64) -> Option<(Vec<u8>, Vec<u8>)> {
+    // The output and the tape around 0, `None` unless the program halted within the fuel
+    let mut state = new_bf_state_from_program(program);
+    set_input_reader(&mut state, Box::new(std::io::Cursor::new(input.to_vec())));
+    capture_output(&mut state);
+    let limits = ResourceLimits { max_steps: Some(fuel), max_tape_cells: Some(1 << 16), ..ResourceLimits::default() };
+    let report = run_with_limits(&mut state, &limits).ok()?;
+    if !matches!(report.outcome, RunOutcome::Halted) {
+        return None;
+    }
+    return Some((report.output, export_tape(&state, -64..64)));
+}
+
+fuzz_target!(|data: &[u8]| {
+    let split = data.iter().position(|&c| c == 0).unwrap_or(data.len());
+    let (mut code, input) = (&data[..split], data.get(split + 1..).unwrap_or_default());
+    if code.starts_with(b"#!") {
+        // `new_program` skips the shebang line, `compile_with` doesn't
+        code = &code[code.iter().position(|&c| c == b'\n').unwrap_or(code.len())..];
+    }
+    // The plain program takes more steps for the same work, so it gets more fuel
+    let optimized = run(new_program(code), input, 100_000);
+    let plain = run(Arc::new(Program::from_ops(&compile_with(code, 0, false).ops)), input, 1_000_000);
+    if let (Some(optimized), Some(plain)) = (optimized, plain) {
+        assert_eq!(optimized, plain, "{}", String::from_utf8_lossy(code));
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+
+// Parsing anything, in every dialect, must never panic
+
+use brainfuckers::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = check_syntax(data);
+    let program = new_program(data);
+    let _ = program.check_syntax();
+    let _ = parse_labels(data, 0);
+    let registry = DialectRegistry::default();
+    for name in registry.names() {
+        if let Ok(code) = registry.translate(name, data) {
+            let _ = new_program(code);
+        }
+    }
+});
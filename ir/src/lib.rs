@@ -0,0 +1,209 @@
+pub const BF_OPCODE_BLOCK_BEGIN:       u8 = 91;
+pub const BF_OPCODE_BLOCK_END:         u8 = 93;
+pub const BF_OPCODE_DECREMENT_VALUE:   u8 = 45;
+pub const BF_OPCODE_INCREMENT_VALUE:   u8 = 43;
+pub const BF_OPCODE_INPUT:             u8 = 44;
+pub const BF_OPCODE_PRINT:             u8 = 46;
+pub const BF_OPCODE_SHIFT_LEFT:        u8 = 60;
+pub const BF_OPCODE_SHIFT_RIGHT:       u8 = 62;
+pub const BF_OPCODE_GET_ENV:           u8 = 36; // Extension
+
+/*
+ * The operations the code is compiled to, shared by the `brainfuckers` crate and its `bf!` macro
+ * so both produce the same operations.
+ *
+ * The code is compiled into a dense list of operations before running,
+ * so the interpreter doesn't have to skip comments or search for matching brackets.
+ * Runs of `+`/`-` and `<`/`>` are merged into a single operation,
+ * and clear and scan loops are replaced by a single operation as well.
+ *
+ * Additions carry the offset of their cell from the cursor, so moves between them are deferred:
+ * in a stretch of `+-<>`, the moves are merged into one `Move` to where the stretch ends,
+ * and the additions after the first move address their cells relative to that (`>+>+<<` is `Add(1, 1), Add(2, 1)`).
+ */
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Add(isize, u8), // Adds to the cell at the offset from the cursor (wrapping), merged `+` and `-`
+    Move(isize), // Moves the cursor, merged `<` and `>`
+    Print, // `.`
+    Input, // `,`
+    JumpIfZero(usize), // `[`, jumps past the matching `]`
+    JumpIfNotZero(usize), // `]`, jumps past the matching `[`
+    GetEnv, // `$` (extension)
+    Clear, // `[-]` and `[+]`, sets the current cell to 0
+    Scan(isize), // `[>]`, `[<<]`, etc., moves the cursor by the given step until it reaches a 0 cell
+    Extension(u8), // A character registered through `register_extension_op`, the index of its handler
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Bytecode {
+    pub ops: Vec<Op>,
+    pub positions: Vec<usize>, // Source position of each operation (of the first byte for merged ones)
+}
+
+pub fn compile(code: &[u8], offset: usize) -> Bytecode {
+    /*
+     * Compiles `code[offset..]`; positions are still relative to the start of `code`.
+     * Unmatched brackets are treated as comments.
+     */
+
+    return compile_with(code, offset, true);
+}
+
+pub fn compile_with(code: &[u8], offset: usize, optimize: bool) -> Bytecode {
+    // Without `optimize`, every instruction becomes its own operation
+    return compile_with_extensions(code, offset, optimize, &[]);
+}
+
+pub fn compile_with_extensions(code: &[u8], offset: usize, optimize: bool, extensions: &[u8]) -> Bytecode {
+    // The characters of `extensions` compile to `Op::Extension` with their index instead of being comments
+
+    let matched = find_matched_brackets(code, offset);
+    let mut bytecode = Bytecode::default();
+    let mut open_blocks: Vec<usize> = Vec::new();
+    let mut deferred = Deferred::default();
+
+    for (i, &opcode) in code.iter().enumerate().skip(offset) {
+        let op = match opcode {
+            BF_OPCODE_INCREMENT_VALUE => Op::Add(0, 1),
+            BF_OPCODE_DECREMENT_VALUE => Op::Add(0, 255),
+            BF_OPCODE_SHIFT_RIGHT => Op::Move(1),
+            BF_OPCODE_SHIFT_LEFT => Op::Move(-1),
+            BF_OPCODE_PRINT => Op::Print,
+            BF_OPCODE_INPUT => Op::Input,
+            BF_OPCODE_GET_ENV => Op::GetEnv,
+            BF_OPCODE_BLOCK_BEGIN if matched[i - offset] => {
+                deferred.flush(&mut bytecode);
+                open_blocks.push(bytecode.ops.len());
+                Op::JumpIfZero(0) // Patched when the matching `]` is found
+            },
+            BF_OPCODE_BLOCK_END if matched[i - offset] => {
+                deferred.flush(&mut bytecode);
+                let begin = open_blocks.pop().unwrap();
+                if optimize && let [Op::JumpIfZero(_), Op::Add(0, 1 | 255)] = bytecode.ops[begin..] {
+                    // Clear loop, it always ends with the current cell being 0
+                    bytecode.ops.truncate(begin);
+                    bytecode.positions.truncate(begin + 1);
+                    bytecode.ops.push(Op::Clear);
+                    continue;
+                }
+                if optimize && let [Op::JumpIfZero(_), Op::Move(delta)] = bytecode.ops[begin..] {
+                    // Scan loop, moves until the current cell is 0
+                    bytecode.ops.truncate(begin);
+                    bytecode.positions.truncate(begin + 1);
+                    bytecode.ops.push(Op::Scan(delta));
+                    continue;
+                }
+                let end = bytecode.ops.len();
+                bytecode.ops[begin] = Op::JumpIfZero(end + 1);
+                Op::JumpIfNotZero(begin + 1)
+            },
+            c if let Some(index) = extensions.iter().position(|&e| e == c) => Op::Extension(index as u8),
+            _ => continue,
+        };
+
+        if !optimize {
+            bytecode.ops.push(op);
+            bytecode.positions.push(i);
+            continue;
+        }
+        match op {
+            Op::Add(_, value) => deferred.add(&mut bytecode, value, i),
+            Op::Move(delta) => deferred.move_cursor(delta, i),
+            op => {
+                deferred.flush(&mut bytecode);
+                bytecode.ops.push(op);
+                bytecode.positions.push(i);
+            },
+        }
+    }
+    deferred.flush(&mut bytecode);
+
+    return bytecode;
+}
+
+#[derive(Default)]
+struct Deferred {
+    offset: isize, // Where the cursor would be if the moves so far were made
+    move_position: Option<usize>, // Source position of the first deferred move
+    additions: Vec<(isize, u8, usize)>, // Offsets from the cursor before the moves, values and source positions
+}
+
+impl Deferred {
+    fn add(&mut self, bytecode: &mut Bytecode, value: u8, position: usize) {
+        if self.move_position.is_none() {
+            // Nothing moved yet, the addition is on the current cell
+            push_merged(bytecode, Op::Add(0, value), position);
+            return;
+        }
+        match self.additions.last_mut() {
+            Some((offset, previous, _)) if *offset == self.offset => {
+                *previous = previous.wrapping_add(value);
+                if *previous == 0 {
+                    self.additions.pop();
+                }
+            },
+            _ => self.additions.push((self.offset, value, position)),
+        }
+    }
+
+    fn move_cursor(&mut self, delta: isize, position: usize) {
+        self.move_position.get_or_insert(position);
+        self.offset += delta;
+    }
+
+    fn flush(&mut self, bytecode: &mut Bytecode) {
+        // Emits the merged move, then the additions relative to where it lands
+        if let Some(position) = self.move_position.take()
+            && self.offset != 0 {
+            push_merged(bytecode, Op::Move(self.offset), position);
+        }
+        for (offset, value, position) in self.additions.drain(..) {
+            push_merged(bytecode, Op::Add(offset - self.offset, value), position);
+        }
+        self.offset = 0;
+    }
+}
+
+fn push_merged(bytecode: &mut Bytecode, op: Op, position: usize) {
+    // Merges the operation into the previous one if they add to the same cell or both move
+    match (bytecode.ops.last_mut(), op) {
+        (Some(Op::Add(previous_offset, previous)), Op::Add(offset, value)) if *previous_offset == offset => {
+            *previous = previous.wrapping_add(value);
+            if *previous == 0 {
+                bytecode.ops.pop();
+                bytecode.positions.pop();
+            }
+        },
+        (Some(Op::Move(previous)), Op::Move(delta)) => {
+            *previous += delta;
+            if *previous == 0 {
+                bytecode.ops.pop();
+                bytecode.positions.pop();
+            }
+        },
+        _ => {
+            bytecode.ops.push(op);
+            bytecode.positions.push(position);
+        },
+    }
+}
+
+fn find_matched_brackets(code: &[u8], offset: usize) -> Vec<bool> {
+    let mut matched = vec![false; code.len() - offset];
+    let mut open_blocks: Vec<usize> = Vec::new();
+    for (i, &opcode) in code[offset..].iter().enumerate() {
+        match opcode {
+            BF_OPCODE_BLOCK_BEGIN => open_blocks.push(i),
+            BF_OPCODE_BLOCK_END => {
+                if let Some(begin) = open_blocks.pop() {
+                    matched[begin] = true;
+                    matched[i] = true;
+                }
+            },
+            _ => {}
+        }
+    }
+    return matched;
+}
@@ -0,0 +1,108 @@
+use brainfuckers_ir::{BF_OPCODE_BLOCK_BEGIN, BF_OPCODE_BLOCK_END, compile};
+use proc_macro::{Delimiter, Group, Literal, Spacing, Span, TokenStream, TokenTree};
+
+/*
+ * `bf!(code, input)` checks the brackets of the code while the Rust code is compiled,
+ * then expands to the compiled operations (a constant) and a call running them with the input.
+ * The compiler comes from `brainfuckers-ir`, shared with the main crate so both produce the same operations.
+ */
+
+#[proc_macro]
+pub fn bf(input: TokenStream) -> TokenStream {
+    // `bf!("+[,.]", input)` evaluates to `Result<Vec<u8>, BfError>`, the input is optional
+    let mut tokens = input.into_iter();
+    let Some(TokenTree::Literal(literal)) = tokens.next() else {
+        return compile_error("`bf!` expects a string literal with the code", Span::call_site());
+    };
+    let Some(code) = parse_string(&literal.to_string()) else {
+        return compile_error("`bf!` expects a string literal with the code", literal.span());
+    };
+    if let Err(message) = check_brackets(code.as_bytes()) {
+        return compile_error(&message, literal.span());
+    }
+    let rest: Vec<TokenTree> = tokens.collect();
+    let input: TokenStream = match rest.split_first() {
+        None => "b\"\"".parse().unwrap(),
+        Some((TokenTree::Punct(comma), input)) if comma.as_char() == ',' && !input.is_empty() => input.iter().cloned().collect(),
+        _ => return compile_error("`bf!` expects the code and optionally the input, separated by a comma", Span::call_site()),
+    };
+
+    let ops: Vec<String> = compile(code.as_bytes(), 0).ops.iter().map(|op| format!("::brainfuckers::Op::{op:?}")).collect();
+    let mut expansion: TokenStream = format!(
+        "const OPS: &[::brainfuckers::Op] = &[{}]; ::brainfuckers::Program::from_ops(OPS).into_fn()",
+        ops.join(", ")
+    ).parse().unwrap();
+    let call = format!("::std::convert::AsRef::<[u8]>::as_ref(&({input}))").parse::<TokenStream>().unwrap();
+    expansion.extend([TokenTree::Group(Group::new(Delimiter::Parenthesis, call))]);
+    return TokenStream::from(TokenTree::Group(Group::new(Delimiter::Brace, expansion)));
+}
+
+fn check_brackets(code: &[u8]) -> Result<(), String> {
+    // Same rules and messages as `check_syntax`
+    let mut open_blocks: Vec<usize> = Vec::new();
+    for (i, &opcode) in code.iter().enumerate() {
+        match opcode {
+            BF_OPCODE_BLOCK_BEGIN => open_blocks.push(i),
+            BF_OPCODE_BLOCK_END if open_blocks.pop().is_none() => return Err(format!("Syntax error at byte {i}: unmatched `]`")),
+            _ => {}
+        }
+    }
+    if let Some(&position) = open_blocks.last() {
+        return Err(format!("Syntax error at byte {position}: unmatched `[`"));
+    }
+    return Ok(());
+}
+
+fn parse_string(literal: &str) -> Option<String> {
+    // Plain and raw string literals, with the escapes Brainfuck code could plausibly contain
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        return raw.get(hashes + 1..raw.len() - hashes - 1).map(String::from);
+    }
+    let inner = literal.strip_prefix('"')?.strip_suffix('"')?;
+    let mut string = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            string.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => string.push('\n'),
+            'r' => string.push('\r'),
+            't' => string.push('\t'),
+            '0' => string.push('\0'),
+            '\n' => {
+                // Line continuation, the leading whitespace of the next line is skipped too
+                let rest: String = chars.clone().collect();
+                let trimmed = rest.trim_start();
+                for _ in 0..rest.chars().count() - trimmed.chars().count() {
+                    chars.next();
+                }
+            },
+            escaped @ ('\\' | '"' | '\'') => string.push(escaped),
+            _ => return None,
+        }
+    }
+    return Some(string);
+}
+
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    // `compile_error!("message")` pointing at the span
+    let mut tokens: Vec<TokenTree> = vec![
+        TokenTree::Punct(proc_macro::Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(proc_macro::Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(proc_macro::Ident::new("core", span)),
+        TokenTree::Punct(proc_macro::Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(proc_macro::Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(proc_macro::Ident::new("compile_error", span)),
+        TokenTree::Punct(proc_macro::Punct::new('!', Spacing::Alone)),
+    ];
+    let mut message = Literal::string(message);
+    message.set_span(span);
+    tokens.push(TokenTree::Group(Group::new(Delimiter::Parenthesis, TokenStream::from(TokenTree::Literal(message)))));
+    for token in &mut tokens {
+        token.set_span(span);
+    }
+    return tokens.into_iter().collect();
+}
@@ -0,0 +1,35 @@
+use crate::*;
+
+use program::first_line_end;
+
+/*
+ * Compiling many programs in a row, like a service running thousands of small ones does, allocates and frees
+ * the same scratch structures of the analyses over and over. A `CompileArena` keeps them in one bump arena instead:
+ * each compilation allocates from it, and everything it allocated is freed in one shot before the next one,
+ * keeping the arena's memory for it. The compiled programs don't borrow from the arena.
+ */
+
+#[derive(Default)]
+pub struct CompileArena {
+    bump: bumpalo::Bump, // Scratch of the latest compilation
+}
+
+impl CompileArena {
+    pub fn new() -> Self {
+        return CompileArena::default();
+    }
+
+    pub fn compile(&mut self, code: impl AsRef<[u8]>) -> Arc<Program> {
+        // Same as `new_program`
+        self.bump.reset();
+        let code = code.as_ref().to_vec();
+        let code_start = if code.starts_with(SHEBANG) { first_line_end(&code) } else { 0 };
+        let fold = |bytecode: &Bytecode| known_zero::fold_known_zero_in(bytecode, &self.bump);
+        return Arc::new(Program::compile_folding(code, code_start, &[], Some(fold)));
+    }
+
+    pub fn allocated_bytes(&self) -> usize {
+        // Memory the arena holds on to between compilations
+        return self.bump.allocated_bytes();
+    }
+}
@@ -0,0 +1,116 @@
+use crate::*;
+
+use std::f64::consts::TAU;
+
+/*
+ * Output as music: every printed byte becomes a tone of a fixed length, with the byte as its MIDI note
+ * number (60 is middle C, 69 is the 440 Hz A). 0 is a rest, and bytes above 127 wrap around to 0..=127.
+ * Tones are 16-bit mono sine waves, faded in and out so consecutive notes don't click.
+ *
+ * `OutputFormat::Wav` collects the samples and writes a WAV file once the output is finished;
+ * `OutputFormat::Pcm` streams the bare samples (signed 16-bit little-endian), which players like
+ * `aplay -f S16_LE -r 44100` or `ffplay -f s16le -ar 44100 -ac 1 -` can play live.
+ */
+
+pub const SAMPLE_RATE: u32 = 44_100;
+pub const DEFAULT_NOTE_LENGTH: Duration = Duration::from_millis(200);
+
+const AMPLITUDE: f64 = 0.3 * i16::MAX as f64; // Loud enough, with room for the fades
+const FADE_SAMPLES: usize = 441; // 10 ms
+
+pub struct ToneWriter {
+    inner: Box<dyn Write + Send>,
+    wav: bool, // Whether the samples are collected for a WAV file instead of being streamed
+    note_samples: usize, // Samples per note
+    samples: Vec<u8>, // Collected samples of a WAV file, little-endian
+}
+
+impl ToneWriter {
+    pub fn new(inner: Box<dyn Write + Send>, format: OutputFormat) -> Self {
+        // Any format but `Wav` streams the samples
+        return ToneWriter::with_note_length(inner, format, DEFAULT_NOTE_LENGTH);
+    }
+
+    pub fn with_note_length(inner: Box<dyn Write + Send>, format: OutputFormat, length: Duration) -> Self {
+        let note_samples = (length.as_secs_f64() * f64::from(SAMPLE_RATE)) as usize;
+        return ToneWriter { inner, wav: format == OutputFormat::Wav, note_samples, samples: Vec::new() };
+    }
+
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        // Writes the WAV file with the notes so far, called when dropped as well
+        if self.wav {
+            self.wav = false;
+            let samples = std::mem::take(&mut self.samples);
+            self.inner.write_all(&wav_header(samples.len()))?;
+            self.inner.write_all(&samples)?;
+        }
+        return self.inner.flush();
+    }
+}
+
+pub fn note_frequency(value: u8) -> Option<f64> {
+    // Hertz of the MIDI note the byte plays, `None` for rests
+    let note = value & 0x7F;
+    if note == 0 {
+        return None;
+    }
+    return Some(440.0 * 2f64.powf((f64::from(note) - 69.0) / 12.0));
+}
+
+fn tone(value: u8, length: usize) -> Vec<u8> {
+    let mut samples = Vec::with_capacity(length * 2);
+    let frequency = note_frequency(value);
+    for i in 0..length {
+        let envelope = (i.min(length - 1 - i) as f64 / FADE_SAMPLES as f64).min(1.0);
+        let sample = match frequency {
+            Some(frequency) => (AMPLITUDE * envelope * (TAU * frequency * i as f64 / f64::from(SAMPLE_RATE)).sin()) as i16,
+            None => 0,
+        };
+        samples.extend(sample.to_le_bytes());
+    }
+    return samples;
+}
+
+fn wav_header(data_length: usize) -> Vec<u8> {
+    // RIFF header of a 16-bit mono PCM file, sizes over 4 GiB are clamped
+    let data_length = u32::try_from(data_length).unwrap_or(u32::MAX - 36);
+    let mut header = Vec::with_capacity(44);
+    header.extend(b"RIFF");
+    header.extend((36 + data_length).to_le_bytes());
+    header.extend(b"WAVEfmt ");
+    header.extend(16u32.to_le_bytes()); // Size of the format chunk
+    header.extend(1u16.to_le_bytes()); // PCM
+    header.extend(1u16.to_le_bytes()); // Mono
+    header.extend(SAMPLE_RATE.to_le_bytes());
+    header.extend((SAMPLE_RATE * 2).to_le_bytes()); // Bytes per second
+    header.extend(2u16.to_le_bytes()); // Bytes per sample
+    header.extend(16u16.to_le_bytes()); // Bits per sample
+    header.extend(b"data");
+    header.extend(data_length.to_le_bytes());
+    return header;
+}
+
+impl Write for ToneWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for &value in buf {
+            let samples = tone(value, self.note_samples);
+            if self.wav {
+                self.samples.extend(samples);
+            }
+            else {
+                self.inner.write_all(&samples)?;
+            }
+        }
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return self.inner.flush();
+    }
+}
+
+impl Drop for ToneWriter {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
@@ -0,0 +1,200 @@
+use std::fs::File;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/*
+ * Audit log of the programs run by `bf daemon --audit-log <file>`: one JSON object per line, appended when a session
+ * stops, or when it's closed, replaced or the daemon stopped before that, so every session ends up with exactly one
+ * record:
+ *
+ * {"timestamp":1760000000,"client":"default","session":"hi","program":"2f0c9d6e5a1b3c47",
+ *  "limits":{"concurrent-runs":1,"cpu-ms-per-minute":null,"memory-cells":100000},
+ *  "outcome":"Halted","steps":1234,"cpu-ms":3,"cells":12}
+ *
+ * `timestamp` is in seconds since the Unix epoch, `program` is the digest of the code (see `bf hash`), `limits` are the
+ * quotas of the client that created the session (null when unlimited), and `outcome` is the reason the program
+ * stopped, `error: <message>`, `closed`, `replaced` or `shutdown` (still running when the daemon was stopped with
+ * Ctrl-C). `cpu-ms` is the time spent stepping it and `cells` the tape cells it used. The log is only ever appended
+ * to; `bf audit query` reads it back.
+ */
+
+pub struct AuditRecord<'a> {
+    pub client: &'a str,
+    pub session: &'a str,
+    pub program: u64, // Digest of the program
+    pub limits: [(&'a str, Option<u64>); 3], // Quotas of the client, by their name in the quota file
+    pub outcome: &'a str,
+    pub steps: u64,
+    pub cpu_ms: u64,
+    pub cells: usize,
+}
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c < ' ' => quoted.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    return quoted;
+}
+
+impl AuditRecord<'_> {
+    fn to_json(&self) -> String {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let limits: Vec<String> = self.limits.iter()
+            .map(|(name, limit)| format!("{}:{}", json_string(name), limit.map_or(String::from("null"), |limit| limit.to_string())))
+            .collect();
+        return format!(
+            "{{\"timestamp\":{timestamp},\"client\":{},\"session\":{},\"program\":\"{:016x}\",\"limits\":{{{}}},\"outcome\":{},\"steps\":{},\"cpu-ms\":{},\"cells\":{}}}",
+            json_string(self.client), json_string(self.session), self.program, limits.join(","), json_string(self.outcome),
+            self.steps, self.cpu_ms, self.cells,
+        );
+    }
+}
+
+pub struct AuditLog(Mutex<File>);
+
+impl AuditLog {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let file = File::options().create(true).append(true).open(path)
+            .map_err(|error| format!("Failed to open audit log `{path}` {error}"))?;
+        return Ok(AuditLog(Mutex::new(file)));
+    }
+
+    pub fn append(&self, record: &AuditRecord) {
+        // A record is written with a single call, so records of concurrent sessions don't interleave
+        let line = format!("{}\n", record.to_json());
+        if let Err(error) = self.0.lock().unwrap().write_all(line.as_bytes()) {
+            eprintln!("Failed to write to the audit log: {error}");
+        }
+    }
+}
+
+#[cfg(feature = "audit")]
+pub fn audit(args: &[String]) -> Result<(), String> {
+    /*
+     * `bf audit query <log> [--client <name>] [--program <digest>] [--outcome <outcome>] [--since <time>]
+     * [--until <time>] [--summary]`: prints the records matching every given filter, times being Unix timestamps
+     * (`--until` exclusive), or with `--summary` their count and total usage.
+     */
+
+    const USAGE: &str = "Usage: bf audit query <log> [--client <name>] [--program <digest>] [--outcome <outcome>] [--since <time>] [--until <time>] [--summary]";
+    let Some((command, args)) = args.split_first() else {
+        return Err(String::from(USAGE));
+    };
+    if command != "query" {
+        return Err(format!("Unknown audit command `{command}`\n{USAGE}"));
+    }
+    let mut path: Option<&str> = None;
+    let mut filters: Vec<(&str, &str)> = Vec::new(); // Fields that have to hold the given text
+    let (mut since, mut until) = (u64::MIN, u64::MAX);
+    let mut summary = false;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--summary" => summary = true,
+            flag @ ("--client" | "--program" | "--outcome") => {
+                filters.push((&flag[2..], crate::flag_value(args, i)?));
+                i += 1;
+            },
+            flag @ ("--since" | "--until") => {
+                let value = crate::flag_value(args, i)?;
+                let time = value.parse().map_err(|_| format!("Invalid value for `{flag}`: {value}"))?;
+                *(if flag == "--since" { &mut since } else { &mut until }) = time;
+                i += 1;
+            },
+            arg if arg.starts_with('-') => return Err(format!("Unknown option `{arg}`")),
+            arg if path.is_none() => path = Some(arg),
+            arg => return Err(format!("Unexpected argument `{arg}`")),
+        }
+        i += 1;
+    }
+    let Some(path) = path else {
+        return Err(String::from(USAGE));
+    };
+
+    let text = std::fs::read_to_string(path).map_err(|error| format!("Failed to read file `{path}` {error}"))?;
+    let (mut runs, mut steps, mut cpu_ms, mut cells) = (0, 0, 0, 0);
+    let records = matching_records(&text, &filters, since..until)
+        .map_err(|(number, error)| format!("Invalid record on line {number} of `{path}`: {error}"))?;
+    for (line, record) in records {
+        if !summary {
+            println!("{line}");
+        }
+        runs += 1;
+        steps += record["steps"].as_u64().unwrap_or(0);
+        cpu_ms += record["cpu-ms"].as_u64().unwrap_or(0);
+        cells = cells.max(record["cells"].as_u64().unwrap_or(0));
+    }
+    if summary {
+        println!("{runs} runs, {steps} steps, {cpu_ms} ms of CPU, at most {cells} cells");
+    }
+    return Ok(());
+}
+
+#[cfg(feature = "audit")]
+fn matching_records<'a>(text: &'a str, filters: &[(&str, &str)], times: std::ops::Range<u64>) -> Result<Vec<(&'a str, serde_json::Value)>, (usize, serde_json::Error)> {
+    // The lines of the log whose fields hold the filters' text and whose timestamp is in the range, parsed;
+    // fails with the number of the first line that isn't a record
+    let mut records = Vec::new();
+    for (number, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let record: serde_json::Value = serde_json::from_str(line).map_err(|error| (number + 1, error))?;
+        let timestamp = record["timestamp"].as_u64().unwrap_or(0);
+        if times.contains(&timestamp) && filters.iter().all(|(field, value)| record[field].as_str() == Some(value)) {
+            records.push((line, record));
+        }
+    }
+    return Ok(records);
+}
+
+#[cfg(not(feature = "audit"))]
+pub fn audit(_args: &[String]) -> Result<(), String> {
+    return Err(String::from("`bf audit` needs the interpreter to be built with the `audit` feature"));
+}
+
+#[cfg(all(test, feature = "audit"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appended_records_read_back_through_the_filters() {
+        let path = std::env::temp_dir().join(format!("brainfuckers-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let log = AuditLog::open(path.to_str().unwrap()).unwrap();
+        let session = "tab\there \"quoted\" back\\slash\nnew line \u{1} é";
+        let record = |client, outcome| AuditRecord {
+            client,
+            session,
+            program: 0x2f0c9d6e5a1b3c47,
+            limits: [("concurrent-runs", Some(1)), ("cpu-ms-per-minute", None), ("memory-cells", Some(100_000))],
+            outcome,
+            steps: 1234,
+            cpu_ms: 3,
+            cells: 12,
+        };
+        log.append(&record("alice", "Halted"));
+        log.append(&record("bob", "error: Runtime error: \"x\"\r"));
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let all = matching_records(&text, &[], u64::MIN..u64::MAX).unwrap();
+        assert_eq!(all.len(), 2);
+        let filters = [("client", "alice"), ("program", "2f0c9d6e5a1b3c47"), ("outcome", "Halted")];
+        let [(_, alice)] = matching_records(&text, &filters, u64::MIN..u64::MAX).unwrap().try_into().unwrap();
+        assert_eq!(alice["session"], session);
+        assert_eq!(alice["limits"]["concurrent-runs"], 1);
+        assert!(alice["limits"]["cpu-ms-per-minute"].is_null());
+        assert_eq!(alice["limits"]["memory-cells"], 100_000);
+        assert_eq!((alice["steps"].as_u64(), alice["cpu-ms"].as_u64(), alice["cells"].as_u64()), (Some(1234), Some(3), Some(12)));
+        let [(_, bob)] = matching_records(&text, &[("outcome", "error: Runtime error: \"x\"\r")], u64::MIN..u64::MAX).unwrap().try_into().unwrap();
+        assert_eq!(bob["client"], "bob");
+        assert!(matching_records(&text, &[("session", session)], 0..1).unwrap().is_empty());
+    }
+}
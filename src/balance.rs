@@ -0,0 +1,85 @@
+use crate::*;
+
+/*
+ * Balance analysis: a loop is balanced when each of its iterations ends on the cell it started on.
+ * The body of a balanced loop only ever touches cells at fixed offsets from where the loop starts,
+ * so what is known about the other cells stays true after it; optimizations can look through it.
+ * A loop whose body moves the cursor by a fixed amount is unbalanced, and one containing a scan,
+ * an unbalanced loop or an extension operation moves it by an amount only known at runtime.
+ */
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Balance {
+    Balanced, // Every iteration ends on the cell it started on
+    Unbalanced(isize), // Every iteration moves the cursor by this much
+    Unknown, // The movement depends on the tape (nested scans or unbalanced loops) or on an extension
+}
+
+#[derive(Clone, Debug)]
+pub struct LoopBalance {
+    pub position: usize, // Source position of the `[`
+    pub depth: usize, // Number of loops around it
+    pub balance: Balance,
+}
+
+pub fn loop_balance(program: &Program) -> Vec<LoopBalance> {
+    /*
+     * Classifies every loop of the program, in source order, including the ones compiled to clears and scans.
+     * The program is analyzed as written, before the known-zero analysis removes any loop;
+     * characters of extension operations are comments to it.
+     */
+
+    let bytecode = compile(&program.code, program.code_start);
+    let mut loops = Vec::new();
+    let mut depth: usize = 0;
+    for (i, (&op, &position)) in bytecode.ops.iter().zip(&bytecode.positions).enumerate() {
+        let balance = match op {
+            Op::Clear => Balance::Balanced,
+            Op::Scan(delta) => Balance::Unbalanced(delta),
+            Op::JumpIfZero(after) => match body_effect(&bytecode.ops, i + 1..after - 1, &mut |_| {}) {
+                Some(0) => Balance::Balanced,
+                Some(movement) => Balance::Unbalanced(movement),
+                None => Balance::Unknown,
+            },
+            Op::JumpIfNotZero(_) => {
+                depth -= 1;
+                continue;
+            },
+            _ => continue,
+        };
+        loops.push(LoopBalance { position, depth, balance });
+        if let Op::JumpIfZero(_) = op {
+            depth += 1;
+        }
+    }
+    return loops;
+}
+
+pub(crate) fn body_effect(ops: &[Op], range: std::ops::Range<usize>, write: &mut dyn FnMut(isize)) -> Option<isize> {
+    /*
+     * The cursor movement of a stretch of operations, or `None` if it isn't fixed. The offsets of the cells it may
+     * write are passed to `write` on the way, maybe more than once; nested loops have to be balanced, their writes
+     * are included. Writes found before a `None` are still passed.
+     */
+
+    let mut offset: isize = 0;
+    let mut i = range.start;
+    while i < range.end {
+        match ops[i] {
+            Op::Add(cell, _) => write(offset + cell),
+            Op::Move(delta) => offset += delta,
+            Op::Input | Op::Clear => write(offset),
+            Op::Print | Op::GetEnv => {},
+            Op::Scan(_) | Op::Extension(_) => return None,
+            Op::JumpIfZero(after) => {
+                if body_effect(ops, i + 1..after - 1, &mut |cell| write(offset + cell))? != 0 {
+                    return None;
+                }
+                i = after - 1;
+            },
+            Op::JumpIfNotZero(_) => unreachable!("loops are skipped from their `[`"),
+        }
+        i += 1;
+    }
+    return Some(offset);
+}
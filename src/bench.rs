@@ -0,0 +1,38 @@
+use crate::*;
+
+use criterion::Throughput;
+
+/*
+ * Hooks for benchmarking programs with criterion (`bench` feature).
+ * Throughput is measured in executed instructions,
+ * so results stay comparable between programs of different lengths.
+ */
+
+pub fn run_in_memory(code: &str, input: &[u8]) -> Result<RunReport, BfError> {
+    // Runs the code with the given input, collecting the output instead of printing it
+    let mut state = new_bf_state(code);
+    set_input_reader(&mut state, Box::new(std::io::Cursor::new(input.to_vec())));
+    capture_output(&mut state);
+    return run_bf(&mut state, None, None);
+}
+
+pub fn instruction_count(code: &str, input: &[u8]) -> Result<u64, BfError> {
+    /*
+     * Counts the instructions of the code the run executes, not the operations of the optimized bytecode,
+     * so the count doesn't change when the optimizer merges more instructions.
+     * The code runs unoptimized for this, one operation per instruction and without loop tracing.
+     */
+
+    let program = new_program(code);
+    program.check_syntax()?;
+    let plain = compile_with(&program.code, program.code_start, false);
+    let mut state = new_bf_state_from_program(Arc::new(Program::from_ops(&plain.ops)));
+    set_hot_loop_threshold(&mut state, None);
+    set_input_reader(&mut state, Box::new(std::io::Cursor::new(input.to_vec())));
+    capture_output(&mut state);
+    return Ok(run_bf(&mut state, None, None)?.steps);
+}
+
+pub fn instruction_throughput(code: &str, input: &[u8]) -> Result<Throughput, BfError> {
+    return Ok(Throughput::Elements(instruction_count(code, input)?));
+}
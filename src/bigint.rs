@@ -0,0 +1,275 @@
+use crate::*;
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::ops::{AddAssign, Neg, SubAssign};
+
+/*
+ * Big-integer cells: every cell is an arbitrary-precision signed integer, so `+` and `-` never wrap, for variants
+ * and generated programs that assume unbounded cells. `-` on a 0 cell makes it -1, and `[-]` on a negative cell
+ * never ends, which is why the clear-loop optimization (and the `u8` folding of runs) doesn't apply here.
+ * Printing writes the cell modulo 256 as a byte, or the whole value in decimal.
+ * Reading stores the byte read, 0 on EOF. Extension operations (`$` included) do nothing.
+ */
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool, // Never set for 0
+    magnitude: Vec<u32>, // Base 2^32 digits, least significant first, without leading zeros (so empty for 0)
+}
+
+impl BigInt {
+    pub fn is_zero(&self) -> bool {
+        return self.magnitude.is_empty();
+    }
+
+    pub fn is_negative(&self) -> bool {
+        return self.negative;
+    }
+
+    pub fn low_byte(&self) -> u8 {
+        // The value modulo 256, what a wrapping cell would hold
+        let byte = self.magnitude.first().map_or(0, |&digit| digit as u8);
+        return if self.negative { byte.wrapping_neg() } else { byte };
+    }
+
+    fn add_signed(&mut self, negative: bool, magnitude: &[u32]) {
+        if self.negative == negative {
+            add_magnitudes(&mut self.magnitude, magnitude);
+        }
+        else if compare_magnitudes(&self.magnitude, magnitude) != Ordering::Less {
+            subtract_magnitudes(&mut self.magnitude, magnitude);
+        }
+        else {
+            let mut difference = magnitude.to_vec();
+            subtract_magnitudes(&mut difference, &self.magnitude);
+            self.magnitude = difference;
+            self.negative = negative;
+        }
+        if self.magnitude.is_empty() {
+            self.negative = false;
+        }
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let magnitude = value.unsigned_abs();
+        let mut digits = vec![magnitude as u32, (magnitude >> 32) as u32];
+        trim(&mut digits);
+        return BigInt { negative: value < 0, magnitude: digits };
+    }
+}
+
+impl AddAssign<&BigInt> for BigInt {
+    fn add_assign(&mut self, other: &BigInt) {
+        self.add_signed(other.negative, &other.magnitude);
+    }
+}
+
+impl SubAssign<&BigInt> for BigInt {
+    fn sub_assign(&mut self, other: &BigInt) {
+        self.add_signed(!other.negative && !other.is_zero(), &other.magnitude);
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(mut self) -> BigInt {
+        self.negative = !self.negative && !self.is_zero();
+        return self;
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Decimal, by dividing by 10^9 repeatedly and printing the remainders as 9-digit groups
+        let mut quotient = self.magnitude.clone();
+        let mut groups: Vec<u32> = Vec::new();
+        while !quotient.is_empty() {
+            let mut remainder: u64 = 0;
+            for digit in quotient.iter_mut().rev() {
+                let dividend = (remainder << 32) | u64::from(*digit);
+                *digit = (dividend / 1_000_000_000) as u32;
+                remainder = dividend % 1_000_000_000;
+            }
+            trim(&mut quotient);
+            groups.push(remainder as u32);
+        }
+        let mut text = String::from(if self.negative { "-" } else { "" });
+        match groups.split_last() {
+            Some((first, rest)) => {
+                text.push_str(&first.to_string());
+                for group in rest.iter().rev() {
+                    text.push_str(&format!("{group:09}"));
+                }
+            },
+            None => text.push('0'),
+        }
+        return f.pad(&text);
+    }
+}
+
+fn trim(digits: &mut Vec<u32>) {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    return a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()));
+}
+
+fn add_magnitudes(a: &mut Vec<u32>, b: &[u32]) {
+    if a.len() < b.len() {
+        a.resize(b.len(), 0);
+    }
+    let mut carry = 0;
+    for (i, digit) in a.iter_mut().enumerate() {
+        let sum = u64::from(*digit) + u64::from(b.get(i).copied().unwrap_or(0)) + carry;
+        *digit = sum as u32;
+        carry = sum >> 32;
+        if carry == 0 && i >= b.len() {
+            break;
+        }
+    }
+    if carry != 0 {
+        a.push(carry as u32);
+    }
+}
+
+fn subtract_magnitudes(a: &mut Vec<u32>, b: &[u32]) {
+    // `a` has to be at least `b`
+    let mut borrow = 0;
+    for (i, digit) in a.iter_mut().enumerate() {
+        let (difference, borrowed) = digit.overflowing_sub(b.get(i).copied().unwrap_or(0));
+        let (difference, borrowed_again) = difference.overflowing_sub(borrow);
+        *digit = difference;
+        borrow = u32::from(borrowed || borrowed_again);
+        if borrow == 0 && i >= b.len() {
+            break;
+        }
+    }
+    trim(a);
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BigCellOptions {
+    pub max_steps: Option<u64>, // Instructions to run at most, unlimited by default
+    pub decimal_output: bool, // Whether `.` prints the value in decimal and a newline instead of a byte
+}
+
+#[derive(Clone, Debug)]
+pub struct BigCellRun {
+    pub cells: BTreeMap<isize, BigInt>, // Cells that aren't 0
+    pub cursor: isize, // Index of the current cell when the run stopped
+    pub steps: u64,
+    pub halted: bool, // False when `max_steps` was reached first
+}
+
+#[derive(Clone, Debug)]
+enum BigOp {
+    Add(BigInt), // A run of `+` and `-`
+    Move(isize), // A run of `<` and `>`
+    Print,
+    Input,
+    JumpIfZero(usize),
+    JumpIfNotZero(usize),
+}
+
+fn compile_big(program: &Program) -> Vec<BigOp> {
+    // Merges runs without wrapping, the jumps of the plain bytecode are moved to where their targets end up
+    let plain = compile_with(&program.code, program.code_start, false).ops;
+    let mut ops: Vec<BigOp> = Vec::new();
+    let mut moved: Vec<usize> = Vec::with_capacity(plain.len() + 1); // Index in `ops` of each plain operation
+    for (i, &op) in plain.iter().enumerate() {
+        let merges = i > 0 && matches!((op, plain[i - 1]), (Op::Add(..), Op::Add(..)) | (Op::Move(_), Op::Move(_)));
+        moved.push(if merges { ops.len() - 1 } else { ops.len() });
+        let op = match op {
+            Op::Add(_, value) => BigOp::Add(BigInt::from(if value == 1 { 1 } else { -1 })),
+            Op::Move(delta) => BigOp::Move(delta),
+            Op::Print => BigOp::Print,
+            Op::Input => BigOp::Input,
+            Op::JumpIfZero(after) => BigOp::JumpIfZero(after),
+            Op::JumpIfNotZero(after) => BigOp::JumpIfNotZero(after),
+            Op::GetEnv | Op::Extension(_) | Op::Clear | Op::Scan(_) => continue,
+        };
+        match (ops.last_mut(), op) {
+            (Some(BigOp::Add(sum)), BigOp::Add(delta)) if merges => *sum += &delta,
+            (Some(BigOp::Move(sum)), BigOp::Move(delta)) if merges => *sum += delta,
+            (_, op) => ops.push(op),
+        }
+    }
+    moved.push(ops.len());
+    for op in &mut ops {
+        if let BigOp::JumpIfZero(after) | BigOp::JumpIfNotZero(after) = op {
+            *after = moved[*after];
+        }
+    }
+    return ops;
+}
+
+pub fn run_big_cells(program: &Program, input: &mut dyn Read, output: &mut dyn Write, options: &BigCellOptions) -> Result<BigCellRun, BfError> {
+    // Runs the program from a zeroed tape of big-integer cells, reading and writing bytes through the given streams
+    let ops = compile_big(program);
+    let mut ptape: Vec<BigInt> = vec![BigInt::default()]; // Cells from 0 rightwards
+    let mut ntape: Vec<BigInt> = Vec::new(); // Cells from -1 leftwards
+    let mut cursor: isize = 0;
+    let mut instruction = 0;
+    let mut steps: u64 = 0;
+    while instruction < ops.len() {
+        if options.max_steps.is_some_and(|max_steps| steps >= max_steps) {
+            break;
+        }
+        steps += 1;
+        let cell = if cursor >= 0 {
+            let index = cursor.unsigned_abs();
+            if index >= ptape.len() {
+                ptape.resize(index + 1, BigInt::default());
+            }
+            &mut ptape[index]
+        }
+        else {
+            let index = cursor.unsigned_abs() - 1;
+            if index >= ntape.len() {
+                ntape.resize(index + 1, BigInt::default());
+            }
+            &mut ntape[index]
+        };
+        match &ops[instruction] {
+            BigOp::Add(delta) => *cell += delta,
+            BigOp::Move(delta) => cursor += delta,
+            BigOp::Print if options.decimal_output => writeln!(output, "{cell}")?,
+            BigOp::Print => output.write_all(&[cell.low_byte()])?,
+            BigOp::Input => {
+                let mut byte = [0];
+                let read = loop {
+                    match input.read(&mut byte) {
+                        Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                        result => break result?,
+                    }
+                };
+                *cell = BigInt::from(if read == 0 { 0 } else { i64::from(byte[0]) }); // 0 on EOF
+            },
+            BigOp::JumpIfZero(after) if cell.is_zero() => {
+                instruction = *after;
+                continue;
+            },
+            BigOp::JumpIfNotZero(after) if !cell.is_zero() => {
+                instruction = *after;
+                continue;
+            },
+            BigOp::JumpIfZero(_) | BigOp::JumpIfNotZero(_) => {},
+        }
+        instruction += 1;
+    }
+    output.flush()?;
+
+    let negative = ntape.into_iter().enumerate().map(|(i, cell)| (-1 - isize::try_from(i).unwrap(), cell));
+    let positive = ptape.into_iter().enumerate().map(|(i, cell)| (isize::try_from(i).unwrap(), cell));
+    let cells = negative.chain(positive).filter(|(_, cell)| !cell.is_zero()).collect();
+    return Ok(BigCellRun { cells, cursor, steps, halted: instruction >= ops.len() });
+}
@@ -0,0 +1,222 @@
+use brainfuckers::*;
+
+use eframe::egui;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/*
+ * `bf-gui <program>`: a graphical debugger, built only on the public stepping and observer APIs.
+ * The tape grid shows the cells around the cursor and fades the ones that changed recently,
+ * the source view highlights the instruction about to run and lists the loops it's in,
+ * and the program runs at the speed of the slider.
+ */
+
+const TAPE_COLUMNS: isize = 16; // Cells per row of the tape grid
+const TAPE_ROWS: isize = 8;
+const HIGHLIGHT_FRAMES: u32 = 30; // How long a changed cell stays highlighted
+const MAX_SPEED: f64 = 1_000_000.0; // Instructions per second at the right end of the slider
+
+#[derive(Clone, Default)]
+struct SharedOutput(Arc<Mutex<Vec<u8>>>); // Where the program prints, read by the output view
+
+impl Write for SharedOutput {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        return Ok(bytes.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return Ok(());
+    }
+}
+
+struct Debugger {
+    code: Vec<u8>,
+    state: BFState,
+    output: SharedOutput,
+    changed: Arc<Mutex<HashMap<isize, u32>>>, // Cells changed recently, with the frames left to highlight them
+    input: String, // Text sent to the program with the `Send` button
+    playing: bool,
+    speed: f64, // Instructions per second
+    pending: f64, // Fraction of an instruction carried over to the next frame
+    last_frame: Instant,
+    status: String, // Why the program stopped, if it did
+}
+
+impl Debugger {
+    fn new(code: Vec<u8>) -> Self {
+        let output = SharedOutput::default();
+        let changed = Arc::new(Mutex::new(HashMap::new()));
+        let state = Debugger::new_state(&code, &output, &changed);
+        return Debugger {
+            code,
+            state,
+            output,
+            changed,
+            input: String::new(),
+            playing: false,
+            speed: 10.0,
+            pending: 0.0,
+            last_frame: Instant::now(),
+            status: String::new(),
+        };
+    }
+
+    fn new_state(code: &[u8], output: &SharedOutput, changed: &Arc<Mutex<HashMap<isize, u32>>>) -> BFState {
+        let mut state = new_bf_state(code);
+        // Input only comes from the input box, reading past it gives 0
+        set_input_reader(&mut state, Box::new(std::io::empty()));
+        set_output_writer(&mut state, Box::new(output.clone()));
+        set_raw_output(&mut state, true);
+        set_loop_tracking(&mut state, true);
+        let changed = changed.clone();
+        on_cell_change(&mut state, Some(Box::new(move |index, _, _| {
+            changed.lock().unwrap().insert(index, HIGHLIGHT_FRAMES);
+        })));
+        return state;
+    }
+
+    fn reset(&mut self) {
+        self.output.0.lock().unwrap().clear();
+        self.changed.lock().unwrap().clear();
+        self.state = Debugger::new_state(&self.code, &self.output, &self.changed);
+        self.playing = false;
+        self.pending = 0.0;
+        self.status.clear();
+    }
+
+    fn run(&mut self, steps: usize) {
+        match step_n(&mut self.state, steps) {
+            StepBatchResult::Running(_) => {},
+            StepBatchResult::Stopped(_, outcome) => {
+                self.playing = false;
+                self.status = format!("Stopped: {outcome:?}");
+            },
+            StepBatchResult::Failed(_, error) => {
+                self.playing = false;
+                self.status = error.to_string();
+            },
+        }
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let running = self.status.is_empty();
+            if ui.add_enabled(running, egui::Button::new(if self.playing { "Pause" } else { "Play" })).clicked() {
+                self.playing = !self.playing;
+                self.last_frame = Instant::now();
+            }
+            if ui.add_enabled(running && !self.playing, egui::Button::new("Step")).clicked() {
+                self.run(1);
+            }
+            if ui.button("Reset").clicked() {
+                self.reset();
+            }
+            ui.add(egui::Slider::new(&mut self.speed, 1.0..=MAX_SPEED).logarithmic(true).text("instructions per second"));
+        });
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.input);
+            if ui.button("Send").clicked() {
+                push_input(&mut self.state, self.input.as_bytes());
+                self.input.clear();
+            }
+            ui.label(&self.status);
+        });
+    }
+
+    fn source_view(&self, ui: &mut egui::Ui) {
+        // The code with the byte of the next instruction highlighted
+        let position = source_position(&self.state);
+        let code = String::from_utf8_lossy(&self.code);
+        let mut job = egui::text::LayoutJob::default();
+        let normal = egui::TextFormat { font_id: egui::FontId::monospace(14.0), ..Default::default() };
+        let current = egui::TextFormat { background: egui::Color32::YELLOW, color: egui::Color32::BLACK, ..normal.clone() };
+        let finished = position >= self.code.len() || !self.status.is_empty();
+        for (index, c) in code.char_indices() {
+            let format = if index == position && !finished { current.clone() } else { normal.clone() };
+            job.append(c.encode_utf8(&mut [0; 4]), 0.0, format);
+        }
+        egui::ScrollArea::vertical().id_salt("source").max_height(200.0).show(ui, |ui| {
+            ui.label(job);
+        });
+        // The open loops, outermost first, like a call stack
+        let loops: Vec<String> = loop_stack(&self.state).iter()
+            .map(|open| format!("[ at byte {} (iteration {})", open.position, open.iteration))
+            .collect();
+        ui.monospace(if loops.is_empty() { String::from("Not in a loop") } else { loops.join("  >  ") });
+    }
+
+    fn tape_view(&self, ui: &mut egui::Ui) {
+        // Rows of cells around the cursor, the cursor's cell in red and recently changed cells tinted
+        let cursor = cursor_position(&self.state);
+        let first = (cursor.div_euclid(TAPE_COLUMNS) - TAPE_ROWS / 2) * TAPE_COLUMNS;
+        let changed = self.changed.lock().unwrap();
+        egui::Grid::new("tape").spacing([4.0, 4.0]).show(ui, |ui| {
+            for row in 0..TAPE_ROWS {
+                let start = first + row * TAPE_COLUMNS;
+                ui.monospace(format!("{start:>6}"));
+                for index in start..start + TAPE_COLUMNS {
+                    let mut text = egui::RichText::new(format!("{:>3}", get_cell(&self.state, index))).monospace();
+                    if let Some(&frames) = changed.get(&index) {
+                        let alpha = (255 * frames / HIGHLIGHT_FRAMES) as u8;
+                        text = text.background_color(egui::Color32::from_rgba_unmultiplied(80, 160, 255, alpha));
+                    }
+                    if index == cursor {
+                        text = text.strong().color(egui::Color32::RED);
+                    }
+                    ui.label(text);
+                }
+                ui.end_row();
+            }
+        });
+    }
+}
+
+impl eframe::App for Debugger {
+    fn logic(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Runs the instructions the speed allows since the last frame
+        let elapsed = self.last_frame.elapsed().as_secs_f64();
+        self.last_frame = Instant::now();
+        if self.playing {
+            self.pending += self.speed * elapsed;
+            let steps = self.pending as usize;
+            self.pending -= steps as f64;
+            self.run(steps);
+            ctx.request_repaint();
+        }
+        self.changed.lock().unwrap().retain(|_, frames| {
+            *frames -= 1;
+            return *frames > 0;
+        });
+        if !self.changed.lock().unwrap().is_empty() {
+            ctx.request_repaint();
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ui, |ui| {
+            self.controls(ui);
+            ui.separator();
+            self.source_view(ui);
+            ui.separator();
+            self.tape_view(ui);
+            ui.separator();
+            let output = String::from_utf8_lossy(&self.output.0.lock().unwrap()).into_owned();
+            egui::ScrollArea::vertical().id_salt("output").stick_to_bottom(true).show(ui, |ui| {
+                ui.monospace(output);
+            });
+        });
+    }
+}
+
+fn main() -> Result<(), String> {
+    let Some(path) = std::env::args().nth(1) else {
+        return Err(String::from("Usage: bf-gui <program>"));
+    };
+    let code = std::fs::read(&path).map_err(|error| format!("Failed to read file `{path}` {error}"))?;
+    let options = eframe::NativeOptions::default();
+    return eframe::run_native("brainfuckers", options, Box::new(|_| Ok(Box::new(Debugger::new(code)))))
+        .map_err(|error| error.to_string());
+}
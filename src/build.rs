@@ -0,0 +1,159 @@
+use crate::*;
+
+use std::path::{Path, PathBuf};
+
+/*
+ * `bf.toml` manifests describe how a program is built into an artifact:
+ *
+ * [package]
+ * name = "hello"
+ * entry = "src/main.b"
+ *
+ * [build]
+ * format = "package"       # `package` (.bfpkg), `bytecode` (operation listing), `c`, `aarch64-linux` or `aarch64-macos`
+ * output = "target/hello.bfpkg"
+ * opt-level = 1            # 0 keeps every instruction as its own operation
+ * dialect = "brainfuck"    # `brainfuck`, `spoon` or `ook`
+ * cell-width = 8           # the only cell width for now
+ * include-paths = ["lib"]
+ * defines = ["big"]
+ *
+ * Everything in `[build]` is optional, paths are relative to the manifest.
+ */
+
+const SUPPORTED_CELL_WIDTH: i64 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArtifactFormat {
+    Package, // The preprocessed code in a `.bfpkg`
+    Bytecode, // A listing of the compiled operations
+    C, // C source code
+    Aarch64(AssemblyTarget), // AArch64 assembly
+}
+
+#[derive(Clone, Debug)]
+pub struct Manifest {
+    pub name: String,
+    pub entry: PathBuf,
+    pub format: ArtifactFormat,
+    pub output: PathBuf,
+    pub optimize: bool,
+    pub dialect: String, // Name in the default `DialectRegistry`
+    pub preprocess: PreprocessOptions,
+}
+
+fn manifest_error(path: &Path, message: &str) -> BfError {
+    return BfError::Format(format!("Invalid manifest `{}`: {message}", path.display()));
+}
+
+pub fn read_manifest(path: &Path) -> Result<Manifest, BfError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|error| BfError::Format(format!("Failed to read file `{}` {error}", path.display())))?;
+    let table: toml::Table = text.parse().map_err(|error: toml::de::Error| manifest_error(path, error.message()))?;
+    let root = path.parent().unwrap_or(Path::new(""));
+    let invalid = |message: &str| manifest_error(path, message);
+
+    let empty = toml::Table::new();
+    let package = table.get("package").and_then(toml::Value::as_table).ok_or_else(|| invalid("missing `[package]`"))?;
+    let build = match table.get("build") {
+        Some(build) => build.as_table().ok_or_else(|| invalid("`build` has to be a table"))?,
+        None => &empty,
+    };
+    let string = |table: &toml::Table, key: &str| -> Result<Option<String>, BfError> {
+        return match table.get(key) {
+            Some(value) => value.as_str().map(|value| Some(String::from(value))).ok_or_else(|| invalid(&format!("`{key}` has to be a string"))),
+            None => Ok(None),
+        };
+    };
+    let strings = |key: &str| -> Result<Vec<String>, BfError> {
+        let Some(value) = build.get(key) else {
+            return Ok(Vec::new());
+        };
+        let array = value.as_array().ok_or_else(|| invalid(&format!("`{key}` has to be an array of strings")))?;
+        return array.iter()
+            .map(|item| item.as_str().map(String::from).ok_or_else(|| invalid(&format!("`{key}` has to be an array of strings"))))
+            .collect();
+    };
+    let integer = |key: &str, default: i64| -> Result<i64, BfError> {
+        return match build.get(key) {
+            Some(value) => value.as_integer().ok_or_else(|| invalid(&format!("`{key}` has to be an integer"))),
+            None => Ok(default),
+        };
+    };
+
+    let name = string(package, "name")?.ok_or_else(|| invalid("missing `package.name`"))?;
+    let entry = string(package, "entry")?.ok_or_else(|| invalid("missing `package.entry`"))?;
+    let format = match string(build, "format")?.as_deref() {
+        None | Some("package") => ArtifactFormat::Package,
+        Some("bytecode") => ArtifactFormat::Bytecode,
+        Some("c") => ArtifactFormat::C,
+        Some("aarch64-linux") => ArtifactFormat::Aarch64(AssemblyTarget::Linux),
+        Some("aarch64-macos") => ArtifactFormat::Aarch64(AssemblyTarget::MacOs),
+        Some(format) => return Err(invalid(&format!(
+            "unknown format `{format}` (expected `package`, `bytecode`, `c`, `aarch64-linux` or `aarch64-macos`)"
+        ))),
+    };
+    let extension = match format {
+        ArtifactFormat::Package => "bfpkg",
+        ArtifactFormat::Bytecode => "bfc",
+        ArtifactFormat::C => "c",
+        ArtifactFormat::Aarch64(_) => "s",
+    };
+    let output = string(build, "output")?.unwrap_or_else(|| format!("target/{name}.{extension}"));
+    let optimize = match integer("opt-level", 1)? {
+        0 => false,
+        1 => true,
+        level => return Err(invalid(&format!("unknown opt-level {level} (expected 0 or 1)"))),
+    };
+    let dialect = string(build, "dialect")?.unwrap_or_else(|| String::from("brainfuck"));
+    if DialectRegistry::default().get(&dialect).is_none() {
+        return Err(invalid(&format!("unsupported dialect `{dialect}`")));
+    }
+    if integer("cell-width", SUPPORTED_CELL_WIDTH)? != SUPPORTED_CELL_WIDTH {
+        return Err(invalid(&format!("only {SUPPORTED_CELL_WIDTH}-bit cells are supported")));
+    }
+    let preprocess = PreprocessOptions {
+        search_paths: strings("include-paths")?.iter().map(|directory| root.join(directory)).collect(),
+        defines: strings("defines")?.into_iter().collect(),
+        literate: match build.get("literate") {
+            Some(value) => value.as_bool().ok_or_else(|| invalid("`literate` has to be a boolean"))?,
+            None => false,
+        },
+        ..PreprocessOptions::default()
+    };
+
+    return Ok(Manifest { name, entry: root.join(entry), format, output: root.join(output), optimize, dialect, preprocess });
+}
+
+pub fn build(manifest: &Manifest) -> Result<(), BfError> {
+    // Writes the artifact, creating its directory if needed
+    trace_span!(INFO, "build", entry = %manifest.entry.display());
+    let (code, map) = preprocess(&manifest.entry, &manifest.preprocess)?;
+    let code = match manifest.dialect.as_str() {
+        "brainfuck" => Ok(code),
+        dialect => DialectRegistry::default().translate(dialect, &code),
+    };
+    let code = code.and_then(|code| check_syntax(&code).map(|()| code)).map_err(|error| match error {
+        BfError::Syntax { position, message } => match map.locate(position) {
+            Some((file, offset)) => BfError::Format(format!("Syntax error in `{}` at byte {offset}: {message}", file.display())),
+            None => BfError::Syntax { position, message },
+        },
+        error => error,
+    })?;
+
+    let artifact = match manifest.format {
+        ArtifactFormat::Package => write_package(&Package { code, ..Package::default() }),
+        ArtifactFormat::Bytecode => {
+            // Headed by the digest, so listings of the same program can be recognized
+            let listing = to_listing(&compile_with(&code, 0, manifest.optimize));
+            format!("# digest {:016x}\n{listing}", new_program(&code).digest()).into_bytes()
+        },
+        ArtifactFormat::C => to_c(&compile_with(&code, 0, manifest.optimize)).into_bytes(),
+        ArtifactFormat::Aarch64(target) => to_aarch64(&compile_with(&code, 0, manifest.optimize), target).into_bytes(),
+    };
+    if let Some(directory) = manifest.output.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    std::fs::write(&manifest.output, artifact)?;
+    return Ok(());
+}
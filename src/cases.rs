@@ -0,0 +1,181 @@
+use crate::*;
+
+use std::path::{Path, PathBuf};
+
+/*
+ * Test cases for Brainfuck programs, one `.toml` file each:
+ *
+ * program = "../src/main.b"    # relative to the case file, or `code = "..."` inline
+ * input = "abc"                # optional
+ * expected-output = "cba"
+ * max-steps = 1000000          # optional
+ *
+ * Rust projects embedding programs can use `assert_bf_output!` instead,
+ * optionally with the expected output kept in a snapshot file.
+ */
+
+pub const DEFAULT_MAX_STEPS: u64 = 100_000_000; // Also used by `assert_bf_output!`
+const UPDATE_SNAPSHOTS_VARIABLE: &str = "UPDATE_SNAPSHOTS";
+
+#[derive(Clone, Debug)]
+pub enum TestProgram {
+    File(PathBuf), // Preprocessed like any program file
+    Inline(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct TestCase {
+    pub name: String, // The file name without the extension
+    pub program: TestProgram,
+    pub input: Vec<u8>,
+    pub expected_output: Vec<u8>,
+    pub max_steps: u64,
+}
+
+pub enum TestResult {
+    Passed,
+    Failed(String), // Why, including a diff of the output when it differs
+}
+
+pub fn read_test_case(path: &Path) -> Result<TestCase, BfError> {
+    let invalid = |message: &str| BfError::Format(format!("Invalid test case `{}`: {message}", path.display()));
+    let text = std::fs::read_to_string(path)
+        .map_err(|error| BfError::Format(format!("Failed to read file `{}` {error}", path.display())))?;
+    let table: toml::Table = text.parse().map_err(|error: toml::de::Error| invalid(error.message()))?;
+    let string = |key: &str| -> Result<Option<&str>, BfError> {
+        return match table.get(key) {
+            Some(value) => value.as_str().map(Some).ok_or_else(|| invalid(&format!("`{key}` has to be a string"))),
+            None => Ok(None),
+        };
+    };
+
+    let program = match (string("program")?, string("code")?) {
+        (Some(program), None) => TestProgram::File(path.parent().unwrap_or(Path::new("")).join(program)),
+        (None, Some(code)) => TestProgram::Inline(String::from(code)),
+        _ => return Err(invalid("needs exactly one of `program` and `code`")),
+    };
+    let expected_output = string("expected-output")?.ok_or_else(|| invalid("missing `expected-output`"))?;
+    let max_steps = match table.get("max-steps") {
+        Some(value) => value.as_integer().and_then(|steps| u64::try_from(steps).ok())
+            .ok_or_else(|| invalid("`max-steps` has to be a positive integer"))?,
+        None => DEFAULT_MAX_STEPS,
+    };
+    return Ok(TestCase {
+        name: path.file_stem().map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+        program,
+        input: string("input")?.unwrap_or_default().as_bytes().to_vec(),
+        expected_output: expected_output.as_bytes().to_vec(),
+        max_steps,
+    });
+}
+
+pub fn discover_test_cases(directory: &Path) -> Result<Vec<PathBuf>, BfError> {
+    // The `.toml` files in the directory, sorted by name
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "toml") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    return Ok(paths);
+}
+
+pub fn run_test_case(case: &TestCase) -> TestResult {
+    let code = match &case.program {
+        TestProgram::File(path) => match preprocess(path, &PreprocessOptions::default()) {
+            Ok((code, _)) => code,
+            Err(error) => return TestResult::Failed(error.to_string()),
+        },
+        TestProgram::Inline(code) => code.clone().into_bytes(),
+    };
+    return match check_bf_output(&code, &case.input, &case.expected_output, case.max_steps) {
+        Ok(()) => TestResult::Passed,
+        Err(reason) => TestResult::Failed(reason),
+    };
+}
+
+fn run_captured(code: &[u8], input: &[u8], max_steps: u64) -> Result<Vec<u8>, String> {
+    let mut state = new_bf_state(code);
+    set_input_reader(&mut state, Box::new(std::io::Cursor::new(input.to_vec())));
+    capture_output(&mut state);
+    let limits = ResourceLimits { max_steps: Some(max_steps), ..ResourceLimits::default() };
+    let report = run_with_limits(&mut state, &limits).map_err(|error| error.to_string())?;
+    if let RunOutcome::FuelExhausted = report.outcome {
+        return Err(format!("Didn't halt in {max_steps} steps"));
+    }
+    return Ok(report.output);
+}
+
+pub fn check_bf_output(code: impl AsRef<[u8]>, input: &[u8], expected: &[u8], max_steps: u64) -> Result<(), String> {
+    let output = run_captured(code.as_ref(), input, max_steps)?;
+    if output != expected {
+        return Err(format!("Output differs:\n{}", diff_lines(expected, &output)));
+    }
+    return Ok(());
+}
+
+pub fn check_bf_snapshot(code: impl AsRef<[u8]>, input: &[u8], snapshot: &Path) -> Result<(), String> {
+    /*
+     * Compares the output with the contents of the snapshot file.
+     * With `UPDATE_SNAPSHOTS=1` in the environment, the file is (re)written with the output instead.
+     */
+
+    let output = run_captured(code.as_ref(), input, DEFAULT_MAX_STEPS)?;
+    if std::env::var_os(UPDATE_SNAPSHOTS_VARIABLE).is_some_and(|value| value == "1") {
+        if let Some(directory) = snapshot.parent() {
+            std::fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+        }
+        return std::fs::write(snapshot, output).map_err(|error| format!("Failed to write file `{}` {error}", snapshot.display()));
+    }
+    let expected = std::fs::read(snapshot).map_err(|error| {
+        format!("Failed to read snapshot `{}` {error} (run with {UPDATE_SNAPSHOTS_VARIABLE}=1 to create it)", snapshot.display())
+    })?;
+    if output != expected {
+        return Err(format!("Output differs from snapshot `{}`:\n{}", snapshot.display(), diff_lines(&expected, &output)));
+    }
+    return Ok(());
+}
+
+#[macro_export]
+macro_rules! assert_bf_output {
+    // `assert_bf_output!(code, input, expected)` or `assert_bf_output!(code, input, snapshot: "relative/path")`
+    ($code:expr, $input:expr, snapshot: $path:expr) => {
+        let snapshot = ::std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join($path);
+        if let Err(message) = $crate::check_bf_snapshot($code, ::std::convert::AsRef::<[u8]>::as_ref(&$input), &snapshot) {
+            panic!("{message}");
+        }
+    };
+    ($code:expr, $input:expr, $expected:expr) => {
+        if let Err(message) = $crate::check_bf_output(
+            $code,
+            ::std::convert::AsRef::<[u8]>::as_ref(&$input),
+            ::std::convert::AsRef::<[u8]>::as_ref(&$expected),
+            $crate::DEFAULT_MAX_STEPS,
+        ) {
+            panic!("{message}");
+        }
+    };
+}
+
+pub fn diff_lines(expected: &[u8], actual: &[u8]) -> String {
+    // Differing lines, prefixed with `-` for the expected and `+` for the actual ones
+    let expected: Vec<&[u8]> = expected.split(|&c| c == NEWLINE).collect();
+    let actual: Vec<&[u8]> = actual.split(|&c| c == NEWLINE).collect();
+    let mut diff = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        let (expected_line, actual_line) = (expected.get(i), actual.get(i));
+        if expected_line == actual_line {
+            continue;
+        }
+        diff.push_str(&format!("line {}:\n", i + 1));
+        if let Some(line) = expected_line {
+            diff.push_str(&format!("- {}\n", line.escape_ascii()));
+        }
+        if let Some(line) = actual_line {
+            diff.push_str(&format!("+ {}\n", line.escape_ascii()));
+        }
+    }
+    return diff;
+}
@@ -0,0 +1,128 @@
+use crate::*;
+
+/*
+ * Checkpoints save a running state to a file, so long computations survive restarts of the machine.
+ * They use the section format of packages after their own magic line:
+ * `code` (to check the checkpoint belongs to the program), `position <source position> <cursor> <highest cursor>`,
+ * `counters <steps> <input bytes> <output bytes>`, `ptape`, `ntape`, `input` (queued, not read yet)
+ * and `output` (an incomplete UTF-8 character waiting to be printed).
+ * Input that was already read from the reader and output that was already printed aren't part of it.
+ */
+
+const CHECKPOINT_MAGIC: &[u8] = b"bfckpt 1\n";
+
+pub fn save_checkpoint(state: &mut BFState, steps: u64) -> Result<Vec<u8>, BfError> {
+    /*
+     * Flushes the writer, so everything the checkpoint counts as printed really was, and serializes the state.
+     * `steps` is the number of steps run so far, given back by `restore_checkpoint` to carry fuel over.
+     */
+
+    if state.captured_output.is_none() {
+        state.writer.flush()?;
+    }
+    let mut bytes = CHECKPOINT_MAGIC.to_vec();
+    package::write_section(&mut bytes, "code", &state.program.code);
+    // The source position rather than the index of the operation, which depends on how the program was optimized
+    let position = format!("position {} {} {}", source_position(state), state.cursor_position, state.highest_cursor);
+    package::write_section(&mut bytes, &position, &[]);
+    package::write_section(&mut bytes, &format!("counters {steps} {} {}", state.input_bytes, state.output_bytes), &[]);
+    package::write_section(&mut bytes, "ptape", &state.ptape);
+    package::write_section(&mut bytes, "ntape", &state.ntape);
+    package::write_section(&mut bytes, "input", &state.input.iter().copied().collect::<Vec<u8>>());
+    package::write_section(&mut bytes, "output", &state.output);
+    return Ok(bytes);
+}
+
+pub fn restore_checkpoint(state: &mut BFState, bytes: &[u8]) -> Result<u64, BfError> {
+    /*
+     * Puts the state back where the checkpoint was saved and returns the number of steps run until then.
+     * The state has to run the same program; its options (limits, I/O, extensions) are kept.
+     * The whole file is checked before anything changes, so the state is left as it was when it's rejected.
+     */
+
+    let invalid = |message: &str| BfError::Format(format!("Invalid checkpoint: {message}"));
+    let Some(rest) = bytes.strip_prefix(CHECKPOINT_MAGIC) else {
+        return Err(invalid("missing the `bfckpt 1` header"));
+    };
+    let sections = package::read_sections(rest).map_err(|message| invalid(&message))?;
+    let number = |value: &str| value.parse::<i64>().map_err(|_| invalid(&format!("bad number `{value}`")));
+
+    let mut seen: Vec<&str> = Vec::new(); // Names of the sections so far, each one is required exactly once
+    let (mut position, mut counters, mut ptape, mut ntape, mut input, mut output) = (None, None, None, None, None, None);
+    for (header, data) in sections {
+        let fields: Vec<&str> = header.split(' ').collect();
+        if seen.contains(&fields[0]) {
+            return Err(invalid(&format!("more than one `{}` section", fields[0])));
+        }
+        seen.push(fields[0]);
+        match fields[..] {
+            ["code", _] if data != state.program.code => {
+                return Err(BfError::Format(String::from("The checkpoint was saved for another program")));
+            },
+            ["code", _] => {},
+            ["position", source, cursor, highest, _] => {
+                let source = usize::try_from(number(source)?).map_err(|_| invalid("negative position"))?;
+                position = Some((source, number(cursor)? as isize, number(highest)? as isize));
+            },
+            ["counters", run_steps, input_bytes, output_bytes, _] => {
+                counters = Some((number(run_steps)? as u64, number(input_bytes)? as u64, number(output_bytes)? as u64));
+            },
+            ["ptape", _] => ptape = Some(data.to_vec()),
+            ["ntape", _] => ntape = Some(data.to_vec()),
+            ["input", _] => input = Some(data.iter().copied().collect()),
+            ["output", _] => output = Some(data.to_vec()),
+            _ => return Err(invalid(&format!("unexpected section `{header}`"))),
+        }
+    }
+    let (Some((source, cursor, highest)), Some((steps, input_bytes, output_bytes)), Some(ptape), Some(ntape), Some(input), Some(output)) =
+        (position, counters, ptape, ntape, input, output) else {
+        return Err(invalid("missing sections"));
+    };
+    if !seen.contains(&"code") {
+        return Err(invalid("missing sections"));
+    }
+    if ptape.is_empty() {
+        return Err(invalid("the tape is empty"));
+    }
+    check_cursor(state, ptape.len(), ntape.len(), cursor, highest).map_err(invalid)?;
+
+    // The tape doesn't start zeroed anymore, which can change the program and so where the position is in it
+    known_zero::forget_zeroed_tape(state);
+    let positions = &state.program.bytecode.positions;
+    state.instruction_position = positions.iter().position(|&start| start >= source).unwrap_or(positions.len());
+    state.cursor_position = cursor;
+    state.highest_cursor = highest;
+    state.lowest_cursor = state.lowest_cursor.min(cursor);
+    state.input_bytes = input_bytes;
+    state.output_bytes = output_bytes;
+    state.ptape = ptape;
+    state.ntape = ntape;
+    state.input = input;
+    state.output = output;
+    state.loop_traces.clear();
+    if let Some(iterations) = state.loop_iterations.as_mut() {
+        iterations.clear();
+    }
+    return Ok(steps);
+}
+
+fn check_cursor(state: &BFState, positive: usize, negative: usize, cursor: isize, highest: isize) -> Result<(), &'static str> {
+    // The cursor comes from the file, it has to be somewhere a run could have moved it within the state's tape limit
+    if highest < 0 || highest == isize::MAX || cursor > highest {
+        return Err("the cursor is outside of the tape");
+    }
+    if state.loops {
+        return match usize::try_from(cursor) {
+            Ok(cursor) if cursor < positive => Ok(()),
+            _ => Err("the cursor is outside of the looping tape"),
+        };
+    }
+    let Some(limit) = state.max_tape_cells else {
+        return Ok(());
+    };
+    let negative = negative.max(if cursor < 0 { cursor.unsigned_abs() } else { 0 });
+    if (highest.unsigned_abs() + 1).checked_add(negative).is_none_or(|cells| cells > limit) {
+        return Err("the tape doesn't fit in the tape limit");
+    }
+    return Ok(());
+}
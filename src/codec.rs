@@ -0,0 +1,301 @@
+use std::io::{BufRead, Read, Write};
+
+/*
+ * Text encodings for the input and the output,
+ * so programs working on binary data can be driven from shells without corrupting it.
+ * Whitespace between the encoded characters of the input is ignored.
+ * Newline translation makes interactive programs behave the same on Windows terminals.
+ */
+
+const BASE64_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const READ_CHUNK: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputFormat {
+    Raw, // The bytes as they are
+    Hex, // Pairs of hex digits
+    Base64, // Standard alphabet, padding is optional
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Utf8, // The bytes as they are, waiting for complete UTF-8 characters
+    Raw, // The bytes as they are, as soon as they're printed
+    Hex, // Pairs of lowercase hex digits
+    Base64, // Standard alphabet with padding
+    Wav, // A WAV file with a tone for every byte, see `ToneWriter`
+    Pcm, // The samples of the tones without a header, for piping into a player
+}
+
+pub struct DecodingReader {
+    inner: Box<dyn BufRead + Send>,
+    format: InputFormat,
+    pending: Vec<u8>, // Encoded characters that don't form a complete group yet
+    decoded: Vec<u8>,
+    position: usize, // Index of the first unconsumed byte in `decoded`
+}
+
+impl DecodingReader {
+    pub fn new(inner: Box<dyn BufRead + Send>, format: InputFormat) -> Self {
+        return DecodingReader { inner, format, pending: Vec::new(), decoded: Vec::new(), position: 0 };
+    }
+
+    fn decode_pending(&mut self, eof: bool) -> std::io::Result<()> {
+        let group = match self.format {
+            InputFormat::Raw => 1,
+            InputFormat::Hex => 2,
+            InputFormat::Base64 => 4,
+        };
+        let complete = if eof { self.pending.len() } else { self.pending.len() - self.pending.len() % group };
+        for chunk in self.pending[..complete].chunks(group) {
+            match self.format {
+                InputFormat::Raw => self.decoded.extend(chunk),
+                InputFormat::Hex => {
+                    let [high, low] = chunk else {
+                        return Err(invalid_data("odd number of hex digits"));
+                    };
+                    self.decoded.push(hex_digit(*high)? << 4 | hex_digit(*low)?);
+                },
+                InputFormat::Base64 => {
+                    let count = chunk.iter().position(|&c| c == b'=').unwrap_or(chunk.len());
+                    if chunk[count..].iter().any(|&c| c != b'=') {
+                        return Err(invalid_data("base64 data after padding"));
+                    }
+                    let mut bits: u32 = 0;
+                    for &c in &chunk[..count] {
+                        bits = bits << 6 | base64_digit(c)?;
+                    }
+                    match count {
+                        0 => return Err(invalid_data("base64 group of only padding")),
+                        1 => return Err(invalid_data("truncated base64 group")),
+                        _ => {},
+                    }
+                    bits <<= 6 * (4 - count);
+                    self.decoded.extend(&bits.to_be_bytes()[1..count]);
+                },
+            }
+        }
+        self.pending.drain(..complete);
+        return Ok(());
+    }
+}
+
+impl Read for DecodingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let length = available.len().min(buf.len());
+        buf[..length].copy_from_slice(&available[..length]);
+        self.consume(length);
+        return Ok(length);
+    }
+}
+
+impl BufRead for DecodingReader {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        while self.position == self.decoded.len() {
+            self.decoded.clear();
+            self.position = 0;
+            let chunk = self.inner.fill_buf()?;
+            if chunk.is_empty() {
+                self.decode_pending(true)?;
+                break;
+            }
+            let length = chunk.len().min(READ_CHUNK);
+            let raw = self.format == InputFormat::Raw;
+            self.pending.extend(chunk[..length].iter().filter(|c| raw || !c.is_ascii_whitespace()));
+            self.inner.consume(length);
+            self.decode_pending(false)?;
+        }
+        return Ok(&self.decoded[self.position..]);
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.decoded.len());
+    }
+}
+
+fn invalid_data(message: &str) -> std::io::Error {
+    return std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Invalid input: {message}"));
+}
+
+fn hex_digit(c: u8) -> std::io::Result<u8> {
+    return match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(invalid_data(&format!("`{}` isn't a hex digit", c.escape_ascii()))),
+    };
+}
+
+fn base64_digit(c: u8) -> std::io::Result<u32> {
+    return match BASE64_ALPHABET.iter().position(|&digit| digit == c) {
+        Some(value) => Ok(value as u32),
+        None => Err(invalid_data(&format!("`{}` isn't a base64 digit", c.escape_ascii()))),
+    };
+}
+
+pub struct EncodingWriter {
+    inner: Box<dyn Write + Send>,
+    format: OutputFormat,
+    pending: Vec<u8>, // Bytes that don't form a complete base64 group yet
+    written: bool, // Whether anything was encoded, the encoded output ends with a newline if so
+}
+
+impl EncodingWriter {
+    pub fn new(inner: Box<dyn Write + Send>, format: OutputFormat) -> Self {
+        return EncodingWriter { inner, format, pending: Vec::new(), written: false };
+    }
+
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        // Writes the padded last group and a newline, called when dropped as well
+        if !self.pending.is_empty() {
+            let mut group = [0; 3];
+            group[..self.pending.len()].copy_from_slice(&self.pending);
+            let mut encoded = encode_base64_group(group);
+            for c in &mut encoded[self.pending.len() + 1..] {
+                *c = b'=';
+            }
+            self.pending.clear();
+            self.inner.write_all(&encoded)?;
+        }
+        if self.written {
+            self.written = false;
+            self.inner.write_all(b"\n")?;
+        }
+        return self.inner.flush();
+    }
+}
+
+fn encode_base64_group(group: [u8; 3]) -> [u8; 4] {
+    let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+    return [18, 12, 6, 0].map(|shift| BASE64_ALPHABET[(bits >> shift & 63) as usize]);
+}
+
+impl Write for EncodingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut encoded: Vec<u8> = Vec::new();
+        match self.format {
+            OutputFormat::Utf8 | OutputFormat::Raw | OutputFormat::Wav | OutputFormat::Pcm => encoded.extend(buf),
+            OutputFormat::Hex => {
+                for c in buf {
+                    encoded.extend(format!("{c:02x}").as_bytes());
+                }
+            },
+            OutputFormat::Base64 => {
+                self.pending.extend(buf);
+                let complete = self.pending.len() - self.pending.len() % 3;
+                for group in self.pending[..complete].chunks(3) {
+                    encoded.extend(encode_base64_group([group[0], group[1], group[2]]));
+                }
+                self.pending.drain(..complete);
+            },
+        }
+        self.inner.write_all(&encoded)?;
+        self.written |= !buf.is_empty() && matches!(self.format, OutputFormat::Hex | OutputFormat::Base64);
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return self.inner.flush();
+    }
+}
+
+impl Drop for EncodingWriter {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NewlineMode {
+    Unchanged, // The bytes as they are
+    Crlf, // CRLF reads as LF, and LF prints as CRLF, for Windows terminals
+    StripCr, // Every CR is dropped, both from the input and the output
+}
+
+pub struct NewlineReader {
+    inner: Box<dyn BufRead + Send>,
+    mode: NewlineMode,
+    pending_cr: bool, // A CR ended the last chunk, it depends on the next byte whether it's kept
+    translated: Vec<u8>,
+    position: usize, // Index of the first unconsumed byte in `translated`
+}
+
+impl NewlineReader {
+    pub fn new(inner: Box<dyn BufRead + Send>, mode: NewlineMode) -> Self {
+        return NewlineReader { inner, mode, pending_cr: false, translated: Vec::new(), position: 0 };
+    }
+}
+
+impl Read for NewlineReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let length = available.len().min(buf.len());
+        buf[..length].copy_from_slice(&available[..length]);
+        self.consume(length);
+        return Ok(length);
+    }
+}
+
+impl BufRead for NewlineReader {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        while self.position == self.translated.len() {
+            self.translated.clear();
+            self.position = 0;
+            let chunk = self.inner.fill_buf()?;
+            if chunk.is_empty() {
+                if std::mem::take(&mut self.pending_cr) {
+                    self.translated.push(b'\r');
+                }
+                break;
+            }
+            let length = chunk.len().min(READ_CHUNK);
+            for &c in &chunk[..length] {
+                if std::mem::take(&mut self.pending_cr) && c != b'\n' {
+                    self.translated.push(b'\r');
+                }
+                match (self.mode, c) {
+                    (NewlineMode::Crlf, b'\r') => self.pending_cr = true,
+                    (NewlineMode::StripCr, b'\r') => {},
+                    _ => self.translated.push(c),
+                }
+            }
+            self.inner.consume(length);
+        }
+        return Ok(&self.translated[self.position..]);
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.translated.len());
+    }
+}
+
+pub struct NewlineWriter {
+    inner: Box<dyn Write + Send>,
+    mode: NewlineMode,
+}
+
+impl NewlineWriter {
+    pub fn new(inner: Box<dyn Write + Send>, mode: NewlineMode) -> Self {
+        return NewlineWriter { inner, mode };
+    }
+}
+
+impl Write for NewlineWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut translated: Vec<u8> = Vec::with_capacity(buf.len());
+        for &c in buf {
+            match (self.mode, c) {
+                (NewlineMode::Crlf, b'\n') => translated.extend(b"\r\n"),
+                (NewlineMode::StripCr, b'\r') => {},
+                _ => translated.push(c),
+            }
+        }
+        self.inner.write_all(&translated)?;
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return self.inner.flush();
+    }
+}
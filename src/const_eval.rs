@@ -0,0 +1,87 @@
+use crate::{
+    BF_OPCODE_BLOCK_BEGIN,
+    BF_OPCODE_BLOCK_END,
+    BF_OPCODE_DECREMENT_VALUE,
+    BF_OPCODE_INCREMENT_VALUE,
+    BF_OPCODE_INPUT,
+    BF_OPCODE_PRINT,
+    BF_OPCODE_SHIFT_LEFT,
+    BF_OPCODE_SHIFT_RIGHT,
+};
+
+/*
+ * A minimal interpreter that works in `const` contexts, so the output of simple programs
+ * can be computed while the Rust code is compiled: `const GREETING: [u8; 2] = run_const(b"...", b"");`
+ * It matches brackets by searching, has a fixed-size tape with the cursor starting in the middle,
+ * and ignores the `$` extension. Problems panic, which makes the constant fail to compile.
+ */
+
+pub const CONST_TAPE_SIZE: usize = 1 << 16;
+
+pub const fn run_const<const N: usize>(code: &[u8], input: &[u8]) -> [u8; N] {
+    // The program has to print exactly `N` bytes; it reads 0 after the input ends
+    let mut tape = [0u8; CONST_TAPE_SIZE];
+    let mut cursor = CONST_TAPE_SIZE / 2;
+    let mut output = [0u8; N];
+    let mut printed = 0;
+    let mut read = 0;
+    let mut ip = 0;
+    while ip < code.len() {
+        match code[ip] {
+            BF_OPCODE_INCREMENT_VALUE => tape[cursor] = tape[cursor].wrapping_add(1),
+            BF_OPCODE_DECREMENT_VALUE => tape[cursor] = tape[cursor].wrapping_sub(1),
+            BF_OPCODE_SHIFT_RIGHT => {
+                if cursor + 1 == CONST_TAPE_SIZE {
+                    panic!("The cursor left the tape of `run_const` on the right");
+                }
+                cursor += 1;
+            },
+            BF_OPCODE_SHIFT_LEFT => {
+                if cursor == 0 {
+                    panic!("The cursor left the tape of `run_const` on the left");
+                }
+                cursor -= 1;
+            },
+            BF_OPCODE_PRINT => {
+                if printed == N {
+                    panic!("The program printed more bytes than the output can hold");
+                }
+                output[printed] = tape[cursor];
+                printed += 1;
+            },
+            BF_OPCODE_INPUT => {
+                tape[cursor] = if read < input.len() { input[read] } else { 0 };
+                read += 1;
+            },
+            BF_OPCODE_BLOCK_BEGIN if tape[cursor] == 0 => ip = matching_bracket(code, ip),
+            BF_OPCODE_BLOCK_END if tape[cursor] != 0 => ip = matching_bracket(code, ip),
+            _ => {},
+        }
+        ip += 1;
+    }
+    if printed != N {
+        panic!("The program printed fewer bytes than the output has to hold");
+    }
+    return output;
+}
+
+const fn matching_bracket(code: &[u8], position: usize) -> usize {
+    let forward = code[position] == BF_OPCODE_BLOCK_BEGIN;
+    let mut depth: usize = 0;
+    let mut i = position;
+    loop {
+        if code[i] == BF_OPCODE_BLOCK_BEGIN {
+            if forward { depth += 1; } else { depth -= 1; }
+        }
+        else if code[i] == BF_OPCODE_BLOCK_END {
+            if forward { depth -= 1; } else { depth += 1; }
+        }
+        if depth == 0 {
+            return i;
+        }
+        if (forward && i + 1 == code.len()) || (!forward && i == 0) {
+            panic!("Unmatched bracket in the code given to `run_const`");
+        }
+        i = if forward { i + 1 } else { i - 1 };
+    }
+}
@@ -0,0 +1,759 @@
+use crate::audit::{AuditLog, AuditRecord};
+use brainfuckers::*;
+
+use std::collections::HashMap;
+use std::io::{BufRead, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/*
+ * `bf daemon [--socket <path>] [--quotas <file>] [--audit-log <file>]`:
+ * keeps named sessions alive for editor plugins and notebooks.
+ * Clients connect to a Unix socket and send one command per line, each answered with one line:
+ * `ok [...]` or `error <message>`. Input and output are hex encoded, so any byte can go through.
+ *
+ * create <name> <code>     starts a session running the rest of the line (replacing one with the same name)
+ * input <name> <hex>       queues input for the session
+ * step <name> [<n>]        runs up to n instructions (1 by default), pausing early when the program waits for input:
+ *                          `ok running <steps>`, `ok waiting <steps>` or `ok stopped <steps> <outcome>`
+ * output <name>            returns the output printed since the last `output`: `ok <hex>`
+ * inspect <name>           `ok <cursor> <source position> <hex of the cells cursor-8..=cursor+8>`
+ * list                     `ok <names...>`, the sessions of the client
+ * metrics                  `ok <hex>`, the runs of all the clients that ended, in the Prometheus text format (`Metrics`)
+ * close <name>             ends the session
+ * login <client> <token>   makes the connection count as that client of the quota file
+ *
+ * Sessions belong to the client that created them: they're shared between that client's connections, and the other
+ * clients can't see them, nor create a session with the same name. They live until closed or until the daemon stops.
+ * Sessions running the same code share its compiled program, found through its digest.
+ *
+ * With `--quotas <file>`, every client gets a budget, so the socket can be opened to untrusted users:
+ *
+ * [default]                # connections that didn't log in, all together
+ * concurrent-runs = 1      # `step` commands running at once
+ * cpu-ms-per-minute = 2000 # time spent stepping, per minute
+ * memory-cells = 100000    # tape cells and buffered I/O bytes of all the sessions the client created
+ *
+ * [clients.alice]
+ * token = "..."
+ * concurrent-runs = 4
+ *
+ * Missing limits are unlimited. A request over budget is rejected with `error quota exceeded: <limit>`
+ * (with `, retry in <n> s` for the time budget), and a `step` running out of time answers `ok running` early.
+ * Every session counts as `SESSION_CELLS` cells on top of its tape, queued input and unread output (a byte each),
+ * for its program and bookkeeping, so `create` and `input` are rejected once the client's sessions would go over
+ * `memory-cells`; sessions reaching the budget while running stop with `TapeLimitExceeded`, or answer `ok running`
+ * early when printing would go over it, until their output is read. A running `step` holds all the memory its
+ * client had left until it returns, so `create`s and steps on the client's other sessions meanwhile only get what
+ * it doesn't. Commands longer than `MAX_LINE` bytes end the connection.
+ *
+ * With `--audit-log <file>`, a record of every session is appended to the file when it ends (see `audit.rs`).
+ * Stopping the daemon with Ctrl-C ends the sessions still running as `shutdown`, so they're logged too; a daemon
+ * killed by any other signal exits without logging them.
+ */
+
+const DEFAULT_SOCKET: &str = "/tmp/brainfuckers.sock";
+const INSPECT_RADIUS: isize = 8; // Cells `inspect` returns on each side of the cursor
+const MAX_STEPS_PER_REQUEST: usize = 10_000_000; // Keeps one request from holding the session forever
+const DEFAULT_CLIENT: &str = "default"; // Connections that didn't log in
+const QUOTA_WINDOW: Duration = Duration::from_secs(60); // Period of `cpu-ms-per-minute`
+const BUDGET_CHECK_INTERVAL: usize = 4096; // Steps between checks of the time left
+const SESSION_CELLS: usize = 1024; // What a session counts against `memory-cells` besides its tape
+const MAX_LINE: usize = 1 << 20; // Longest command in bytes, without its newline
+
+#[derive(Clone, Default)]
+struct SharedOutput(Arc<Mutex<Vec<u8>>>); // Output of a session, drained by `output`
+
+impl Write for SharedOutput {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        return Ok(bytes.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return Ok(());
+    }
+}
+
+struct Session {
+    state: BFState,
+    output: SharedOutput,
+    steps: u64, // Run so far
+    cpu: Duration, // Time spent stepping so far
+    outcome: Option<String>, // Why the program ended, stepping does nothing anymore once set
+}
+
+struct Entry {
+    session: Arc<Mutex<Session>>, // Locked while a command uses it, without holding the other sessions
+    program: Arc<Program>, // Shared with new sessions running the same code
+    owner: String, // Client that created it, its tape counts against that client's memory
+    cells: usize, // Its `footprint` as of its last command, or all it may use while a `step` runs
+}
+
+#[derive(Clone, Debug, Default)]
+struct Quota {
+    concurrent_runs: Option<usize>,
+    cpu_ms_per_minute: Option<u64>,
+    memory_cells: Option<usize>,
+}
+
+struct Client {
+    token: Option<String>, // `None` for the default client, which doesn't log in
+    quota: Quota,
+    running: usize, // `step` commands running right now
+    window_start: Instant,
+    used: Duration, // Time spent stepping since `window_start`
+}
+
+impl Client {
+    fn new(token: Option<String>, quota: Quota) -> Self {
+        return Client { token, quota, running: 0, window_start: Instant::now(), used: Duration::ZERO };
+    }
+
+    fn time_left(&mut self) -> Result<Option<Duration>, String> {
+        // Stepping time left in the current minute, `None` without a budget
+        let Some(budget) = self.quota.cpu_ms_per_minute.map(Duration::from_millis) else {
+            return Ok(None);
+        };
+        if self.window_start.elapsed() >= QUOTA_WINDOW {
+            self.window_start = Instant::now();
+            self.used = Duration::ZERO;
+        }
+        if self.used >= budget {
+            let retry = QUOTA_WINDOW.saturating_sub(self.window_start.elapsed()).as_secs() + 1;
+            return Err(format!("quota exceeded: cpu-ms-per-minute, retry in {retry} s"));
+        }
+        return Ok(Some(budget - self.used));
+    }
+}
+
+struct Daemon {
+    sessions: HashMap<String, Entry>,
+    clients: HashMap<String, Client>, // The default client included
+    audit: Option<Arc<AuditLog>>,
+    metrics: Arc<Metrics>, // Recorded when a session's program ends, read without the daemon's lock
+}
+
+impl Daemon {
+    fn session(&self, client: &str, name: &str) -> Result<&Entry, String> {
+        // The client's session, the ones of other clients are hidden
+        return self.sessions.get(name).filter(|entry| entry.owner == client).ok_or_else(|| format!("no session named `{name}`"));
+    }
+
+    fn detach(&mut self, client: &str, name: &str, replacement: Option<Entry>) -> Result<Option<Ended>, String> {
+        /*
+         * Removes the client's session, or puts `replacement` in its place, and returns the one taken out
+         * to be logged with `log_ended`. Replacing a session that doesn't exist just adds the new one.
+         */
+
+        let replacing = replacement.is_some();
+        match self.sessions.get(name) {
+            Some(entry) if entry.owner != client && replacing => return Err(format!("the name `{name}` is taken by another client")),
+            Some(entry) if entry.owner != client => return Err(format!("no session named `{name}`")),
+            None if !replacing => return Err(format!("no session named `{name}`")),
+            _ => {},
+        }
+        let entry = match replacement {
+            Some(replacement) => self.sessions.insert(String::from(name), replacement),
+            None => self.sessions.remove(name),
+        };
+        return Ok(entry.map(|entry| {
+            let quota = self.clients[&entry.owner].quota.clone();
+            (entry, quota, self.audit.clone())
+        }));
+    }
+
+    fn entry_of(&mut self, name: &str, session: &Arc<Mutex<Session>>) -> Option<&mut Entry> {
+        // The entry of the session, unless it was closed or replaced in the meantime
+        return self.sessions.get_mut(name).filter(|entry| Arc::ptr_eq(&entry.session, session));
+    }
+
+    fn memory_used(&self, owner: &str, except: Option<&str>) -> usize {
+        // Tape cells of the client's sessions but `except`, and the fixed cost of each
+        return self.sessions.iter()
+            .filter(|(name, entry)| entry.owner == owner && Some(name.as_str()) != except)
+            .map(|(_, entry)| SESSION_CELLS + entry.cells)
+            .sum();
+    }
+}
+
+type Shared = Arc<Mutex<Daemon>>;
+type Ended = (Entry, Quota, Option<Arc<AuditLog>>); // A session taken out of the daemon, with what logging it needs
+
+fn log_session(audit: Option<&AuditLog>, name: &str, owner: &str, quota: &Quota, session: &Session, outcome: &str) {
+    let Some(audit) = audit else {
+        return;
+    };
+    audit.append(&AuditRecord {
+        client: owner,
+        session: name,
+        program: program(&session.state).digest(),
+        limits: [
+            ("concurrent-runs", quota.concurrent_runs.map(|limit| limit as u64)),
+            ("cpu-ms-per-minute", quota.cpu_ms_per_minute),
+            ("memory-cells", quota.memory_cells.map(|limit| limit as u64)),
+        ],
+        outcome,
+        steps: session.steps,
+        cpu_ms: session.cpu.as_millis() as u64,
+        cells: tape_stats(&session.state).cells(),
+    });
+}
+
+fn end_session(shared: &Shared, client: &str, name: &str, outcome: &str, replacement: Option<Entry>) -> Result<(), String> {
+    // Removes the client's session, or puts `replacement` in its place, see `Daemon::detach`
+    let ended = shared.lock().unwrap().detach(client, name, replacement)?;
+    log_ended(ended, name, outcome);
+    return Ok(());
+}
+
+fn log_ended(ended: Option<Ended>, name: &str, outcome: &str) {
+    // Logs a session taken out of the daemon unless it already ended, its lock is taken after the daemon's is released
+    let Some((entry, quota, audit)) = ended else {
+        return;
+    };
+    let session = entry.session.lock().unwrap();
+    if session.outcome.is_none() {
+        log_session(audit.as_deref(), name, &entry.owner, &quota, &session, outcome);
+    }
+}
+
+fn shut_down(shared: &Shared) {
+    // Logs the sessions that didn't end yet, waiting for the steps running on them
+    let (sessions, quotas, audit) = {
+        let mut daemon = shared.lock().unwrap();
+        let sessions = std::mem::take(&mut daemon.sessions);
+        let quotas: HashMap<String, Quota> = daemon.clients.iter().map(|(name, client)| (name.clone(), client.quota.clone())).collect();
+        (sessions, quotas, daemon.audit.clone())
+    };
+    for (name, entry) in sessions {
+        let session = entry.session.lock().unwrap();
+        if session.outcome.is_none() {
+            log_session(audit.as_deref(), &name, &entry.owner, &quotas[&entry.owner], &session, "shutdown");
+        }
+    }
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    return bytes.iter().map(|c| format!("{c:02x}")).collect();
+}
+
+fn decode_hex(text: &str) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(2) {
+        return Err(String::from("odd number of hex digits"));
+    }
+    // Works on bytes, slicing the text could cut a multi-byte character
+    return text.as_bytes().chunks(2)
+        .map(|pair| {
+            if !pair.iter().all(u8::is_ascii_hexdigit) {
+                return Err(format!("invalid hex `{}`", String::from_utf8_lossy(pair)));
+            }
+            return Ok(u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap());
+        })
+        .collect();
+}
+
+fn new_session(code: &str, sessions: &HashMap<String, Entry>) -> Result<Session, String> {
+    check_syntax(code.as_bytes()).map_err(|error| error.to_string())?;
+    let output = SharedOutput::default();
+    let compiled = new_program(code);
+    // Comments are compared too, source positions in `inspect` depend on them
+    let shared = sessions.values()
+        .map(|entry| entry.program.clone())
+        .find(|program| program.digest() == compiled.digest() && program.code() == compiled.code());
+    let mut state = new_bf_state_from_program(shared.unwrap_or(compiled));
+    // Input only comes from `input` commands, `step` pauses instead of reading past it
+    set_input_reader(&mut state, Box::new(std::io::empty()));
+    set_output_writer(&mut state, Box::new(output.clone()));
+    set_raw_output(&mut state, true);
+    return Ok(Session { state, output, steps: 0, cpu: Duration::ZERO, outcome: None });
+}
+
+fn footprint(session: &Session) -> usize {
+    // What the session counts against `memory-cells` besides `SESSION_CELLS`: its tape, queued input and unread output
+    return tape_stats(&session.state).cells() + pending_input(&session.state) + session.output.0.lock().unwrap().len();
+}
+
+fn step_session(session: &mut Session, n: usize, time_left: Option<Duration>, memory: Option<usize>, metrics: &Metrics) -> String {
+    /*
+     * Stops early, still `running`, when the client's time budget runs out or when printing would take the session
+     * past `memory`. The tape gets what the input and output leave of `memory`, less with every byte printed.
+     * A program that ends is recorded in `metrics` as one run, of the steps and time of all its `step`s.
+     */
+
+    // An ended session answers with how it ended, every time it's stepped again
+    if let Some(outcome) = &session.outcome {
+        return match outcome.strip_prefix("error: ") {
+            Some(error) => format!("error {error}"),
+            None => format!("ok stopped 0 {outcome}"),
+        };
+    }
+    // Cells the tape can use once `printing` more bytes are buffered
+    let tape_limit = |session: &Session, printing: usize| {
+        let buffered = pending_input(&session.state) + session.output.0.lock().unwrap().len() + printing;
+        return memory.map(|memory| memory.saturating_sub(buffered));
+    };
+    let limit = tape_limit(session, 0);
+    set_max_tape_cells(&mut session.state, limit);
+    let started = Instant::now();
+    let mut steps = 0;
+    let mut ended = None;
+    let response = loop {
+        if steps >= n {
+            break format!("ok running {steps}");
+        }
+        if next_op(&session.state) == Some(Op::Input) && pending_input(&session.state) == 0 {
+            break format!("ok waiting {steps}");
+        }
+        if steps % BUDGET_CHECK_INTERVAL == 0 && time_left.is_some_and(|time_left| started.elapsed() >= time_left) {
+            break format!("ok running {steps}");
+        }
+        if let Some(memory) = memory
+            && next_op(&session.state) == Some(Op::Print) {
+            if footprint(session) >= memory {
+                break format!("ok running {steps}");
+            }
+            let limit = tape_limit(session, 1);
+            set_max_tape_cells(&mut session.state, limit);
+        }
+        match step_n(&mut session.state, 1) {
+            StepBatchResult::Running(executed) => steps += executed,
+            StepBatchResult::Stopped(executed, outcome) => {
+                steps += executed;
+                session.outcome = Some(format!("{outcome:?}"));
+                let response = format!("ok stopped {steps} {outcome:?}");
+                ended = Some(Ok(outcome));
+                break response;
+            },
+            StepBatchResult::Failed(executed, error) => {
+                steps += executed;
+                session.outcome = Some(format!("error: {error}"));
+                let response = format!("error {error}");
+                ended = Some(Err(error));
+                break response;
+            },
+        }
+    };
+    session.steps += steps as u64;
+    session.cpu += started.elapsed();
+    if let Some(result) = ended {
+        let report = result.map(|outcome| RunReport {
+            outcome,
+            steps: session.steps,
+            output: Vec::new(), // Sent to `output` commands instead
+            duration: session.cpu,
+            final_cursor: cursor_position(&session.state),
+            tape_extent: tape_extent(&session.state),
+        });
+        metrics.record(&session.state, &report);
+    }
+    return response;
+}
+
+fn step(shared: &Shared, client_name: &str, name: &str, n: usize) -> Result<String, String> {
+    // Takes a run slot and the time left from the client, and gives back what wasn't used
+    let (session, owner, quota, audit, metrics, time_left) = {
+        let mut daemon = shared.lock().unwrap();
+        let entry = daemon.session(client_name, name)?;
+        let (session, owner) = (entry.session.clone(), entry.owner.clone());
+        let quota = daemon.clients[&owner].quota.clone();
+        let (audit, metrics) = (daemon.audit.clone(), daemon.metrics.clone());
+        let client = daemon.clients.get_mut(client_name).unwrap();
+        if client.quota.concurrent_runs.is_some_and(|limit| client.running >= limit) {
+            return Err(String::from("quota exceeded: concurrent-runs"));
+        }
+        let time_left = client.time_left()?;
+        client.running += 1;
+        (session, owner, quota, audit, metrics, time_left)
+    };
+
+    /*
+     * Waiting for a command already running on the session isn't charged, only the time holding it.
+     * While the step runs, the session counts as all the memory the client had left, so steps running
+     * on its other sessions at the same time can't use it too. The daemon's lock is taken while holding
+     * the session's, never the other way around.
+     */
+    let (response, used) = {
+        let mut guard = session.lock().unwrap();
+        let started = Instant::now();
+        let memory = quota.memory_cells.map(|limit| {
+            let mut daemon = shared.lock().unwrap();
+            let memory = limit.saturating_sub(daemon.memory_used(&owner, Some(name)) + SESSION_CELLS);
+            let used = footprint(&guard);
+            if let Some(entry) = daemon.entry_of(name, &session) {
+                entry.cells = memory.max(used);
+            }
+            memory
+        });
+        let running = guard.outcome.is_none();
+        let response = step_session(&mut guard, n, time_left, memory, &metrics);
+        if running && let Some(outcome) = &guard.outcome {
+            log_session(audit.as_deref(), name, &owner, &quota, &guard, outcome);
+        }
+        // Gives back what the session didn't use
+        if let Some(entry) = shared.lock().unwrap().entry_of(name, &session) {
+            entry.cells = footprint(&guard);
+        }
+        (response, started.elapsed())
+    };
+
+    let mut daemon = shared.lock().unwrap();
+    let client = daemon.clients.get_mut(client_name).unwrap();
+    client.running -= 1;
+    client.used += used;
+    return Ok(response);
+}
+
+fn handle_command(shared: &Shared, client: &mut String, line: &str) -> Result<String, String> {
+    let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let (name, argument) = rest.split_once(' ').unwrap_or((rest, ""));
+    if command == "list" {
+        let daemon = shared.lock().unwrap();
+        let mut names: Vec<&str> = daemon.sessions.iter()
+            .filter(|(_, entry)| entry.owner == *client)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        names.sort();
+        return Ok(format!("ok {}", names.join(" ")).trim_end().to_string());
+    }
+    if command == "metrics" {
+        let metrics = shared.lock().unwrap().metrics.clone();
+        return Ok(format!("ok {}", encode_hex(metrics.render().as_bytes())));
+    }
+    if name.is_empty() {
+        return Err(format!("`{command}` needs a session name"));
+    }
+    if command == "login" {
+        let daemon = shared.lock().unwrap();
+        let known = daemon.clients.get(name).and_then(|client| client.token.as_deref());
+        if known.is_none_or(|token| !crate::constant_time_eq(token.as_bytes(), argument.as_bytes())) {
+            return Err(String::from("unknown client or wrong token"));
+        }
+        *client = String::from(name);
+        return Ok(String::from("ok"));
+    }
+    if command == "step" {
+        let n = if argument.is_empty() { Ok(1) } else { argument.parse::<usize>() };
+        let n = n.map_err(|_| format!("invalid number of steps `{argument}`"))?;
+        return step(shared, client, name, n.min(MAX_STEPS_PER_REQUEST));
+    }
+    if command == "close" {
+        end_session(shared, client, name, "closed", None)?;
+        return Ok(String::from("ok"));
+    }
+    if command == "create" {
+        /*
+         * The session it replaces is only ended once the new one is ready, a failing `create` keeps it.
+         * The quota is checked in the same critical section as the insertion, so concurrent `create`s can't both fit.
+         */
+        let ended = {
+            let mut daemon = shared.lock().unwrap();
+            let needed = daemon.memory_used(client, Some(name)) + SESSION_CELLS;
+            if daemon.clients[client.as_str()].quota.memory_cells.is_some_and(|limit| needed > limit) {
+                return Err(String::from("quota exceeded: memory-cells"));
+            }
+            let session = new_session(argument, &daemon.sessions)?;
+            let program = program(&session.state);
+            let entry = Entry { session: Arc::new(Mutex::new(session)), program, owner: client.clone(), cells: 0 };
+            daemon.detach(client, name, Some(entry))?
+        };
+        log_ended(ended, name, "replaced");
+        return Ok(String::from("ok"));
+    }
+    let session = shared.lock().unwrap().session(client, name)?.session.clone();
+    let mut guard = session.lock().unwrap();
+    return match command {
+        "input" => {
+            // Queued input counts against the memory quota until the program reads it
+            let input = decode_hex(argument)?;
+            let mut daemon = shared.lock().unwrap();
+            let needed = daemon.memory_used(client, Some(name)) + SESSION_CELLS + footprint(&guard) + input.len();
+            if daemon.clients[client.as_str()].quota.memory_cells.is_some_and(|limit| needed > limit) {
+                return Err(String::from("quota exceeded: memory-cells"));
+            }
+            push_input(&mut guard.state, &input);
+            if let Some(entry) = daemon.entry_of(name, &session) {
+                entry.cells = footprint(&guard);
+            }
+            Ok(String::from("ok"))
+        },
+        "output" => {
+            let output = std::mem::take(&mut *guard.output.0.lock().unwrap());
+            if let Some(entry) = shared.lock().unwrap().entry_of(name, &session) {
+                entry.cells = footprint(&guard);
+            }
+            Ok(format!("ok {}", encode_hex(&output)).trim_end().to_string())
+        },
+        "inspect" => {
+            let cursor = cursor_position(&guard.state);
+            let cells = export_tape(&guard.state, cursor - INSPECT_RADIUS..cursor + INSPECT_RADIUS + 1);
+            Ok(format!("ok {cursor} {} {}", source_position(&guard.state), encode_hex(&cells)))
+        },
+        _ => Err(format!("unknown command `{command}`")),
+    };
+}
+
+fn serve(shared: Shared, stream: UnixStream) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut client = String::from(DEFAULT_CLIENT);
+    let mut reader = std::io::BufReader::new(stream);
+    let mut line = Vec::new();
+    loop {
+        // A line can't grow past `MAX_LINE`, the connection ends instead
+        line.clear();
+        if (&mut reader).take(MAX_LINE as u64 + 1).read_until(b'\n', &mut line)? == 0 {
+            return Ok(());
+        }
+        if line.last() != Some(&b'\n') && line.len() > MAX_LINE {
+            writeln!(writer, "error command longer than {MAX_LINE} bytes")?;
+            return Ok(());
+        }
+        let line = std::str::from_utf8(&line).map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        let line = line.trim_end_matches('\n').trim_end_matches('\r');
+        if line.is_empty() {
+            continue;
+        }
+        let response = handle_command(&shared, &mut client, line).unwrap_or_else(|message| format!("error {message}"));
+        writeln!(writer, "{response}")?;
+    }
+}
+
+fn read_quotas(path: &str) -> Result<HashMap<String, Client>, String> {
+    // The default client and the ones of `[clients.<name>]`, each with its `token`
+    let invalid = |message: &str| format!("Invalid quota file `{path}`: {message}");
+    let text = std::fs::read_to_string(path).map_err(|error| format!("Failed to read file `{path}` {error}"))?;
+    let table: toml::Table = text.parse().map_err(|error: toml::de::Error| invalid(error.message()))?;
+    let quota = |table: &toml::Table| -> Result<Quota, String> {
+        let limit = |key: &str| -> Result<Option<u64>, String> {
+            return match table.get(key) {
+                None => Ok(None),
+                Some(value) => value.as_integer().and_then(|value| u64::try_from(value).ok()).map(Some)
+                    .ok_or_else(|| invalid(&format!("`{key}` has to be a non-negative integer"))),
+            };
+        };
+        return Ok(Quota {
+            concurrent_runs: limit("concurrent-runs")?.map(|limit| limit as usize),
+            cpu_ms_per_minute: limit("cpu-ms-per-minute")?,
+            memory_cells: limit("memory-cells")?.map(|limit| limit as usize),
+        });
+    };
+
+    let empty = toml::Table::new();
+    let mut clients = HashMap::new();
+    let default = match table.get("default") {
+        Some(default) => default.as_table().ok_or_else(|| invalid("`default` has to be a table"))?,
+        None => &empty,
+    };
+    clients.insert(String::from(DEFAULT_CLIENT), Client::new(None, quota(default)?));
+    let named = match table.get("clients") {
+        Some(named) => named.as_table().ok_or_else(|| invalid("`clients` has to be a table"))?,
+        None => &empty,
+    };
+    for (name, client) in named {
+        let client = client.as_table().ok_or_else(|| invalid(&format!("`clients.{name}` has to be a table")))?;
+        let token = client.get("token").and_then(toml::Value::as_str)
+            .ok_or_else(|| invalid(&format!("`clients.{name}` needs a `token`")))?;
+        if name == DEFAULT_CLIENT {
+            return Err(invalid(&format!("`{DEFAULT_CLIENT}` can't be the name of a client")));
+        }
+        clients.insert(name.clone(), Client::new(Some(String::from(token)), quota(client)?));
+    }
+    return Ok(clients);
+}
+
+pub fn daemon(args: &[String]) -> Result<(), String> {
+    let mut path = DEFAULT_SOCKET;
+    let mut clients = HashMap::from([(String::from(DEFAULT_CLIENT), Client::new(None, Quota::default()))]);
+    let mut audit = None;
+    let mut i = 0;
+    while i < args.len() {
+        match (args[i].as_str(), args.get(i + 1)) {
+            ("--socket", Some(value)) => path = value,
+            ("--quotas", Some(value)) => clients = read_quotas(value)?,
+            ("--audit-log", Some(value)) => audit = Some(Arc::new(AuditLog::open(value)?)),
+            _ => return Err(String::from("Usage: bf daemon [--socket <path>] [--quotas <file>] [--audit-log <file>]")),
+        }
+        i += 2;
+    }
+    // A socket left over from a previous daemon would make binding fail, anything else at the path is left alone
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(format!("`{path}` exists and isn't a socket"));
+        }
+        if UnixStream::connect(path).is_err() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+    let listener = UnixListener::bind(path).map_err(|error| format!("Failed to listen on `{path}` {error}"))?;
+    eprintln!("Listening on {path}");
+
+    let shared: Shared = Arc::new(Mutex::new(Daemon { sessions: HashMap::new(), clients, audit, metrics: Arc::new(Metrics::new()) }));
+    let (handler_shared, socket) = (shared.clone(), String::from(path));
+    if let Err(error) = ctrlc::set_handler(move || {
+        shut_down(&handler_shared);
+        let _ = std::fs::remove_file(&socket);
+        std::process::exit(crate::EXIT_INTERRUPTED.into());
+    }) {
+        eprintln!("warning: failed to install the Ctrl-C handler, sessions still running won't be logged on exit: {error}");
+    }
+    for stream in listener.incoming() {
+        let stream = stream.map_err(|error| error.to_string())?;
+        let shared = shared.clone();
+        std::thread::spawn(move || {
+            if let Err(error) = serve(shared, stream) {
+                eprintln!("Connection failed: {error}");
+            }
+        });
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    struct Connection {
+        reader: BufReader<UnixStream>,
+        writer: UnixStream,
+    }
+
+    impl Connection {
+        fn open(shared: &Shared) -> Self {
+            // One end of a socket pair served like an accepted connection
+            let (client, server) = UnixStream::pair().unwrap();
+            let shared = shared.clone();
+            std::thread::spawn(move || serve(shared, server));
+            return Connection { reader: BufReader::new(client.try_clone().unwrap()), writer: client };
+        }
+
+        fn send(&mut self, command: &str) -> String {
+            writeln!(self.writer, "{command}").unwrap();
+            let mut response = String::new();
+            self.reader.read_line(&mut response).unwrap();
+            return String::from(response.trim_end());
+        }
+    }
+
+    fn daemon(quota: Quota) -> Shared {
+        let clients = HashMap::from([(String::from(DEFAULT_CLIENT), Client::new(None, quota))]);
+        return Arc::new(Mutex::new(Daemon { sessions: HashMap::new(), clients, audit: None, metrics: Arc::new(Metrics::new()) }));
+    }
+
+    #[test]
+    fn sessions_count_against_the_memory_quota() {
+        let shared = daemon(Quota { memory_cells: Some(2 * SESSION_CELLS + 100), ..Quota::default() });
+        let mut connection = Connection::open(&shared);
+        assert_eq!(connection.send("create a +"), "ok");
+        assert_eq!(connection.send("create b +"), "ok");
+        assert_eq!(connection.send("create c +"), "error quota exceeded: memory-cells");
+        // Replacing a session doesn't count it twice
+        assert_eq!(connection.send("create b ++"), "ok");
+        assert_eq!(connection.send("close a"), "ok");
+        assert_eq!(connection.send("create c +"), "ok");
+        assert_eq!(connection.send("list"), "ok b c");
+    }
+
+    #[test]
+    fn concurrent_creates_dont_go_over_the_memory_quota() {
+        let shared = daemon(Quota { memory_cells: Some(2 * SESSION_CELLS), ..Quota::default() });
+        let creates: Vec<_> = (0..8)
+            .map(|i| {
+                let mut connection = Connection::open(&shared);
+                std::thread::spawn(move || connection.send(&format!("create s{i} +")))
+            })
+            .collect();
+        let created = creates.into_iter().map(|create| create.join().unwrap()).filter(|response| response == "ok").count();
+        assert_eq!(created, 2);
+        assert_eq!(shared.lock().unwrap().sessions.len(), 2);
+    }
+
+    #[test]
+    fn tapes_stop_at_what_the_memory_quota_leaves() {
+        let shared = daemon(Quota { memory_cells: Some(SESSION_CELLS + 100), ..Quota::default() });
+        let mut connection = Connection::open(&shared);
+        assert_eq!(connection.send("create a +[>+]"), "ok");
+        let response = connection.send("step a 100000");
+        assert!(response.starts_with("ok stopped ") && response.ends_with(" TapeLimitExceeded"), "{response}");
+        assert_eq!(connection.send("create b +"), "error quota exceeded: memory-cells");
+        // Stepping it again repeats how it ended
+        assert_eq!(connection.send("step a"), "ok stopped 0 TapeLimitExceeded");
+    }
+
+    #[test]
+    fn running_steps_hold_the_memory_they_may_use() {
+        let shared = daemon(Quota { memory_cells: Some(2 * SESSION_CELLS + 100), ..Quota::default() });
+        let mut connection = Connection::open(&shared);
+        assert_eq!(connection.send("create a +[]"), "ok");
+        let mut running = Connection::open(&shared);
+        let step = std::thread::spawn(move || running.send(&format!("step a {MAX_STEPS_PER_REQUEST}")));
+        while shared.lock().unwrap().sessions["a"].cells == 0 {
+            assert!(!step.is_finished(), "the step ended before it was seen running");
+            std::thread::yield_now();
+        }
+        assert_eq!(connection.send("create b +"), "error quota exceeded: memory-cells");
+        assert_eq!(step.join().unwrap(), format!("ok running {MAX_STEPS_PER_REQUEST}"));
+        assert_eq!(connection.send("create b +"), "ok");
+    }
+
+    #[test]
+    fn input_and_output_count_against_the_memory_quota() {
+        let shared = daemon(Quota { memory_cells: Some(SESSION_CELLS + 100), ..Quota::default() });
+        let mut connection = Connection::open(&shared);
+        assert_eq!(connection.send("create a ,[.]"), "ok");
+        assert_eq!(connection.send(&format!("input a {}", "01".repeat(100))), "error quota exceeded: memory-cells");
+        assert_eq!(connection.send("input a 01"), "ok");
+        // Printing stops once the tape and the output fill the quota, until the output is read
+        let response = connection.send("step a 100000");
+        assert!(response.starts_with("ok running "), "{response}");
+        assert_eq!(connection.send("step a 100000"), "ok running 0");
+        assert_eq!(connection.send("output a"), format!("ok {}", "01".repeat(99)));
+        let response = connection.send("step a 10");
+        assert!(response.starts_with("ok running ") && response != "ok running 0", "{response}");
+    }
+
+    #[test]
+    fn steps_over_the_time_or_run_quota_are_rejected() {
+        let shared = daemon(Quota { cpu_ms_per_minute: Some(0), ..Quota::default() });
+        let mut connection = Connection::open(&shared);
+        assert_eq!(connection.send("create a +"), "ok");
+        let response = connection.send("step a");
+        assert!(response.starts_with("error quota exceeded: cpu-ms-per-minute, retry in "), "{response}");
+
+        let shared = daemon(Quota { concurrent_runs: Some(0), ..Quota::default() });
+        let mut connection = Connection::open(&shared);
+        assert_eq!(connection.send("create a +"), "ok");
+        assert_eq!(connection.send("step a"), "error quota exceeded: concurrent-runs");
+    }
+
+    #[test]
+    fn ended_programs_are_recorded_in_the_metrics() {
+        let shared = daemon(Quota::default());
+        let mut connection = Connection::open(&shared);
+        assert_eq!(connection.send("create a +."), "ok");
+        assert_eq!(connection.send("create b +[]"), "ok");
+        assert_eq!(connection.send("step a 10"), "ok stopped 2 Halted");
+        assert_eq!(connection.send("step b 10"), "ok running 10");
+        let response = connection.send("metrics");
+        let metrics = String::from_utf8(decode_hex(response.strip_prefix("ok ").unwrap()).unwrap()).unwrap();
+        assert!(metrics.contains("\nbf_runs_total 1\n"), "{metrics}");
+        assert!(metrics.contains("\nbf_output_bytes_total 1\n"), "{metrics}");
+        assert!(metrics.contains("\nbf_run_duration_seconds_count 1\n"), "{metrics}");
+    }
+
+    #[test]
+    fn long_commands_end_the_connection() {
+        let shared = daemon(Quota::default());
+        let mut connection = Connection::open(&shared);
+        let mut writer = connection.writer.try_clone().unwrap();
+        // The daemon stops reading partway, so the rest of the line may fail to go through
+        std::thread::spawn(move || writer.write_all(format!("create a {}\n", "+".repeat(MAX_LINE)).as_bytes()));
+        let mut response = String::new();
+        connection.reader.read_line(&mut response).unwrap();
+        assert_eq!(response, format!("error command longer than {MAX_LINE} bytes\n"));
+        assert_eq!(connection.reader.read_line(&mut response).unwrap(), 0);
+        assert!(shared.lock().unwrap().sessions.is_empty());
+    }
+}
@@ -0,0 +1,59 @@
+use crate::*;
+
+/*
+ * The `decimal` extension, for teaching arithmetic without converting digits by hand:
+ * `;` prints the current cell as a decimal number (`65` instead of `A`) and `%` reads one into it.
+ * Reading skips everything up to the first digit, then takes digits up to the next byte that isn't one,
+ * which is consumed too (usually the newline or space after the number). Values wrap around like the cells do,
+ * and EOF before any digit reads 0. Both are extension ops (see `extension_ops.rs`), so only the interpreter runs them.
+ */
+
+struct PrintDecimal;
+
+impl ExtensionOp for PrintDecimal {
+    fn character(&self) -> u8 {
+        return b';';
+    }
+
+    fn execute(&mut self, state: &mut BFState) {
+        let value = get_cell(state, cursor_position(state));
+        for digit in value.to_string().bytes() {
+            if !print_byte(state, digit) {
+                return;
+            }
+        }
+    }
+}
+
+struct ReadDecimal;
+
+impl ExtensionOp for ReadDecimal {
+    fn character(&self) -> u8 {
+        return b'%';
+    }
+
+    fn execute(&mut self, state: &mut BFState) {
+        if !input_allowed(state) {
+            return;
+        }
+        let mut value: u8 = 0;
+        let mut digits = false;
+        while let Some(c) = read_byte(state) {
+            if c.is_ascii_digit() {
+                value = value.wrapping_mul(10).wrapping_add(c - b'0');
+                digits = true;
+            }
+            else if digits {
+                break;
+            }
+        }
+        let cursor = state.cursor_position;
+        set_value_at(state, cursor, value);
+    }
+}
+
+pub fn enable_decimal_extension(state: &mut BFState) {
+    // Enables `;` and `%`, recompiling the code like `register_extension_op`
+    register_extension_op(state, Box::new(PrintDecimal)).unwrap();
+    register_extension_op(state, Box::new(ReadDecimal)).unwrap();
+}
@@ -0,0 +1,347 @@
+use crate::*;
+
+use std::fmt;
+
+/*
+ * Front-ends for the languages of the brainfuck family. A `Dialect` tokenizes its source to operations,
+ * which are turned back into brainfuck code, so everything after that (packages, builds, narrations)
+ * works the same for every dialect. Other crates add their own through a `DialectRegistry`.
+ *
+ * Spoon encodes the commands as a prefix code of bits, anything but `0` and `1` is a comment:
+ * `1` +, `000` -, `010` >, `011` <, `00100` [, `0011` ], `001010` ., `0010110` , and `00101110` (debug, ignored).
+ * `00101111` (exit) has no brainfuck equivalent and is rejected.
+ *
+ * Ook! uses pairs of `Ook.`, `Ook?` and `Ook!` words, anything between them is a comment:
+ * `Ook. Ook?` >, `Ook? Ook.` <, `Ook. Ook.` +, `Ook! Ook!` -, `Ook! Ook.` ., `Ook. Ook!` ,, `Ook! Ook?` [ and `Ook? Ook!` ].
+ * `OokRot13` reads Ook! sources obfuscated with ROT13, whose words are `Bbx.`, `Bbx?` and `Bbx!`. ROT13 only
+ * changes letters, so brainfuck and Spoon sources read the same after it and need no such dialect.
+ *
+ * An `OpcodeMap` is brainfuck with other bytes for the eight commands (keyboard layouts, obfuscated programs),
+ * read from a TOML table like `"+" = "a"` with one byte per command (a one-byte string or an integer).
+ * Each byte stands for one command only, and any other byte is a comment, brainfuck commands included.
+ *
+ * `detect_dialect` guesses the dialect of a source from its signature: `Ook` words with almost no brainfuck commands
+ * besides their dots make it Ook! (`Bbx` words Ook! in ROT13), and `0` and `1` far outnumbering the commands make it
+ * Spoon. The guess has to tokenize too, anything else is brainfuck.
+ */
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DialectError {
+    pub position: usize, // Byte of the source the error is about
+    pub message: String,
+}
+
+impl fmt::Display for DialectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "Syntax error at byte {}: {}", self.position, self.message);
+    }
+}
+
+impl std::error::Error for DialectError {}
+
+impl From<DialectError> for BfError {
+    fn from(error: DialectError) -> Self {
+        return BfError::Syntax { position: error.position, message: error.message };
+    }
+}
+
+pub trait Dialect: Send + Sync {
+    fn name(&self) -> &str; // What `--dialect` and manifests call it
+
+    /*
+     * The operations of the source. Jump targets are worked out again afterwards,
+     * so tokenizers can leave them at 0, but the brackets have to be balanced.
+     */
+    fn tokenize(&self, source: &[u8]) -> Result<Vec<Op>, DialectError>;
+}
+
+pub struct Brainfuck;
+pub struct Spoon;
+pub struct Ook;
+pub struct OokRot13;
+
+impl Dialect for Brainfuck {
+    fn name(&self) -> &str {
+        return "brainfuck";
+    }
+
+    fn tokenize(&self, source: &[u8]) -> Result<Vec<Op>, DialectError> {
+        return commands_to_ops(&source.iter().enumerate().map(|(position, &c)| (c, position)).collect::<Vec<_>>());
+    }
+}
+
+const SPOON_CODES: [(&[u8], Option<u8>); 9] = [
+    (b"1", Some(b'+')),
+    (b"000", Some(b'-')),
+    (b"010", Some(b'>')),
+    (b"011", Some(b'<')),
+    (b"00100", Some(b'[')),
+    (b"0011", Some(b']')),
+    (b"001010", Some(b'.')),
+    (b"0010110", Some(b',')),
+    (b"00101110", None), // Debug
+];
+const SPOON_EXIT: &[u8] = b"00101111";
+
+impl Dialect for Spoon {
+    fn name(&self) -> &str {
+        return "spoon";
+    }
+
+    fn tokenize(&self, source: &[u8]) -> Result<Vec<Op>, DialectError> {
+        // Commands are positioned where their bits start
+        let mut commands: Vec<(u8, usize)> = Vec::new();
+        let mut bits: Vec<u8> = Vec::new();
+        let mut start = 0;
+        for (position, &c) in source.iter().enumerate() {
+            if c != b'0' && c != b'1' {
+                continue;
+            }
+            if bits.is_empty() {
+                start = position;
+            }
+            bits.push(c);
+            if bits == SPOON_EXIT {
+                return Err(DialectError { position: start, message: String::from("Spoon's exit command isn't supported") });
+            }
+            if let Some((_, command)) = SPOON_CODES.iter().find(|(code, _)| *code == bits.as_slice()) {
+                if let Some(command) = command {
+                    commands.push((*command, start));
+                }
+                bits.clear();
+            }
+        }
+        if !bits.is_empty() {
+            return Err(DialectError { position: start, message: String::from("incomplete Spoon command") });
+        }
+        return commands_to_ops(&commands);
+    }
+}
+
+const OOK_PAIRS: [([u8; 2], u8); 8] = [
+    ([b'.', b'?'], b'>'),
+    ([b'?', b'.'], b'<'),
+    ([b'.', b'.'], b'+'),
+    ([b'!', b'!'], b'-'),
+    ([b'!', b'.'], b'.'),
+    ([b'.', b'!'], b','),
+    ([b'!', b'?'], b'['),
+    ([b'?', b'!'], b']'),
+];
+
+impl Dialect for Ook {
+    fn name(&self) -> &str {
+        return "ook";
+    }
+
+    fn tokenize(&self, source: &[u8]) -> Result<Vec<Op>, DialectError> {
+        let mut words: Vec<(u8, usize)> = Vec::new(); // Punctuation and position of each `Ook` word
+        let mut i = 0;
+        while i + 4 <= source.len() {
+            if &source[i..i + 3] == b"Ook" && b".?!".contains(&source[i + 3]) {
+                words.push((source[i + 3], i));
+                i += 4;
+            }
+            else {
+                i += 1;
+            }
+        }
+        if !words.len().is_multiple_of(2) {
+            return Err(DialectError { position: words[words.len() - 1].1, message: String::from("unpaired `Ook`") });
+        }
+        let mut commands: Vec<(u8, usize)> = Vec::new();
+        for pair in words.chunks_exact(2) {
+            let [(first, position), (second, _)] = [pair[0], pair[1]];
+            let Some((_, command)) = OOK_PAIRS.iter().find(|(words, _)| *words == [first, second]) else {
+                return Err(DialectError { position, message: String::from("`Ook? Ook?` has no meaning") });
+            };
+            commands.push((*command, position));
+        }
+        return commands_to_ops(&commands);
+    }
+}
+
+fn rot13(c: u8) -> u8 {
+    return match c {
+        b'a'..=b'z' => (c - b'a' + 13) % 26 + b'a',
+        b'A'..=b'Z' => (c - b'A' + 13) % 26 + b'A',
+        _ => c,
+    };
+}
+
+impl Dialect for OokRot13 {
+    fn name(&self) -> &str {
+        return "ook-rot13";
+    }
+
+    fn tokenize(&self, source: &[u8]) -> Result<Vec<Op>, DialectError> {
+        // ROT13 keeps every byte in place, so positions in the decoded source are positions in this one
+        let decoded: Vec<u8> = source.iter().map(|&c| rot13(c)).collect();
+        return Ook.tokenize(&decoded);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpcodeMap {
+    characters: [u8; 8], // Byte standing for each command, in the order of `COMMANDS`
+}
+
+impl OpcodeMap {
+    pub fn new(characters: [u8; 8]) -> Result<Self, BfError> {
+        // Rejects a byte standing for two commands, which would make the mapping ambiguous
+        for (i, &c) in characters.iter().enumerate() {
+            if let Some(j) = characters[..i].iter().position(|&previous| previous == c) {
+                let (first, second) = (char::from(COMMANDS[j]), char::from(COMMANDS[i]));
+                return Err(BfError::Format(format!("Byte {c} stands for both `{first}` and `{second}`")));
+            }
+        }
+        return Ok(OpcodeMap { characters });
+    }
+
+    pub fn parse(text: &str) -> Result<Self, BfError> {
+        let table: toml::Table = text.parse().map_err(|error: toml::de::Error| BfError::Format(format!("Invalid opcode map: {}", error.message())))?;
+        if let Some(key) = table.keys().find(|key| !matches!(key.as_bytes(), [c] if COMMANDS.contains(c))) {
+            return Err(BfError::Format(format!("`{key}` isn't a brainfuck command")));
+        }
+        let mut characters = [0; 8];
+        for (character, &command) in characters.iter_mut().zip(COMMANDS) {
+            let command = char::from(command);
+            let value = table.get(command.to_string().as_str())
+                .ok_or_else(|| BfError::Format(format!("The opcode map doesn't give a byte for `{command}`")))?;
+            *character = match value {
+                toml::Value::String(text) if text.len() == 1 => text.as_bytes()[0],
+                toml::Value::Integer(byte) if let Ok(byte) = u8::try_from(*byte) => byte,
+                _ => return Err(BfError::Format(format!("The byte for `{command}` has to be a one-byte string or an integer from 0 to 255"))),
+            };
+        }
+        return OpcodeMap::new(characters);
+    }
+
+    pub fn characters(&self) -> [u8; 8] {
+        return self.characters;
+    }
+}
+
+impl Dialect for OpcodeMap {
+    fn name(&self) -> &str {
+        return "remapped";
+    }
+
+    fn tokenize(&self, source: &[u8]) -> Result<Vec<Op>, DialectError> {
+        let commands: Vec<(u8, usize)> = source.iter().enumerate()
+            .filter_map(|(position, c)| Some((COMMANDS[self.characters.iter().position(|character| character == c)?], position)))
+            .collect();
+        return commands_to_ops(&commands);
+    }
+}
+
+const OOK_NOISE: usize = 8; // Words of an Ook! source per brainfuck command allowed in its comments
+const SPOON_NOISE: usize = 8; // Bits of a Spoon source per brainfuck command allowed in its comments
+const MIN_SIGNATURE: usize = 8; // Ook words or Spoon bits a source needs before it's taken for anything but brainfuck
+
+pub fn detect_dialect(source: &[u8]) -> &'static str {
+    // The name of the built-in dialect the source is most likely in
+    let commands = source.iter().filter(|c| COMMANDS.contains(c)).count();
+    let candidates: [(&[u8], &'static dyn Dialect); 2] = [(b"Ook", &Ook), (b"Bbx", &OokRot13)];
+    for (word, dialect) in candidates {
+        let ook_words: Vec<u8> = source.windows(4)
+            .filter(|window| &window[..3] == word && b".?!".contains(&window[3]))
+            .map(|window| window[3])
+            .collect();
+        let ook_dots = ook_words.iter().filter(|&&c| c == b'.').count();
+        if ook_words.len() >= MIN_SIGNATURE && (commands - ook_dots) * OOK_NOISE <= ook_words.len() && dialect.tokenize(source).is_ok() {
+            return dialect.name();
+        }
+    }
+    let bits = source.iter().filter(|&&c| c == b'0' || c == b'1').count();
+    if bits >= MIN_SIGNATURE && commands * SPOON_NOISE <= bits && Spoon.tokenize(source).is_ok() {
+        return Spoon.name();
+    }
+    return Brainfuck.name();
+}
+
+fn commands_to_ops(commands: &[(u8, usize)]) -> Result<Vec<Op>, DialectError> {
+    // Brainfuck commands with their source positions, anything else is skipped; reports unbalanced brackets
+    let code: Vec<u8> = commands.iter().map(|&(command, _)| command).collect();
+    if let Err(BfError::Syntax { position, message }) = check_syntax(&code) {
+        return Err(DialectError { position: commands[position].1, message });
+    }
+    return Ok(compile_with(&code, 0, false).ops);
+}
+
+pub fn to_brainfuck(ops: &[Op]) -> Vec<u8> {
+    // Brainfuck code doing what the operations do
+    let mut code: Vec<u8> = Vec::new();
+    for op in ops {
+        match *op {
+            Op::Add(offset, value) => {
+                // Goes to the cell and back
+                code.extend(moves(offset));
+                if value < 128 {
+                    code.extend(std::iter::repeat_n(b'+', usize::from(value)));
+                }
+                else {
+                    code.extend(std::iter::repeat_n(b'-', 256 - usize::from(value)));
+                }
+                code.extend(moves(-offset));
+            },
+            Op::Move(delta) => code.extend(moves(delta)),
+            Op::Print => code.push(b'.'),
+            Op::Input => code.push(b','),
+            Op::JumpIfZero(_) => code.push(b'['),
+            Op::JumpIfNotZero(_) => code.push(b']'),
+            Op::GetEnv => code.push(b'$'),
+            Op::Extension(_) => {}, // The characters belong to a state, not to the operations
+            Op::Clear => code.extend(b"[-]"),
+            Op::Scan(delta) => {
+                code.push(b'[');
+                code.extend(moves(delta));
+                code.push(b']');
+            },
+        }
+    }
+    return code;
+}
+
+fn moves(delta: isize) -> impl Iterator<Item = u8> {
+    return std::iter::repeat_n(if delta < 0 { b'<' } else { b'>' }, delta.unsigned_abs());
+}
+
+pub struct DialectRegistry {
+    dialects: Vec<Box<dyn Dialect>>,
+}
+
+impl Default for DialectRegistry {
+    fn default() -> Self {
+        // The built-in dialects
+        return DialectRegistry { dialects: vec![Box::new(Brainfuck), Box::new(Spoon), Box::new(Ook), Box::new(OokRot13)] };
+    }
+}
+
+impl DialectRegistry {
+    pub fn register(&mut self, dialect: Box<dyn Dialect>) {
+        // Replaces the dialect with the same name, if there's one
+        self.dialects.retain(|existing| existing.name() != dialect.name());
+        self.dialects.push(dialect);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Dialect> {
+        return self.dialects.iter().find(|dialect| dialect.name() == name).map(|dialect| dialect.as_ref());
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        return self.dialects.iter().map(|dialect| dialect.name()).collect();
+    }
+
+    pub fn translate(&self, name: &str, source: &[u8]) -> Result<Vec<u8>, BfError> {
+        // The source in the named dialect as brainfuck code
+        let Some(dialect) = self.get(name) else {
+            return Err(BfError::Format(format!("Unknown dialect `{name}` (expected {})", self.names().join(", "))));
+        };
+        let code = to_brainfuck(&dialect.tokenize(source)?);
+        if check_syntax(&code).is_err() {
+            return Err(BfError::Format(format!("The `{name}` dialect produced unbalanced brackets")));
+        }
+        return Ok(code);
+    }
+}
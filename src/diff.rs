@@ -0,0 +1,199 @@
+use crate::*;
+
+/*
+ * Structural diffs of programs, for reviewing changes to generated code: both programs are put in a canonical form
+ * first, so comments, whitespace and how runs of `+-<>` are split don't show up in the diff.
+ * The canonical form is the compiled operations, one per line, indented by their loop depth:
+ *
+ *   +3
+ *   [
+ *     > 1
+ *     +1 at -1
+ *   ]
+ *
+ * Additions name the cell they're on relative to the cursor when it isn't the current one (see `Op::Add`).
+ * The lines are compared with Myers' algorithm, which finds the shortest edit script.
+ */
+
+const CONTEXT: usize = 3; // Unchanged lines shown around each change
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    Removed(String), // Only in the old program
+    Added(String), // Only in the new program
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffHunk {
+    pub old_start: usize, // Index of the first line of the hunk in the canonical form of the old program
+    pub new_start: usize, // Same in the new program
+    pub old_position: usize, // Position in the code of the old program of the first line, its length past the end
+    pub new_position: usize, // Same in the new program
+    pub lines: Vec<DiffLine>,
+}
+
+impl DiffHunk {
+    pub fn old_len(&self) -> usize {
+        return self.lines.iter().filter(|line| !matches!(line, DiffLine::Added(_))).count();
+    }
+
+    pub fn new_len(&self) -> usize {
+        return self.lines.iter().filter(|line| !matches!(line, DiffLine::Removed(_))).count();
+    }
+}
+
+pub fn canonical_form(program: &Program) -> Vec<String> {
+    // The lines of the canonical form, programs with the same lines do the same
+    return canonical_lines(&compile(&program.code, program.code_start).ops);
+}
+
+fn canonical_lines(ops: &[Op]) -> Vec<String> {
+    let mut depth: usize = 0;
+    let mut lines = Vec::new();
+    for &op in ops {
+        if let Op::JumpIfNotZero(_) = op {
+            depth -= 1;
+        }
+        let text = match op {
+            Op::Add(offset, value) => {
+                let amount = if value < 128 { format!("+{value}") } else { format!("-{}", 256 - usize::from(value)) };
+                if offset == 0 { amount } else { format!("{amount} at {offset}") }
+            },
+            Op::Move(delta) => format!("{} {}", if delta < 0 { '<' } else { '>' }, delta.unsigned_abs()),
+            Op::Print => String::from("."),
+            Op::Input => String::from(","),
+            Op::JumpIfZero(_) => String::from("["),
+            Op::JumpIfNotZero(_) => String::from("]"),
+            Op::GetEnv => String::from("$"),
+            Op::Clear => String::from("clear"),
+            Op::Scan(step) => format!("scan {} {}", if step < 0 { '<' } else { '>' }, step.unsigned_abs()),
+            Op::Extension(index) => format!("extension {index}"),
+        };
+        lines.push(format!("{}{text}", "  ".repeat(depth)));
+        if let Op::JumpIfZero(_) = op {
+            depth += 1;
+        }
+    }
+    return lines;
+}
+
+pub fn diff_programs(old: &Program, new: &Program) -> Vec<DiffHunk> {
+    // The changes between the canonical forms, with a few unchanged lines around them; empty if they're the same
+    let (old_bytecode, new_bytecode) = (compile(&old.code, old.code_start), compile(&new.code, new.code_start));
+    let position = |program: &Program, bytecode: &Bytecode, line: usize| bytecode.positions.get(line).copied().unwrap_or(program.code.len());
+    let (old_lines, new_lines) = (canonical_lines(&old_bytecode.ops), canonical_lines(&new_bytecode.ops));
+    let script = edit_script(&old_lines, &new_lines);
+    let changes: Vec<usize> = (0..script.len()).filter(|&index| script[index] != Edit::Keep).collect();
+
+    // Changes close enough for their contexts to overlap go in the same hunk
+    let mut ranges: Vec<(usize, usize)> = Vec::new(); // First and last change of each hunk, as indices in `script`
+    for &change in &changes {
+        match ranges.last_mut() {
+            Some((_, last)) if change - *last <= 2 * CONTEXT + 1 => *last = change,
+            _ => ranges.push((change, change)),
+        }
+    }
+
+    let mut hunks = Vec::new();
+    let (mut i, mut j) = (0, 0); // Lines of the old and new program before `script[index]`
+    let mut index = 0;
+    for (first, last) in ranges {
+        let (start, end) = (first.saturating_sub(CONTEXT), (last + CONTEXT + 1).min(script.len()));
+        while index < start {
+            i += usize::from(script[index] != Edit::Add);
+            j += usize::from(script[index] != Edit::Remove);
+            index += 1;
+        }
+        let mut hunk = DiffHunk {
+            old_start: i,
+            new_start: j,
+            old_position: position(old, &old_bytecode, i),
+            new_position: position(new, &new_bytecode, j),
+            lines: Vec::new(),
+        };
+        while index < end {
+            hunk.lines.push(match script[index] {
+                Edit::Keep => DiffLine::Same(old_lines[i].clone()),
+                Edit::Remove => DiffLine::Removed(old_lines[i].clone()),
+                Edit::Add => DiffLine::Added(new_lines[j].clone()),
+            });
+            i += usize::from(script[index] != Edit::Add);
+            j += usize::from(script[index] != Edit::Remove);
+            index += 1;
+        }
+        hunks.push(hunk);
+    }
+    return hunks;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Edit {
+    Keep, // The next line of both programs
+    Remove, // The next line of the old program
+    Add, // The next line of the new program
+}
+
+fn edit_script(old: &[String], new: &[String]) -> Vec<Edit> {
+    /*
+     * Myers' algorithm: for each number of edits `d`, the furthest point reached on each diagonal `k` (`x - y`) is kept,
+     * and extended by following equal lines. The points of each round are kept to walk the path back from the end.
+     * The common start and end are left out of the search, so a small change in a large program stays cheap.
+     */
+
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+    let (n, m) = (a.len() as isize, b.len() as isize);
+
+    let max = n + m;
+    let mut furthest = vec![0; 2 * max as usize + 3]; // Furthest `x` on each diagonal, indexed by `k + max + 1`
+    let index = |k: isize| (k + max + 1) as usize;
+    let mut rounds: Vec<Vec<isize>> = Vec::new(); // `furthest` before each round, on diagonals `-d..=d`
+    'search: for d in 0..=max {
+        rounds.push(furthest[index(-d)..=index(d)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && furthest[index(k - 1)] < furthest[index(k + 1)]) {
+                furthest[index(k + 1)] // Down, a line added
+            }
+            else {
+                furthest[index(k - 1)] + 1 // Right, a line removed
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut script = vec![Edit::Keep; suffix];
+    let (mut x, mut y) = (n, m);
+    for (d, round) in rounds.iter().enumerate().rev() {
+        let d = d as isize;
+        if d == 0 {
+            script.extend(std::iter::repeat_n(Edit::Keep, x as usize));
+            break;
+        }
+        let at = |k: isize| round[(k + d) as usize];
+        let k = x - y;
+        let previous_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+        let previous_x = at(previous_k);
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            script.push(Edit::Keep);
+            x -= 1;
+            y -= 1;
+        }
+        script.push(if x == previous_x { Edit::Add } else { Edit::Remove });
+        x = previous_x;
+        y = previous_y;
+    }
+    script.extend(std::iter::repeat_n(Edit::Keep, prefix));
+    script.reverse();
+    return script;
+}
@@ -0,0 +1,37 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum BfError {
+    Syntax { position: usize, message: String }, // The code can't be executed (e.g. unbalanced brackets)
+    Runtime(String), // Something went wrong while the code was running
+    Io(std::io::Error), // Reading the input or writing the output failed
+    Format(String), // A file (e.g. a package) couldn't be parsed
+    Host { number: u8, message: String }, // A host function the program called (`syscall` extension) failed
+}
+
+impl fmt::Display for BfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BfError::Syntax { position, message } => write!(f, "Syntax error at byte {position}: {message}"),
+            BfError::Runtime(message) => write!(f, "Runtime error: {message}"),
+            BfError::Io(error) => write!(f, "I/O error: {error}"),
+            BfError::Format(message) => write!(f, "{message}"),
+            BfError::Host { number, message } => write!(f, "Host function {number} failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for BfError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BfError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for BfError {
+    fn from(error: std::io::Error) -> Self {
+        return BfError::Io(error);
+    }
+}
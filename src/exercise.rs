@@ -0,0 +1,112 @@
+use brainfuckers::*;
+
+use std::path::Path;
+
+/*
+ * `bf exercise`: small graded exercises for learning brainfuck. `show` gives the task and one example,
+ * `check` runs a solution against the example and hidden inputs (the expected outputs are computed from them)
+ * and grades it by the share of tests it passes; a test fails if the output differs or the program runs out of steps.
+ */
+
+const USAGE: &str = "Usage: bf exercise [list | show <name> | check <name> <solution>]";
+const MAX_STEPS: u64 = 10_000_000; // Per test, far more than any reasonable solution needs
+
+struct Exercise {
+    name: &'static str,
+    title: &'static str,
+    task: &'static str,
+    inputs: &'static [&'static str], // The first one is the example, the others are hidden
+    solve: fn(&[u8]) -> Vec<u8>, // The expected output for an input
+}
+
+const EXERCISES: &[Exercise] = &[
+    Exercise {
+        name: "print-x",
+        title: "Print a letter",
+        task: "Print the letter `X` (ASCII 88).",
+        inputs: &[""],
+        solve: |_| b"X".to_vec(),
+    },
+    Exercise {
+        name: "hello",
+        title: "Hello, World!",
+        task: "Print `Hello, World!` followed by a newline.",
+        inputs: &[""],
+        solve: |_| b"Hello, World!\n".to_vec(),
+    },
+    Exercise {
+        name: "echo",
+        title: "Copy the input",
+        task: "Print the input as it is. Reading past the end of the input gives 0.",
+        inputs: &["abc", "", "Hello, World!\n", "two\nlines\n", "~!@#$%^&*()"],
+        solve: |input| input.to_vec(),
+    },
+    Exercise {
+        name: "add",
+        title: "Add two numbers",
+        task: "The input is two digits. Print the digit of their sum, which is never more than 9.",
+        inputs: &["34", "00", "09", "90", "45", "27", "18", "63"],
+        solve: |input| vec![input[0] + input[1] - b'0'],
+    },
+    Exercise {
+        name: "reverse",
+        title: "Reverse the input",
+        task: "Print the input backwards. Reading past the end of the input gives 0, and the input has no 0 bytes.",
+        inputs: &["abc", "", "a", "racecar", "Hello, World!\n", "0123456789"],
+        solve: |input| input.iter().rev().copied().collect(),
+    },
+];
+
+fn find(name: &str) -> Result<&'static Exercise, String> {
+    return EXERCISES.iter().find(|exercise| exercise.name == name)
+        .ok_or_else(|| format!("Unknown exercise `{name}` (see `bf exercise list`)"));
+}
+
+pub fn exercise(args: &[String]) -> Result<bool, String> {
+    // Whether the solution passed every test, always true for the commands that don't check one
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args[..] {
+        [] | ["list"] => {
+            for exercise in EXERCISES {
+                println!("{:<10} {}", exercise.name, exercise.title);
+            }
+            return Ok(true);
+        },
+        ["show", name] => {
+            let exercise = find(name)?;
+            let example = exercise.inputs[0].as_bytes();
+            println!("{}\n\n{}\n", exercise.title, exercise.task);
+            println!("Example input:  \"{}\"", example.escape_ascii());
+            println!("Example output: \"{}\"", (exercise.solve)(example).escape_ascii());
+            return Ok(true);
+        },
+        ["check", name, solution] => return check(find(name)?, solution),
+        _ => return Err(String::from(USAGE)),
+    }
+}
+
+fn check(exercise: &Exercise, solution: &str) -> Result<bool, String> {
+    let (code, map) = preprocess(Path::new(solution), &PreprocessOptions::default()).map_err(|error| error.to_string())?;
+    new_program(&code).check_syntax().map_err(|error| match error {
+        BfError::Syntax { position, message } if let Some((file, offset)) = map.locate(position) => {
+            format!("Syntax error in `{}` at byte {offset}: {message}", file.display())
+        },
+        error => error.to_string(),
+    })?;
+
+    let mut passed = 0;
+    for (i, input) in exercise.inputs.iter().enumerate() {
+        let name = if i == 0 { String::from("example") } else { format!("hidden test {i}") };
+        let expected = (exercise.solve)(input.as_bytes());
+        match check_bf_output(&code, input.as_bytes(), &expected, MAX_STEPS) {
+            Ok(()) => {
+                println!("{name} ... ok");
+                passed += 1;
+            },
+            Err(reason) => println!("{name} ... FAILED\n{}", reason.trim_end()),
+        }
+    }
+    let total = exercise.inputs.len();
+    println!("\nGrade: {}% ({passed} of {total} tests passed)", passed * 100 / total);
+    return Ok(passed == total);
+}
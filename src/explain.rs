@@ -0,0 +1,59 @@
+use crate::*;
+
+/*
+ * Plain-English narration of the operations, for teaching.
+ * Each sentence describes what the next step is about to do and why,
+ * using the values the cells have right before it.
+ */
+
+pub fn explain_step(state: &BFState, radix: DisplayRadix) -> Option<String> {
+    // `None` when there's nothing left to execute
+    let op = *state.program.bytecode.ops.get(state.instruction_position)?;
+    let cursor = state.cursor_position;
+    let raw_value = get_value_at(state, cursor);
+    let value = format_cell(raw_value, radix);
+
+    let sentence = match op {
+        Op::Add(offset, amount) => {
+            let cell = cursor + offset;
+            let raw_value = get_value_at(state, cell);
+            let (value, result) = (format_cell(raw_value, radix), format_cell(raw_value.wrapping_add(amount), radix));
+            if amount < 128 {
+                format!("add {amount} to cell {cell} ({value} -> {result})")
+            }
+            else {
+                format!("subtract {} from cell {cell} ({value} -> {result})", amount.wrapping_neg())
+            }
+        },
+        Op::Move(delta) => {
+            let direction = if delta < 0 { "left" } else { "right" };
+            let distance = delta.unsigned_abs();
+            if distance == 1 {
+                format!("move {direction} to cell {}", cursor + delta)
+            }
+            else {
+                format!("move {direction} by {distance} to cell {}", cursor + delta)
+            }
+        },
+        Op::Print if radix != DisplayRadix::Char && (raw_value.is_ascii_graphic() || raw_value == b' ') => {
+            format!("print cell {cursor} = {value} ('{}')", raw_value as char)
+        },
+        Op::Print => format!("print cell {cursor} = {value}"),
+        Op::Input => format!("read a byte of input into cell {cursor}"),
+        Op::GetEnv => format!("look up the environment variable named from cell {cursor} on"),
+        Op::Extension(index) => format!("run extension operation {index} on cell {cursor}"),
+        Op::Clear => format!("clear cell {cursor} (was {value})"),
+        Op::Scan(delta) => {
+            let direction = if delta < 0 { "left" } else { "right" };
+            format!("move {direction} by {} until a cell is 0, starting from cell {cursor} = {value}", delta.unsigned_abs())
+        },
+        Op::JumpIfZero(_) if raw_value == 0 => format!("skip the loop because cell {cursor} = {value}"),
+        Op::JumpIfZero(_) => format!("enter the loop because cell {cursor} = {value}"),
+        Op::JumpIfNotZero(_) if raw_value == 0 => format!("leave the loop because cell {cursor} = {value}"),
+        Op::JumpIfNotZero(_) => format!("loop repeats because cell {cursor} = {value}"),
+    };
+    return match current_label(state) {
+        Some(label) => Some(format!("byte {} (in {label}): {sentence}", source_position(state))),
+        None => Some(format!("byte {}: {sentence}", source_position(state))),
+    };
+}
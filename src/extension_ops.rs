@@ -0,0 +1,62 @@
+use crate::*;
+
+/*
+ * Custom instructions for embedders: each `ExtensionOp` claims a character that would otherwise be
+ * a comment, and its handler runs with the state whenever the program reaches that character.
+ * The characters compile to `Op::Extension` with the index of their handler, so dispatching one
+ * costs an index into the state's handlers and a virtual call; the other operations don't pay anything.
+ *
+ * Only the interpreter runs them. Programs using them can't be transpiled or run by the JIT,
+ * and the hot loop tracer leaves loops containing them alone.
+ */
+
+pub trait ExtensionOp: Send {
+    fn character(&self) -> u8; // The instruction character, can't be a command or `$`
+
+    /*
+     * Runs the instruction. The handler can read and change the tape through `get_cell`, `set_cell`
+     * and `cursor_position`, and reach the I/O through `push_input` and the like.
+     */
+    fn execute(&mut self, state: &mut BFState);
+}
+
+pub fn register_extension_op(state: &mut BFState, op: Box<dyn ExtensionOp>) -> Result<(), BfError> {
+    /*
+     * Registers the handler on the state, replacing the one with the same character if there's one.
+     * The code is compiled again with the new character, so it's meant to be called before running;
+     * it resets the instruction position.
+     */
+
+    let c = op.character();
+    if b"+-<>.,[]$".contains(&c) {
+        return Err(BfError::Runtime(format!("`{}` is already an instruction", c.escape_ascii())));
+    }
+    match state.extension_ops.iter().position(|existing| existing.as_ref().is_some_and(|existing| existing.character() == c)) {
+        Some(index) => state.extension_ops[index] = Some(op),
+        None => state.extension_ops.push(Some(op)),
+    }
+    let code = state.program.code.clone();
+    let code_start = state.program.code_start;
+    recompile(state, code, code_start);
+    return Ok(());
+}
+
+pub(crate) fn extension_characters(state: &BFState) -> Vec<u8> {
+    // Indexed like the handlers, so the position of a character is its `Op::Extension` index
+    return state.extension_ops.iter().map(|op| op.as_ref().map_or(0, |op| op.character())).collect();
+}
+
+pub(crate) fn recompile(state: &mut BFState, code: Vec<u8>, code_start: usize) {
+    let assumes_zeroed_tape = state.program.assumes_zeroed_tape;
+    program::replace_program(state, Program::compile(code, code_start, &extension_characters(state), assumes_zeroed_tape));
+    state.instruction_position = 0;
+}
+
+pub(crate) fn execute_extension_op(state: &mut BFState, index: u8) {
+    // The handler is taken out of its slot while it runs, since it borrows the state mutably
+    let Some(mut op) = state.extension_ops.get_mut(usize::from(index)).and_then(Option::take) else {
+        return;
+    };
+    op.execute(state);
+    state.extension_ops[usize::from(index)] = Some(op);
+}
@@ -0,0 +1,99 @@
+use crate::*;
+
+use std::ops::BitOr;
+
+/*
+ * Capability flags for extensions, combined like bitflags: `Extensions::ENV | Extensions::DEBUG`.
+ * Characters of extensions are comments unless enabled, so a program written for an extension
+ * silently misbehaves without it. `Program::check_extensions` rejects such programs up front instead,
+ * reporting the first character that belongs to an extension that isn't enabled.
+ * Only `$`, the decimal I/O and the host calls are implemented so far; the others are recognized so programs using them fail fast.
+ */
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Extensions(u32);
+
+const EXTENSION_CHARACTERS: [(Extensions, &str, &[u8]); 9] = [
+    (Extensions::ENV, "env", b"$"),
+    (Extensions::DEBUG, "debug", b"#"),
+    (Extensions::RANDOM, "random", b"?"),
+    (Extensions::CLOCK, "clock", b"^"),
+    (Extensions::FILE_IO, "file-io", b"&"),
+    (Extensions::PROCEDURES, "procedures", b"():"),
+    (Extensions::MULTI_TAPE, "multi-tape", b"~"),
+    (Extensions::DECIMAL, "decimal", b";%"),
+    (Extensions::SYSCALL, "syscall", b"!"),
+];
+
+impl Extensions {
+    pub const NONE: Extensions = Extensions(0);
+    pub const ENV: Extensions = Extensions(1 << 0); // `$` reads an allowed environment variable
+    pub const DEBUG: Extensions = Extensions(1 << 1); // `#` dumps the state
+    pub const RANDOM: Extensions = Extensions(1 << 2); // `?` sets the cell to a random value
+    pub const CLOCK: Extensions = Extensions(1 << 3); // `^` sets the cell to the current time
+    pub const FILE_IO: Extensions = Extensions(1 << 4); // `&` switches the I/O to a file
+    pub const PROCEDURES: Extensions = Extensions(1 << 5); // pbrain's `(`, `)` and `:`
+    pub const MULTI_TAPE: Extensions = Extensions(1 << 6); // `~` switches to the next tape
+    pub const DECIMAL: Extensions = Extensions(1 << 7); // `;` prints the cell in decimal, `%` reads a decimal number
+    pub const SYSCALL: Extensions = Extensions(1 << 8); // `!` calls a host function, only embedders can provide them
+    pub const SUPPORTED: Extensions = Extensions(Extensions::ENV.0 | Extensions::DECIMAL.0); // The ones the `bf` command can run
+
+    pub const fn contains(self, other: Extensions) -> bool {
+        return self.0 & other.0 == other.0;
+    }
+
+    pub fn from_name(name: &str) -> Option<Extensions> {
+        return EXTENSION_CHARACTERS.iter().find(|(_, n, _)| *n == name).map(|(extension, _, _)| *extension);
+    }
+
+    pub fn names(self) -> Vec<&'static str> {
+        return EXTENSION_CHARACTERS.iter()
+            .filter(|(extension, _, _)| self.contains(*extension))
+            .map(|(_, name, _)| *name)
+            .collect();
+    }
+}
+
+impl BitOr for Extensions {
+    type Output = Extensions;
+
+    fn bitor(self, other: Extensions) -> Extensions {
+        return Extensions(self.0 | other.0);
+    }
+}
+
+pub fn check_charset(code: &[u8], enabled: Extensions) -> Result<(), BfError> {
+    /*
+     * Strict mode: fails on the first byte that is neither a command, whitespace nor a character
+     * of an enabled extension, to catch corrupted or wrongly encoded files. Comments are rejected too.
+     */
+
+    for (position, &c) in code.iter().enumerate() {
+        let extension = EXTENSION_CHARACTERS.iter().any(|(extension, _, characters)| enabled.contains(*extension) && characters.contains(&c));
+        if COMMANDS.contains(&c) || c.is_ascii_whitespace() || extension {
+            continue;
+        }
+        let message = if c.is_ascii_graphic() {
+            format!("unexpected character `{}`", c as char)
+        }
+        else {
+            format!("unexpected byte 0x{c:02X}")
+        };
+        return Err(BfError::Syntax { position, message });
+    }
+    return Ok(());
+}
+
+pub fn check_extensions(code: &[u8], enabled: Extensions) -> Result<(), BfError> {
+    // Fails on the first character of an extension that isn't enabled, positions are relative to the code
+    for (position, c) in code.iter().enumerate() {
+        let Some((extension, name, _)) = EXTENSION_CHARACTERS.iter().find(|(_, _, characters)| characters.contains(c)) else {
+            continue;
+        };
+        if !enabled.contains(*extension) {
+            let message = format!("`{}` belongs to the `{name}` extension, which isn't enabled", *c as char);
+            return Err(BfError::Syntax { position, message });
+        }
+    }
+    return Ok(());
+}
@@ -0,0 +1,159 @@
+use crate::*;
+
+use std::collections::HashMap;
+
+/*
+ * Hints about common beginner mistakes, found without running the program:
+ * unmatched brackets, with where the missing or extra one probably is; loops that never end once entered,
+ * because their body ends on the cell they check without ever changing it (and the ones the program surely enters,
+ * following its code from a zeroed tape); and reading input when none is given.
+ * Loops entered on a cell known to be 0, like comment loops at the start or right after another loop, are left alone.
+ */
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HintKind {
+    UnmatchedBracket,
+    EndlessLoop,
+    MissingInput,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hint {
+    pub kind: HintKind,
+    pub position: usize, // Position in the code of the instruction the hint is about
+    pub message: String,
+    pub suggestion: Option<usize>, // Position in the code where the message suggests a change
+}
+
+pub fn program_hints(program: &Program, input_given: bool) -> Vec<Hint> {
+    // The hints in source order; `input_given` tells whether the program has any input besides a terminal
+    let mut hints = bracket_hints(&program.code, program.code_start);
+    let bytecode = compile(&program.code, program.code_start);
+    hints.extend(loop_hints(&bytecode));
+    if !input_given && let Some(i) = bytecode.ops.iter().position(|&op| op == Op::Input) {
+        hints.push(Hint {
+            kind: HintKind::MissingInput,
+            position: bytecode.positions[i],
+            message: String::from("the program reads input but none is given, so it will wait for the terminal; pipe the input in or pass it as arguments"),
+            suggestion: None,
+        });
+    }
+    hints.sort_by_key(|hint| hint.position);
+    return hints;
+}
+
+fn bracket_hints(code: &[u8], start: usize) -> Vec<Hint> {
+    let commands: Vec<(usize, u8)> = code.iter().copied().enumerate().skip(start).filter(|&(_, c)| COMMANDS.contains(&c)).collect();
+    let mut hints = Vec::new();
+    let mut open: Vec<usize> = Vec::new(); // Indices in `commands` of the unclosed `[`
+    let mut top_level_start = start; // Where the code after the last loop at the top level starts
+    for (index, &(position, c)) in commands.iter().enumerate() {
+        match c {
+            b'[' => open.push(index),
+            b']' => match open.pop() {
+                Some(_) if open.is_empty() => top_level_start = position + 1,
+                Some(_) => {},
+                None if index > 0 && commands[index - 1].1 == b']' => hints.push(Hint {
+                    kind: HintKind::UnmatchedBracket,
+                    position,
+                    message: String::from("unmatched `]`, right after the `]` that closes the loop already; one of them is probably a typo"),
+                    suggestion: None,
+                }),
+                None => hints.push(Hint {
+                    kind: HintKind::UnmatchedBracket,
+                    position,
+                    message: String::from("unmatched `]`; if it closes a loop, the `[` is probably missing where the code before it starts"),
+                    suggestion: Some(top_level_start),
+                }),
+            },
+            _ => {},
+        }
+    }
+    for index in open {
+        let position = commands[index].0;
+        let hint = if commands.get(index + 1).is_some_and(|&(_, c)| c == b'[') {
+            Hint {
+                kind: HintKind::UnmatchedBracket,
+                position,
+                message: String::from("unmatched `[`, right before another `[`; one of them is probably a typo"),
+                suggestion: None,
+            }
+        }
+        else {
+            let line_end = code[position..].iter().position(|&c| c == NEWLINE).map_or(code.len(), |i| position + i);
+            Hint {
+                kind: HintKind::UnmatchedBracket,
+                position,
+                message: String::from("unmatched `[`; its `]` is probably missing at the end of the line"),
+                suggestion: Some(line_end),
+            }
+        };
+        hints.push(hint);
+    }
+    return hints;
+}
+
+fn loop_hints(bytecode: &Bytecode) -> Vec<Hint> {
+    let ops = &bytecode.ops;
+
+    // The values of the cells the top-level loops are entered with, following the code from a zeroed tape
+    let mut entries: HashMap<usize, Option<u8>> = HashMap::new(); // By index of the `[`, `None` if unknown
+    let mut cells: HashMap<isize, Option<u8>> = HashMap::new(); // By offset from the start, 0 if missing
+    let mut cursor: isize = 0;
+    let mut i = 0;
+    while i < ops.len() {
+        match ops[i] {
+            Op::Add(offset, value) => {
+                let cell = cells.entry(cursor + offset).or_insert(Some(0));
+                *cell = cell.map(|cell| cell.wrapping_add(value));
+            },
+            Op::Move(delta) => cursor += delta,
+            Op::Input | Op::GetEnv => {
+                cells.insert(cursor, None);
+            },
+            Op::Clear => {
+                cells.insert(cursor, Some(0));
+            },
+            Op::Print => {},
+            Op::JumpIfZero(after) => {
+                let value = cells.get(&cursor).copied().unwrap_or(Some(0));
+                entries.insert(i, value);
+                let mut written = Vec::new();
+                if value != Some(0) && balance::body_effect(ops, i + 1..after - 1, &mut |cell| written.push(cell)) != Some(0) {
+                    break; // The cursor moves by an amount only known at runtime
+                }
+                for cell in written {
+                    cells.insert(cursor + cell, None);
+                }
+                cells.insert(cursor, Some(0));
+                i = after;
+                continue;
+            },
+            Op::JumpIfNotZero(_) | Op::Scan(_) | Op::Extension(_) => break,
+        }
+        i += 1;
+    }
+
+    let mut hints = Vec::new();
+    let mut skipped_until = 0; // End of the last loop entered on 0, the loops in it are never entered
+    for (i, &op) in ops.iter().enumerate() {
+        let Op::JumpIfZero(after) = op else {
+            continue;
+        };
+        let after_loop = i > 0 && matches!(ops[i - 1], Op::JumpIfNotZero(_) | Op::Clear | Op::Scan(_));
+        if i < skipped_until || after_loop || entries.get(&i) == Some(&Some(0)) {
+            skipped_until = skipped_until.max(after);
+            continue;
+        }
+        let mut changes_own_cell = false;
+        if balance::body_effect(ops, i + 1..after - 1, &mut |cell| changes_own_cell |= cell == 0) != Some(0) || changes_own_cell {
+            continue;
+        }
+        let message = match entries.get(&i) {
+            Some(Some(value)) => format!("this loop never ends: it's entered with its cell at {value}, and its body never changes that cell"),
+            _ => String::from("this loop never ends once entered: its body never changes the cell it checks"),
+        };
+        hints.push(Hint { kind: HintKind::EndlessLoop, position: bytecode.positions[i], message, suggestion: None });
+    }
+    return hints;
+}
@@ -0,0 +1,69 @@
+use crate::*;
+
+use std::collections::BTreeMap;
+
+/*
+ * The `syscall` extension: `!` calls the host function whose number is in the current cell, so an embedder can
+ * give programs exactly the capabilities it chooses and nothing else. Each function is registered with a number
+ * and an arity; its arguments are that many cells right after the current one, and it can overwrite them
+ * with its results. The cursor doesn't move. Calling a number without a function stops the run with a runtime error,
+ * a function that fails with `BfError::Host` carrying its message, and an argument window reaching past
+ * `max_tape_cells` with `TapeLimitExceeded`.
+ */
+
+pub type HostFunction = Box<dyn FnMut(&mut [u8]) -> Result<(), String> + Send>;
+
+#[derive(Default)]
+pub struct HostFunctions {
+    functions: BTreeMap<u8, (usize, HostFunction)>, // Arity and callback of each number
+}
+
+impl HostFunctions {
+    pub fn new() -> Self {
+        return HostFunctions::default();
+    }
+
+    pub fn register(&mut self, number: u8, arity: usize, function: impl FnMut(&mut [u8]) -> Result<(), String> + Send + 'static) {
+        // Replaces the function with the same number, if there's one
+        self.functions.insert(number, (arity, Box::new(function)));
+    }
+
+    pub fn numbers(&self) -> Vec<u8> {
+        return self.functions.keys().copied().collect();
+    }
+}
+
+impl ExtensionOp for HostFunctions {
+    fn character(&self) -> u8 {
+        return b'!';
+    }
+
+    fn execute(&mut self, state: &mut BFState) {
+        let cursor = state.cursor_position;
+        let number = get_value_at(state, cursor);
+        let Some((arity, function)) = self.functions.get_mut(&number) else {
+            state.error = Some(BfError::Runtime(format!("No host function is registered as {number}")));
+            return;
+        };
+        let start = cursor.saturating_add(1);
+        let end = start.saturating_add_unsigned(*arity);
+        if *arity > 0 && !within_tape_limit(state, end - 1) {
+            state.tape_limit_reached = true;
+            return;
+        }
+        let mut arguments = export_tape(state, start..end);
+        if let Err(message) = function(&mut arguments) {
+            state.error = Some(BfError::Host { number, message });
+            return;
+        }
+        for (index, value) in (start..end).zip(arguments) {
+            set_value_at(state, index, value);
+        }
+        state.highest_cursor = state.highest_cursor.max(end - 1);
+    }
+}
+
+pub fn enable_syscall_extension(state: &mut BFState, functions: HostFunctions) {
+    // Enables `!` with the given functions, recompiling the code like `register_extension_op`
+    register_extension_op(state, Box::new(functions)).unwrap();
+}
@@ -0,0 +1,167 @@
+use crate::*;
+
+/*
+ * Brainloller and Braincopter, dialects whose programs are PNG images.
+ * The instruction pointer starts at the top left pixel heading east, and every pixel it passes
+ * is a command; two of them turn it 90 degrees, and the program ends when it leaves the image.
+ * Since the turns don't depend on the tape, the path can be traced once and converted to brainfuck.
+ *
+ * Brainloller uses exact colors (anything else is a comment); Braincopter computes the command
+ * from any color as `(-2 * red + 3 * green + blue) % 11`, so programs can hide in photos.
+ */
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageDialect {
+    Brainloller,
+    Braincopter,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PixelCommand {
+    Command(u8), // One of the 8 brainfuck commands
+    TurnRight, // Clockwise
+    TurnLeft, // Counterclockwise
+    Nop,
+}
+
+// Colors of Brainloller, the order of `BRAINCOPTER_COMMANDS` as well
+const BRAINLOLLER_COLORS: [([u8; 3], PixelCommand); 11] = [
+    ([255, 0, 0], PixelCommand::Command(b'>')),
+    ([128, 0, 0], PixelCommand::Command(b'<')),
+    ([0, 255, 0], PixelCommand::Command(b'+')),
+    ([0, 128, 0], PixelCommand::Command(b'-')),
+    ([0, 0, 255], PixelCommand::Command(b'.')),
+    ([0, 0, 128], PixelCommand::Command(b',')),
+    ([255, 255, 0], PixelCommand::Command(b'[')),
+    ([128, 128, 0], PixelCommand::Command(b']')),
+    ([0, 255, 255], PixelCommand::TurnRight),
+    ([0, 128, 128], PixelCommand::TurnLeft),
+    ([0, 0, 0], PixelCommand::Nop),
+];
+
+const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)]; // East, south, west and north
+
+fn pixel_command(dialect: ImageDialect, pixel: [u8; 3]) -> PixelCommand {
+    return match dialect {
+        ImageDialect::Brainloller => BRAINLOLLER_COLORS.iter()
+            .find(|(color, _)| *color == pixel)
+            .map_or(PixelCommand::Nop, |(_, command)| *command),
+        ImageDialect::Braincopter => {
+            let [red, green, blue] = pixel.map(i32::from);
+            let value = (-2 * red + 3 * green + blue).rem_euclid(11);
+            BRAINLOLLER_COLORS[value as usize].1
+        },
+    };
+}
+
+fn command_pixel(dialect: ImageDialect, command: PixelCommand) -> [u8; 3] {
+    let index = BRAINLOLLER_COLORS.iter().position(|(_, c)| *c == command).unwrap();
+    return match dialect {
+        ImageDialect::Brainloller => BRAINLOLLER_COLORS[index].0,
+        ImageDialect::Braincopter => {
+            // Gray with the blue channel nudged until it encodes the command
+            let blue = (128..139).find(|&blue| (128 + blue) % 11 == index).unwrap();
+            [128, 128, blue as u8]
+        },
+    };
+}
+
+pub fn decode_image(png: &[u8], dialect: ImageDialect) -> Result<Vec<u8>, BfError> {
+    // Traces the path of the instruction pointer and returns the brainfuck commands along it
+    let (width, height, pixels) = read_pixels(png)?;
+    let mut code: Vec<u8> = Vec::new();
+    let mut visited = vec![false; width * height * DIRECTIONS.len()];
+    let (mut x, mut y, mut direction) = (0, 0, 0);
+    while x < width && y < height {
+        let index = y * width + x;
+        if std::mem::replace(&mut visited[index * DIRECTIONS.len() + direction], true) {
+            return Err(BfError::Format(format!("The instruction pointer goes around in circles from pixel ({x}, {y})")));
+        }
+        match pixel_command(dialect, pixels[index]) {
+            PixelCommand::Command(command) => code.push(command),
+            PixelCommand::TurnRight => direction = (direction + 1) % DIRECTIONS.len(),
+            PixelCommand::TurnLeft => direction = (direction + DIRECTIONS.len() - 1) % DIRECTIONS.len(),
+            PixelCommand::Nop => {},
+        }
+        let (dx, dy) = DIRECTIONS[direction];
+        // Leaving on the left or the top wraps around to a huge index, which ends the loop as well
+        x = x.wrapping_add_signed(dx);
+        y = y.wrapping_add_signed(dy);
+    }
+    return Ok(code);
+}
+
+pub fn encode_image(code: &[u8], dialect: ImageDialect, width: usize) -> Result<Vec<u8>, BfError> {
+    /*
+     * An image running the commands of the code, laid out in rows of the given width (at least 3 pixels).
+     * The rows snake down the image: the first and the last column turn the instruction pointer
+     * to the next row, so each row holds `width - 2` commands.
+     */
+
+    if width < 3 {
+        return Err(BfError::Format(String::from("Images have to be at least 3 pixels wide")));
+    }
+    let commands: Vec<u8> = code.iter().copied().filter(|c| b"><+-.,[]".contains(c)).collect();
+    let per_row = width - 2;
+    let height = commands.len().div_ceil(per_row).max(1);
+    let nop = command_pixel(dialect, PixelCommand::Nop);
+    let mut pixels = vec![nop; width * height];
+    for row in 0..height {
+        let last = row + 1 == height;
+        let line = &mut pixels[row * width..(row + 1) * width];
+        let start = (row * per_row).min(commands.len());
+        let end = (start + per_row).min(commands.len());
+        for (i, &command) in commands[start..end].iter().enumerate() {
+            let column = if row % 2 == 0 { 1 + i } else { width - 2 - i };
+            line[column] = command_pixel(dialect, PixelCommand::Command(command));
+        }
+        if row % 2 == 0 {
+            // Heading east: coming down from the previous row on the left, going down on the right
+            if row > 0 {
+                line[0] = command_pixel(dialect, PixelCommand::TurnLeft);
+            }
+            if !last {
+                line[width - 1] = command_pixel(dialect, PixelCommand::TurnRight);
+            }
+        }
+        else {
+            line[width - 1] = command_pixel(dialect, PixelCommand::TurnRight);
+            if !last {
+                line[0] = command_pixel(dialect, PixelCommand::TurnLeft);
+            }
+        }
+    }
+    return write_pixels(width, height, &pixels);
+}
+
+fn image_error(error: impl std::fmt::Display) -> BfError {
+    return BfError::Format(format!("Invalid image: {error}"));
+}
+
+fn read_pixels(png: &[u8]) -> Result<(usize, usize, Vec<[u8; 3]>), BfError> {
+    // The pixels as RGB, whatever the color type and bit depth of the file are
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(png));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(image_error)?;
+    let mut buffer = vec![0; reader.output_buffer_size().ok_or_else(|| image_error("too large"))?];
+    let info = reader.next_frame(&mut buffer).map_err(image_error)?;
+    let channels = info.color_type.samples();
+    let pixels = buffer[..info.buffer_size()].chunks_exact(channels).map(|pixel| match *pixel {
+        [gray] | [gray, _] => [gray; 3],
+        [red, green, blue, ..] => [red, green, blue],
+        _ => [0; 3],
+    });
+    return Ok((info.width as usize, info.height as usize, pixels.collect()));
+}
+
+pub(crate) fn write_pixels(width: usize, height: usize, pixels: &[[u8; 3]]) -> Result<Vec<u8>, BfError> {
+    let mut png: Vec<u8> = Vec::new();
+    let size = |length: usize| u32::try_from(length).map_err(|_| image_error("too large"));
+    let mut encoder = png::Encoder::new(&mut png, size(width)?, size(height)?);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(image_error)?;
+    writer.write_image_data(pixels.as_flattened()).map_err(image_error)?;
+    writer.finish().map_err(image_error)?;
+    return Ok(png);
+}
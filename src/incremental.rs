@@ -0,0 +1,155 @@
+use crate::*;
+
+/*
+ * Reruns a program after edits without starting over, for live coding.
+ * While running, snapshots of the tape are recorded along with how far into the source the run has depended on
+ * so far: the end of the furthest operation it executed. After an edit, the latest snapshot that only depended on
+ * the unchanged prefix of the code is restored, and the run goes on from there with the new code.
+ * The input is fixed for the whole session, so replaying always reads the same bytes.
+ * Hot loops are traced anew after resuming, so step counts can differ a little from a fresh run's.
+ */
+
+pub const DEFAULT_SNAPSHOT_INTERVAL: u64 = 1024;
+const MAX_SNAPSHOTS: usize = 256; // Past this, every other snapshot is dropped and the interval doubles
+
+struct Snapshot {
+    reach: usize, // Source bytes the run depended on until this point (exclusive end)
+    source_position: usize, // Of the next operation
+    steps: u64,
+    cursor: isize,
+    highest_cursor: isize,
+    ptape: Vec<u8>,
+    ntape: Vec<u8>,
+    input_read: usize, // Bytes of the input consumed
+    output_length: usize, // Bytes printed
+}
+
+pub struct IncrementalReport {
+    pub outcome: RunOutcome,
+    pub output: Vec<u8>, // The whole output, including the part replayed from the snapshot
+    pub steps: u64, // Steps of the whole run, including the ones skipped thanks to the snapshot
+    pub resumed_at: u64, // Step the run resumed from, 0 if it started over
+}
+
+pub struct IncrementalRun {
+    code: Vec<u8>, // Code of the previous run
+    input: Vec<u8>,
+    output: Vec<u8>, // Output of the previous run
+    snapshots: Vec<Snapshot>, // In the order they were taken
+    interval: u64, // Steps between snapshots
+}
+
+impl IncrementalRun {
+    pub fn new(input: &[u8]) -> Self {
+        return IncrementalRun::with_snapshot_interval(input, DEFAULT_SNAPSHOT_INTERVAL);
+    }
+
+    pub fn with_snapshot_interval(input: &[u8], interval: u64) -> Self {
+        return IncrementalRun { code: Vec::new(), input: input.to_vec(), output: Vec::new(), snapshots: Vec::new(), interval: interval.max(1) };
+    }
+
+    pub fn run(&mut self, code: impl AsRef<[u8]>, fuel: Option<u64>) -> Result<IncrementalReport, BfError> {
+        /*
+         * Runs the code until it halts or has taken `fuel` steps in total, resuming from the latest snapshot
+         * the edit didn't invalidate. Syntax errors are reported before anything is discarded.
+         */
+
+        let code = code.as_ref();
+        check_syntax(code)?;
+        let prefix = self.code.iter().zip(code).take_while(|(a, b)| a == b).count();
+        let mut state = new_bf_state(code);
+        self.snapshots.retain(|snapshot| snapshot.reach <= prefix);
+        // The snapshot has to point at an operation of the new program, merging can move them around
+        while let Some(snapshot) = self.snapshots.last() {
+            match state.program.bytecode.positions.iter().position(|&position| position == snapshot.source_position) {
+                Some(index) => {
+                    state.instruction_position = index;
+                    break;
+                },
+                None => {
+                    self.snapshots.pop();
+                },
+            }
+        }
+
+        let (mut steps, mut reach) = (0, 0);
+        set_input_reader(&mut state, Box::new(std::io::empty()));
+        set_raw_output(&mut state, true);
+        capture_output(&mut state);
+        match self.snapshots.last() {
+            Some(snapshot) => {
+                state.ptape = snapshot.ptape.clone();
+                state.ntape = snapshot.ntape.clone();
+                state.cursor_position = snapshot.cursor;
+                state.highest_cursor = snapshot.highest_cursor;
+                push_input(&mut state, &self.input[snapshot.input_read..]);
+                self.output.truncate(snapshot.output_length);
+                (steps, reach) = (snapshot.steps, snapshot.reach);
+            },
+            None => {
+                push_input(&mut state, &self.input);
+                self.output.clear();
+            },
+        }
+        let resumed_at = steps;
+        self.code = code.to_vec();
+
+        let outcome = loop {
+            if fuel.is_some_and(|fuel| steps >= fuel) {
+                break RunOutcome::FuelExhausted;
+            }
+            if steps.is_multiple_of(self.interval) && steps > resumed_at {
+                self.snapshot(&mut state, steps, reach);
+            }
+            let before = state.instruction_position;
+            let running = step_bf(&mut state);
+            reach = reach.max(operation_end(&state, before));
+            if state.instruction_position > 0 {
+                reach = reach.max(operation_end(&state, state.instruction_position - 1));
+            }
+            if !running {
+                break stop_outcome(&mut state)?;
+            }
+            steps += 1;
+        };
+        self.collect_output(&mut state);
+        return Ok(IncrementalReport { outcome, output: self.output.clone(), steps, resumed_at });
+    }
+
+    fn collect_output(&mut self, state: &mut BFState) {
+        self.output.extend(state.captured_output.as_mut().map(std::mem::take).unwrap_or_default());
+    }
+
+    fn snapshot(&mut self, state: &mut BFState, steps: u64, reach: usize) {
+        self.collect_output(state);
+        if self.snapshots.len() >= MAX_SNAPSHOTS {
+            let mut index = 0;
+            self.snapshots.retain(|_| {
+                index += 1;
+                return index % 2 == 0;
+            });
+            self.interval *= 2;
+        }
+        self.snapshots.push(Snapshot {
+            reach,
+            source_position: source_position(state),
+            steps,
+            cursor: state.cursor_position,
+            highest_cursor: state.highest_cursor,
+            ptape: state.ptape.clone(),
+            ntape: state.ntape.clone(),
+            input_read: self.input.len() - state.input.len(),
+            output_length: self.output.len(),
+        });
+    }
+
+    pub fn snapshots(&self) -> usize {
+        return self.snapshots.len();
+    }
+}
+
+fn operation_end(state: &BFState, index: usize) -> usize {
+    // Where the source of an operation ends: merged operations span until the next one
+    let positions = &state.program.bytecode.positions;
+    return positions.get(index + 1).copied().unwrap_or(state.program.code.len());
+}
@@ -0,0 +1,344 @@
+use crate::*;
+
+use cranelift_codegen::ir::condcodes::IntCC;
+use cranelift_codegen::ir::{AbiParam, Block, InstBuilder, MemFlagsData, Signature, UserFuncName, Value, types};
+use cranelift_codegen::isa::{TargetFrontendConfig, TargetIsa};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{Linkage, Module, default_libcall_names};
+
+/*
+ * Compiles the bytecode to native code with Cranelift, on any platform Cranelift supports.
+ * `lower` is the only part that knows about operations, Cranelift's x86_64 and AArch64 backends share it,
+ * so the JIT runs the same way on Apple Silicon and ARM servers. The AArch64 backend is always built,
+ * so its output is tested on every host. Compiling takes a while, so it only pays off for long-running programs.
+ *
+ * The compiled code works on a fixed-size copy of the tape around the cursor,
+ * and stops with an error when the cursor leaves it. Printing and reading go through the state,
+ * so output limits, captured output and input formats work like they do in `run_bf`.
+ * Fuel, timeouts, interrupts, observers and the `$` extension are not supported.
+ */
+
+pub const JIT_TAPE_SIZE: usize = 1 << 20;
+
+const STATUS_OUT_OF_BOUNDS: isize = -1; // The cursor left the tape
+const STATUS_STOPPED: isize = -2; // Printing or reading failed, or the output limit was reached
+
+type EntryFunction = unsafe extern "C" fn(*mut u8, *mut BFState, PrintCallback, InputCallback) -> isize;
+type PrintCallback = extern "C" fn(*mut BFState, i32) -> i32;
+type InputCallback = extern "C" fn(*mut BFState) -> i32;
+
+pub struct JitProgram {
+    module: Option<JITModule>, // Owns the executable memory, only taken when dropped
+    entry: EntryFunction,
+}
+
+fn jit_error(error: impl std::fmt::Display) -> BfError {
+    return BfError::Runtime(format!("JIT compilation failed: {error}"));
+}
+
+impl JitProgram {
+    pub fn compile(program: &Program) -> Result<Self, BfError> {
+        trace_span!(DEBUG, "jit_compile", operations = program.bytecode.ops.len());
+        program.check_syntax()?;
+        if program.bytecode.ops.iter().any(|op| matches!(op, Op::Extension(_))) {
+            return Err(BfError::Runtime(String::from("Extension operations aren't supported by the JIT")));
+        }
+
+        let isa = cranelift_native::builder()
+            .map_err(|message| BfError::Runtime(format!("JIT isn't supported on this platform: {message}")))?
+            .finish(jit_flags()?)
+            .map_err(jit_error)?;
+        let mut module = JITModule::new(JITBuilder::with_isa(isa, default_libcall_names()));
+
+        let signature = entry_signature(module.isa());
+        let id = module.declare_function("run", Linkage::Local, &signature).map_err(jit_error)?;
+        let mut context = module.make_context();
+        context.func.signature = signature;
+        context.func.name = UserFuncName::user(0, id.as_u32());
+
+        let mut builder_context = FunctionBuilderContext::new();
+        lower(FunctionBuilder::new(&mut context.func, &mut builder_context), &program.bytecode.ops, module.target_config());
+        module.define_function(id, &mut context).map_err(jit_error)?;
+        module.clear_context(&mut context);
+        module.finalize_definitions().map_err(jit_error)?;
+
+        // SAFETY: the function was defined with this signature above
+        let entry = unsafe { std::mem::transmute::<*const u8, EntryFunction>(module.get_finalized_function(id)) };
+        return Ok(JitProgram { module: Some(module), entry });
+    }
+}
+
+impl Drop for JitProgram {
+    fn drop(&mut self) {
+        if let Some(module) = self.module.take() {
+            // SAFETY: `entry` can't be called anymore, nothing else points into the module
+            unsafe { module.free_memory() };
+        }
+    }
+}
+
+fn jit_flags() -> Result<settings::Flags, BfError> {
+    let mut flags = settings::builder();
+    flags.set("opt_level", "speed").map_err(jit_error)?;
+    flags.set("use_colocated_libcalls", "false").map_err(jit_error)?;
+    flags.set("is_pic", "false").map_err(jit_error)?;
+    return Ok(settings::Flags::new(flags));
+}
+
+fn entry_signature(isa: &dyn TargetIsa) -> Signature {
+    // The signature of `EntryFunction` in the target's C calling convention
+    let pointer = isa.pointer_type();
+    let mut signature = Signature::new(isa.default_call_conv());
+    signature.params.extend([AbiParam::new(pointer); 4]);
+    signature.returns.push(AbiParam::new(pointer));
+    return signature;
+}
+
+struct Lowering<'a> {
+    builder: FunctionBuilder<'a>,
+    tape: Value,
+    cursor: Variable,
+    out_of_bounds: Block,
+    stopped: Block,
+}
+
+fn lower(mut builder: FunctionBuilder, ops: &[Op], config: TargetFrontendConfig) {
+    // `fn(tape, state, print, input) -> cursor or status`, the cursor starts in the middle of the tape
+    let pointer = config.pointer_type();
+    let entry = builder.create_block();
+    builder.append_block_params_for_function_params(entry);
+    builder.switch_to_block(entry);
+    let [tape, state, print, input] = builder.block_params(entry)[..] else {
+        unreachable!();
+    };
+
+    let mut print_signature = Signature::new(builder.func.signature.call_conv);
+    print_signature.params.extend([AbiParam::new(pointer), AbiParam::new(types::I32)]);
+    print_signature.returns.push(AbiParam::new(types::I32));
+    let print_signature = builder.import_signature(print_signature);
+    let mut input_signature = Signature::new(builder.func.signature.call_conv);
+    input_signature.params.push(AbiParam::new(pointer));
+    input_signature.returns.push(AbiParam::new(types::I32));
+    let input_signature = builder.import_signature(input_signature);
+
+    let cursor = builder.declare_var(pointer);
+    let start = builder.ins().iconst(pointer, i64::try_from(JIT_TAPE_SIZE / 2).unwrap());
+    builder.def_var(cursor, start);
+    let out_of_bounds = builder.create_block();
+    let stopped = builder.create_block();
+    let mut lowering = Lowering { builder, tape, cursor, out_of_bounds, stopped };
+    let mut loops: Vec<(Block, Block)> = Vec::new(); // Body and exit of each open loop
+
+    for op in ops {
+        match *op {
+            Op::Add(offset, value) => {
+                let address = if offset == 0 { lowering.cell_address() } else { lowering.offset_cell_address(offset) };
+                let cell = lowering.builder.ins().load(types::I8, MemFlagsData::trusted(), address, 0);
+                let cell = lowering.builder.ins().iadd_imm_s(cell, i64::from(value));
+                lowering.builder.ins().store(MemFlagsData::trusted(), cell, address, 0);
+            },
+            Op::Move(delta) => lowering.move_cursor(delta),
+            Op::Print => {
+                let address = lowering.cell_address();
+                let cell = lowering.builder.ins().uload8(types::I32, MemFlagsData::trusted(), address, 0);
+                let call = lowering.builder.ins().call_indirect(print_signature, print, &[state, cell]);
+                let failed = lowering.builder.inst_results(call)[0];
+                lowering.continue_unless(failed);
+            },
+            Op::Input => {
+                let call = lowering.builder.ins().call_indirect(input_signature, input, &[state]);
+                let value = lowering.builder.inst_results(call)[0];
+                let failed = lowering.builder.ins().icmp_imm_s(IntCC::SignedLessThan, value, 0);
+                lowering.continue_unless(failed);
+                let address = lowering.cell_address();
+                lowering.builder.ins().istore8(MemFlagsData::trusted(), value, address, 0);
+            },
+            Op::Clear => {
+                let address = lowering.cell_address();
+                let zero = lowering.builder.ins().iconst(types::I8, 0);
+                lowering.builder.ins().store(MemFlagsData::trusted(), zero, address, 0);
+            },
+            Op::Scan(delta) => {
+                let header = lowering.builder.create_block();
+                let body = lowering.builder.create_block();
+                let after = lowering.builder.create_block();
+                lowering.builder.ins().jump(header, &[]);
+                lowering.builder.switch_to_block(header);
+                let cell = lowering.current_cell();
+                lowering.builder.ins().brif(cell, body, &[], after, &[]);
+                lowering.builder.switch_to_block(body);
+                lowering.move_cursor(delta);
+                lowering.builder.ins().jump(header, &[]);
+                lowering.builder.switch_to_block(after);
+            },
+            Op::JumpIfZero(_) => {
+                let body = lowering.builder.create_block();
+                let after = lowering.builder.create_block();
+                let cell = lowering.current_cell();
+                lowering.builder.ins().brif(cell, body, &[], after, &[]);
+                lowering.builder.switch_to_block(body);
+                loops.push((body, after));
+            },
+            Op::JumpIfNotZero(_) => {
+                let (body, after) = loops.pop().unwrap();
+                let cell = lowering.current_cell();
+                lowering.builder.ins().brif(cell, body, &[], after, &[]);
+                lowering.builder.switch_to_block(after);
+            },
+            Op::GetEnv | Op::Extension(_) => {},
+        }
+    }
+
+    let Lowering { mut builder, .. } = lowering;
+    let cursor = builder.use_var(cursor);
+    builder.ins().return_(&[cursor]);
+    for (block, status) in [(out_of_bounds, STATUS_OUT_OF_BOUNDS), (stopped, STATUS_STOPPED)] {
+        builder.switch_to_block(block);
+        let status = builder.ins().iconst(pointer, i64::try_from(status).unwrap());
+        builder.ins().return_(&[status]);
+    }
+    builder.seal_all_blocks();
+    builder.finalize(config);
+}
+
+impl Lowering<'_> {
+    fn cell_address(&mut self) -> Value {
+        let cursor = self.builder.use_var(self.cursor);
+        return self.builder.ins().iadd(self.tape, cursor);
+    }
+
+    fn current_cell(&mut self) -> Value {
+        let address = self.cell_address();
+        return self.builder.ins().load(types::I8, MemFlagsData::trusted(), address, 0);
+    }
+
+    fn offset_cell_address(&mut self, offset: isize) -> Value {
+        // The address of the cell `offset` cells away from the cursor, which has to be on the tape too
+        let cursor = self.builder.use_var(self.cursor);
+        let cell = self.builder.ins().iadd_imm_s(cursor, i64::try_from(offset).unwrap());
+        self.check_bounds(cell);
+        return self.builder.ins().iadd(self.tape, cell);
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        let cursor = self.builder.use_var(self.cursor);
+        let cursor = self.builder.ins().iadd_imm_s(cursor, i64::try_from(delta).unwrap());
+        self.builder.def_var(self.cursor, cursor);
+        self.check_bounds(cursor);
+    }
+
+    fn check_bounds(&mut self, index: Value) {
+        // Negative indices wrap around to huge unsigned numbers, so one comparison checks both ends
+        let size = i64::try_from(JIT_TAPE_SIZE).unwrap();
+        let inside = self.builder.ins().icmp_imm_s(IntCC::UnsignedLessThan, index, size);
+        let next = self.builder.create_block();
+        self.builder.ins().brif(inside, next, &[], self.out_of_bounds, &[]);
+        self.builder.switch_to_block(next);
+    }
+
+    fn continue_unless(&mut self, failed: Value) {
+        let next = self.builder.create_block();
+        self.builder.ins().brif(failed, self.stopped, &[], next, &[]);
+        self.builder.switch_to_block(next);
+    }
+}
+
+extern "C" fn jit_print(state: *mut BFState, value: i32) -> i32 {
+    // SAFETY: `run_jit` passes a state it borrows mutably for the whole run
+    let state = unsafe { &mut *state };
+    if !print_byte(state, value as u8) {
+        return 1;
+    }
+    return 0;
+}
+
+extern "C" fn jit_input(state: *mut BFState) -> i32 {
+    // SAFETY: `run_jit` passes a state it borrows mutably for the whole run
+    let state = unsafe { &mut *state };
+    read_char(state);
+    if state.error.is_some() {
+        return -1;
+    }
+    return i32::from(current_value(state));
+}
+
+pub fn run_jit(state: &mut BFState, program: &JitProgram) -> Result<RunReport, BfError> {
+    /*
+     * Runs the compiled program on the state from the beginning,
+     * with the cells within half the JIT tape size of the cursor copied in and back out.
+     * Steps aren't counted, `RunReport::steps` is always 0.
+     */
+
+    if state.env_extension {
+        return Err(BfError::Runtime(String::from("The `$` extension isn't supported by the JIT")));
+    }
+    if state.eof_behavior == EofBehavior::Unchanged {
+        // The input callback can't see the JIT's tape, it only writes the cell it reads into
+        return Err(BfError::Runtime(String::from("Leaving the cell unchanged at EOF isn't supported by the JIT")));
+    }
+    let origin = state.cursor_position - isize::try_from(JIT_TAPE_SIZE / 2).unwrap();
+    let (lowest, highest) = tape_extent(state);
+    let mut tape = export_tape(state, origin..origin + isize::try_from(JIT_TAPE_SIZE).unwrap());
+    let outside = (lowest..origin).chain(origin + isize::try_from(JIT_TAPE_SIZE).unwrap()..=highest);
+    if outside.into_iter().any(|index| get_value_at(state, index) != 0) {
+        return Err(BfError::Runtime(format!("The tape doesn't fit in the {JIT_TAPE_SIZE} cells of the JIT")));
+    }
+
+    let started = Instant::now();
+    // SAFETY: the tape has `JIT_TAPE_SIZE` cells, and the callbacks match the signature `lower` uses
+    let result = unsafe { (program.entry)(tape.as_mut_ptr(), state, jit_print, jit_input) };
+
+    // Printing may have allocated the cell under the cursor, so the extent is taken again
+    let (lowest, highest) = tape_extent(state);
+    for (index, &value) in (origin..).zip(&tape) {
+        if value != 0 || (lowest..=highest).contains(&index) {
+            set_value_at(state, index, value);
+            state.highest_cursor = state.highest_cursor.max(index);
+        }
+    }
+    let outcome = match result {
+        STATUS_OUT_OF_BOUNDS => {
+            return Err(BfError::Runtime(format!("The cursor left the {JIT_TAPE_SIZE} cells of the JIT")));
+        },
+        STATUS_STOPPED => stop_outcome(state)?,
+        cursor => {
+            state.cursor_position = origin + cursor;
+            state.lowest_cursor = state.lowest_cursor.min(state.cursor_position);
+            state.instruction_position = state.program.bytecode.ops.len();
+            stop_outcome(state)?
+        },
+    };
+
+    return Ok(RunReport {
+        outcome,
+        steps: 0,
+        output: state.captured_output.as_mut().map(std::mem::take).unwrap_or_default(),
+        duration: started.elapsed(),
+        final_cursor: state.cursor_position,
+        tape_extent: tape_extent(state),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cranelift_codegen::Context;
+    use cranelift_codegen::control::ControlPlane;
+    use cranelift_codegen::ir::Function;
+    use cranelift_codegen::isa;
+
+    #[test]
+    fn lowers_for_aarch64() {
+        // Compiles without running, so it works on any host
+        let ops = compile(b"++++++++[>++++++++<-]>+.<+[>,.<-]>>[-]<<[->+>+<<]>>>+++[<]", 0).ops;
+        for target in ["aarch64-unknown-linux-gnu", "aarch64-apple-darwin"] {
+            let isa = isa::lookup_by_name(target).unwrap().finish(jit_flags().unwrap()).unwrap();
+            let mut context = Context::for_function(Function::with_name_signature(UserFuncName::default(), entry_signature(&*isa)));
+            lower(FunctionBuilder::new(&mut context.func, &mut FunctionBuilderContext::new()), &ops, isa.frontend_config());
+            let code = context.compile(&*isa, &mut ControlPlane::default()).unwrap();
+            assert!(!code.code_buffer().is_empty(), "nothing was emitted for {target}");
+        }
+    }
+}
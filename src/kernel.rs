@@ -0,0 +1,584 @@
+use brainfuckers::*;
+
+use serde_json::{Value, json};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/*
+ * `bf kernel --connection-file <file>`: a minimal Jupyter kernel, so notebooks can run brainfuck cells.
+ * Every cell runs against the same tape and cursor, like lines of `bf repl`; a cell starting with `%tape`
+ * shows the cells around the cursor as a table instead. Programs read 0 when they ask for input,
+ * and interrupting the kernel stops the running cell.
+ *
+ * Jupyter talks ZeroMQ, which is spoken here directly: ZMTP 3 over TCP with the NULL mechanism, enough for
+ * the ROUTER (shell, control, stdin), PUB (iopub) and REP (heartbeat) sockets a kernel needs.
+ * Messages are signed with HMAC-SHA256 when the connection file has a key.
+ */
+
+const PROTOCOL_VERSION: &str = "5.3";
+const TAPE_VIEW_RADIUS: isize = 8; // Cells `%tape` shows on each side of the cursor
+const DELIMITER: &[u8] = b"<IDS|MSG>";
+
+// ZMTP framing
+
+const FLAG_MORE: u8 = 1;
+const FLAG_LONG: u8 = 2;
+const FLAG_COMMAND: u8 = 4;
+const MAX_FRAME_SIZE: usize = 64 << 20; // The size comes from the peer, before any signature is checked
+
+fn handshake(stream: &mut TcpStream, socket_type: &str) -> std::io::Result<()> {
+    // Greeting of ZMTP 3.0 with the NULL mechanism, then a READY command with our socket type
+    let mut greeting = [0u8; 64];
+    greeting[0] = 0xFF;
+    greeting[9] = 0x7F;
+    greeting[10] = 3;
+    greeting[12..16].copy_from_slice(b"NULL");
+    stream.write_all(&greeting)?;
+    let mut peer = [0u8; 64];
+    stream.read_exact(&mut peer)?;
+    if peer[0] != 0xFF || peer[9] != 0x7F || peer[10] < 3 {
+        return Err(std::io::Error::other("the peer doesn't speak ZMTP 3"));
+    }
+
+    let mut ready = vec![5];
+    ready.extend(b"READY");
+    ready.push(11);
+    ready.extend(b"Socket-Type");
+    ready.extend((socket_type.len() as u32).to_be_bytes());
+    ready.extend(socket_type.as_bytes());
+    write_frame(stream, &ready, FLAG_COMMAND)?;
+    // The peer's READY
+    read_frame(stream)?;
+    return Ok(());
+}
+
+fn write_frame(stream: &mut impl Write, body: &[u8], flags: u8) -> std::io::Result<()> {
+    let mut frame = Vec::with_capacity(body.len() + 9);
+    match u8::try_from(body.len()) {
+        Ok(size) => frame.extend([flags, size]),
+        Err(_) => {
+            frame.push(flags | FLAG_LONG);
+            frame.extend((body.len() as u64).to_be_bytes());
+        },
+    }
+    frame.extend(body);
+    return stream.write_all(&frame);
+}
+
+fn read_frame(stream: &mut impl Read) -> std::io::Result<(u8, Vec<u8>)> {
+    let mut flags = [0u8; 1];
+    stream.read_exact(&mut flags)?;
+    let size = if flags[0] & FLAG_LONG != 0 {
+        let mut size = [0u8; 8];
+        stream.read_exact(&mut size)?;
+        usize::try_from(u64::from_be_bytes(size)).unwrap_or(usize::MAX)
+    }
+    else {
+        let mut size = [0u8; 1];
+        stream.read_exact(&mut size)?;
+        usize::from(size[0])
+    };
+    if size > MAX_FRAME_SIZE {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("frame of {size} bytes, the limit is {MAX_FRAME_SIZE}")));
+    }
+    let mut body = vec![0; size];
+    stream.read_exact(&mut body)?;
+    return Ok((flags[0], body));
+}
+
+fn read_message(stream: &mut impl Read) -> std::io::Result<Vec<Vec<u8>>> {
+    // The frames of the next message, commands (like subscriptions and pings) are skipped
+    let mut frames: Vec<Vec<u8>> = Vec::new();
+    loop {
+        let (flags, body) = read_frame(stream)?;
+        if flags & FLAG_COMMAND != 0 {
+            continue;
+        }
+        frames.push(body);
+        if flags & FLAG_MORE == 0 {
+            return Ok(frames);
+        }
+    }
+}
+
+fn write_message(stream: &mut impl Write, frames: &[Vec<u8>]) -> std::io::Result<()> {
+    for (i, frame) in frames.iter().enumerate() {
+        write_frame(stream, frame, if i + 1 < frames.len() { FLAG_MORE } else { 0 })?;
+    }
+    return stream.flush();
+}
+
+// HMAC-SHA256
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut hash: [u32; 8] = [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend((data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = hash;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(majority);
+            (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+        }
+        for (value, added) in hash.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *value = value.wrapping_add(added);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (bytes, value) in digest.chunks_exact_mut(4).zip(hash) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    return digest;
+}
+
+fn hmac_sha256(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > 64 {
+        block[..32].copy_from_slice(&sha256(key));
+    }
+    else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner: Vec<u8> = block.iter().map(|b| b ^ 0x36).collect();
+    for part in parts {
+        inner.extend(*part);
+    }
+    let mut outer: Vec<u8> = block.iter().map(|b| b ^ 0x5c).collect();
+    outer.extend(sha256(&inner));
+    return sha256(&outer);
+}
+
+// Jupyter messages
+
+struct Message {
+    identities: Vec<Vec<u8>>, // Routing frames before the delimiter, sent back with the reply
+    header: Value,
+    content: Value,
+}
+
+struct Connection {
+    key: Vec<u8>, // Empty when messages aren't signed
+    session: String, // Id of this kernel's session, in the header of every message
+    counter: AtomicU64, // For unique message ids
+    subscribers: Mutex<Vec<TcpStream>>, // Connections to the iopub socket
+}
+
+impl Connection {
+    fn sign(&self, parts: &[&[u8]]) -> String {
+        if self.key.is_empty() {
+            return String::new();
+        }
+        return hmac_sha256(&self.key, parts).iter().map(|b| format!("{b:02x}")).collect();
+    }
+
+    fn parse(&self, frames: Vec<Vec<u8>>) -> Option<Message> {
+        let delimiter = frames.iter().position(|frame| frame == DELIMITER)?;
+        let parts = frames.get(delimiter + 2..delimiter + 6)?;
+        let expected = self.sign(&parts.iter().map(Vec::as_slice).collect::<Vec<_>>());
+        if !crate::constant_time_eq(&frames[delimiter + 1], expected.as_bytes()) {
+            eprintln!("Dropped a message with an invalid signature");
+            return None;
+        }
+        return Some(Message {
+            identities: frames[..delimiter].to_vec(),
+            header: serde_json::from_slice(&parts[0]).ok()?,
+            content: serde_json::from_slice(&parts[3]).ok()?,
+        });
+    }
+
+    fn frames(&self, identities: &[Vec<u8>], msg_type: &str, parent: &Value, content: Value) -> Vec<Vec<u8>> {
+        let header = json!({
+            "msg_id": format!("{}-{}", self.session, self.counter.fetch_add(1, Ordering::Relaxed)),
+            "session": self.session,
+            "username": "kernel",
+            "date": timestamp(),
+            "msg_type": msg_type,
+            "version": PROTOCOL_VERSION,
+        });
+        let parts = [header.to_string(), parent.to_string(), String::from("{}"), content.to_string()].map(String::into_bytes);
+        let signature = self.sign(&parts.iter().map(Vec::as_slice).collect::<Vec<_>>());
+        let mut frames = identities.to_vec();
+        frames.push(DELIMITER.to_vec());
+        frames.push(signature.into_bytes());
+        frames.extend(parts);
+        return frames;
+    }
+
+    fn publish(&self, msg_type: &str, parent: &Value, content: Value) {
+        // Sends the message to every iopub subscriber, dropping the ones that went away
+        let frames = self.frames(&[msg_type.as_bytes().to_vec()], msg_type, parent, content);
+        self.subscribers.lock().unwrap().retain_mut(|stream| write_message(stream, &frames).is_ok());
+    }
+}
+
+fn timestamp() -> String {
+    // ISO 8601 in UTC, from days since the epoch to a civil date
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let (days, seconds) = ((now.as_secs() / 86400) as i64, now.as_secs() % 86400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    return format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:06}Z",
+        seconds / 3600, seconds / 60 % 60, seconds % 60, now.subsec_micros(),
+    );
+}
+
+// Kernel
+
+struct Session {
+    state: BFState,
+    execution_count: u64,
+}
+
+fn kernel_info() -> Value {
+    return json!({
+        "status": "ok",
+        "protocol_version": PROTOCOL_VERSION,
+        "implementation": "brainfuckers",
+        "implementation_version": env!("CARGO_PKG_VERSION"),
+        "language_info": {
+            "name": "brainfuck",
+            "version": "",
+            "mimetype": "text/x-brainfuck",
+            "file_extension": ".b",
+        },
+        "banner": "brainfuckers: every cell runs against the same tape, `%tape` shows it",
+    });
+}
+
+fn tape_table(state: &BFState) -> (String, String) {
+    // Plain text and HTML views of the cells around the cursor
+    let cursor = cursor_position(state);
+    let range = cursor - TAPE_VIEW_RADIUS..cursor + TAPE_VIEW_RADIUS + 1;
+    let mut text = String::new();
+    let mut html = String::from("<table><tr>");
+    for index in range.clone() {
+        html.push_str(&format!("<th>{index}</th>"));
+    }
+    html.push_str("</tr><tr>");
+    for index in range {
+        let value = get_cell(state, index);
+        if index == cursor {
+            text.push_str(&format!("[{value}] "));
+            html.push_str(&format!("<td><b>{value}</b></td>"));
+        }
+        else {
+            text.push_str(&format!("{value} "));
+            html.push_str(&format!("<td>{value}</td>"));
+        }
+    }
+    html.push_str("</tr></table>");
+    return (format!("cells {}..={}: {}", cursor - TAPE_VIEW_RADIUS, cursor + TAPE_VIEW_RADIUS, text.trim_end()), html);
+}
+
+fn execute(connection: &Connection, session: &mut Session, interrupted: &AtomicBool, request: &Message) -> Value {
+    let code = request.content["code"].as_str().unwrap_or_default();
+    session.execution_count += 1;
+    let count = session.execution_count;
+    connection.publish("execute_input", &request.header, json!({ "code": code, "execution_count": count }));
+
+    if code.trim_start().starts_with("%tape") {
+        let (text, html) = tape_table(&session.state);
+        let data = json!({ "text/plain": text, "text/html": html });
+        connection.publish("execute_result", &request.header, json!({ "execution_count": count, "data": data, "metadata": {} }));
+        return json!({ "status": "ok", "execution_count": count, "user_expressions": {} });
+    }
+
+    // Only interrupts arriving while the cell runs stop it, one sent while the kernel was idle is dropped
+    interrupted.store(false, Ordering::Relaxed);
+    load_code(&mut session.state, code);
+    let result = check_syntax(code.as_bytes()).and_then(|()| run_bf(&mut session.state, None, None));
+    let error = match result {
+        Ok(report) => {
+            if !report.output.is_empty() {
+                let text = String::from_utf8_lossy(&report.output);
+                connection.publish("stream", &request.header, json!({ "name": "stdout", "text": text }));
+            }
+            match report.outcome {
+                RunOutcome::Interrupted => Some((String::from("Interrupted"), String::from("The cell was interrupted"))),
+                _ => None,
+            }
+        },
+        Err(error) => Some((String::from("Error"), error.to_string())),
+    };
+    let Some((name, value)) = error else {
+        return json!({ "status": "ok", "execution_count": count, "user_expressions": {} });
+    };
+    let error = json!({ "ename": name, "evalue": value, "traceback": [value] });
+    connection.publish("error", &request.header, error.clone());
+    let mut reply = json!({ "status": "error", "execution_count": count });
+    reply.as_object_mut().unwrap().extend(error.as_object().unwrap().clone());
+    return reply;
+}
+
+fn handle(connection: &Connection, session: &Mutex<Session>, interrupted: &AtomicBool, stream: &mut TcpStream, request: Message) -> std::io::Result<()> {
+    // Answers a shell or control request, wrapped in busy and idle statuses
+    let msg_type = request.header["msg_type"].as_str().unwrap_or_default().to_string();
+    connection.publish("status", &request.header, json!({ "execution_state": "busy" }));
+    let reply = match msg_type.as_str() {
+        "kernel_info_request" => Some(("kernel_info_reply", kernel_info())),
+        "execute_request" => Some(("execute_reply", execute(connection, &mut session.lock().unwrap(), interrupted, &request))),
+        "is_complete_request" => {
+            let complete = check_syntax(request.content["code"].as_str().unwrap_or_default().as_bytes()).is_ok();
+            Some(("is_complete_reply", json!({ "status": if complete { "complete" } else { "incomplete" }, "indent": "" })))
+        },
+        "complete_request" => {
+            let cursor = request.content["cursor_pos"].clone();
+            Some(("complete_reply", json!({ "status": "ok", "matches": [], "cursor_start": cursor, "cursor_end": cursor, "metadata": {} })))
+        },
+        "inspect_request" => Some(("inspect_reply", json!({ "status": "ok", "found": false, "data": {}, "metadata": {} }))),
+        "history_request" => Some(("history_reply", json!({ "status": "ok", "history": [] }))),
+        "comm_info_request" => Some(("comm_info_reply", json!({ "status": "ok", "comms": {} }))),
+        "interrupt_request" => {
+            interrupted.store(true, Ordering::Relaxed);
+            Some(("interrupt_reply", json!({ "status": "ok" })))
+        },
+        "shutdown_request" => Some(("shutdown_reply", json!({ "status": "ok", "restart": request.content["restart"] }))),
+        _ => None,
+    };
+    if let Some((reply_type, content)) = reply {
+        write_message(stream, &connection.frames(&request.identities, reply_type, &request.header, content))?;
+    }
+    connection.publish("status", &request.header, json!({ "execution_state": "idle" }));
+    if msg_type == "shutdown_request" {
+        std::process::exit(0);
+    }
+    return Ok(());
+}
+
+fn listen(config: &Value, port: &str) -> Result<TcpListener, String> {
+    let ip = config["ip"].as_str().unwrap_or("127.0.0.1");
+    let number = config[port].as_u64().ok_or_else(|| format!("The connection file has no `{port}`"))?;
+    let number = u16::try_from(number).map_err(|_| format!("Invalid `{port}` {number} in the connection file"))?;
+    return TcpListener::bind((ip, number)).map_err(|error| format!("Failed to listen on {ip}:{number} {error}"));
+}
+
+fn accept_each(listener: TcpListener, serve: impl Fn(TcpStream) -> std::io::Result<()> + Send + Sync + 'static) {
+    // Serves every connection on its own thread
+    let serve = Arc::new(serve);
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let serve = serve.clone();
+            std::thread::spawn(move || {
+                let _ = serve(stream);
+            });
+        }
+    });
+}
+
+pub fn kernel(args: &[String]) -> Result<(), String> {
+    let [flag, path] = args else {
+        return Err(String::from("Usage: bf kernel --connection-file <file>"));
+    };
+    if flag != "--connection-file" {
+        return Err(String::from("Usage: bf kernel --connection-file <file>"));
+    }
+    let text = std::fs::read_to_string(path).map_err(|error| format!("Failed to read file `{path}` {error}"))?;
+    let config: Value = serde_json::from_str(&text).map_err(|error| format!("Invalid connection file `{path}`: {error}"))?;
+    if config["transport"].as_str().is_some_and(|transport| transport != "tcp") {
+        return Err(String::from("Only the `tcp` transport is supported"));
+    }
+    if config["signature_scheme"].as_str().is_some_and(|scheme| scheme != "hmac-sha256") {
+        return Err(String::from("Only the `hmac-sha256` signature scheme is supported"));
+    }
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler_flag = interrupted.clone();
+    if let Err(error) = ctrlc::set_handler(move || handler_flag.store(true, Ordering::Relaxed)) {
+        eprintln!("Failed to install the interrupt handler: {error}");
+    }
+    let mut state = new_bf_state("");
+    set_input_reader(&mut state, Box::new(std::io::empty()));
+    set_raw_output(&mut state, true);
+    capture_output(&mut state);
+    set_interrupt_flag(&mut state, interrupted.clone());
+    let session = Arc::new(Mutex::new(Session { state, execution_count: 0 }));
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+    let connection = Arc::new(Connection {
+        key: config["key"].as_str().unwrap_or_default().as_bytes().to_vec(),
+        session: format!("{:x}-{nanos:x}", std::process::id()),
+        counter: AtomicU64::new(0),
+        subscribers: Mutex::new(Vec::new()),
+    });
+
+    let heartbeat = listen(&config, "hb_port")?;
+    accept_each(heartbeat, |mut stream| {
+        handshake(&mut stream, "REP")?;
+        loop {
+            let frames = read_message(&mut stream)?;
+            write_message(&mut stream, &frames)?;
+        }
+    });
+    let iopub = listen(&config, "iopub_port")?;
+    let subscribers = connection.clone();
+    accept_each(iopub, move |mut stream| {
+        handshake(&mut stream, "PUB")?;
+        subscribers.subscribers.lock().unwrap().push(stream);
+        return Ok(());
+    });
+    // Nothing asks for input, but clients expect the socket to be there
+    let stdin = listen(&config, "stdin_port")?;
+    accept_each(stdin, |mut stream| {
+        handshake(&mut stream, "ROUTER")?;
+        loop {
+            read_message(&mut stream)?;
+        }
+    });
+    for port in ["shell_port", "control_port"] {
+        let listener = listen(&config, port)?;
+        let (connection, session, interrupted) = (connection.clone(), session.clone(), interrupted.clone());
+        accept_each(listener, move |mut stream| {
+            handshake(&mut stream, "ROUTER")?;
+            loop {
+                let frames = read_message(&mut stream)?;
+                if let Some(request) = connection.parse(frames) {
+                    handle(&connection, &session, &interrupted, &mut stream, request)?;
+                }
+            }
+        });
+    }
+    eprintln!("Kernel started with {path}");
+    loop {
+        std::thread::park();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        return bytes.iter().map(|b| format!("{b:02x}")).collect();
+    }
+
+    fn connection_with_key(key: &[u8]) -> Connection {
+        return Connection { key: key.to_vec(), session: String::from("test"), counter: AtomicU64::new(0), subscribers: Mutex::new(Vec::new()) };
+    }
+
+    #[test]
+    fn hmac_matches_rfc_4231() {
+        let large_key = [0xaa; 131]; // Longer than a block, hashed first
+        let cases: [(&[u8], &[u8], &str); 6] = [
+            (&[0x0b; 20], b"Hi There", "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"),
+            (b"Jefe", b"what do ya want for nothing?", "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"),
+            (&[0xaa; 20], &[0xdd; 50], "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe"),
+            (&std::array::from_fn::<u8, 25, _>(|i| i as u8 + 1), &[0xcd; 50], "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b"),
+            (&large_key, b"Test Using Larger Than Block-Size Key - Hash Key First", "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"),
+            (
+                &large_key,
+                b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm.",
+                "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+            ),
+        ];
+        for (i, (key, data, expected)) in cases.into_iter().enumerate() {
+            assert_eq!(hex(&hmac_sha256(key, &[data])), expected, "test case {}", i + 1);
+            // The signed parts are hashed as if they were one message
+            let (first, second) = data.split_at(data.len() / 2);
+            assert_eq!(hex(&hmac_sha256(key, &[first, second])), expected, "test case {} in two parts", i + 1);
+        }
+    }
+
+    #[test]
+    fn interrupts_while_idle_dont_stop_the_next_cell() {
+        let connection = connection_with_key(b"");
+        let mut state = new_bf_state("");
+        capture_output(&mut state);
+        let interrupted = Arc::new(AtomicBool::new(true));
+        set_interrupt_flag(&mut state, interrupted.clone());
+        let mut session = Session { state, execution_count: 0 };
+        let frames = connection.frames(&[], "execute_request", &json!({}), json!({ "code": "+++" }));
+        let reply = execute(&connection, &mut session, &interrupted, &connection.parse(frames).unwrap());
+        assert_eq!(reply["status"], "ok");
+        assert_eq!(get_cell(&session.state, 0), 3);
+    }
+
+    #[test]
+    fn ports_out_of_range_are_rejected() {
+        let error = listen(&json!({ "shell_port": 70000 }), "shell_port").unwrap_err();
+        assert_eq!(error, "Invalid `shell_port` 70000 in the connection file");
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let frames = vec![b"id".to_vec(), Vec::new(), vec![7; 255], vec![9; 256], vec![1; 70_000]];
+        let mut bytes = Vec::new();
+        write_frame(&mut bytes, b"\x05READY", FLAG_COMMAND).unwrap(); // Skipped by `read_message`
+        write_message(&mut bytes, &frames).unwrap();
+        // Short frames have a 1-byte size, longer ones an 8-byte size
+        assert_eq!(bytes.len(), 8 + (2 + 2) + 2 + (2 + 255) + (9 + 256) + (9 + 70_000));
+        let mut reader = bytes.as_slice();
+        assert_eq!(read_message(&mut reader).unwrap(), frames);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected_before_allocating() {
+        let mut bytes = vec![FLAG_LONG];
+        bytes.extend(u64::MAX.to_be_bytes());
+        let error = read_frame(&mut bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn messages_need_a_valid_signature() {
+        let connection = connection_with_key(b"secret");
+        let frames = connection.frames(&[b"client".to_vec()], "execute_request", &json!({}), json!({ "code": "+" }));
+        let message = connection.parse(frames.clone()).unwrap();
+        assert_eq!(message.identities, [b"client".to_vec()]);
+        assert_eq!(message.content["code"], "+");
+
+        let mut tampered = frames.clone();
+        *tampered.last_mut().unwrap() = json!({ "code": "-" }).to_string().into_bytes();
+        assert!(connection.parse(tampered).is_none());
+        let mut unsigned = frames.clone();
+        unsigned[2] = Vec::new();
+        assert!(connection.parse(unsigned).is_none());
+        assert!(connection_with_key(b"other").parse(frames).is_none());
+        // Without a key, messages aren't signed
+        let unkeyed = connection_with_key(b"");
+        let frames = unkeyed.frames(&[], "kernel_info_request", &json!({}), json!({}));
+        assert!(frames[1].is_empty());
+        assert!(unkeyed.parse(frames).is_some());
+    }
+}
@@ -0,0 +1,376 @@
+use crate::*;
+use extension_ops::extension_characters;
+
+#[cfg(not(feature = "arena"))]
+use std::collections::HashMap;
+
+/*
+ * Known-zero analysis: a dataflow pass over the bytecode tracking the cells whose values are known statically,
+ * relative to the cursor. At the start of the program every cell is 0, after a loop its cell is 0,
+ * and additions keep the values known. Nothing is known at the start of a loop body, since it's also reached
+ * from its `]`, nor after input or an extension operation. After a balanced loop (see `balance.rs`),
+ * only the cells its body may write are forgotten; anything else forgets the whole tape.
+ *
+ * With that, loops entered on a known 0 cell (comment loops, `][`) and clears of a known 0 cell are removed,
+ * and loops entered on a known nonzero cell whose body is sure to end on the same cell at 0 (`if` blocks like
+ * `+[>+<-]`) run exactly once, so their brackets are removed.
+ *
+ * The start of the program is only assumed to be zeroed by programs compiled from code; states whose tape is
+ * changed from outside (`load_tape`, `set_cell`) switch to a version of the program compiled without the pass.
+ * Additions merged across a removed loop cover instructions on both sides of it, so each folded op remembers
+ * where the last instruction it covers ends, which is where that version goes on from.
+ *
+ * With the `arena` feature, the cells known at each point are allocated in a bump arena (see `arena.rs`),
+ * which is freed all at once after the pass instead of map by map.
+ */
+
+const MAX_DEPTH: usize = 256; // Deeper nested loops aren't analyzed, the pass recurses into them
+const MAX_ONCE_DEPTH: usize = 4; // Loops nested deeper in a loop aren't tried as run-once, each level may fold its body twice
+
+#[cfg(feature = "arena")]
+pub(crate) type Scratch<'a> = &'a bumpalo::Bump;
+#[cfg(not(feature = "arena"))]
+pub(crate) type Scratch<'a> = std::marker::PhantomData<&'a ()>;
+
+#[cfg(feature = "arena")]
+type Cells<'a> = hashbrown::HashMap<isize, Option<u8>, hashbrown::DefaultHashBuilder, Scratch<'a>>;
+#[cfg(not(feature = "arena"))]
+type Cells<'a> = HashMap<isize, Option<u8>>;
+
+#[derive(Clone)]
+struct Knowledge<'a> {
+    cells: Cells<'a>, // Values by offset from where the analysis started, `None` if unknown
+    rest_zero: bool, // Cells missing from `cells` are 0, true until something unknown happened since the start
+    offset: isize, // Cursor, relative to where the analysis started
+    drifted: bool, // The cursor moved by an unknown distance, so `offset` doesn't match the real cursor anymore
+    scratch: Scratch<'a>, // Where `cells` is allocated
+}
+
+impl<'a> Knowledge<'a> {
+    fn new(scratch: Scratch<'a>, offset: isize) -> Self {
+        // Nothing known yet, not even that the tape is zeroed
+        #[cfg(feature = "arena")]
+        let cells = Cells::with_hasher_in(hashbrown::DefaultHashBuilder::default(), scratch);
+        #[cfg(not(feature = "arena"))]
+        let cells = Cells::new();
+        return Knowledge { cells, rest_zero: false, offset, drifted: false, scratch };
+    }
+
+    fn value(&self) -> Option<u8> {
+        return match self.cells.get(&self.offset) {
+            Some(&value) => value,
+            None => self.rest_zero.then_some(0),
+        };
+    }
+
+    fn set(&mut self, value: Option<u8>) {
+        self.cells.insert(self.offset, value);
+    }
+
+    fn forget(&mut self) {
+        self.cells.clear();
+        self.rest_zero = false;
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Folded {
+    pub(crate) bytecode: Bytecode,
+    pub(crate) resume_positions: Vec<usize>, // Source position right after the last instruction each op covers
+}
+
+impl Folded {
+    fn unchanged(bytecode: &Bytecode) -> Self {
+        let resume_positions = bytecode.positions.iter().map(|position| position + 1).collect();
+        return Folded { bytecode: bytecode.clone(), resume_positions };
+    }
+}
+
+pub(crate) fn fold_known_zero(bytecode: &Bytecode) -> Folded {
+    #[cfg(feature = "arena")]
+    return fold_known_zero_in(bytecode, &bumpalo::Bump::new());
+    #[cfg(not(feature = "arena"))]
+    return fold_known_zero_in(bytecode, std::marker::PhantomData);
+}
+
+pub(crate) fn fold_known_zero_in(bytecode: &Bytecode, scratch: Scratch) -> Folded {
+    // The bytecode of a whole program, assuming it starts on a zeroed tape
+    let mut depth: usize = 0;
+    for op in &bytecode.ops {
+        match op {
+            Op::JumpIfZero(_) => depth += 1,
+            Op::JumpIfNotZero(_) => depth -= 1,
+            _ => continue,
+        }
+        if depth > MAX_DEPTH {
+            return Folded::unchanged(bytecode);
+        }
+    }
+
+    let mut knowledge = Knowledge { rest_zero: true, ..Knowledge::new(scratch, 0) };
+    let mut folded = Folded::default();
+    fold(bytecode, 0..bytecode.ops.len(), &mut knowledge, &mut folded);
+
+    // Jump targets are set once everything is in place
+    let ops = &mut folded.bytecode.ops;
+    let mut open_blocks: Vec<usize> = Vec::new();
+    for index in 0..ops.len() {
+        match ops[index] {
+            Op::JumpIfZero(_) => open_blocks.push(index),
+            Op::JumpIfNotZero(_) => {
+                let begin = open_blocks.pop().unwrap();
+                ops[begin] = Op::JumpIfZero(index + 1);
+                ops[index] = Op::JumpIfNotZero(begin + 1);
+            },
+            _ => {},
+        }
+    }
+    return folded;
+}
+
+fn fold(bytecode: &Bytecode, range: std::ops::Range<usize>, knowledge: &mut Knowledge<'_>, folded: &mut Folded) {
+    let mut i = range.start;
+    while i < range.end {
+        let (op, position) = (bytecode.ops[i], bytecode.positions[i]);
+        match op {
+            Op::Add(offset, value) => {
+                knowledge.offset += offset;
+                knowledge.set(knowledge.value().map(|known| known.wrapping_add(value)));
+                knowledge.offset -= offset;
+                emit(folded, op, position);
+            },
+            Op::Move(delta) => {
+                knowledge.offset += delta;
+                emit(folded, op, position);
+            },
+            Op::Print | Op::GetEnv => emit(folded, op, position),
+            Op::Input => {
+                knowledge.set(None);
+                emit(folded, op, position);
+            },
+            Op::Extension(_) => {
+                // Handlers can do anything to the tape and the cursor
+                knowledge.forget();
+                knowledge.drifted = true;
+                emit(folded, op, position);
+            },
+            Op::Clear => {
+                if knowledge.value() != Some(0) {
+                    emit(folded, op, position);
+                }
+                knowledge.set(Some(0));
+            },
+            Op::Scan(_) => {
+                if knowledge.value() != Some(0) {
+                    emit(folded, op, position);
+                    knowledge.forget();
+                    knowledge.drifted = true;
+                    knowledge.set(Some(0));
+                }
+            },
+            Op::JumpIfZero(after) => {
+                let body = i + 1..after - 1;
+                match knowledge.value() {
+                    Some(0) => {}, // Never entered
+                    Some(_) if let Some(once) = run_once(bytecode, body.clone(), knowledge) => {
+                        let drifted = knowledge.drifted;
+                        *knowledge = once.0;
+                        knowledge.drifted |= drifted;
+                        for ((op, position), resume) in once.1.bytecode.ops.into_iter().zip(once.1.bytecode.positions).zip(once.1.resume_positions) {
+                            emit_covering(folded, op, position, resume);
+                        }
+                    },
+                    _ => {
+                        emit(folded, Op::JumpIfZero(0), position);
+                        let mut inner = Knowledge::new(knowledge.scratch, knowledge.offset);
+                        fold(bytecode, body.clone(), &mut inner, folded);
+                        emit(folded, Op::JumpIfNotZero(0), bytecode.positions[after - 1]);
+                        let offset = knowledge.offset;
+                        let movement = balance::body_effect(&bytecode.ops, body, &mut |cell| {
+                            knowledge.cells.insert(offset + cell, None);
+                        });
+                        if movement != Some(0) {
+                            knowledge.forget();
+                            knowledge.drifted = true;
+                        }
+                        knowledge.set(Some(0));
+                    },
+                }
+                i = after;
+                continue;
+            },
+            Op::JumpIfNotZero(_) => unreachable!("loops are folded from their `[`"),
+        }
+        i += 1;
+    }
+}
+
+fn run_once<'a>(bytecode: &Bytecode, body: std::ops::Range<usize>, knowledge: &Knowledge<'a>) -> Option<(Knowledge<'a>, Folded)> {
+    /*
+     * Folds the body of a loop entered on a known nonzero cell, if it surely runs only once:
+     * it ends on the cell it started on with that cell known to be 0. Loops in the body have to be balanced
+     * for anything to be known at its end.
+     */
+
+    let mut depth: usize = 0;
+    for op in &bytecode.ops[body.clone()] {
+        match op {
+            Op::JumpIfZero(_) => depth += 1,
+            Op::JumpIfNotZero(_) => depth -= 1,
+            _ => continue,
+        }
+        if depth > MAX_ONCE_DEPTH {
+            return None;
+        }
+    }
+    let mut once = knowledge.clone();
+    once.drifted = false;
+    let mut folded = Folded::default();
+    fold(bytecode, body, &mut once, &mut folded);
+    if once.drifted || once.offset != knowledge.offset || once.value() != Some(0) {
+        return None;
+    }
+    return Some((once, folded));
+}
+
+fn emit(folded: &mut Folded, op: Op, position: usize) {
+    emit_covering(folded, op, position, position + 1);
+}
+
+fn emit_covering(folded: &mut Folded, op: Op, position: usize, resume_position: usize) {
+    // Additions and moves brought together by a removed loop are merged, like the compiler does
+    let Folded { bytecode, resume_positions } = folded;
+    let cancelled = match (bytecode.ops.last_mut(), op) {
+        (Some(Op::Add(previous_offset, previous)), Op::Add(offset, value)) if *previous_offset == offset => {
+            *previous = previous.wrapping_add(value);
+            *previous == 0
+        },
+        (Some(Op::Move(previous)), Op::Move(delta)) => {
+            *previous += delta;
+            *previous == 0
+        },
+        _ => {
+            bytecode.ops.push(op);
+            bytecode.positions.push(position);
+            resume_positions.push(resume_position);
+            return;
+        },
+    };
+    if cancelled {
+        bytecode.ops.pop();
+        bytecode.positions.pop();
+        resume_positions.pop();
+    }
+    // What was merged (or cancelled out) ran along with the last op
+    if let Some(last) = resume_positions.last_mut() {
+        *last = resume_position;
+    }
+}
+
+pub(crate) fn forget_zeroed_tape(state: &mut BFState) {
+    /*
+     * Called when the tape is changed from outside the program: switches to the program compiled without the pass,
+     * since the cells may not hold what the analysis expected anymore. It goes on right after the last instruction
+     * that ran, so what the pass left out from there (like a leading `[-]`, before the run started) runs after all.
+     * An extension op changing the tape is still running, so that one goes on from the op itself; its handler is
+     * out of its slot meanwhile, so its character is taken from the code.
+     */
+
+    if !state.program.assumes_zeroed_tape {
+        return;
+    }
+    let ip = state.instruction_position;
+    let mut characters = extension_characters(state);
+    let running = match state.program.bytecode.ops.get(ip) {
+        Some(&Op::Extension(index)) if characters.get(usize::from(index)) == Some(&0) => {
+            characters[usize::from(index)] = state.program.code[source_position(state)];
+            true
+        },
+        _ => false,
+    };
+    let position = match ip.checked_sub(1) {
+        _ if running => source_position(state),
+        Some(previous) => state.program.resume_positions[previous],
+        None => state.program.code_start,
+    };
+    let program = Program::compile(state.program.code.clone(), state.program.code_start, &characters, false);
+    let index = program.bytecode.positions.iter().position(|&start| start >= position).unwrap_or(program.bytecode.ops.len());
+    program::replace_program(state, program);
+    state.instruction_position = index;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(code: &str, assumes_zeroed_tape: bool, pause_at: usize, change: impl Fn(&mut BFState)) -> (Vec<u8>, isize, Vec<u8>) {
+        // Runs up to the instruction at `pause_at` in the code, changes the tape and runs the rest
+        let program = Program::compile(code.as_bytes().to_vec(), 0, &[], assumes_zeroed_tape);
+        let mut state = new_bf_state_from_program(Arc::new(program));
+        set_input_reader(&mut state, Box::new(std::io::empty()));
+        capture_output(&mut state);
+        while source_position(&state) < pause_at && step_bf(&mut state) {}
+        change(&mut state);
+        let report = run_bf(&mut state, Some(100_000), None).unwrap();
+        assert!(matches!(report.outcome, RunOutcome::Halted));
+        return (report.output, cursor_position(&state), export_tape(&state, -16..16));
+    }
+
+    fn assert_same_as_unfolded(code: &str, pause_at: usize, change: impl Fn(&mut BFState)) {
+        assert_eq!(run(code, true, pause_at, &change), run(code, false, pause_at, &change), "{code}");
+    }
+
+    fn folded_ops(code: &str) -> usize {
+        return Program::compile(code.as_bytes().to_vec(), 0, &[], true).bytecode.ops.len();
+    }
+
+    fn unfolded_ops(code: &str) -> usize {
+        return Program::compile(code.as_bytes().to_vec(), 0, &[], false).bytecode.ops.len();
+    }
+
+    #[test]
+    fn comment_loops_at_the_start_are_removed() {
+        for code in ["[comment, with. commands- +]++++++[>+++++++<-]>.", "[.][-][<>]+.", "[[+]>>[<]]+++."] {
+            assert!(folded_ops(code) < unfolded_ops(code), "{code}");
+            assert_same_as_unfolded(code, 0, |_| {});
+        }
+    }
+
+    #[test]
+    fn if_blocks_run_once_without_their_brackets() {
+        let code = "+[>+<-]>.";
+        assert_eq!(folded_ops(code), unfolded_ops(code) - 2);
+        assert!(!Program::compile(code.as_bytes().to_vec(), 0, &[], true).bytecode.ops.iter().any(|op| matches!(op, Op::JumpIfZero(_))));
+        assert_same_as_unfolded(code, 0, |_| {});
+        assert_same_as_unfolded("++>+[>++<-[>+<-]]>.>.", 0, |_| {});
+    }
+
+    #[test]
+    fn unbalanced_loops_forget_the_tape() {
+        // `[-]` right after `[>]` is on a 0 cell, the ones after moving again aren't known anymore
+        for code in ["+>++>+++<<[>][-]<[-]<.>>>+.", "+>++>+++<<[>>]<[-]<.", "+>++>+++<<[>+>][-]<[-]<<."] {
+            assert_same_as_unfolded(code, 0, |_| {});
+        }
+    }
+
+    #[test]
+    fn changing_the_tape_mid_run_goes_on_without_the_pass() {
+        let code = "+.>[-]+.>[.-]<<[-]";
+        let set = code.find('>').unwrap();
+        assert_same_as_unfolded(code, set, |state| set_cell(state, 1, 5));
+        assert_same_as_unfolded(code, set, |state| load_tape(state, &[7, 3, 3], 0).unwrap());
+        let loaded = code.rfind('>').unwrap();
+        assert_same_as_unfolded(code, loaded, |state| load_tape(state, &[3], 2).unwrap());
+        assert_same_as_unfolded(code, 0, |state| set_cell(state, 0, 9));
+    }
+
+    #[test]
+    fn additions_merged_across_a_removed_loop_run_once() {
+        // The two `+` become one op once `[.]` is removed, changing the tape after it mustn't run the second one again
+        for code in [">+<[.]>+<", ">+<[.]>+<.>.", "+[-]>+<[.]>+>[.]<.", ">[.]<+."] {
+            for pause_at in 0..code.len() {
+                assert_same_as_unfolded(code, pause_at, |state| set_cell(state, 5, 1));
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+/*
+ * Labels are comment directives like `@label{main_loop}` marking a place in the source.
+ * Names are limited to ASCII letters, digits and `_`,
+ * so a label is always a plain comment and never changes what the code does.
+ */
+
+const LABEL_PREFIX: &[u8] = b"@label{";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Label {
+    pub name: String,
+    pub position: usize, // Source position of the `@`
+}
+
+pub fn parse_labels(code: &[u8], offset: usize) -> Vec<Label> {
+    // Labels in `code[offset..]`, in source order; positions are relative to the start of `code`
+    let mut labels: Vec<Label> = Vec::new();
+    let mut i = offset;
+    while i < code.len() {
+        if !code[i..].starts_with(LABEL_PREFIX) {
+            i += 1;
+            continue;
+        }
+        let name_start = i + LABEL_PREFIX.len();
+        let name_length = code[name_start..].iter().take_while(|c| c.is_ascii_alphanumeric() || **c == b'_').count();
+        let name_end = name_start + name_length;
+        if name_length > 0 && code.get(name_end) == Some(&b'}') {
+            let name = String::from_utf8(code[name_start..name_end].to_vec()).unwrap();
+            labels.push(Label { name, position: i });
+            i = name_end + 1;
+        }
+        else {
+            i += 1;
+        }
+    }
+    return labels;
+}
+
+pub fn nearest_label(labels: &[Label], position: usize) -> Option<&Label> {
+    // The last label at or before the position
+    let index = labels.partition_point(|label| label.position <= position);
+    return index.checked_sub(1).map(|index| &labels[index]);
+}
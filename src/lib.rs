@@ -1,12 +1,203 @@
+/*
+ * Spans and events for the `tracing` ecosystem, compiled out without the `tracing` feature.
+ * `trace_span!` enters the span until the end of the enclosing block.
+ */
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::event!(tracing::Level::$level, $($arg)*);
+    };
+}
+
+macro_rules! trace_span {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::span!(tracing::Level::$level, $($arg)*).entered();
+    };
+}
+
+#[cfg(feature = "arena")]
+mod arena;
+mod audio;
+mod balance;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(feature = "bigint")]
+mod bigint;
+mod build;
+mod charset;
+mod checkpoint;
+mod cases;
+mod codec;
+mod const_eval;
+mod decimal_io;
+mod dialects;
+mod diff;
+mod error;
+mod explain;
+mod extension_ops;
+mod extensions;
+mod hints;
+mod host_calls;
+#[cfg(feature = "images")]
+mod image;
+mod incremental;
+#[cfg(feature = "cranelift")]
+mod jit;
+mod known_zero;
+mod labels;
+mod limits;
+mod loop_detector;
+mod metrics;
+mod output_cache;
+mod package;
+mod pool;
+mod preprocess;
+mod presets;
+mod program;
+#[cfg(feature = "symbolic")]
+mod reachability;
+mod stats;
+#[cfg(feature = "symbolic")]
+mod symbolic;
+mod taint;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod trace;
+mod transpile;
+mod turtle;
+mod wasm;
+mod window;
+
+#[cfg(feature = "arena")]
+pub use arena::CompileArena;
+pub use audio::{DEFAULT_NOTE_LENGTH, SAMPLE_RATE, ToneWriter, note_frequency};
+pub use balance::{Balance, LoopBalance, loop_balance};
+#[cfg(feature = "bigint")]
+pub use bigint::{BigCellOptions, BigCellRun, BigInt, run_big_cells};
+pub use build::{ArtifactFormat, Manifest, build, read_manifest};
+pub use charset::{Charset, TranslatingReader, TranslatingWriter};
+pub use checkpoint::{restore_checkpoint, save_checkpoint};
+pub use cases::{
+    DEFAULT_MAX_STEPS,
+    TestCase,
+    TestProgram,
+    TestResult,
+    check_bf_output,
+    check_bf_snapshot,
+    diff_lines,
+    discover_test_cases,
+    read_test_case,
+    run_test_case,
+};
+pub use codec::{DecodingReader, EncodingWriter, InputFormat, NewlineMode, NewlineReader, NewlineWriter, OutputFormat};
+pub use const_eval::{CONST_TAPE_SIZE, run_const};
+pub use decimal_io::enable_decimal_extension;
+pub use dialects::{Brainfuck, Dialect, DialectError, DialectRegistry, Ook, OokRot13, OpcodeMap, Spoon, detect_dialect, to_brainfuck};
+pub use diff::{DiffHunk, DiffLine, canonical_form, diff_programs};
+pub use error::BfError;
+pub use explain::explain_step;
+pub use extension_ops::{ExtensionOp, register_extension_op};
+pub use extensions::{Extensions, check_charset, check_extensions};
+pub use hints::{Hint, HintKind, program_hints};
+pub use host_calls::{HostFunction, HostFunctions, enable_syscall_extension};
+#[cfg(feature = "images")]
+pub use image::{ImageDialect, decode_image, encode_image};
+pub use incremental::{DEFAULT_SNAPSHOT_INTERVAL, IncrementalReport, IncrementalRun};
+pub use brainfuckers_ir::{Bytecode, Op, compile, compile_with, compile_with_extensions};
+#[cfg(feature = "cranelift")]
+pub use jit::{JIT_TAPE_SIZE, JitProgram, run_jit};
+pub use labels::{Label, nearest_label, parse_labels};
+pub use limits::{ResourceLimits, apply_limits, run_with_limits};
+pub use loop_detector::detect_infinite_loops;
+pub use metrics::Metrics;
+pub use output_cache::{file_key, is_output_only, read_cached_output, write_cached_output};
+#[cfg(feature = "macros")]
+pub use brainfuckers_macros::bf;
+pub use package::{Package, apply_package, read_package, write_package};
+pub use pool::{MachinePool, StatePool};
+pub use preprocess::{PreprocessOptions, SourceMap, preprocess};
+pub use presets::{Preset, PresetTarget};
+pub use program::{Program, new_program};
+#[cfg(feature = "symbolic")]
+pub use reachability::{Reachability, reachable};
+pub use stats::{ProgramStats, program_stats};
+#[cfg(feature = "symbolic")]
+pub use symbolic::{Constraint, Expr, ReachingPath, SymbolicOptions, SymbolicReport, Target, explore, solve_input};
+pub use taint::{is_tainted, set_taint_tracking, tainted_cells, tainted_output};
+pub use trace::{DEFAULT_HOT_LOOP_THRESHOLD, set_hot_loop_threshold};
+pub use transpile::{AssemblyTarget, to_aarch64, to_c, to_listing, to_llvm};
+pub use turtle::{CanvasFormat, Line, Turtle, TurtleWriter};
+pub use wasm::{to_wasm, to_wat};
+pub use window::run_with_window;
+
+use brainfuckers_ir::{
+    BF_OPCODE_BLOCK_BEGIN,
+    BF_OPCODE_BLOCK_END,
+    BF_OPCODE_DECREMENT_VALUE,
+    BF_OPCODE_INCREMENT_VALUE,
+    BF_OPCODE_INPUT,
+    BF_OPCODE_PRINT,
+    BF_OPCODE_SHIFT_LEFT,
+    BF_OPCODE_SHIFT_RIGHT,
+};
+use std::collections::{HashSet, VecDeque};
+use std::io::{BufRead, Write};
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use loop_detector::LoopDetector;
+use taint::Taint;
+use trace::LoopTrace;
+
 pub struct BFState {
-    code: Vec<u8>, // The brainfuck code
+    program: Arc<Program>, // The code and everything compiled from it, can be shared with other states
     ptape: Vec<u8>, // Vector of memory cells (positive direction, including 0)
     ntape: Vec<u8>, // Vector of memory cells (negative direction)
-    instruction_position: usize, // Index of the current instruction
+    instruction_position: usize, // Index of the current operation in the bytecode
     cursor_position: isize, // Index of the current memory cell
+    highest_cursor: isize, // Highest index the cursor has been at or added to, so pools know which cells to clear
+    lowest_cursor: isize, // Lowest index the cursor has been at, cells written below it are counted by `ntape`
+    tape_resizes: (u64, u64), // Times `ptape` and `ntape` grew
     loops: bool, // Whether the memory tape loops around or expands
-    output: Vec<u8>, // Used to buffer characters before printing (for UTF-8 Unicode)
+    output: Vec<u8>, // Used to buffer an incomplete UTF-8 character before printing
+    raw_output: bool, // Print bytes as they come instead of waiting for complete UTF-8 characters
+    writer: Box<dyn Write + Send>, // Where the output is printed (buffered stdout by default)
+    captured_output: Option<Vec<u8>>, // When set, the output is collected here instead of being printed
+    diagnostics: Option<Box<dyn Write + Send>>, // Where warnings are written (stderr by default), never the output
+    warnings: HashSet<String>, // Warnings written so far, each one is only written once
+    error: Option<BfError>, // Set when printing, reading or an extension operation fails, which halts the program
+    max_output_bytes: Option<u64>, // Printing more than this halts the program
+    max_output_rate: Option<u64>, // Bytes per second, printing is slowed down to match it
+    output_bytes: u64, // Number of bytes printed so far
+    output_started: Option<Instant>, // When the first byte was printed
+    output_limit_reached: bool, // Set when the program tried to print past `max_output_bytes`
+    max_tape_cells: Option<usize>, // Moving the cursor past what this many cells can hold halts the program
+    tape_limit_reached: bool, // Set when the cursor tried to leave the cells allowed by `max_tape_cells`
+    input_allowed: bool, // Reading input fails when this isn't set
     newline_0: bool, // Newline character will be converted into null (0) in the input
+    eof_behavior: EofBehavior, // What reading past the end of the input does to the cell
+    input: VecDeque<u8>, // Pending input, consumed before reading from the reader
+    input_bytes: u64, // Number of bytes read so far, not counting EOF
+    reader: Box<dyn BufRead + Send>, // Where the input is read from (buffered stdin by default)
+    reader_buffered: usize, // Bytes left in the reader's buffer, reading blocks when it's 0
+    env_extension: bool, // Whether the environment variable extension (`$`) is enabled
+    env_allowlist: Vec<String>, // Environment variables the program is allowed to read
+    extension_ops: Vec<Option<Box<dyn ExtensionOp>>>, // Handlers of `Op::Extension`, empty only while one runs
+    interrupt: Option<Arc<AtomicBool>>, // When set to true, `run_bf` stops at the next check
+    check_invariants: bool, // Validate the state after every step (debug builds only)
+    determinism: DeterminismLevel, // Which nondeterministic features the program may use
+    observer: Option<StepObserver>, // Called before every step, for tracing and narration
+    cell_observer: Option<CellObserver>, // Called when a step changes the value of the cell it worked on
+    cursor_observer: Option<CursorObserver>, // Called when a step moves the cursor
+    yield_hook: Option<(u64, YieldHook)>, // Called every that many steps of `run_bf`, can cancel the run
+    hot_loop_threshold: Option<u32>, // Iterations before a loop gets traced, `None` if tracing is off
+    loop_traces: Vec<LoopTrace>, // What's known about each loop, indexed by the position of its `[` in the bytecode
+    ip_history: Option<(usize, VecDeque<usize>)>, // Capacity and bytecode positions of the latest executed instructions
+    loop_iterations: Option<Vec<u64>>, // Current iteration of each loop, indexed like `loop_traces`, when tracked
+    loop_detector: Option<LoopDetector>, // Hashes of the states seen so far, to stop once one repeats
+    taint: Option<Taint>, // Which cells and printed bytes depend on input, when tracked
 }
 
 /*
@@ -20,109 +211,1158 @@ pub struct BFState {
  */
 
 const NEWLINE:                     u8 = 10;
-const BF_OPCODE_BLOCK_BEGIN:       u8 = 91;
-const BF_OPCODE_BLOCK_END:         u8 = 93;
-const BF_OPCODE_DECREMENT_VALUE:   u8 = 45;
-const BF_OPCODE_INCREMENT_VALUE:   u8 = 43;
-const BF_OPCODE_INPUT:             u8 = 44;
-const BF_OPCODE_PRINT:             u8 = 46;
-const BF_OPCODE_SHIFT_LEFT:        u8 = 60;
-const BF_OPCODE_SHIFT_RIGHT:       u8 = 62;
-
-pub fn new_bf_state(code: &str) -> BFState {
+const SHEBANG:                   &[u8] = b"#!";
+const COMMANDS:               &[u8; 8] = b"+-<>.,[]"; // The eight commands, in the order tables of them follow
+
+const TIMEOUT_CHECK_INTERVAL: u64 = 1024; // How many steps to run between wall-clock and interrupt checks
+
+#[derive(Debug)]
+pub enum RunOutcome {
+    Halted, // Reached the end of the code
+    FuelExhausted, // Ran out of the allowed number of steps
+    TimedOut, // Ran out of the allowed wall-clock time
+    Interrupted, // Stopped through the interrupt flag
+    OutputLimitExceeded, // Tried to print more than the allowed number of bytes
+    TapeLimitExceeded, // Tried to move the cursor past the allowed number of cells
+    Cancelled, // The yield hook returned `ControlFlow::Break`
+    LoopDetected, // The state repeated, the program would never halt (see `detect_infinite_loops`)
+}
+
+pub enum StepBatchResult {
+    Running(usize), // Executed the whole batch, the program can continue
+    Stopped(usize, RunOutcome), // The program stopped after the given number of steps
+    Failed(usize, BfError), // Printing failed after the given number of steps
+}
+
+pub type StepObserver = Box<dyn FnMut(&BFState) + Send>;
+pub type CellObserver = Box<dyn FnMut(isize, u8, u8) + Send>; // Index of the cell, old and new value
+pub type CursorObserver = Box<dyn FnMut(isize, isize) + Send>; // Old and new cursor position
+pub type YieldHook = Box<dyn FnMut(&BFState) -> ControlFlow<()> + Send>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeterminismLevel {
+    Unrestricted, // Any option and extension can be used
+    Deterministic, // Only what produces byte-identical output across runs and platforms
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisplayRadix {
+    Decimal, // `72`
+    Hex, // `48`
+    Binary, // `01001000`
+    Char, // `'H'`, escaped when not printable
+}
+
+impl std::str::FromStr for DisplayRadix {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        return match name {
+            "dec" => Ok(DisplayRadix::Decimal),
+            "hex" => Ok(DisplayRadix::Hex),
+            "bin" => Ok(DisplayRadix::Binary),
+            "char" => Ok(DisplayRadix::Char),
+            _ => Err(format!("Unknown radix `{name}` (expected `dec`, `hex`, `bin` or `char`)")),
+        };
+    }
+}
+
+pub struct RunReport {
+    pub outcome: RunOutcome, // Why the run stopped
+    pub steps: u64, // Number of instructions executed during the run
+    pub output: Vec<u8>, // Output of the run, only collected after `capture_output`
+    pub duration: Duration, // Wall-clock time of the run
+    pub final_cursor: isize, // Index of the current memory cell when the run stopped
+    pub tape_extent: (isize, isize), // Indices of the lowest and highest allocated memory cells
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TapeStats {
+    pub highest: isize, // Highest index the cursor has been at or a cell was written to
+    pub lowest: isize, // Lowest one, 0 if the program never went left
+    pub ptape_resizes: u64, // Times the positive tape grew past its allocated cells (3000 at the start)
+    pub ntape_resizes: u64, // Times the negative tape grew, it starts empty
+}
+
+impl TapeStats {
+    pub fn cells(&self) -> usize {
+        // The smallest `max_tape_cells` the run fits in
+        return (self.highest - self.lowest).unsigned_abs() + 1;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EofBehavior {
+    Zero, // The cell is set to 0
+    Unchanged, // The cell keeps its value
+    MaxValue, // The cell is set to 255, like C's `EOF` (-1) stored in a byte
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little, // Least significant byte in the lowest cell
+    Big, // Most significant byte in the lowest cell
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpenLoop {
+    pub position: usize, // Source position of the loop's `[`
+    pub iteration: u64, // 1 during the first pass through the body, 0 if unknown
+}
+
+pub fn new_bf_state(code: impl AsRef<[u8]>) -> BFState {
+    return new_bf_state_from_program(new_program(code));
+}
+
+pub fn new_bf_state_from_program(program: Arc<Program>) -> BFState {
+    // Only the tape, the cursor and the I/O are created, the compiled program is shared
+    let writer = Box::new(std::io::BufWriter::new(std::io::stdout()));
+    let reader = Box::new(std::io::BufReader::new(std::io::stdin()));
+    return blank_state(program, vec![0; 3000], writer, reader);
+}
+
+pub(crate) fn blank_state(program: Arc<Program>, ptape: Vec<u8>, writer: Box<dyn Write + Send>, reader: Box<dyn BufRead + Send>) -> BFState {
+    // A state at the start of the program with the default options, on the given tape (zeroed) and I/O
     return BFState {
-        code: code.as_bytes().to_vec(),
-        ptape: vec![0; 3000],
+        program,
+        ptape,
         ntape: Vec::new(),
         instruction_position: 0,
         cursor_position: 0,
+        highest_cursor: 0,
+        lowest_cursor: 0,
+        tape_resizes: (0, 0),
         loops: false,
         output: Vec::new(),
-        newline_0: false
+        raw_output: false,
+        writer,
+        captured_output: None,
+        diagnostics: Some(Box::new(std::io::stderr())),
+        warnings: HashSet::new(),
+        error: None,
+        max_output_bytes: None,
+        max_output_rate: None,
+        output_bytes: 0,
+        output_started: None,
+        output_limit_reached: false,
+        max_tape_cells: None,
+        tape_limit_reached: false,
+        input_allowed: true,
+        newline_0: false,
+        eof_behavior: EofBehavior::Zero,
+        input: VecDeque::new(),
+        input_bytes: 0,
+        reader,
+        reader_buffered: 0,
+        env_extension: false,
+        env_allowlist: Vec::new(),
+        extension_ops: Vec::new(),
+        interrupt: None,
+        check_invariants: false,
+        determinism: DeterminismLevel::Unrestricted,
+        observer: None,
+        cell_observer: None,
+        cursor_observer: None,
+        yield_hook: None,
+        hot_loop_threshold: Some(DEFAULT_HOT_LOOP_THRESHOLD),
+        loop_traces: Vec::new(),
+        ip_history: None,
+        loop_iterations: None,
+        loop_detector: None,
+        taint: None,
+    };
+}
+
+pub fn program(state: &BFState) -> Arc<Program> {
+    return state.program.clone();
+}
+
+pub fn skip_first_line(state: &mut BFState) {
+    /*
+     * Makes the first line of the code a comment.
+     * Meant to be called before running; resets the instruction position.
+     */
+
+    let code = state.program.code.clone();
+    let code_start = program::first_line_end(&code);
+    extension_ops::recompile(state, code, code_start);
+}
+
+pub fn load_code(state: &mut BFState, code: impl AsRef<[u8]>) {
+    /*
+     * Replaces the code, keeping the tape, the cursor and the pending input.
+     * Meant for running snippets one after another against the same memory.
+     * The tape may hold anything by then, so the code is compiled without the known-zero pass.
+     */
+
+    let characters = extension_ops::extension_characters(state);
+    program::replace_program(state, Program::compile(code.as_ref().to_vec(), 0, &characters, false));
+    state.instruction_position = 0;
+}
+
+pub fn restart(state: &mut BFState) {
+    /*
+     * Starts the program over on a cleared tape, keeping the I/O, the limits and the input not read yet,
+     * so a program handling one record can be run again for the next one. Counters (`output_bytes`, `input_bytes`,
+     * `tape_stats`) go on from where they were.
+     */
+
+    let touched = if state.loops { state.ptape.len() } else { usize::try_from(state.highest_cursor + 1).unwrap_or(0).min(state.ptape.len()) };
+    state.ptape[..touched].fill(0);
+    state.ntape.fill(0);
+    state.cursor_position = 0;
+    state.instruction_position = 0;
+    taint::forget_tainted_cells(state);
+}
+
+pub fn push_input(state: &mut BFState, input: &[u8]) {
+    state.input.extend(input);
+}
+
+pub fn set_input_reader(state: &mut BFState, reader: Box<dyn BufRead + Send>) {
+    state.reader = reader;
+    state.reader_buffered = 0;
+}
+
+pub fn set_output_writer(state: &mut BFState, writer: Box<dyn Write + Send>) {
+    state.writer = writer;
+}
+
+pub fn set_diagnostics_writer(state: &mut BFState, writer: Option<Box<dyn Write + Send>>) {
+    // Where warnings about the run are written, stderr by default; `None` drops them
+    state.diagnostics = writer;
+}
+
+pub fn warn(state: &mut BFState, message: &str) {
+    // Writes `warning: <message>` to the diagnostics writer, once per state for each message
+    if state.warnings.insert(String::from(message))
+        && let Some(writer) = state.diagnostics.as_mut() {
+        let _ = writeln!(writer, "warning: {message}");
+    }
+}
+
+pub fn set_raw_output(state: &mut BFState, raw_output: bool) {
+    state.raw_output = raw_output;
+}
+
+pub fn set_input_format(state: &mut BFState, format: InputFormat) {
+    // Decodes whatever the current reader reads, meant to be called after `set_input_reader`
+    if format != InputFormat::Raw {
+        let reader = std::mem::replace(&mut state.reader, Box::new(std::io::empty()));
+        set_input_reader(state, Box::new(DecodingReader::new(reader, format)));
+    }
+}
+
+pub fn set_output_format(state: &mut BFState, format: OutputFormat) {
+    /*
+     * Encodes whatever is printed through the current writer, meant to be called after `set_output_writer`.
+     * Encoded output is finished (padded and terminated with a newline) when the state is dropped.
+     */
+
+    set_raw_output(state, format != OutputFormat::Utf8);
+    if matches!(format, OutputFormat::Hex | OutputFormat::Base64) {
+        let writer = std::mem::replace(&mut state.writer, Box::new(std::io::sink()));
+        state.writer = Box::new(EncodingWriter::new(writer, format));
+    }
+    if matches!(format, OutputFormat::Wav | OutputFormat::Pcm) {
+        let writer = std::mem::replace(&mut state.writer, Box::new(std::io::sink()));
+        state.writer = Box::new(ToneWriter::new(writer, format));
+    }
+}
+
+pub fn set_newline_mode(state: &mut BFState, mode: NewlineMode) {
+    /*
+     * Translates newlines read by the current reader and printed through the current writer,
+     * meant to be called after the formats are set. Pushed input isn't translated.
+     */
+
+    if mode != NewlineMode::Unchanged {
+        let reader = std::mem::replace(&mut state.reader, Box::new(std::io::empty()));
+        set_input_reader(state, Box::new(NewlineReader::new(reader, mode)));
+        let writer = std::mem::replace(&mut state.writer, Box::new(std::io::sink()));
+        state.writer = Box::new(NewlineWriter::new(writer, mode));
+    }
+}
+
+pub fn set_charset(state: &mut BFState, charset: &Charset) {
+    /*
+     * Translates the bytes read by the current reader and printed through the current writer,
+     * meant to be called after the formats are set. Pushed input isn't translated.
+     * The output is raw then, since the program's bytes aren't UTF-8 before the translation.
+     */
+
+    if *charset == Charset::identity() {
+        return;
+    }
+    let reader = std::mem::replace(&mut state.reader, Box::new(std::io::empty()));
+    set_input_reader(state, Box::new(TranslatingReader::new(reader, charset.input)));
+    let writer = std::mem::replace(&mut state.writer, Box::new(std::io::sink()));
+    state.writer = Box::new(TranslatingWriter::new(writer, charset.output));
+    set_raw_output(state, true);
+}
+
+pub fn capture_output(state: &mut BFState) {
+    /*
+     * Collects the output in memory instead of printing it.
+     * The collected output is returned in `RunReport::output`.
+     */
+
+    if state.captured_output.is_none() {
+        state.captured_output = Some(Vec::new());
+    }
+}
+
+pub fn set_max_output_bytes(state: &mut BFState, limit: Option<u64>) {
+    state.max_output_bytes = limit;
+}
+
+pub fn set_max_tape_cells(state: &mut BFState, limit: Option<usize>) {
+    // Counts the cells of both directions, a looping tape is never limited
+    state.max_tape_cells = limit;
+}
+
+pub fn set_eof_behavior(state: &mut BFState, behavior: EofBehavior) {
+    state.eof_behavior = behavior;
+}
+
+pub fn set_input_allowed(state: &mut BFState, allowed: bool) {
+    // Without input, reading fails with a permission error instead of reading EOF
+    state.input_allowed = allowed;
+}
+
+pub fn set_max_output_rate(state: &mut BFState, bytes_per_second: Option<u64>) {
+    state.max_output_rate = bytes_per_second.filter(|&rate| rate > 0);
+}
+
+pub fn set_interrupt_flag(state: &mut BFState, flag: Arc<AtomicBool>) {
+    state.interrupt = Some(flag);
+}
+
+pub fn enable_env_extension(state: &mut BFState, allowlist: &[&str]) {
+    /*
+     * Enables the `$` instruction.
+     * It reads a null-terminated variable name starting from the current cell,
+     * then queues the value of that variable followed by a null (0) byte as input.
+     * Variables that are not in the allowlist (or not set) read as an empty string.
+     */
+
+    state.env_extension = true;
+    state.env_allowlist.extend(allowlist.iter().map(|name| String::from(*name)));
+}
+
+pub fn set_determinism(state: &mut BFState, level: DeterminismLevel) {
+    /*
+     * Sandbox operators can require deterministic runs;
+     * `run_bf` refuses to start when the state uses something that isn't allowed.
+     */
+
+    state.determinism = level;
+}
+
+pub fn check_determinism(state: &BFState, timeout: Option<Duration>) -> Result<(), BfError> {
+    /*
+     * Reports the first option that depends on the wall-clock or the environment.
+     * Interrupts are still allowed, they stop the run but can't change what was printed before.
+     */
+
+    if state.determinism == DeterminismLevel::Unrestricted {
+        return Ok(());
+    }
+    let forbidden = if timeout.is_some() {
+        "timeouts depend on the wall-clock"
+    }
+    else if state.max_output_rate.is_some() {
+        "output rate limits depend on the wall-clock"
+    }
+    else if state.env_extension {
+        "the `$` extension depends on the environment"
+    }
+    else {
+        return Ok(());
     };
+    return Err(BfError::Runtime(format!("Not allowed in deterministic mode: {forbidden}")));
 }
 
+#[inline]
 pub fn step_bf(state: &mut BFState) -> bool {
-    if state.instruction_position >= state.code.len() {
-        return false;
+    let before = state.instruction_position;
+    if let Some((capacity, history)) = state.ip_history.as_mut()
+        && before < state.program.bytecode.ops.len() {
+        if history.len() == *capacity {
+            history.pop_front();
+        }
+        history.push_back(before);
+    }
+    let watched = (state.cell_observer.is_some() || state.cursor_observer.is_some()).then(|| {
+        // Additions can change a cell other than the current one
+        let cell = match state.program.bytecode.ops.get(before) {
+            Some(Op::Add(offset, _)) => state.cursor_position + offset,
+            _ => state.cursor_position,
+        };
+        return (state.cursor_position, cell, get_value_at(state, cell));
+    });
+    if state.taint.is_some() {
+        taint::track(state);
+    }
+    let running = step(state);
+    if state.loop_iterations.is_some() {
+        count_iteration(state, before);
+    }
+    if let Some((cursor, cell, value)) = watched {
+        notify_changes(state, cursor, cell, value);
+    }
+    if cfg!(debug_assertions) && state.check_invariants
+        && let Err(error) = validate_invariants(state) {
+        panic!("{error}");
     }
+    return running;
+}
 
-    let opcode = state.code[state.instruction_position];
-    let current_value = get_value_at(state, state.cursor_position);
+#[inline]
+fn step(state: &mut BFState) -> bool {
+    if state.instruction_position >= state.program.bytecode.ops.len() || state.error.is_some()
+        || state.output_limit_reached || state.tape_limit_reached {
+        return false;
+    }
 
-    match opcode {
-        BF_OPCODE_INCREMENT_VALUE => {
-            set_value_at(state, state.cursor_position, wrapping_increment(current_value));
-            state.instruction_position += 1;
+    match state.program.bytecode.ops[state.instruction_position] {
+        Op::Add(0, value) => {
+            match current_cell_mut(state) {
+                Some(cell) => *cell = cell.wrapping_add(value),
+                None => {
+                    let current_value = get_value_at(state, state.cursor_position);
+                    set_value_at(state, state.cursor_position, current_value.wrapping_add(value));
+                },
+            }
         },
-        BF_OPCODE_DECREMENT_VALUE => {
-            set_value_at(state, state.cursor_position, wrapping_decrement(current_value));
-            state.instruction_position += 1;
+        Op::Add(offset, value) => {
+            let index = state.cursor_position + offset;
+            match usize::try_from(index).ok().filter(|_| index <= state.highest_cursor).and_then(|i| state.ptape.get_mut(i)) {
+                Some(cell) => *cell = cell.wrapping_add(value),
+                None => {
+                    // A cell the cursor never reached, as if it moved there
+                    if !within_tape_limit(state, index) {
+                        state.tape_limit_reached = true;
+                        return false;
+                    }
+                    let current_value = get_value_at(state, index);
+                    set_value_at(state, index, current_value.wrapping_add(value));
+                    state.highest_cursor = state.highest_cursor.max(index);
+                },
+            }
         },
-        BF_OPCODE_SHIFT_LEFT => {
-            if state.loops && (state.cursor_position <= 0) {
-                state.cursor_position = (state.ptape.len() - 1).try_into().unwrap();
+        Op::Move(delta) => {
+            move_cursor(state, delta);
+            if !within_tape_limit(state, state.cursor_position) {
+                state.tape_limit_reached = true;
+                return false;
             }
-            else {
-                state.cursor_position -= 1;
+        },
+        Op::Print => {
+            if !print_byte(state, current_value(state)) {
+                return false;
+            }
+        },
+        Op::Input => {
+            read_char(state);
+        },
+        Op::GetEnv => {
+            if state.env_extension {
+                queue_env_var(state);
+            }
+        },
+        Op::Extension(index) => extension_ops::execute_extension_op(state, index),
+        Op::Clear => {
+            match current_cell_mut(state) {
+                Some(cell) => *cell = 0,
+                None => set_value_at(state, state.cursor_position, 0),
+            }
+        },
+        Op::Scan(delta) => {
+            scan_for_zero(state, delta);
+            if !within_tape_limit(state, state.cursor_position) {
+                state.tape_limit_reached = true;
+                return false;
+            }
+        },
+        Op::JumpIfZero(target) => {
+            if current_value(state) == 0 || trace::run_hot_loop(state, state.instruction_position) {
+                state.instruction_position = target;
+                return true;
+            }
+        },
+        Op::JumpIfNotZero(target) => {
+            if current_value(state) != 0 && !trace::run_hot_loop(state, target - 1) {
+                state.instruction_position = target;
+                return true;
             }
-            state.instruction_position += 1;
         },
-        BF_OPCODE_SHIFT_RIGHT => {
-            if state.loops && (state.cursor_position >= state.ptape.len().try_into().unwrap()) {
-                state.cursor_position = 0;
+    }
+
+    state.instruction_position += 1;
+    return true;
+}
+
+fn move_cursor(state: &mut BFState, delta: isize) {
+    if !state.loops {
+        state.cursor_position += delta;
+        state.highest_cursor = state.highest_cursor.max(state.cursor_position);
+        state.lowest_cursor = state.lowest_cursor.min(state.cursor_position);
+        return;
+    }
+
+    for _ in 0..delta.unsigned_abs() {
+        if delta < 0 {
+            if state.cursor_position <= 0 {
+                state.cursor_position = (state.ptape.len() - 1).try_into().unwrap();
             }
             else {
-                state.cursor_position += 1;
+                state.cursor_position -= 1;
             }
-            state.instruction_position += 1;
-        },
-        BF_OPCODE_PRINT => {
-            print_char(state);
-            state.instruction_position += 1;
-        },
-        BF_OPCODE_INPUT => {
-            read_char_from_stdin(state);
-            state.instruction_position += 1;
-        },
-        BF_OPCODE_BLOCK_BEGIN => {
-            if get_value_at(state, state.cursor_position) == 0 {
-                let mut depth = 0;
-                for i in state.instruction_position..state.code.len() {
-                    match state.code[i] {
-                        BF_OPCODE_BLOCK_BEGIN => depth += 1,
-                        BF_OPCODE_BLOCK_END => {
-                            depth -= 1;
-                            if depth == 0 { state.instruction_position = i; break; };
-                        },
-                        _ => {}
-                    }
-                }
+        }
+        else if state.cursor_position >= state.ptape.len().try_into().unwrap() {
+            state.cursor_position = 0;
+        }
+        else {
+            state.cursor_position += 1;
+        }
+    }
+}
+
+fn scan_for_zero(state: &mut BFState, delta: isize) {
+    /*
+     * Single steps are searched with `memchr`, which picks the fastest SIMD implementation at runtime.
+     * Cells outside of the allocated tapes are 0, so the search always ends.
+     */
+
+    if state.loops || delta.unsigned_abs() != 1 {
+        while current_value(state) != 0 {
+            move_cursor(state, delta);
+        }
+        return;
+    }
+    if current_value(state) == 0 {
+        return;
+    }
+
+    let cursor = state.cursor_position;
+    state.cursor_position = if delta > 0 {
+        if cursor < 0 {
+            // The negative tape is stored from -1 downwards, so going right searches it backwards
+            let end = usize::try_from(-1 - cursor).unwrap();
+            match memchr::memrchr(0, &state.ntape[..=end]) {
+                Some(i) => -1 - isize::try_from(i).unwrap(),
+                None => scan_ptape_right(state, 0),
             }
-            state.instruction_position += 1;
-        },
-        BF_OPCODE_BLOCK_END => {
-            if get_value_at(state, state.cursor_position) != 0 {
-                let mut depth = 0;
-                for i in (0..=state.instruction_position).rev() {
-                    match state.code[i] {
-                        BF_OPCODE_BLOCK_END => depth += 1,
-                        BF_OPCODE_BLOCK_BEGIN => {
-                            depth -= 1;
-                            if depth == 0 { state.instruction_position = i; break; };
-                        },
-                        _ => {}
-                    }
-                }
+        }
+        else {
+            scan_ptape_right(state, usize::try_from(cursor).unwrap())
+        }
+    }
+    else if cursor >= 0 {
+        let start = usize::try_from(cursor).unwrap();
+        match memchr::memrchr(0, &state.ptape[..=start]) {
+            Some(i) => isize::try_from(i).unwrap(),
+            None => scan_ntape_left(state, 0),
+        }
+    }
+    else {
+        scan_ntape_left(state, usize::try_from(-1 - cursor).unwrap())
+    };
+    state.highest_cursor = state.highest_cursor.max(state.cursor_position);
+    state.lowest_cursor = state.lowest_cursor.min(state.cursor_position);
+}
+
+fn scan_ptape_right(state: &BFState, start: usize) -> isize {
+    // Index of the first 0 cell from `ptape[start]` on
+    let offset = memchr::memchr(0, &state.ptape[start..]).unwrap_or(state.ptape.len() - start);
+    return isize::try_from(start + offset).unwrap();
+}
+
+fn scan_ntape_left(state: &BFState, start: usize) -> isize {
+    // Index of the first 0 cell from `ntape[start]` on, going left
+    let offset = memchr::memchr(0, state.ntape.get(start..).unwrap_or_default()).unwrap_or(state.ntape.len().saturating_sub(start));
+    return -1 - isize::try_from(start + offset).unwrap();
+}
+
+pub fn set_step_observer(state: &mut BFState, observer: Option<StepObserver>) {
+    /*
+     * The observer sees the state right before each step executed by `run_bf` or `step_n`.
+     * Stepping is noticeably slower while an observer is set.
+     */
+
+    state.observer = observer;
+}
+
+pub fn on_cell_change(state: &mut BFState, observer: Option<CellObserver>) {
+    /*
+     * The observer is called with the index, the old and the new value whenever a step changes the cell
+     * it works on (the cursor's, or the one an addition's offset points to), so visualizers don't have to compare the whole tape every frame.
+     * Changes extension operations make to other cells aren't reported. Loops aren't traced while it's set.
+     */
+
+    state.cell_observer = observer;
+}
+
+pub fn on_cursor_move(state: &mut BFState, observer: Option<CursorObserver>) {
+    // The observer is called with the old and the new position whenever a step moves the cursor
+    state.cursor_observer = observer;
+}
+
+fn notify_changes(state: &mut BFState, cursor: isize, cell: isize, value: u8) {
+    // Compares the cursor and the cell the step worked on with what they were before the step
+    let new_value = get_value_at(state, cell);
+    if new_value != value && let Some(observer) = state.cell_observer.as_mut() {
+        observer(cell, value, new_value);
+    }
+    if state.cursor_position != cursor && let Some(observer) = state.cursor_observer.as_mut() {
+        observer(cursor, state.cursor_position);
+    }
+}
+
+pub(crate) fn is_observed(state: &BFState) -> bool {
+    // Whether something has to see every step, which rules out running loops through their traces
+    return state.observer.is_some() || state.cell_observer.is_some() || state.cursor_observer.is_some()
+        || state.loop_iterations.is_some() || state.taint.is_some();
+}
+
+fn count_iteration(state: &mut BFState, before: usize) {
+    // Entering a loop starts its first iteration, jumping back from its `]` starts the next one
+    let ops = &state.program.bytecode.ops;
+    let Some(iterations) = state.loop_iterations.as_mut() else {
+        return;
+    };
+    if iterations.len() != ops.len() {
+        iterations.resize(ops.len(), 0);
+    }
+    match ops.get(before) {
+        Some(Op::JumpIfZero(_)) if state.instruction_position == before + 1 => iterations[before] = 1,
+        Some(&Op::JumpIfNotZero(target)) if state.instruction_position == target => iterations[target - 1] += 1,
+        _ => {},
+    }
+}
+
+pub fn yield_every_n_steps(state: &mut BFState, n: u64, hook: YieldHook) {
+    /*
+     * Calls the hook every `n` steps while `run_bf` is running, so hosts can render or poll events
+     * without threads. Returning `ControlFlow::Break` stops the run with `RunOutcome::Cancelled`;
+     * the run can be resumed by calling `run_bf` again.
+     */
+
+    state.yield_hook = Some((n.max(1), hook));
+}
+
+pub fn clear_yield_hook(state: &mut BFState) {
+    state.yield_hook = None;
+}
+
+pub fn set_ip_history(state: &mut BFState, length: Option<usize>) {
+    /*
+     * Remembers the positions of the last `length` executed instructions, for a backtrace-like context
+     * when the program fails or gets interrupted. `None` (the default) turns it off.
+     * A traced loop runs in a single step, so only its `[` is remembered.
+     */
+
+    state.ip_history = length.filter(|&length| length > 0).map(|length| (length, VecDeque::with_capacity(length)));
+}
+
+pub fn set_loop_tracking(state: &mut BFState, enabled: bool) {
+    /*
+     * Counts the iterations of the loops as they run, for `loop_stack`.
+     * Loops aren't traced while it's enabled, so every iteration is counted.
+     */
+
+    state.loop_iterations = enabled.then(Vec::new);
+}
+
+pub fn loop_stack(state: &BFState) -> Vec<OpenLoop> {
+    /*
+     * The loops the next instruction is inside of, outermost first, like a call stack.
+     * Iterations are only known for loops entered while `set_loop_tracking` was enabled, they're 0 otherwise.
+     */
+
+    let bytecode = &state.program.bytecode;
+    let mut stack = Vec::new();
+    for (index, op) in bytecode.ops.iter().enumerate().take(state.instruction_position) {
+        if let Op::JumpIfZero(target) = *op
+            && state.instruction_position < target {
+            let iteration = state.loop_iterations.as_ref().and_then(|iterations| iterations.get(index)).copied().unwrap_or(0);
+            stack.push(OpenLoop { position: bytecode.positions[index], iteration });
+        }
+    }
+    return stack;
+}
+
+pub fn recent_ips(state: &BFState) -> Vec<usize> {
+    // Source positions of the instructions remembered by `set_ip_history`, oldest first
+    let Some((_, history)) = &state.ip_history else {
+        return Vec::new();
+    };
+    return history.iter().map(|&index| state.program.bytecode.positions[index]).collect();
+}
+
+pub fn set_check_invariants(state: &mut BFState, check_invariants: bool) {
+    /*
+     * Makes `step_bf` panic as soon as the state becomes invalid.
+     * Only has an effect in debug builds, meant for catching interpreter bugs early.
+     */
+
+    state.check_invariants = check_invariants;
+}
+
+pub fn validate_invariants(state: &BFState) -> Result<(), BfError> {
+    let violation = |message: String| Err(BfError::Runtime(format!("Invariant violated: {message}")));
+    let ops = &state.program.bytecode.ops;
+    let positions = &state.program.bytecode.positions;
+
+    if state.ptape.is_empty() {
+        return violation(String::from("the positive tape is empty"));
+    }
+    if state.loops && (state.cursor_position < 0 || state.cursor_position > state.ptape.len().try_into().unwrap()) {
+        return violation(format!("cursor {} is outside of the looping tape", state.cursor_position));
+    }
+    if state.instruction_position > ops.len() {
+        return violation(format!("instruction position {} is past the end of the bytecode", state.instruction_position));
+    }
+    if positions.len() != ops.len() {
+        return violation(format!("{} operations but {} source positions", ops.len(), positions.len()));
+    }
+    // Programs from `Program::from_ops` have no code, their positions are the operation indices
+    if positions.windows(2).any(|pair| pair[0] >= pair[1])
+        || positions.first().is_some_and(|&position| position < state.program.code_start)
+        || (!state.program.code.is_empty() && positions.last().is_some_and(|&position| position >= state.program.code.len())) {
+        return violation(String::from("source positions are out of order or out of the code"));
+    }
+    for (i, op) in ops.iter().enumerate() {
+        let consistent = match *op {
+            Op::JumpIfZero(target) => target >= 1 && ops.get(target - 1) == Some(&Op::JumpIfNotZero(i + 1)),
+            Op::JumpIfNotZero(target) => target >= 1 && ops.get(target - 1) == Some(&Op::JumpIfZero(i + 1)),
+            _ => true,
+        };
+        if !consistent {
+            return violation(format!("the jump at operation {i} doesn't match its counterpart"));
+        }
+    }
+    if !state.raw_output && state.error.is_none() && state.output.len() >= 4 {
+        return violation(format!("{} bytes are waiting to be printed, more than an incomplete UTF-8 character", state.output.len()));
+    }
+    return Ok(());
+}
+
+pub fn source_position(state: &BFState) -> usize {
+    // Position of the current instruction in the code
+    return match state.program.bytecode.positions.get(state.instruction_position) {
+        Some(&position) => position,
+        None => state.program.code.len(),
+    };
+}
+
+pub fn labels(state: &BFState) -> &[Label] {
+    return &state.program.labels;
+}
+
+pub fn label_position(state: &BFState, name: &str) -> Option<usize> {
+    // Source position of the first label with the given name, meant for breakpoints
+    return state.program.labels.iter().find(|label| label.name == name).map(|label| label.position);
+}
+
+pub fn current_label(state: &BFState) -> Option<&str> {
+    // Name of the nearest label before the current instruction
+    return nearest_label(&state.program.labels, source_position(state)).map(|label| label.name.as_str());
+}
+
+pub fn check_syntax(code: &[u8]) -> Result<(), BfError> {
+    let mut open_blocks: Vec<usize> = Vec::new();
+    for (i, &opcode) in code.iter().enumerate() {
+        match opcode {
+            BF_OPCODE_BLOCK_BEGIN => open_blocks.push(i),
+            BF_OPCODE_BLOCK_END if open_blocks.pop().is_none() => {
+                return Err(BfError::Syntax { position: i, message: String::from("unmatched `]`") });
+            },
+            _ => {}
+        }
+    }
+    if let Some(&position) = open_blocks.last() {
+        return Err(BfError::Syntax { position, message: String::from("unmatched `[`") });
+    }
+    return Ok(());
+}
+
+pub fn run_bf(state: &mut BFState, fuel: Option<u64>, timeout: Option<Duration>) -> Result<RunReport, BfError> {
+    /*
+     * Runs the code until it halts or one of the limits is reached.
+     * `fuel` is the maximum number of steps; `None` means unlimited.
+     */
+
+    trace_span!(INFO, "run_bf", ?fuel, ?timeout);
+    state.program.check_syntax()?;
+    check_determinism(state, timeout)?;
+
+    let started = Instant::now();
+    let mut steps: u64 = 0;
+    let mut since_yield: u64 = 0;
+    let outcome = loop {
+        if timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+            break RunOutcome::TimedOut;
+        }
+        if state.interrupt.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+            break RunOutcome::Interrupted;
+        }
+        let batch = match fuel {
+            Some(fuel) if fuel - steps < TIMEOUT_CHECK_INTERVAL => fuel - steps,
+            _ => TIMEOUT_CHECK_INTERVAL,
+        };
+        if batch == 0 {
+            break RunOutcome::FuelExhausted;
+        }
+        let batch = match &state.yield_hook {
+            Some((every, _)) => batch.min(every - since_yield),
+            None => batch,
+        };
+        let (executed, running) = execute(state, batch);
+        steps += executed;
+        since_yield += executed;
+        if !running {
+            break stop_outcome(state)?;
+        }
+        if loop_detector::repeats(state, executed) {
+            break RunOutcome::LoopDetected;
+        }
+        if let Some((every, mut hook)) = state.yield_hook.take() {
+            let flow = if since_yield >= every {
+                since_yield = 0;
+                hook(state)
             }
-            state.instruction_position += 1;
+            else {
+                ControlFlow::Continue(())
+            };
+            state.yield_hook = Some((every, hook));
+            if flow.is_break() {
+                break RunOutcome::Cancelled;
+            }
+        }
+    };
+    trace_event!(INFO, ?outcome, steps, duration = ?started.elapsed(), "run finished");
+
+    return Ok(RunReport {
+        outcome,
+        steps,
+        output: state.captured_output.as_mut().map(std::mem::take).unwrap_or_default(),
+        duration: started.elapsed(),
+        final_cursor: state.cursor_position,
+        tape_extent: tape_extent(state),
+    });
+}
+
+pub fn step_n(state: &mut BFState, n: usize) -> StepBatchResult {
+    /*
+     * Executes up to `n` instructions without any limit or interrupt checks,
+     * then flushes the output. Meant to be called repeatedly from event loops.
+     */
+
+    let (steps, running) = execute(state, n as u64);
+    let steps = steps as usize;
+    if !running {
+        return match stop_outcome(state) {
+            Ok(outcome) => StepBatchResult::Stopped(steps, outcome),
+            Err(error) => StepBatchResult::Failed(steps, error),
+        };
+    }
+    if let Err(error) = state.writer.flush() {
+        return StepBatchResult::Failed(steps, BfError::Io(error));
+    }
+    return StepBatchResult::Running(steps);
+}
+
+pub fn run_for(state: &mut BFState, budget: Duration) -> StepBatchResult {
+    /*
+     * Executes as many instructions as fit in the time budget, then flushes the output.
+     * Meant to be called once per frame from game loops and GUIs; `Running` means the budget ran out.
+     * The clock is checked every 1024 steps, so a run can go over the budget by that many steps.
+     */
+
+    let start = Instant::now();
+    let mut steps = 0;
+    loop {
+        let (executed, running) = execute(state, TIMEOUT_CHECK_INTERVAL);
+        steps += executed as usize;
+        if !running {
+            return match stop_outcome(state) {
+                Ok(outcome) => StepBatchResult::Stopped(steps, outcome),
+                Err(error) => StepBatchResult::Failed(steps, error),
+            };
+        }
+        if start.elapsed() >= budget {
+            break;
+        }
+    }
+    if let Err(error) = state.writer.flush() {
+        return StepBatchResult::Failed(steps, BfError::Io(error));
+    }
+    return StepBatchResult::Running(steps);
+}
+
+fn execute(state: &mut BFState, max_steps: u64) -> (u64, bool) {
+    // Returns the number of executed steps, and whether the program can continue
+    let mut steps: u64 = 0;
+    while steps < max_steps {
+        if let Some(mut observer) = state.observer.take() {
+            observer(state);
+            state.observer = Some(observer);
+        }
+        if !step_bf(state) {
+            return (steps, false);
+        }
+        steps += 1;
+    }
+    return (steps, true);
+}
+
+fn stop_outcome(state: &mut BFState) -> Result<RunOutcome, BfError> {
+    // Figures out why `step_bf` returned false
+    if let Some(error) = state.error.take() {
+        return Err(error);
+    }
+    // Nothing can complete a pending UTF-8 character anymore
+    flush_output(state)?;
+    if state.output_limit_reached {
+        return Ok(RunOutcome::OutputLimitExceeded);
+    }
+    if state.tape_limit_reached {
+        return Ok(RunOutcome::TapeLimitExceeded);
+    }
+    return Ok(RunOutcome::Halted);
+}
+
+pub fn tape_extent(state: &BFState) -> (isize, isize) {
+    let lowest = -isize::try_from(state.ntape.len()).unwrap();
+    let highest = isize::try_from(state.ptape.len()).unwrap() - 1;
+    return (lowest, highest);
+}
+
+pub fn tape_stats(state: &BFState) -> TapeStats {
+    // High-water marks of the tape since the state was created, to size `max_tape_cells`
+    let written = -isize::try_from(state.ntape.len()).unwrap();
+    return TapeStats {
+        highest: state.highest_cursor,
+        lowest: state.lowest_cursor.min(written),
+        ptape_resizes: state.tape_resizes.0,
+        ntape_resizes: state.tape_resizes.1,
+    };
+}
+
+pub fn cursor_position(state: &BFState) -> isize {
+    return state.cursor_position;
+}
+
+pub fn next_op(state: &BFState) -> Option<Op> {
+    // The operation the next step executes, `None` once the program ended
+    return state.program.bytecode.ops.get(state.instruction_position).copied();
+}
+
+pub fn pending_input(state: &BFState) -> usize {
+    // Bytes pushed with `push_input` that weren't read yet
+    return state.input.len();
+}
+
+pub fn input_remaining(state: &mut BFState) -> bool {
+    // Whether reading would get a byte rather than EOF, waiting for the reader if it has nothing buffered
+    if !state.input.is_empty() {
+        return true;
+    }
+    return match state.reader.fill_buf() {
+        Ok(buffer) => {
+            state.reader_buffered = buffer.len();
+            !buffer.is_empty()
         },
-        _ => {
-            state.instruction_position += 1;
+        Err(_) => false,
+    };
+}
+
+pub fn input_bytes(state: &BFState) -> u64 {
+    return state.input_bytes;
+}
+
+pub fn output_bytes(state: &BFState) -> u64 {
+    return state.output_bytes;
+}
+
+pub fn get_cell(state: &BFState, index: isize) -> u8 {
+    return get_value_at(state, index);
+}
+
+pub fn set_cell(state: &mut BFState, index: isize, value: u8) {
+    // Expands the tape as needed, like `load_tape`, so an index far from 0 allocates every cell up to it
+    known_zero::forget_zeroed_tape(state);
+    set_value_at(state, index, value);
+    state.highest_cursor = state.highest_cursor.max(index);
+}
+
+pub fn export_tape(state: &BFState, range: std::ops::Range<isize>) -> Vec<u8> {
+    // Values of the cells in the range, cells that were never allocated read as 0
+    return range.map(|index| get_value_at(state, index)).collect();
+}
+
+pub fn load_tape(state: &mut BFState, bytes: &[u8], origin: isize) -> Result<(), BfError> {
+    /*
+     * Copies the bytes into the cells starting from `origin`, expanding the tape as needed; bytes past the last index are dropped.
+     * Bytes the program couldn't reach within `max_tape_cells` are rejected before anything is written.
+     */
+
+    if !tape_fits(state, origin, bytes.len()) {
+        return Err(tape_limit_error(origin, bytes.len()));
+    }
+    known_zero::forget_zeroed_tape(state);
+    if let Ok(start) = usize::try_from(origin)
+        && let Some(end) = start.checked_add(bytes.len()).filter(|&end| end > start && isize::try_from(end).is_ok()) {
+        // Only the positive tape, in one copy
+        if end > state.ptape.len() {
+            state.ptape.resize(end, 0);
+            state.tape_resizes.0 += 1;
         }
+        state.ptape[start..end].copy_from_slice(bytes);
+        state.highest_cursor = state.highest_cursor.max(isize::try_from(end - 1).unwrap());
+        return Ok(());
     }
+    for (i, &value) in bytes.iter().enumerate() {
+        let Some(index) = origin.checked_add_unsigned(i) else {
+            break;
+        };
+        set_value_at(state, index, value);
+        state.highest_cursor = state.highest_cursor.max(index);
+    }
+    return Ok(());
+}
 
-    return true;
+pub fn read_u32_at(state: &BFState, index: isize, endianness: Endianness) -> u32 {
+    // The number stored in the 4 cells starting from `index`, cells that were never allocated read as 0
+    let bytes: [u8; 4] = std::array::from_fn(|i| get_value_at(state, index.saturating_add_unsigned(i)));
+    return match endianness {
+        Endianness::Little => u32::from_le_bytes(bytes),
+        Endianness::Big => u32::from_be_bytes(bytes),
+    };
+}
+
+pub fn write_u32_at(state: &mut BFState, index: isize, value: u32, endianness: Endianness) -> Result<(), BfError> {
+    // Stores the number in the 4 cells starting from `index`, expanding the tape as needed and within the tape limit like `load_tape`
+    let bytes = match endianness {
+        Endianness::Little => value.to_le_bytes(),
+        Endianness::Big => value.to_be_bytes(),
+    };
+    return load_tape(state, &bytes, index);
+}
+
+pub fn reserve_tape(state: &mut BFState, positive: usize, negative: usize) -> Result<(), BfError> {
+    /*
+     * Allocates room for that many cells from 0 rightwards and from -1 leftwards, so a program that needs them
+     * doesn't grow the tape while it runs. The cells are still 0 and nothing else about the tape changes.
+     * Cells the program couldn't reach within `max_tape_cells` aren't allocated, the call fails instead.
+     */
+
+    let cells = positive.checked_add(negative);
+    let origin = isize::try_from(negative).map_or(isize::MIN, |negative| -negative);
+    if !cells.is_some_and(|cells| tape_fits(state, origin, cells)) {
+        return Err(tape_limit_error(origin, cells.unwrap_or(usize::MAX)));
+    }
+    if state.ptape.len() < positive {
+        state.ptape.resize(positive, 0);
+    }
+    state.ntape.reserve(negative.saturating_sub(state.ntape.len()));
+    return Ok(());
+}
+
+pub fn adopt_tape(state: &mut BFState, tape: Vec<u8>, origin: isize) -> Result<(), BfError> {
+    /*
+     * Replaces the whole tape with the buffer, its first byte being the cell at `origin` and everything else 0.
+     * With `origin` at 0 the buffer becomes the tape as it is, without copying; otherwise it's loaded like `load_tape`.
+     * A buffer that doesn't fit in `max_tape_cells` is rejected and the tape is left as it was.
+     */
+
+    // The negative cells are dropped, so they don't count against the limit
+    let negative = std::mem::take(&mut state.ntape);
+    if !tape_fits(state, origin, tape.len()) {
+        state.ntape = negative;
+        return Err(tape_limit_error(origin, tape.len()));
+    }
+    known_zero::forget_zeroed_tape(state);
+    if origin != 0 || tape.is_empty() {
+        state.ptape.fill(0);
+        return load_tape(state, &tape, origin);
+    }
+    let highest = isize::try_from(tape.len()).unwrap() - 1;
+    state.ptape = tape;
+    state.highest_cursor = state.highest_cursor.max(highest);
+    return Ok(());
+}
+
+pub fn flush_output(state: &mut BFState) -> Result<(), BfError> {
+    /*
+     * Prints the buffered output even if it isn't valid UTF-8 yet.
+     * Meant to be called when the program stops before completing a character.
+     */
+
+    match &mut state.captured_output {
+        Some(captured) => captured.append(&mut state.output),
+        None => {
+            state.writer.write_all(&state.output)?;
+            state.writer.flush()?;
+            state.output.clear();
+        },
+    }
+    return Ok(());
+}
+
+pub fn format_state_snapshot(state: &BFState, radix: DisplayRadix) -> String {
+    /*
+     * Human-readable snapshot of the current state.
+     * Tapes are written in the given radix, the negative tape starting from cell -1;
+     * trailing zero cells are omitted. The pending output is always written as hex bytes.
+     */
+
+    let mut snapshot = String::new();
+    snapshot.push_str(&format!("instruction_position: {}\n", state.instruction_position));
+    snapshot.push_str(&format!("source_position: {}\n", source_position(state)));
+    if let Some(label) = current_label(state) {
+        snapshot.push_str(&format!("label: {label}\n"));
+    }
+    snapshot.push_str(&format!("cursor_position: {}\n", state.cursor_position));
+    snapshot.push_str(&format!("current_value: {}\n", format_cell(get_value_at(state, state.cursor_position), radix)));
+    snapshot.push_str(&format!("ptape: {}\n", format_cells_trimmed(&state.ptape, radix)));
+    snapshot.push_str(&format!("ntape: {}\n", format_cells_trimmed(&state.ntape, radix)));
+    snapshot.push_str(&format!("pending_output: {}\n", format_cells_trimmed(&state.output, DisplayRadix::Hex)));
+    return snapshot;
+}
+
+pub fn format_cell(value: u8, radix: DisplayRadix) -> String {
+    return match radix {
+        DisplayRadix::Decimal => format!("{value}"),
+        DisplayRadix::Hex => format!("{value:02x}"),
+        DisplayRadix::Binary => format!("{value:08b}"),
+        DisplayRadix::Char => format!("'{}'", value.escape_ascii()),
+    };
+}
+
+fn format_cells_trimmed(bytes: &[u8], radix: DisplayRadix) -> String {
+    let length = bytes.iter().rposition(|&c| c != 0).map_or(0, |i| i + 1);
+    let cells: Vec<String> = bytes[..length].iter().map(|&c| format_cell(c, radix)).collect();
+    return cells.join(" ");
+}
+
+#[inline]
+fn current_value(state: &BFState) -> u8 {
+    // Fast path for the common case, the current cell is already allocated on the positive tape
+    if let Ok(index) = usize::try_from(state.cursor_position)
+        && let Some(&value) = state.ptape.get(index) {
+        return value;
+    }
+    return get_value_at(state, state.cursor_position);
+}
+
+#[inline]
+fn current_cell_mut(state: &mut BFState) -> Option<&mut u8> {
+    // Same as `current_value`, `None` means the slow path has to be taken
+    let index = usize::try_from(state.cursor_position).ok()?;
+    return state.ptape.get_mut(index);
 }
 
 fn get_value_at(state: &BFState, mut index: isize) -> u8 {
@@ -149,6 +1389,37 @@ fn get_value_at(state: &BFState, mut index: isize) -> u8 {
     return *state.ntape.get(index).unwrap_or(&0);
 }
 
+fn within_tape_limit(state: &BFState, index: isize) -> bool {
+    // Whether writing to the cell at the index keeps the tape within `max_tape_cells`
+    return cells_within_tape_limit(state, index, index);
+}
+
+pub(crate) fn tape_fits(state: &BFState, origin: isize, length: usize) -> bool {
+    // Whether `load_tape` can write that many cells from `origin` and keep the tape within `max_tape_cells`
+    let Some(last) = length.checked_sub(1) else {
+        return true;
+    };
+    return cells_within_tape_limit(state, origin, origin.saturating_add_unsigned(last));
+}
+
+fn tape_limit_error(origin: isize, length: usize) -> BfError {
+    // What loading cells the program couldn't reach reports, named like the `TapeLimitExceeded` outcome of a run
+    return BfError::Runtime(format!("Tape limit exceeded: {length} cells from cell {origin} don't fit in `max_tape_cells`"));
+}
+
+pub(crate) fn cells_within_tape_limit(state: &BFState, lowest: isize, highest: isize) -> bool {
+    let Some(limit) = state.max_tape_cells else {
+        return true;
+    };
+    if state.loops {
+        return true;
+    }
+    // The positive tape is allocated ahead, so the highest cursor position counts instead of its length
+    let positive = state.highest_cursor.max(highest).max(0).unsigned_abs() + 1;
+    let negative = state.ntape.len().max(if lowest < 0 { lowest.unsigned_abs() } else { 0 });
+    return positive.checked_add(negative).is_some_and(|cells| cells <= limit);
+}
+
 fn set_value_at(state: &mut BFState, index: isize, value: u8) {
     /*
      * WARNING: This won't check for the tape type!
@@ -157,62 +1428,161 @@ fn set_value_at(state: &mut BFState, index: isize, value: u8) {
      */
 
     {
-        let ptape_len: isize = (state.ptape.len()).try_into().unwrap();
-        let ntape_len: isize = (state.ntape.len()).try_into().unwrap();
-
-        if index >= ptape_len {
-            state.ptape.resize((index + 1).try_into().unwrap(), 0);
+        // Sizes are computed unsigned, so indices near the ends of `isize` don't overflow
+        if index >= 0 && index.unsigned_abs() >= state.ptape.len() {
+            state.ptape.resize(index.unsigned_abs() + 1, 0);
+            state.tape_resizes.0 += 1;
         }
-        if -index > ntape_len {
-            state.ntape.resize((-index).try_into().unwrap(), 0);
+        if index < 0 && index.unsigned_abs() > state.ntape.len() {
+            state.ntape.resize(index.unsigned_abs(), 0);
+            state.tape_resizes.1 += 1;
         }
     }
 
     if index >= 0 {
-        let index: usize = index.try_into().unwrap();
-        state.ptape[index] = value;
+        state.ptape[index.unsigned_abs()] = value;
     }
     else {
-        let index: usize = ((-1) - index).try_into().unwrap();
-        state.ntape[index] = value;
+        state.ntape[index.unsigned_abs() - 1] = value;
     }
 }
 
-fn read_char_from_stdin(state: &mut BFState) {
-    let mut buff = vec![0];
-    match std::io::Read::read_exact(&mut std::io::stdin(), &mut buff) {
-        Ok(()) => {
-            let c: u8 = buff[0];
-            let c = if c == NEWLINE && state.newline_0 { 0 } else { c };
-            let cursor = state.cursor_position;
-            set_value_at(state, cursor, c);
+fn read_char(state: &mut BFState) {
+    if !input_allowed(state) {
+        return;
+    }
+    let c = match read_byte(state) {
+        Some(NEWLINE) if state.newline_0 => 0,
+        Some(c) => c,
+        None => match state.eof_behavior {
+            EofBehavior::Zero => 0,
+            EofBehavior::Unchanged => return,
+            EofBehavior::MaxValue => 255,
         },
-        Err(_) => {
-            let cursor = state.cursor_position;
-            set_value_at(state, cursor, 0);
-        }
+    };
+    let cursor = state.cursor_position;
+    set_value_at(state, cursor, c);
+}
+
+pub(crate) fn input_allowed(state: &mut BFState) -> bool {
+    // Fails the run with a permission error when reading isn't allowed
+    if !state.input_allowed {
+        state.error = Some(BfError::Io(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "The program isn't allowed to read input")));
+        return false;
     }
+    return true;
 }
 
-fn print_char(state: &mut BFState) {
-    let value = get_value_at(state, state.cursor_position);
-    state.output.push(value);
+pub(crate) fn read_byte(state: &mut BFState) -> Option<u8> {
+    // The next input byte, queued ones first, `None` at EOF (or when the input can't be decoded)
+    if let Some(c) = state.input.pop_front() {
+        state.input_bytes += 1;
+        return Some(c);
+    }
 
-    match String::from_utf8(state.output.clone()) {
-        Ok(s) => {
-            print!("{}", s);
-            state.output.clear();
+    // Whatever was printed so far might be a prompt for this input
+    if state.reader_buffered == 0
+        && let Err(error) = state.writer.flush() {
+        trace_event!(WARN, %error, "flushing the output before reading failed");
+        state.error = Some(BfError::Io(error));
+    }
+    if state.reader_buffered == 0 {
+        trace_event!(TRACE, "waiting for input");
+    }
+
+    return match state.reader.fill_buf() {
+        Ok(buffer) if !buffer.is_empty() => {
+            let c = buffer[0];
+            state.reader_buffered = buffer.len() - 1;
+            state.reader.consume(1);
+            state.input_bytes += 1;
+            Some(c)
+        },
+        Err(error) if error.kind() == std::io::ErrorKind::InvalidData => {
+            // The input couldn't be decoded, going on would silently corrupt it
+            trace_event!(WARN, %error, "decoding the input failed");
+            state.error = Some(BfError::Io(error));
+            None
+        },
+        Err(error) if !matches!(error.kind(), std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::Interrupted) => {
+            warn(state, &format!("reading the input failed ({error}), it's treated as ended"));
+            state.reader_buffered = 0;
+            None
         },
-        Err(_) => {}
+        _ => {
+            trace_event!(DEBUG, "input ended");
+            state.reader_buffered = 0;
+            None
+        },
+    };
+}
+
+fn queue_env_var(state: &mut BFState) {
+    let mut name: Vec<u8> = Vec::new();
+    let mut index = state.cursor_position;
+    loop {
+        let c = get_value_at(state, index);
+        if c == 0 { break; }
+        name.push(c);
+        index += 1;
+    }
+
+    let allowed = state.env_allowlist.iter().any(|allowed| allowed.as_bytes() == name.as_slice());
+    if !allowed {
+        warn(state, &format!("the program read the environment variable `{}`, which isn't allowed, as empty", name.escape_ascii()));
     }
+    if allowed
+        && let Some(value) = std::str::from_utf8(&name).ok().and_then(std::env::var_os) {
+        state.input.extend(value.as_encoded_bytes());
+    }
+    state.input.push_back(0);
 }
 
-fn wrapping_increment(x: u8) -> u8 {
-    if x < 255 { return x + 1 };
-    return 0
+pub(crate) fn print_byte(state: &mut BFState, value: u8) -> bool {
+    // Prints like `.` does, false when the output limit was reached or printing failed, which stops the run
+    if state.max_output_bytes.is_some_and(|limit| state.output_bytes >= limit) {
+        trace_event!(DEBUG, bytes = state.output_bytes, "output limit reached");
+        state.output_limit_reached = true;
+        return false;
+    }
+    if let Err(error) = print_char(state, value) {
+        trace_event!(WARN, %error, "printing failed");
+        state.error = Some(BfError::Io(error));
+        return false;
+    }
+    return true;
 }
 
-fn wrapping_decrement(x: u8) -> u8 {
-    if x > 0 { return x - 1 };
-    return 255
+fn print_char(state: &mut BFState, value: u8) -> std::io::Result<()> {
+    state.output.push(value);
+    state.output_bytes += 1;
+
+    if let Some(rate) = state.max_output_rate {
+        let started = *state.output_started.get_or_insert_with(Instant::now);
+        let allowed_at = Duration::from_secs_f64(state.output_bytes as f64 / rate as f64);
+        let elapsed = started.elapsed();
+        if allowed_at > elapsed {
+            state.writer.flush()?;
+            std::thread::sleep(allowed_at - elapsed);
+        }
+    }
+
+    /*
+     * Only an incomplete UTF-8 character is kept in the buffer,
+     * invalid sequences are printed as they are since they will never become valid.
+     */
+    let complete = match std::str::from_utf8(&state.output) {
+        Ok(_) => true,
+        Err(error) => error.error_len().is_some(),
+    };
+    if state.raw_output || complete {
+        match &mut state.captured_output {
+            Some(captured) => captured.append(&mut state.output),
+            None => {
+                state.writer.write_all(&state.output)?;
+                state.output.clear();
+            },
+        }
+    }
+    return Ok(());
 }
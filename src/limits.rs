@@ -0,0 +1,41 @@
+use crate::*;
+
+/*
+ * Every resource limit of a sandboxed run in one place.
+ * `run_with_limits` applies them to the state and runs it, the same way for the CLI,
+ * test cases and embedders, instead of each of them calling the setters and passing fuel around.
+ */
+
+#[derive(Clone, Debug)]
+pub struct ResourceLimits {
+    pub max_steps: Option<u64>, // Fuel, the run stops with `FuelExhausted` after this many steps
+    pub max_tape_cells: Option<usize>, // Cells of both directions, the run stops with `TapeLimitExceeded` past them
+    pub max_output: Option<u64>, // Printed bytes, the run stops with `OutputLimitExceeded` past them
+    pub wall_timeout: Option<Duration>, // The run stops with `TimedOut` after this much time
+    pub allow_input: bool, // Reading input fails with a permission error when not set
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        // No limits at all, like a state that was never configured
+        return ResourceLimits {
+            max_steps: None,
+            max_tape_cells: None,
+            max_output: None,
+            wall_timeout: None,
+            allow_input: true,
+        };
+    }
+}
+
+pub fn apply_limits(state: &mut BFState, limits: &ResourceLimits) {
+    // Sets the limits that are enforced by the state itself, the rest are arguments of `run_bf`
+    set_max_tape_cells(state, limits.max_tape_cells);
+    set_max_output_bytes(state, limits.max_output);
+    set_input_allowed(state, limits.allow_input);
+}
+
+pub fn run_with_limits(state: &mut BFState, limits: &ResourceLimits) -> Result<RunReport, BfError> {
+    apply_limits(state, limits);
+    return run_bf(state, limits.max_steps, limits.wall_timeout);
+}
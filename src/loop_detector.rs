@@ -0,0 +1,61 @@
+use crate::*;
+
+use std::collections::HashSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/*
+ * Infinite loop detection: every so often, `run_bf` hashes the instruction position, the cursor and the tape.
+ * A program without input is deterministic, so once the same state comes back it will loop forever,
+ * and the run stops with `RunOutcome::LoopDetected` instead of burning its fuel.
+ * Reading input can break the cycle, so the states seen so far are forgotten whenever a byte is read.
+ * The tape is hashed at most once per that many steps as it has cells, to keep the cost per step constant.
+ * Only cycles spanning fewer than `MAX_STATES` checks are caught; slow programs are never stopped by mistake,
+ * except for a hash collision, which is unlikely enough to ignore.
+ */
+
+const MIN_CHECK_INTERVAL: u64 = 1024; // Steps between checks on small tapes
+const MAX_STATES: usize = 1 << 16; // Hashes kept before starting over
+
+pub(crate) struct LoopDetector {
+    seen: HashSet<u64>, // Hashes of the states at previous checks
+    input_bytes: u64, // Bytes read when the states were seen
+    unchecked: u64, // Steps run since the last check
+}
+
+pub fn detect_infinite_loops(state: &mut BFState, enabled: bool) {
+    // Makes `run_bf` stop with `RunOutcome::LoopDetected` once the state repeats, see above
+    state.loop_detector = enabled.then(|| LoopDetector { seen: HashSet::new(), input_bytes: 0, unchecked: 0 });
+}
+
+pub(crate) fn repeats(state: &mut BFState, steps: u64) -> bool {
+    // Called by `run_bf` after each batch of steps
+    let Some(mut detector) = state.loop_detector.take() else {
+        return false;
+    };
+    detector.unchecked += steps;
+    let interval = MIN_CHECK_INTERVAL.max((state.ptape.len() + state.ntape.len()) as u64);
+    let mut repeated = false;
+    if detector.unchecked >= interval {
+        detector.unchecked = 0;
+        if detector.input_bytes != state.input_bytes || detector.seen.len() >= MAX_STATES {
+            detector.seen.clear();
+            detector.input_bytes = state.input_bytes;
+        }
+        repeated = !detector.seen.insert(state_hash(state));
+    }
+    state.loop_detector = Some(detector);
+    return repeated;
+}
+
+fn state_hash(state: &BFState) -> u64 {
+    // Trailing zero cells are left out, the tape growing doesn't change the state
+    let trim = |tape: &[u8]| tape.len() - tape.iter().rev().take_while(|&&cell| cell == 0).count();
+    let mut hasher = DefaultHasher::new();
+    state.instruction_position.hash(&mut hasher);
+    state.cursor_position.hash(&mut hasher);
+    state.ptape[..trim(&state.ptape)].hash(&mut hasher);
+    state.ntape[..trim(&state.ntape)].hash(&mut hasher);
+    state.output.hash(&mut hasher);
+    state.input.hash(&mut hasher);
+    return hasher.finish();
+}
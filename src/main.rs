@@ -1,27 +1,1835 @@
+mod audit;
+#[cfg(unix)]
+mod daemon;
+mod exercise;
+#[cfg(feature = "kernel")]
+mod kernel;
+mod repl;
+
 use brainfuckers::*;
 
-fn main() {
-    let code: String = match std::env::args().nth(1) {
-        Some(filename) => {
-            let code: Vec<u8> = match std::fs::read(&filename) {
-                Ok(c) => c,
-                Err(error) => {
-                    panic!("Failed to read file `{filename}` {error}")
+use std::io::{BufRead, IsTerminal, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+const EXIT_OK:              u8 = 0;
+const EXIT_FAILURE:         u8 = 1; // Bad arguments, unreadable files, etc.
+const EXIT_SYNTAX_ERROR:    u8 = 2;
+const EXIT_RUNTIME_ERROR:   u8 = 3;
+const EXIT_TIMEOUT:         u8 = 4;
+const EXIT_FUEL_EXHAUSTED:  u8 = 5;
+const EXIT_OUTPUT_LIMIT:    u8 = 6;
+const EXIT_TAPE_LIMIT:      u8 = 7;
+const EXIT_LOOP_DETECTED:   u8 = 8;
+const EXIT_INTERRUPTED:     u8 = 130; // 128 + SIGINT, like shells do
+
+// Options a package can hold and whether they take a value: limits and I/O modes, nothing that reads or writes files
+const PACKAGE_OPTIONS: &[(&str, bool)] = &[
+    ("--fuel", true), ("--timeout", true), ("--max-output-bytes", true), ("--max-tape-cells", true),
+    ("--max-output-rate", true), ("--no-input", false), ("--eof", true), ("--newlines", true), ("--charset", true),
+    ("--input-format", true), ("--output-format", true), ("--raw-output", false), ("--decimal-io", false),
+    ("--preset", true), ("--deterministic", false), ("--no-loop-tracing", false), ("--exit-cell", false),
+    ("--ignore-first-line", false), ("--strict-charset", false), ("--args-as-input", true),
+];
+// Options `pack` applies to the code or the tape image it stores, so packages don't need them
+const BAKED_IN_OPTIONS: &[(&str, bool)] = &[
+    ("--tape-init", true), ("--tape-origin", true), ("--dialect", true), ("--opcode-map", true),
+    ("--include-path", true), ("--define", true), ("--literate", false), ("--max-macro-depth", true),
+    ("--max-expansion", true), ("--max-macro-uses", true),
+];
+
+enum ArgsSeparator {
+    Space, // Joined into a single line, like they were typed
+    Newline, // Each argument is terminated by a newline
+    Nul, // Each argument is terminated by a null (0) byte
+}
+
+enum TapeFormat {
+    Raw, // The bytes as they are
+    Hex, // Space separated hex bytes
+    Csv, // `index,value` rows with a header
+}
+
+struct Options {
+    filename: Option<String>,
+    limits: ResourceLimits, // Steps, time, tape cells, output and input the program may use
+    exit_cell: bool, // Use the final value of cell 0 as the exit code
+    filter: bool, // Run the program again on a cleared tape when it halts before the input ends
+    per_line: bool, // Run the program once per line of input, each run reading only its line
+    ignore_first_line: bool, // Treat the first line of the code as a comment
+    program_args: Vec<String>, // Arguments after the filename, passed to the program as input
+    args_separator: ArgsSeparator, // How the program arguments are serialized
+    allowed_env: Vec<String>, // Environment variables readable through the `$` extension
+    decimal_io: bool, // Enable `;` and `%`, which print and read cells as decimal numbers
+    extensions: Option<Extensions>, // Extensions the program may use, their characters aren't comments then
+    strict_charset: bool, // Anything but commands and whitespace is a syntax error
+    dump_on_interrupt: Option<String>, // Where to write a state snapshot when interrupted
+    checkpoint_every: Option<u64>, // Steps between checkpoints
+    checkpoint_file: Option<String>, // Where checkpoints are written
+    resume: Option<String>, // Checkpoint to continue the run from
+    max_output_rate: Option<u64>, // Maximum printed bytes per second
+    input_format: InputFormat, // How the input read from stdin is encoded
+    output_format: OutputFormat, // How the output is encoded
+    newline_mode: NewlineMode, // How newlines are translated in both directions
+    eof_behavior: EofBehavior, // What reading past the end of the input does to the cell
+    charset: Option<Charset>, // Translation tables for the input and the output
+    deterministic: bool, // Refuse anything that could make the output differ between runs
+    loop_tracing: bool, // Run hot linear loops in a single step
+    jit: bool, // Compile the program to native code before running it
+    image: Option<String>, // Image dialect of the program file (`brainloller` or `braincopter`)
+    dialect: Option<String>, // Language of the program file, translated to brainfuck after preprocessing; guessed if not given
+    opcode_map: Option<OpcodeMap>, // Bytes standing for the commands in the program file, instead of `+-<>.,[]`
+    tape_init: Option<String>, // File whose bytes are loaded into the tape before running
+    tape_origin: isize, // Index of the cell where the tape image starts
+    dump_tape: Option<String>, // Where to write the final tape contents
+    dump_tape_range: Option<(isize, isize)>, // Cells to dump (end exclusive), all allocated cells by default
+    dump_tape_format: TapeFormat,
+    canvas: Option<String>, // Where to render the turtle drawing the output describes
+    explain: bool, // Narrate each step on stderr
+    explain_window: Option<(u64, u64)>, // Steps to narrate (end exclusive), all of them by default
+    explain_rate: Option<f64>, // Maximum narrated steps per second
+    radix: DisplayRadix, // How cell values are shown in narrations and snapshots
+    ip_history: Option<usize>, // Latest instructions to show when the program fails or gets interrupted
+    loop_stack: bool, // Show the loops the program was in when it fails or gets interrupted
+    detect_loops: bool, // Stop when the state repeats
+    taint: bool, // Report which cells and output bytes depend on input
+    tape_stats: bool, // Report how much of the tape the program used
+    hints: bool, // Point out likely mistakes before running, and loops that may be why a run didn't end
+    warnings: bool, // Write warnings (hints included) on stderr
+    cache_dir: Option<String>, // Where the output of programs without input is cached between runs
+    read_files: Vec<String>, // Files the options read, besides the program, for the output cache key
+    preprocess: PreprocessOptions, // How directives like `@include` are expanded
+}
+
+impl PresetTarget for Options {
+    // `--preset` sets the options, the state is configured from them later
+    fn set_max_tape_cells(&mut self, limit: Option<usize>) {
+        self.limits.max_tape_cells = limit;
+    }
+
+    fn set_eof_behavior(&mut self, behavior: EofBehavior) {
+        self.eof_behavior = behavior;
+    }
+
+    fn set_output_format(&mut self, format: OutputFormat) {
+        self.output_format = format;
+    }
+
+    fn set_newline_mode(&mut self, mode: NewlineMode) {
+        self.newline_mode = mode;
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        filename: None,
+        limits: ResourceLimits::default(),
+        exit_cell: false,
+        filter: false,
+        per_line: false,
+        ignore_first_line: false,
+        program_args: Vec::new(),
+        args_separator: ArgsSeparator::Space,
+        allowed_env: Vec::new(),
+        decimal_io: false,
+        extensions: None,
+        strict_charset: false,
+        dump_on_interrupt: None,
+        checkpoint_every: None,
+        checkpoint_file: None,
+        resume: None,
+        max_output_rate: None,
+        input_format: InputFormat::Raw,
+        output_format: OutputFormat::Utf8,
+        newline_mode: NewlineMode::Unchanged,
+        eof_behavior: EofBehavior::Zero,
+        charset: None,
+        deterministic: false,
+        loop_tracing: true,
+        jit: false,
+        image: None,
+        dialect: None,
+        opcode_map: None,
+        tape_init: None,
+        tape_origin: 0,
+        dump_tape: None,
+        dump_tape_range: None,
+        dump_tape_format: TapeFormat::Raw,
+        canvas: None,
+        explain: false,
+        explain_window: None,
+        explain_rate: None,
+        radix: DisplayRadix::Decimal,
+        ip_history: None,
+        loop_stack: false,
+        detect_loops: false,
+        taint: false,
+        hints: false,
+        warnings: true,
+        tape_stats: false,
+        cache_dir: None,
+        read_files: Vec::new(),
+        preprocess: PreprocessOptions::default(),
+    };
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--fuel" => {
+                let value = flag_value(args, i)?;
+                options.limits.max_steps = Some(value.parse().map_err(|_| format!("Invalid value for `--fuel`: {value}"))?);
+                i += 1;
+            },
+            "--timeout" => {
+                let value = flag_value(args, i)?;
+                let seconds: f64 = value.parse().map_err(|_| format!("Invalid value for `--timeout`: {value}"))?;
+                options.limits.wall_timeout = Some(Duration::try_from_secs_f64(seconds).map_err(|_| format!("Invalid value for `--timeout`: {value}"))?);
+                i += 1;
+            },
+            "--max-output-bytes" => {
+                let value = flag_value(args, i)?;
+                options.limits.max_output = Some(value.parse().map_err(|_| format!("Invalid value for `--max-output-bytes`: {value}"))?);
+                i += 1;
+            },
+            "--max-tape-cells" => {
+                let value = flag_value(args, i)?;
+                options.limits.max_tape_cells = Some(value.parse().map_err(|_| format!("Invalid value for `--max-tape-cells`: {value}"))?);
+                i += 1;
+            },
+            "--no-input" => options.limits.allow_input = false,
+            "--checkpoint-every" => {
+                let value = flag_value(args, i)?;
+                options.checkpoint_every = Some(value.parse().ok().filter(|&n| n > 0).ok_or_else(|| format!("Invalid value for `--checkpoint-every`: {value}"))?);
+                i += 1;
+            },
+            "--checkpoint-file" => {
+                options.checkpoint_file = Some(String::from(flag_value(args, i)?));
+                i += 1;
+            },
+            "--resume" => {
+                options.resume = Some(String::from(flag_value(args, i)?));
+                options.read_files.push(String::from(flag_value(args, i)?));
+                i += 1;
+            },
+            "--max-output-rate" => {
+                let value = flag_value(args, i)?;
+                options.max_output_rate = Some(value.parse().map_err(|_| format!("Invalid value for `--max-output-rate`: {value}"))?);
+                i += 1;
+            },
+            "--raw-output" => options.output_format = OutputFormat::Raw,
+            "--charset" => {
+                let value = flag_value(args, i)?;
+                options.charset = Some(Charset::preset(value).ok_or_else(|| format!("Invalid value for `--charset`: {value} (expected `ebcdic-037` or `ebcdic-500`)"))?);
+                i += 1;
+            },
+            "--charset-file" => {
+                let value = flag_value(args, i)?;
+                let bytes = std::fs::read(value).map_err(|error| format!("Failed to read file `{value}` {error}"))?;
+                options.charset = Some(Charset::from_bytes(&bytes).map_err(|error| error.to_string())?);
+                options.read_files.push(String::from(value));
+                i += 1;
+            },
+            "--newlines" => {
+                options.newline_mode = match flag_value(args, i)? {
+                    "unchanged" => NewlineMode::Unchanged,
+                    "crlf" => NewlineMode::Crlf,
+                    "strip-cr" => NewlineMode::StripCr,
+                    value => return Err(format!("Invalid value for `--newlines`: {value} (expected `unchanged`, `crlf` or `strip-cr`)")),
+                };
+                i += 1;
+            },
+            "--eof" => {
+                options.eof_behavior = match flag_value(args, i)? {
+                    "0" => EofBehavior::Zero,
+                    "unchanged" => EofBehavior::Unchanged,
+                    "255" => EofBehavior::MaxValue,
+                    value => return Err(format!("Invalid value for `--eof`: {value} (expected `0`, `unchanged` or `255`)")),
+                };
+                i += 1;
+            },
+            "--input-format" => {
+                options.input_format = match flag_value(args, i)? {
+                    "raw" => InputFormat::Raw,
+                    "hex" => InputFormat::Hex,
+                    "base64" => InputFormat::Base64,
+                    value => return Err(format!("Invalid value for `--input-format`: {value} (expected `raw`, `hex` or `base64`)")),
+                };
+                i += 1;
+            },
+            "--output-format" => {
+                options.output_format = match flag_value(args, i)? {
+                    "utf8" => OutputFormat::Utf8,
+                    "raw" => OutputFormat::Raw,
+                    "hex" => OutputFormat::Hex,
+                    "base64" => OutputFormat::Base64,
+                    "wav" => OutputFormat::Wav,
+                    "pcm" => OutputFormat::Pcm,
+                    value => return Err(format!("Invalid value for `--output-format`: {value} (expected `utf8`, `raw`, `hex`, `base64`, `wav` or `pcm`)")),
+                };
+                i += 1;
+            },
+            "--preset" => {
+                // Later options override what the preset sets
+                let value = flag_value(args, i)?;
+                let Some(preset) = Preset::named(value) else {
+                    let names: Vec<&str> = Preset::all().iter().map(|preset| preset.name).collect();
+                    return Err(format!("Invalid value for `--preset`: {value} (expected {})", names.join(", ")));
+                };
+                preset.apply(&mut options).map_err(|error| format!("Invalid value for `--preset`: {value} ({error})"))?;
+                i += 1;
+            },
+            "--deterministic" => options.deterministic = true,
+            "--no-loop-tracing" => options.loop_tracing = false,
+            "--jit" if cfg!(feature = "cranelift") => options.jit = true,
+            "--jit" => return Err(String::from("`--jit` needs the interpreter to be built with the `cranelift` feature")),
+            "--dialect" => {
+                let value = flag_value(args, i)?;
+                let registry = DialectRegistry::default();
+                if registry.get(value).is_none() {
+                    return Err(format!("Invalid value for `--dialect`: {value} (expected {})", registry.names().join(", ")));
+                }
+                options.dialect = Some(String::from(value));
+                i += 1;
+            },
+            "--opcode-map" => {
+                let value = flag_value(args, i)?;
+                let text = std::fs::read_to_string(value).map_err(|error| format!("Failed to read file `{value}` {error}"))?;
+                options.opcode_map = Some(OpcodeMap::parse(&text).map_err(|error| format!("{error} (in `{value}`)"))?);
+                options.read_files.push(String::from(value));
+                i += 1;
+            },
+            "--image" if cfg!(feature = "images") => {
+                options.image = match flag_value(args, i)? {
+                    value @ ("brainloller" | "braincopter") => Some(String::from(value)),
+                    value => return Err(format!("Invalid value for `--image`: {value} (expected `brainloller` or `braincopter`)")),
+                };
+                i += 1;
+            },
+            "--image" => return Err(String::from("`--image` needs the interpreter to be built with the `images` feature")),
+            "--exit-cell" => options.exit_cell = true,
+            "--filter" => options.filter = true,
+            "--per-line" => options.per_line = true,
+            "--ignore-first-line" => options.ignore_first_line = true,
+            "--extensions" => {
+                let mut extensions = Extensions::NONE;
+                for name in flag_value(args, i)?.split(',').filter(|name| *name != "none") {
+                    let extension = Extensions::from_name(name).ok_or_else(|| format!("Unknown extension: {name}"))?;
+                    if !Extensions::SUPPORTED.contains(extension) {
+                        return Err(format!("The `{name}` extension isn't supported yet"));
+                    }
+                    extensions = extensions | extension;
+                }
+                options.extensions = Some(extensions);
+                i += 1;
+            },
+            "--strict-charset" => options.strict_charset = true,
+            "--allow-env" => {
+                options.allowed_env.push(String::from(flag_value(args, i)?));
+                i += 1;
+            },
+            "--decimal-io" => options.decimal_io = true,
+            "--dump-on-interrupt" => {
+                options.dump_on_interrupt = Some(String::from(flag_value(args, i)?));
+                i += 1;
+            },
+            "--canvas" => {
+                let path = flag_value(args, i)?;
+                if path.ends_with(".png") && !cfg!(feature = "images") {
+                    return Err(String::from("PNG canvases need the `images` feature, use an `.svg` file instead"));
+                }
+                options.canvas = Some(String::from(path));
+                i += 1;
+            },
+            "--tape-init" => {
+                options.tape_init = Some(String::from(flag_value(args, i)?));
+                options.read_files.push(String::from(flag_value(args, i)?));
+                i += 1;
+            },
+            "--tape-origin" => {
+                let value = flag_value(args, i)?;
+                options.tape_origin = value.parse().map_err(|_| format!("Invalid value for `--tape-origin`: {value}"))?;
+                i += 1;
+            },
+            "--dump-tape" => {
+                options.dump_tape = Some(String::from(flag_value(args, i)?));
+                i += 1;
+            },
+            "--dump-tape-range" => {
+                let value = flag_value(args, i)?;
+                let range = value.split_once("..").and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)));
+                options.dump_tape_range = Some(range.ok_or_else(|| format!("Invalid value for `--dump-tape-range`: {value} (expected `<start>..<end>`)"))?);
+                i += 1;
+            },
+            "--dump-tape-format" => {
+                options.dump_tape_format = match flag_value(args, i)? {
+                    "raw" => TapeFormat::Raw,
+                    "hex" => TapeFormat::Hex,
+                    "csv" => TapeFormat::Csv,
+                    value => return Err(format!("Invalid value for `--dump-tape-format`: {value} (expected `raw`, `hex` or `csv`)")),
+                };
+                i += 1;
+            },
+            "--explain" => options.explain = true,
+            "--explain-window" => {
+                let value = flag_value(args, i)?;
+                let window = value.split_once("..").and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)));
+                options.explain_window = Some(window.ok_or_else(|| format!("Invalid value for `--explain-window`: {value} (expected `<start>..<end>`)"))?);
+                i += 1;
+            },
+            "--explain-rate" => {
+                let value = flag_value(args, i)?;
+                let rate: f64 = value.parse().map_err(|_| format!("Invalid value for `--explain-rate`: {value}"))?;
+                if rate <= 0.0 || Duration::try_from_secs_f64(1.0 / rate).is_err() {
+                    return Err(format!("Invalid value for `--explain-rate`: {value}"));
                 }
+                options.explain_rate = Some(rate);
+                i += 1;
+            },
+            "--radix" => {
+                options.radix = flag_value(args, i)?.parse()?;
+                i += 1;
+            },
+            "--ip-history" => {
+                let value = flag_value(args, i)?;
+                options.ip_history = Some(value.parse().ok().filter(|&n| n > 0).ok_or_else(|| format!("Invalid value for `--ip-history`: {value}"))?);
+                i += 1;
+            },
+            "--loop-stack" => options.loop_stack = true,
+            "--detect-loops" => options.detect_loops = true,
+            "--taint" => options.taint = true,
+            "--tape-stats" => options.tape_stats = true,
+            "--hints" => options.hints = true,
+            "--quiet" | "--warnings=off" => options.warnings = false,
+            "--warnings=on" => options.warnings = true,
+            "--warnings" => {
+                options.warnings = match flag_value(args, i)? {
+                    "on" => true,
+                    "off" => false,
+                    value => return Err(format!("Invalid value for `--warnings`: {value} (expected `on` or `off`)")),
+                };
+                i += 1;
+            },
+            "--cache-dir" => {
+                options.cache_dir = Some(String::from(flag_value(args, i)?));
+                i += 1;
+            },
+            "--include-path" | "--literate" | "--define" | "--max-macro-depth" | "--max-expansion" | "--max-macro-uses" => {
+                preprocess_flag(args, &mut i, &mut options.preprocess)?;
+            },
+            "--args-as-input" => {
+                options.args_separator = match flag_value(args, i)? {
+                    "newline" => ArgsSeparator::Newline,
+                    "nul" => ArgsSeparator::Nul,
+                    value => return Err(format!("Invalid value for `--args-as-input`: {value} (expected `newline` or `nul`)")),
+                };
+                i += 1;
+            },
+            arg if arg.starts_with("--") => return Err(format!("Unknown option `{arg}`")),
+            arg => {
+                // Everything after the filename belongs to the program
+                options.filename = Some(String::from(arg));
+                options.program_args = args[i + 1..].to_vec();
+                break;
+            },
+        }
+        i += 1;
+    }
+
+    let limits = &options.limits;
+    if options.jit && (limits.max_steps.is_some() || limits.wall_timeout.is_some() || limits.max_tape_cells.is_some() || options.explain || options.detect_loops || options.taint) {
+        return Err(String::from("`--jit` can't be combined with `--fuel`, `--timeout`, `--max-tape-cells`, `--explain`, `--detect-loops` or `--taint`"));
+    }
+    if options.checkpoint_every.is_some() != options.checkpoint_file.is_some() {
+        return Err(String::from("`--checkpoint-every` and `--checkpoint-file` have to be given together"));
+    }
+    if options.jit && (options.checkpoint_every.is_some() || options.resume.is_some()) {
+        return Err(String::from("`--jit` can't be combined with checkpoints"));
+    }
+    if (options.filter || options.per_line) && (options.checkpoint_every.is_some() || options.resume.is_some()) {
+        return Err(String::from("`--filter` and `--per-line` can't be combined with checkpoints"));
+    }
+    if options.opcode_map.is_some() && options.dialect.as_ref().is_some_and(|dialect| dialect != "brainfuck") {
+        return Err(String::from("`--opcode-map` can't be combined with `--dialect`"));
+    }
+    if options.filter && options.per_line {
+        return Err(String::from("`--filter` and `--per-line` can't be combined"));
+    }
+    return Ok(options);
+}
+
+fn preprocess_flag(args: &[String], i: &mut usize, options: &mut PreprocessOptions) -> Result<bool, String> {
+    // Parses the preprocessing option at `args[*i]`, moving `i` to its value; `false` if it isn't one
+    match args[*i].as_str() {
+        "--include-path" => {
+            options.search_paths.push(PathBuf::from(flag_value(args, *i)?));
+            *i += 1;
+        },
+        "--literate" => options.literate = true,
+        "--define" => {
+            options.defines.insert(String::from(flag_value(args, *i)?));
+            *i += 1;
+        },
+        "--max-macro-depth" => {
+            let value = flag_value(args, *i)?;
+            options.max_macro_depth = value.parse().map_err(|_| format!("Invalid value for `--max-macro-depth`: {value}"))?;
+            *i += 1;
+        },
+        "--max-expansion" => {
+            let value = flag_value(args, *i)?;
+            options.max_expansion = value.parse().map_err(|_| format!("Invalid value for `--max-expansion`: {value}"))?;
+            *i += 1;
+        },
+        "--max-macro-uses" => {
+            let value = flag_value(args, *i)?;
+            options.max_macro_uses = value.parse().map_err(|_| format!("Invalid value for `--max-macro-uses`: {value}"))?;
+            *i += 1;
+        },
+        _ => return Ok(false),
+    }
+    return Ok(true);
+}
+
+fn take_preprocess_flags(args: &[String]) -> Result<(Vec<String>, PreprocessOptions), String> {
+    // Splits the preprocessing options off the arguments of a subcommand, which parses the rest
+    let mut options = PreprocessOptions::default();
+    let mut rest = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        if !preprocess_flag(args, &mut i, &mut options)? {
+            rest.push(args[i].clone());
+        }
+        i += 1;
+    }
+    return Ok((rest, options));
+}
+
+fn load_checked_source(filename: &str, options: &PreprocessOptions) -> Result<(Arc<Program>, SourceMap), String> {
+    // The preprocessed program of a subcommand, with syntax errors located in the file they come from
+    let (code, map) = preprocess(Path::new(filename), options).map_err(|error| error.to_string())?;
+    let program = new_program(&code);
+    program.check_syntax().map_err(|error| match error {
+        BfError::Syntax { position, message } if let Some((file, offset)) = map.locate(position) => {
+            format!("Syntax error in `{}` at byte {offset}: {message}", file.display())
+        },
+        error => error.to_string(),
+    })?;
+    return Ok((program, map));
+}
+
+fn run_records(state: &mut BFState, options: &Options, resumed_steps: u64, next_line: &AtomicBool) -> Result<RunReport, BfError> {
+    /*
+     * `--filter`: a program handling one record and halting is run again for the next one, on a cleared tape,
+     * until the input ends. Runs that didn't read anything aren't repeated, they would never get to the end.
+     * `--per-line`: the program runs once for every line, reading EOF after it (see `LineReader`), and not at all
+     * without input. The limits apply to each run.
+     */
+
+    if options.per_line && !input_remaining(state) {
+        return Ok(RunReport {
+            outcome: RunOutcome::Halted,
+            steps: 0,
+            output: Vec::new(),
+            duration: Duration::ZERO,
+            final_cursor: 0,
+            tape_extent: tape_extent(state),
+        });
+    }
+    loop {
+        let read = input_bytes(state);
+        let report = run_with_checkpoints(state, options, resumed_steps)?;
+        if !matches!(report.outcome, RunOutcome::Halted) {
+            return Ok(report);
+        }
+        if options.per_line {
+            next_line.store(true, Ordering::Relaxed);
+        }
+        else if !options.filter || input_bytes(state) == read {
+            return Ok(report);
+        }
+        if !input_remaining(state) {
+            return Ok(report);
+        }
+        restart(state);
+    }
+}
+
+fn run_with_checkpoints(state: &mut BFState, options: &Options, resumed_steps: u64) -> Result<RunReport, BfError> {
+    /*
+     * Runs the program in stretches of `--checkpoint-every` steps, writing a checkpoint after each one.
+     * The checkpoint is written next to the file first and renamed over it, so a crash never leaves half of one.
+     */
+
+    let (Some(every), Some(path)) = (options.checkpoint_every, &options.checkpoint_file) else {
+        return run(state, options);
+    };
+    yield_every_n_steps(state, every, Box::new(|_| ControlFlow::Break(())));
+    let started = Instant::now();
+    let mut limits = options.limits.clone();
+    let mut steps = 0;
+    loop {
+        let mut report = run_with_limits(state, &limits)?;
+        steps += report.steps;
+        if !matches!(report.outcome, RunOutcome::Cancelled) {
+            report.steps = steps;
+            report.duration = started.elapsed();
+            return Ok(report);
+        }
+        let checkpoint = save_checkpoint(state, resumed_steps + steps)?;
+        let temporary = format!("{path}.tmp");
+        std::fs::write(&temporary, checkpoint).and_then(|()| std::fs::rename(&temporary, path))?;
+        limits.max_steps = options.limits.max_steps.map(|fuel| fuel - steps);
+        limits.wall_timeout = options.limits.wall_timeout.map(|timeout| timeout.saturating_sub(started.elapsed()));
+    }
+}
+
+#[cfg(feature = "cranelift")]
+fn run(state: &mut BFState, options: &Options) -> Result<RunReport, BfError> {
+    if options.jit {
+        let jit = JitProgram::compile(&program(state))?;
+        apply_limits(state, &options.limits);
+        return run_jit(state, &jit);
+    }
+    return run_with_limits(state, &options.limits);
+}
+
+#[cfg(not(feature = "cranelift"))]
+fn run(state: &mut BFState, options: &Options) -> Result<RunReport, BfError> {
+    return run_with_limits(state, &options.limits);
+}
+
+struct LineReader {
+    inner: std::io::BufReader<std::io::Stdin>,
+    line_ended: bool, // A newline was read, reading gets EOF until the next line is requested
+    next_line: Arc<AtomicBool>, // Set by `--per-line` between runs, skips what's left of the line and starts the next one
+}
+
+impl std::io::Read for LineReader {
+    fn read(&mut self, bytes: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(bytes.len());
+        bytes[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        return Ok(n);
+    }
+}
+
+impl BufRead for LineReader {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.next_line.swap(false, Ordering::Relaxed) {
+            if !self.line_ended {
+                self.inner.skip_until(b'\n')?;
+            }
+            self.line_ended = false;
+        }
+        if self.line_ended {
+            return Ok(&[]);
+        }
+        let buffer = self.inner.fill_buf()?;
+        let end = buffer.iter().position(|&c| c == b'\n').map_or(buffer.len(), |newline| newline + 1);
+        return Ok(&buffer[..end]);
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.line_ended |= self.inner.buffer()[..amount].contains(&b'\n');
+        self.inner.consume(amount);
+    }
+}
+
+struct RecordingWriter {
+    inner: Box<dyn Write + Send>,
+    recorded: Arc<Mutex<Vec<u8>>>, // Everything written so far, for the output cache
+}
+
+impl Write for RecordingWriter {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(bytes)?;
+        self.recorded.lock().unwrap().extend_from_slice(&bytes[..written]);
+        return Ok(written);
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return self.inner.flush();
+    }
+}
+
+fn output_cacheable(state: &BFState, options: &Options, package: &Option<Package>) -> bool {
+    // Runs whose only result is what they print to stdout, and which print the same thing every time
+    return is_output_only(&program(state))
+        && !options.exit_cell
+        && options.tape_init.is_none()
+        && package.as_ref().is_none_or(|package| package.tape.is_empty())
+        && options.resume.is_none()
+        && options.checkpoint_every.is_none()
+        && options.dump_tape.is_none()
+        && options.canvas.is_none()
+        && !options.explain
+        && !options.tape_stats // Reports on stderr, which a cached run wouldn't print
+        && !options.taint
+        && !options.hints
+        && !options.per_line // Both run the program again for every line or every chunk of stdin
+        && !options.filter;
+}
+
+#[cfg(feature = "images")]
+fn image_dialect(name: &str) -> Result<ImageDialect, String> {
+    return match name {
+        "brainloller" => Ok(ImageDialect::Brainloller),
+        "braincopter" => Ok(ImageDialect::Braincopter),
+        _ => Err(format!("Unknown image dialect `{name}` (expected `brainloller` or `braincopter`)")),
+    };
+}
+
+#[cfg(feature = "images")]
+fn read_image_program(filename: &str, dialect: &str) -> Result<Vec<u8>, String> {
+    let png = std::fs::read(filename).map_err(|error| format!("Failed to read file `{filename}` {error}"))?;
+    return decode_image(&png, image_dialect(dialect)?).map_err(|error| error.to_string());
+}
+
+#[cfg(not(feature = "images"))]
+fn read_image_program(_filename: &str, _dialect: &str) -> Result<Vec<u8>, String> {
+    return Err(String::from("Image programs need the interpreter to be built with the `images` feature"));
+}
+
+#[cfg(feature = "images")]
+fn encode_image_program(args: &[String]) -> Result<(), String> {
+    // `bf encode-image <dialect> <program> -o <file> [--width <pixels>]`
+    let (args, preprocess) = take_preprocess_flags(args)?;
+    let args = args.as_slice();
+    let mut positional: Vec<&str> = Vec::new();
+    let mut output: Option<&str> = None;
+    let mut width: usize = 64;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-o" | "--output" => {
+                output = Some(flag_value(args, i)?);
+                i += 1;
+            },
+            "--width" => {
+                let value = flag_value(args, i)?;
+                width = value.parse().map_err(|_| format!("Invalid value for `--width`: {value}"))?;
+                i += 1;
+            },
+            arg if arg.starts_with('-') => return Err(format!("Unknown option `{arg}`")),
+            arg => positional.push(arg),
+        }
+        i += 1;
+    }
+    let (&[dialect, filename], Some(output)) = (positional.as_slice(), output) else {
+        return Err(String::from("Usage: bf encode-image <brainloller|braincopter> <program> -o <file> [--width <pixels>]"));
+    };
+
+    let (program, _) = load_checked_source(filename, &preprocess)?;
+    let png = encode_image(program.code(), image_dialect(dialect)?, width).map_err(|error| error.to_string())?;
+    return std::fs::write(output, png).map_err(|error| format!("Failed to write file `{output}` {error}"));
+}
+
+#[cfg(not(feature = "images"))]
+fn encode_image_program(_args: &[String]) -> Result<(), String> {
+    return Err(String::from("`encode-image` needs the interpreter to be built with the `images` feature"));
+}
+
+fn translate(code: &[u8], options: &Options) -> Result<Option<Vec<u8>>, BfError> {
+    // The code as brainfuck, `None` if it's brainfuck already: it's kept as it is then, with its comments and labels
+    if let Some(map) = &options.opcode_map {
+        let mut registry = DialectRegistry::default();
+        registry.register(Box::new(map.clone()));
+        return registry.translate(map.name(), code).map(Some);
+    }
+    let dialect = options.dialect.as_deref().unwrap_or_else(|| detect_dialect(code));
+    if dialect == "brainfuck" {
+        return Ok(None);
+    }
+    return DialectRegistry::default().translate(dialect, code).map(Some);
+}
+
+fn flag_value(args: &[String], i: usize) -> Result<&str, String> {
+    match args.get(i + 1) {
+        Some(value) => Ok(value.as_str()),
+        None => Err(format!("Missing value for `{}`", args[i])),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    // For secrets: every byte is compared, so the time taken doesn't tell how much of a guess was right
+    if a.len() != b.len() {
+        return false;
+    }
+    let difference = a.iter().zip(b).fold(0, |difference, (x, y)| difference | (x ^ y));
+    return std::hint::black_box(difference) == 0;
+}
+
+fn serialize_args(args: &[String], separator: &ArgsSeparator) -> Vec<u8> {
+    let mut input: Vec<u8> = Vec::new();
+    match separator {
+        ArgsSeparator::Space => {
+            input.extend(args.join(" ").as_bytes());
+            input.push(b'\n');
+        },
+        ArgsSeparator::Newline | ArgsSeparator::Nul => {
+            let terminator = if let ArgsSeparator::Nul = separator { 0 } else { b'\n' };
+            for arg in args {
+                input.extend(arg.as_bytes());
+                input.push(terminator);
+            }
+        },
+    }
+    return input;
+}
+
+fn format_tape(cells: &[u8], start: isize, format: &TapeFormat) -> Vec<u8> {
+    match format {
+        TapeFormat::Raw => cells.to_vec(),
+        TapeFormat::Hex => {
+            let hex: Vec<String> = cells.iter().map(|c| format!("{c:02x}")).collect();
+            format!("{}\n", hex.join(" ")).into_bytes()
+        },
+        TapeFormat::Csv => {
+            let mut csv = String::from("index,value\n");
+            for (index, value) in (start..).zip(cells) {
+                csv.push_str(&format!("{index},{value}\n"));
+            }
+            csv.into_bytes()
+        },
+    }
+}
+
+struct SourceLine {
+    name: String, // The file, `<program>` for code that didn't come from one
+    line: usize, // Counted from 1
+    column: usize, // In characters, counted from 1
+    text: String, // The whole line, without its newline
+}
+
+fn source_line(position: usize, code: &[u8], source_map: Option<&SourceMap>) -> Option<SourceLine> {
+    // The line an instruction of the code is on, in the file it came from when there's a source map
+    let (name, text, offset) = match source_map {
+        Some(map) => {
+            let (file, offset) = map.locate(position)?;
+            (file.display().to_string(), std::fs::read(file).ok()?, offset)
+        },
+        None if position < code.len() => (String::from("<program>"), code.to_vec(), position),
+        None => return None,
+    };
+    let start = text[..offset].iter().rposition(|&c| c == b'\n').map_or(0, |i| i + 1);
+    let end = text[offset..].iter().position(|&c| c == b'\n').map_or(text.len(), |i| offset + i);
+    return Some(SourceLine {
+        name,
+        line: text[..start].iter().filter(|&&c| c == b'\n').count() + 1,
+        column: String::from_utf8_lossy(&text[start..offset]).chars().count() + 1,
+        text: String::from_utf8_lossy(&text[start..end]).trim_end_matches('\r').to_string(),
+    });
+}
+
+fn print_report(state: &BFState, message: &str, source_map: Option<&SourceMap>) {
+    /*
+     * Why the run stopped, like rustc reports errors: the message, the line of the current instruction with a caret
+     * under it, the loops it's in (innermost first, with their iteration under `--loop-stack`) and the latest
+     * executed instructions under `--ip-history`. Colored when stderr is a terminal, unless `NO_COLOR` is set.
+     */
+
+    let color = std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let paint = |style: &str, text: &str| if color { format!("\x1b[{style}m{text}\x1b[0m") } else { String::from(text) };
+    let (red, blue) = ("1;31", "1;34");
+    let code = program(state).code().to_vec();
+    let locate = |position: usize| match source_line(position, &code, source_map) {
+        Some(line) => format!("{}:{}:{}", line.name, line.line, line.column),
+        None => format!("byte {position}"),
+    };
+
+    // Capitalized messages start lowercase after `error:`, acronyms like `I/O` stay as they are
+    let mut chars = message.chars();
+    let message = match (chars.next(), chars.next()) {
+        (Some(first), Some(second)) if second.is_lowercase() => format!("{}{}", first.to_lowercase(), &message[first.len_utf8()..]),
+        _ => String::from(message),
+    };
+    eprintln!("{}{}", paint(red, "error"), paint("1", &format!(": {message}")));
+    let current = source_line(source_position(state), &code, source_map);
+    let gutter = " ".repeat(current.as_ref().map_or(1, |line| line.line.to_string().len()));
+    if let Some(line) = current {
+        let padding: String = line.text.chars().take(line.column - 1).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+        eprintln!("{gutter}{} {}:{}:{}", paint(blue, "-->"), line.name, line.line, line.column);
+        eprintln!("{gutter} {}", paint(blue, "|"));
+        eprintln!("{} {}", paint(blue, &format!("{} |", line.line)), line.text);
+        eprintln!("{gutter} {} {padding}{}", paint(blue, "|"), paint(red, "^"));
+    }
+    for open in loop_stack(state).iter().rev() {
+        let iteration = if open.iteration > 0 { format!(", iteration {}", open.iteration) } else { String::new() };
+        eprintln!("{gutter} {} in loop at {}{iteration}", paint(blue, "="), locate(open.position));
+    }
+    let positions = recent_ips(state);
+    if !positions.is_empty() {
+        eprintln!("{gutter} {} last {} instructions, oldest first:", paint(blue, "="), positions.len());
+        for position in positions {
+            eprintln!("{gutter}     {}  {}", code[position] as char, locate(position));
+        }
+    }
+}
+
+fn locate_hint(position: usize, source_map: Option<&SourceMap>) -> String {
+    // Suggestions may point right past the end of the code, which is right after its last byte
+    let located = source_map.and_then(|map| {
+        return map.locate(position).or_else(|| map.locate(position.checked_sub(1)?).map(|(file, offset)| (file, offset + 1)));
+    });
+    return match located {
+        Some((file, offset)) => format!("in `{}` at byte {offset}", file.display()),
+        None => format!("at byte {position}"),
+    };
+}
+
+fn print_hints(hints: &[Hint], source_map: Option<&SourceMap>) {
+    for hint in hints {
+        let suggestion = hint.suggestion.map_or_else(String::new, |position| format!(" ({})", locate_hint(position, source_map)));
+        eprintln!("Hint {}: {}{suggestion}", locate_hint(hint.position, source_map), hint.message);
+    }
+}
+
+fn format_ranges(indices: &[i64]) -> String {
+    // `0-3, 7, 9-10` for sorted indices
+    let mut ranges: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < indices.len() {
+        let start = indices[i];
+        while i + 1 < indices.len() && indices[i + 1] == indices[i] + 1 {
+            i += 1;
+        }
+        ranges.push(if indices[i] == start { start.to_string() } else { format!("{start}-{}", indices[i]) });
+        i += 1;
+    }
+    return if ranges.is_empty() { String::from("none") } else { ranges.join(", ") };
+}
+
+fn print_taint(state: &BFState) {
+    // What `--taint` found, after the output
+    let cells: Vec<i64> = tainted_cells(state).into_iter().map(|cell| cell as i64).collect();
+    let output: Vec<i64> = tainted_output(state).into_iter().map(|index| index as i64).collect();
+    eprintln!("Cells depending on input: {}", format_ranges(&cells));
+    eprintln!("Output bytes depending on input: {}", format_ranges(&output));
+}
+
+fn print_tape_stats(state: &BFState) {
+    // What `--tape-stats` found, after the output
+    let stats = tape_stats(state);
+    eprintln!("Tape used: cells {} to {} ({} cells)", stats.lowest, stats.highest, stats.cells());
+    eprintln!("Tape resizes: {} positive, {} negative", stats.ptape_resizes, stats.ntape_resizes);
+}
+
+fn explainer(window: Option<(u64, u64)>, rate: Option<f64>, radix: DisplayRadix) -> StepObserver {
+    // Narrates the steps in the window on stderr, sleeping between them to match the rate
+    let (start, end) = window.unwrap_or((0, u64::MAX));
+    let delay = rate.map(|rate| Duration::from_secs_f64(1.0 / rate));
+    let mut step: u64 = 0;
+    return Box::new(move |state| {
+        if (start..end).contains(&step)
+            && let Some(sentence) = explain_step(state, radix) {
+            eprintln!("step {step}, {sentence}");
+            if let Some(delay) = delay {
+                std::thread::sleep(delay);
+            }
+        }
+        step += 1;
+    });
+}
+
+fn pack(args: &[String]) -> Result<(), String> {
+    /*
+     * `bf pack [--input <file>] <bundle> [options] <program> [args...]`
+     * Everything from the options on is stored like it would be passed to `bf`, except the files
+     * which are embedded; the program arguments are serialized into the input.
+     */
+
+    let mut package = Package::default();
+    let (input_file, rest) = match args.first().map(String::as_str) {
+        Some("--input") => (Some(flag_value(args, 0)?), args.get(2..).unwrap_or_default()),
+        _ => (None, args),
+    };
+    let Some((bundle, rest)) = rest.split_first() else {
+        return Err(String::from("Missing the bundle filename"));
+    };
+    let options = parse_args(rest)?;
+    let Some(filename) = &options.filename else {
+        return Err(String::from("Missing the program filename"));
+    };
+
+    let (code, _) = preprocess(Path::new(filename), &options.preprocess).map_err(|error| error.to_string())?;
+    package.code = translate(&code, &options).map_err(|error| error.to_string())?.unwrap_or(code);
+    let mut stored = rest[..rest.len() - options.program_args.len() - 1].iter();
+    while let Some(arg) = stored.next() {
+        if let Some(takes_value) = option_arity(BAKED_IN_OPTIONS, arg) {
+            if takes_value {
+                stored.next(); // Replaced by the embedded tape image, or already applied to the code
+            }
+            continue;
+        }
+        let Some(takes_value) = option_arity(PACKAGE_OPTIONS, arg) else {
+            return Err(format!("`{arg}` can't be packaged, pass it to `bf run` instead"));
+        };
+        package.args.push(arg.clone());
+        if takes_value {
+            package.args.extend(stored.next().cloned());
+        }
+    }
+    if let Some(tape_file) = &options.tape_init {
+        package.tape = std::fs::read(tape_file).map_err(|error| format!("Failed to read file `{tape_file}` {error}"))?;
+        package.tape_origin = options.tape_origin;
+    }
+    if !options.program_args.is_empty() {
+        package.input.extend(serialize_args(&options.program_args, &options.args_separator));
+    }
+    if let Some(input_file) = input_file {
+        package.input.extend(std::fs::read(input_file).map_err(|error| format!("Failed to read file `{input_file}` {error}"))?);
+    }
+    std::fs::write(bundle, write_package(&package)).map_err(|error| format!("Failed to write file `{bundle}` {error}"))?;
+    return Ok(());
+}
+
+fn emit(args: &[String], backend: fn(&Bytecode) -> Vec<u8>) -> Result<(), String> {
+    /*
+     * `bf emit-<target> <program> [-o <file>]`, the result is printed when no file is given.
+     * The program is preprocessed and optimized the same way it would be for running.
+     */
+
+    let (args, preprocess) = take_preprocess_flags(args)?;
+    let args = args.as_slice();
+    let mut filename: Option<&str> = None;
+    let mut output: Option<&str> = None;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-o" | "--output" => {
+                output = Some(flag_value(args, i)?);
+                i += 1;
+            },
+            arg if arg.starts_with('-') => return Err(format!("Unknown option `{arg}`")),
+            arg => filename = Some(arg),
+        }
+        i += 1;
+    }
+    let Some(filename) = filename else {
+        return Err(String::from("Missing the program filename"));
+    };
+
+    let (program, _) = load_checked_source(filename, &preprocess)?;
+    let result = backend(program.bytecode());
+    match output {
+        Some(output) => std::fs::write(output, result).map_err(|error| format!("Failed to write file `{output}` {error}"))?,
+        None => std::io::stdout().write_all(&result).map_err(|error| format!("Failed to print the result {error}"))?,
+    }
+    return Ok(());
+}
+
+fn analyze(args: &[String]) -> Result<(), String> {
+    // `bf analyze <program>`, prints the balance of each loop, indented by nesting, and how many there are of each
+    let (args, preprocess) = take_preprocess_flags(args)?;
+    let args = args.as_slice();
+    let [filename] = args else {
+        return Err(String::from("Usage: bf analyze <program>"));
+    };
+    let (program, map) = load_checked_source(filename, &preprocess)?;
+    let loops = loop_balance(&program);
+    for info in &loops {
+        let location = match map.locate(info.position) {
+            Some((file, offset)) if map.files().len() > 1 => format!("`{}` byte {offset}", file.display()),
+            Some((_, offset)) => format!("byte {offset}"),
+            None => format!("byte {}", info.position),
+        };
+        let balance = match info.balance {
+            Balance::Balanced => String::from("balanced"),
+            Balance::Unbalanced(movement) => format!("unbalanced, moves {movement:+} per iteration"),
+            Balance::Unknown => String::from("unbalanced, moves depending on the tape"),
+        };
+        println!("{}{location}: {balance}", "  ".repeat(info.depth));
+    }
+    let balanced = loops.iter().filter(|info| info.balance == Balance::Balanced).count();
+    println!("\n{} loops, {balanced} balanced, {} unbalanced", loops.len(), loops.len() - balanced);
+    return Ok(());
+}
+
+fn stats(args: &[String]) -> Result<(), String> {
+    // `bf stats <program>`, prints what the program is made of without running it
+    let (args, preprocess) = take_preprocess_flags(args)?;
+    let args = args.as_slice();
+    let [filename] = args else {
+        return Err(String::from("Usage: bf stats <program>"));
+    };
+    let (program, _) = load_checked_source(filename, &preprocess)?;
+    let stats = program_stats(&program);
+    println!("{} commands", stats.commands());
+    for (command, count) in stats.command_counts {
+        println!("  {}  {count}", char::from(command));
+    }
+    println!("{} loops, nested {} deep at most", stats.loops, stats.max_depth);
+    let ratio = if stats.code_bytes == 0 { 0.0 } else { stats.comment_bytes as f64 * 100.0 / stats.code_bytes as f64 };
+    println!("{} of {} bytes are comments ({ratio:.1}%)", stats.comment_bytes, stats.code_bytes);
+    let (lowest, highest) = stats.tape_extent;
+    println!("Uses cells {lowest} to {highest} at least ({} cells)", highest - lowest + 1);
+    return Ok(());
+}
+
+fn diff(args: &[String]) -> Result<(), String> {
+    // `bf diff <old> <new>`, prints the changes between the canonical forms of two programs
+    let (args, preprocess) = take_preprocess_flags(args)?;
+    let args = args.as_slice();
+    let [old, new] = args else {
+        return Err(String::from("Usage: bf diff <old> <new>"));
+    };
+    let mut programs = Vec::new();
+    for filename in [old, new] {
+        programs.push(load_checked_source(filename, &preprocess)?);
+    }
+    let [(old_program, old_map), (new_program, new_map)] = &programs[..] else {
+        unreachable!();
+    };
+    let locate = |map: &SourceMap, position: usize| match map.locate(position) {
+        Some((file, offset)) => format!("{} byte {offset}", file.display()),
+        None => String::from("end"),
+    };
+
+    let hunks = diff_programs(old_program, new_program);
+    if hunks.is_empty() {
+        println!("The programs are the same once normalized");
+    }
+    for hunk in hunks {
+        println!(
+            "@@ -{},{} +{},{} @@ {}, {}",
+            hunk.old_start + 1, hunk.old_len(), hunk.new_start + 1, hunk.new_len(),
+            locate(old_map, hunk.old_position), locate(new_map, hunk.new_position),
+        );
+        for line in hunk.lines {
+            match line {
+                DiffLine::Same(text) => println!(" {text}"),
+                DiffLine::Removed(text) => println!("-{text}"),
+                DiffLine::Added(text) => println!("+{text}"),
+            }
+        }
+    }
+    return Ok(());
+}
+
+fn hash(args: &[String]) -> Result<(), String> {
+    // `bf hash <programs...>`, prints the digest of each preprocessed program followed by its filename
+    let (args, preprocess) = take_preprocess_flags(args)?;
+    let args = args.as_slice();
+    if args.is_empty() {
+        return Err(String::from("Usage: bf hash <programs...>"));
+    }
+    for filename in args {
+        let (program, _) = load_checked_source(filename, &preprocess)?;
+        println!("{:016x}  {filename}", program.digest());
+    }
+    return Ok(());
+}
+
+#[cfg(feature = "bigint")]
+fn run_bigint(args: &[String]) -> Result<(), String> {
+    // `bf bigint <program> [--decimal] [--fuel <steps>]`, runs the program with arbitrary-precision cells on stdin and stdout
+    let (args, preprocess) = take_preprocess_flags(args)?;
+    let args = args.as_slice();
+    let mut filename: Option<&str> = None;
+    let mut options = BigCellOptions::default();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--decimal" => options.decimal_output = true,
+            "--fuel" => {
+                let value = flag_value(args, i)?;
+                options.max_steps = Some(value.parse().map_err(|_| format!("Invalid value for `--fuel`: {value}"))?);
+                i += 1;
+            },
+            arg if arg.starts_with('-') => return Err(format!("Unknown option `{arg}`")),
+            arg if filename.is_none() => filename = Some(arg),
+            arg => return Err(format!("Unexpected argument `{arg}`")),
+        }
+        i += 1;
+    }
+    let Some(filename) = filename else {
+        return Err(String::from("Usage: bf bigint <program> [--decimal] [--fuel <steps>]"));
+    };
+
+    let (program, _) = load_checked_source(filename, &preprocess)?;
+    let mut output = std::io::BufWriter::new(std::io::stdout().lock());
+    let run = run_big_cells(&program, &mut std::io::stdin().lock(), &mut output, &options).map_err(|error| error.to_string())?;
+    if !run.halted {
+        return Err(format!("The program didn't halt within {} steps", run.steps));
+    }
+    return Ok(());
+}
+
+#[cfg(not(feature = "bigint"))]
+fn run_bigint(_args: &[String]) -> Result<(), String> {
+    return Err(String::from("`bf bigint` needs the interpreter to be built with the `bigint` feature"));
+}
+
+#[cfg(feature = "symbolic")]
+fn explore_paths(args: &[String]) -> Result<(), String> {
+    // `bf explore <program> (--reach <byte> | --output <text>) [--max-steps <n>] [--max-paths <n>] [--max-inputs <n>]`
+    let (args, preprocess) = take_preprocess_flags(args)?;
+    let args = args.as_slice();
+    let mut filename: Option<&str> = None;
+    let mut target: Option<Target> = None;
+    let mut options = SymbolicOptions::default();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--reach" => {
+                let value = flag_value(args, i)?;
+                target = Some(Target::Position(value.parse().map_err(|_| format!("Invalid value for `--reach`: {value}"))?));
+                i += 1;
+            },
+            "--output" => {
+                target = Some(Target::Output(flag_value(args, i)?.as_bytes().to_vec()));
+                i += 1;
+            },
+            flag @ ("--max-steps" | "--max-paths" | "--max-inputs") => {
+                let value = flag_value(args, i)?;
+                let value: usize = value.parse().map_err(|_| format!("Invalid value for `{flag}`: {value}"))?;
+                match flag {
+                    "--max-steps" => options.max_steps = value,
+                    "--max-paths" => options.max_paths = value,
+                    _ => options.max_inputs = value,
+                }
+                i += 1;
+            },
+            arg if arg.starts_with('-') => return Err(format!("Unknown option `{arg}`")),
+            arg if filename.is_none() => filename = Some(arg),
+            arg => return Err(format!("Unexpected argument `{arg}`")),
+        }
+        i += 1;
+    }
+    let (Some(filename), Some(target)) = (filename, target) else {
+        return Err(String::from("Usage: bf explore <program> (--reach <byte> | --output <text>) [--max-steps <n>] [--max-paths <n>] [--max-inputs <n>]"));
+    };
+
+    let (program, _) = load_checked_source(filename, &preprocess)?;
+    let report = explore(&program, &target, &options);
+    for (i, path) in report.paths.iter().enumerate() {
+        let constraints: Vec<String> = path.constraints.iter().map(Constraint::to_string).collect();
+        let constraints = if constraints.is_empty() { String::from("none") } else { constraints.join(", ") };
+        println!("Path {} ({} steps): {constraints}", i + 1, path.steps);
+        println!("  input: \"{}\"", path.input.escape_ascii());
+    }
+    println!("\n{} of {} explored paths reach the target", report.paths.len(), report.explored);
+    if !report.complete {
+        println!("Some paths were cut by the limits, others may reach it too");
+    }
+    return Ok(());
+}
+
+#[cfg(feature = "symbolic")]
+fn solve_target(args: &[String]) -> Result<(), String> {
+    // `bf solve <program> --target <text> [--max-steps <n>] [--max-paths <n>] [--max-inputs <n>]`, prints the input
+    let (args, preprocess) = take_preprocess_flags(args)?;
+    let args = args.as_slice();
+    let mut filename: Option<&str> = None;
+    let mut target: Option<&str> = None;
+    let mut options = SymbolicOptions::default();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--target" => {
+                target = Some(flag_value(args, i)?);
+                i += 1;
+            },
+            flag @ ("--max-steps" | "--max-paths" | "--max-inputs") => {
+                let value = flag_value(args, i)?;
+                let value: usize = value.parse().map_err(|_| format!("Invalid value for `{flag}`: {value}"))?;
+                match flag {
+                    "--max-steps" => options.max_steps = value,
+                    "--max-paths" => options.max_paths = value,
+                    _ => options.max_inputs = value,
+                }
+                i += 1;
+            },
+            arg if arg.starts_with('-') => return Err(format!("Unknown option `{arg}`")),
+            arg if filename.is_none() => filename = Some(arg),
+            arg => return Err(format!("Unexpected argument `{arg}`")),
+        }
+        i += 1;
+    }
+    let (Some(filename), Some(target)) = (filename, target) else {
+        return Err(String::from("Usage: bf solve <program> --target <text> [--max-steps <n>] [--max-paths <n>] [--max-inputs <n>]"));
+    };
+
+    let (program, _) = load_checked_source(filename, &preprocess)?;
+    let Some(input) = solve_input(&program, target.as_bytes(), &options) else {
+        return Err(format!("No input of at most {} bytes found printing the target", options.max_inputs));
+    };
+    std::io::stdout().write_all(&input).map_err(|error| format!("Failed to print the input {error}"))?;
+    return Ok(());
+}
+
+#[cfg(not(feature = "symbolic"))]
+fn solve_target(_args: &[String]) -> Result<(), String> {
+    return Err(String::from("`bf solve` needs the interpreter to be built with the `symbolic` feature"));
+}
+
+#[cfg(feature = "symbolic")]
+fn check_reachable(args: &[String]) -> Result<(), String> {
+    // `bf reachable <program> <byte> [--bound <steps>]`, whether the command at that byte can ever execute
+    let (args, preprocess) = take_preprocess_flags(args)?;
+    let args = args.as_slice();
+    let mut positional: Vec<&str> = Vec::new();
+    let mut bound = SymbolicOptions::default().max_steps;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--bound" => {
+                let value = flag_value(args, i)?;
+                bound = value.parse().map_err(|_| format!("Invalid value for `--bound`: {value}"))?;
+                i += 1;
+            },
+            arg if arg.starts_with('-') => return Err(format!("Unknown option `{arg}`")),
+            arg => positional.push(arg),
+        }
+        i += 1;
+    }
+    let &[filename, offset] = positional.as_slice() else {
+        return Err(String::from("Usage: bf reachable <program> <byte> [--bound <steps>]"));
+    };
+    let offset: usize = offset.parse().map_err(|_| format!("Invalid byte position: {offset}"))?;
+
+    let (program, _) = load_checked_source(filename, &preprocess)?;
+    match reachable(&program, offset, bound) {
+        Reachability::Reachable(input) => println!("Reachable, with the input \"{}\"", input.escape_ascii()),
+        Reachability::Unreachable => println!("Unreachable"),
+        Reachability::Unknown => {
+            let max_inputs = SymbolicOptions::default().max_inputs;
+            println!("Unknown, not reached within {bound} steps per path and {max_inputs} input bytes");
+        },
+    }
+    return Ok(());
+}
+
+#[cfg(not(feature = "symbolic"))]
+fn check_reachable(_args: &[String]) -> Result<(), String> {
+    return Err(String::from("`bf reachable` needs the interpreter to be built with the `symbolic` feature"));
+}
+
+#[cfg(not(feature = "symbolic"))]
+fn explore_paths(_args: &[String]) -> Result<(), String> {
+    return Err(String::from("`bf explore` needs the interpreter to be built with the `symbolic` feature"));
+}
+
+fn run_tests(directory: &Path) -> Result<bool, String> {
+    // Runs every case in the directory, returns whether all of them passed
+    let paths = discover_test_cases(directory).map_err(|error| format!("Failed to read directory `{}` {error}", directory.display()))?;
+    let mut failed: Vec<String> = Vec::new();
+    for path in &paths {
+        let (name, result) = match read_test_case(path) {
+            Ok(case) => (case.name.clone(), run_test_case(&case)),
+            Err(error) => (path.display().to_string(), TestResult::Failed(error.to_string())),
+        };
+        match result {
+            TestResult::Passed => println!("test {name} ... ok"),
+            TestResult::Failed(reason) => {
+                println!("test {name} ... FAILED\n{}", reason.trim_end());
+                failed.push(name);
+            },
+        }
+    }
+    println!("\n{} passed, {} failed", paths.len() - failed.len(), failed.len());
+    return Ok(failed.is_empty());
+}
+
+fn option_arity(options: &[(&str, bool)], arg: &str) -> Option<bool> {
+    // Whether the option takes a value, `None` if it isn't in the list
+    return options.iter().find(|(name, _)| *name == arg).map(|&(_, takes_value)| takes_value);
+}
+
+fn check_package_args(args: &[String]) -> Result<(), String> {
+    // Packages come from anywhere, so only the options of `PACKAGE_OPTIONS` are accepted from them
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(takes_value) = option_arity(PACKAGE_OPTIONS, arg) else {
+            return Err(format!("Invalid package: packages can't set `{arg}`"));
+        };
+        if takes_value && args.next().is_none() {
+            return Err(format!("Invalid package: missing the value of `{arg}`"));
+        }
+    }
+    return Ok(());
+}
+
+fn read_bundle(args: &[String]) -> Result<(Options, Package), String> {
+    // `bf run [options] <bundle> [args...]`, the given options override the packaged ones
+    let filename = parse_args(args)?.filename.ok_or_else(|| String::from("Missing the bundle filename"))?;
+    let bytes = std::fs::read(&filename).map_err(|error| format!("Failed to read file `{filename}` {error}"))?;
+    let package = read_package(&bytes).map_err(|error| format!("{error} (`{filename}`)"))?;
+    check_package_args(&package.args).map_err(|message| format!("{message} (`{filename}`)"))?;
+    let combined: Vec<String> = package.args.iter().chain(args).cloned().collect();
+    return Ok((parse_args(&combined)?, package));
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let parsed = match args.first().map(String::as_str) {
+        Some("pack") => {
+            if let Err(message) = pack(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some("build") => {
+            // `bf build [manifest]`, `bf.toml` in the current directory by default
+            let path = Path::new(args.get(1).map_or("bf.toml", String::as_str));
+            if let Err(error) = read_manifest(path).and_then(|manifest| build(&manifest)) {
+                eprintln!("{error}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some("audit") => {
+            if let Err(message) = audit::audit(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        #[cfg(unix)]
+        Some("daemon") => {
+            if let Err(message) = daemon::daemon(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        #[cfg(not(unix))]
+        Some("daemon") => {
+            eprintln!("`bf daemon` needs Unix domain sockets, which this platform doesn't have");
+            return ExitCode::from(EXIT_FAILURE);
+        },
+        #[cfg(feature = "kernel")]
+        Some("kernel") => {
+            if let Err(message) = kernel::kernel(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        #[cfg(not(feature = "kernel"))]
+        Some("kernel") => {
+            eprintln!("`bf kernel` needs the `kernel` feature");
+            return ExitCode::from(EXIT_FAILURE);
+        },
+        Some("encode-image") => {
+            if let Err(message) = encode_image_program(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some(command @ ("emit-llvm" | "emit-wasm" | "emit-wat")) => {
+            let backend: fn(&Bytecode) -> Vec<u8> = match command {
+                "emit-llvm" => |bytecode| to_llvm(bytecode).into_bytes(),
+                "emit-wasm" => to_wasm,
+                _ => |bytecode| to_wat(bytecode).into_bytes(),
+            };
+            if let Err(message) = emit(&args[1..], backend) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some("analyze") => {
+            if let Err(message) = analyze(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some("bigint") => {
+            if let Err(message) = run_bigint(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some("explore") => {
+            if let Err(message) = explore_paths(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some("reachable") => {
+            if let Err(message) = check_reachable(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some("solve") => {
+            if let Err(message) = solve_target(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some("stats") => {
+            if let Err(message) = stats(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some("diff") => {
+            if let Err(message) = diff(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some("hash") => {
+            if let Err(message) = hash(&args[1..]) {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some("test") => {
+            // `bf test [directory]`, `tests` by default
+            let directory = Path::new(args.get(1).map_or("tests", String::as_str));
+            return match run_tests(directory) {
+                Ok(true) => ExitCode::from(EXIT_OK),
+                Ok(false) => ExitCode::from(EXIT_FAILURE),
+                Err(message) => {
+                    eprintln!("{message}");
+                    ExitCode::from(EXIT_FAILURE)
+                },
+            };
+        },
+        Some("exercise") => {
+            return match exercise::exercise(&args[1..]) {
+                Ok(true) => ExitCode::from(EXIT_OK),
+                Ok(false) => ExitCode::from(EXIT_FAILURE),
+                Err(message) => {
+                    eprintln!("{message}");
+                    ExitCode::from(EXIT_FAILURE)
+                },
             };
-            String::from_utf8(code).unwrap()
         },
-        None => {
-            let mut buf = String::new();
-            match std::io::stdin().read_line(&mut buf) {
+        Some("repl") => {
+            if let Err(message) = repl::repl() {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+            return ExitCode::from(EXIT_OK);
+        },
+        Some("run") => read_bundle(&args[1..]).map(|(options, package)| (options, Some(package))),
+        _ => parse_args(&args).map(|options| (options, None)),
+    };
+    let (mut options, package) = match parsed {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(EXIT_FAILURE);
+        }
+    };
+
+    let mut source_map: Option<SourceMap> = None;
+    let mut read_files: Vec<PathBuf> = options.filename.iter().map(PathBuf::from).collect(); // For the output cache key
+    let code: Vec<u8> = match (&options.filename, &package) {
+        (_, Some(package)) => package.code.clone(),
+        (Some(filename), None) if let Some(dialect) = &options.image => match read_image_program(filename, dialect) {
+            Ok(code) => code,
+            Err(message) => {
+                eprintln!("{message}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+        },
+        (Some(filename), None) => match preprocess(Path::new(filename), &options.preprocess) {
+            Ok((code, map)) => {
+                read_files = map.files().to_vec();
+                source_map = Some(map);
+                code
+            },
+            Err(error) => {
+                eprintln!("{error}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+        },
+        (None, None) => {
+            let mut buf = Vec::new();
+            match std::io::stdin().lock().read_until(b'\n', &mut buf) {
                 Ok(_) => buf,
-                Err(error) => panic!("{error}"),
+                Err(error) => {
+                    eprintln!("{error}");
+                    return ExitCode::from(EXIT_FAILURE);
+                }
+            }
+        },
+    };
+
+    let code = if package.is_some() {
+        code // Packaged code is translated already
+    }
+    else {
+        match translate(&code, &options) {
+            Ok(None) => code,
+            Ok(Some(code)) => {
+                source_map = None; // Positions refer to the translated code from now on
+                code
+            },
+            Err(BfError::Syntax { position, message }) if let Some((file, offset)) = source_map.as_ref().and_then(|map| map.locate(position)) => {
+                eprintln!("Syntax error in `{}` at byte {offset}: {message}", file.display());
+                return ExitCode::from(EXIT_SYNTAX_ERROR);
+            },
+            Err(error) => {
+                eprintln!("{error}");
+                return ExitCode::from(EXIT_SYNTAX_ERROR);
+            },
+        }
+    };
+
+    let mut state = new_bf_state(&code);
+    if options.ignore_first_line {
+        skip_first_line(&mut state);
+    }
+    /*
+     * Keyed by all the arguments, since most options change the output, and by every file the run reads:
+     * the program with its includes (or the bundle, or the image) and the files of options like `--charset-file`.
+     */
+    read_files.extend(options.read_files.iter().map(PathBuf::from));
+    let cache_key = file_key(args.join("\0").as_bytes(), &read_files).ok(); // `None` if a file can't be read anymore
+    let cacheable = cache_key.is_some() && output_cacheable(&state, &options, &package);
+    let cache_dir = options.cache_dir.as_deref().map(Path::new).filter(|_| cacheable);
+    let cache_key = cache_key.unwrap_or_default();
+    if let Some(directory) = cache_dir
+        && let Some(output) = read_cached_output(directory, &program(&state), &cache_key) {
+        let mut stdout = std::io::stdout();
+        return match stdout.write_all(&output).and_then(|()| stdout.flush()) {
+            Err(error) if error.kind() != std::io::ErrorKind::BrokenPipe => {
+                eprintln!("{error}");
+                ExitCode::from(EXIT_RUNTIME_ERROR)
+            },
+            _ => ExitCode::from(EXIT_OK),
+        };
+    }
+    let recorded = Arc::new(Mutex::new(Vec::new()));
+    if cache_dir.is_some() {
+        let stdout = Box::new(std::io::BufWriter::new(std::io::stdout()));
+        set_output_writer(&mut state, Box::new(RecordingWriter { inner: stdout, recorded: recorded.clone() }));
+    }
+    set_max_output_rate(&mut state, options.max_output_rate);
+    let next_line = Arc::new(AtomicBool::new(false));
+    if options.per_line {
+        let reader = std::io::BufReader::new(std::io::stdin());
+        set_input_reader(&mut state, Box::new(LineReader { inner: reader, line_ended: false, next_line: next_line.clone() }));
+    }
+    set_input_format(&mut state, options.input_format);
+    if let Some(path) = &options.canvas {
+        let format = if path.ends_with(".png") { CanvasFormat::Png } else { CanvasFormat::Svg };
+        match std::fs::File::create(path) {
+            Ok(file) => set_output_writer(&mut state, Box::new(TurtleWriter::new(Box::new(file), format))),
+            Err(error) => {
+                eprintln!("Failed to create file `{path}` {error}");
+                return ExitCode::from(EXIT_FAILURE);
+            },
+        }
+        set_raw_output(&mut state, true);
+    }
+    set_output_format(&mut state, options.output_format);
+    if let Some(charset) = &options.charset {
+        set_charset(&mut state, charset);
+    }
+    set_newline_mode(&mut state, options.newline_mode);
+    set_eof_behavior(&mut state, options.eof_behavior);
+    if !options.loop_tracing {
+        set_hot_loop_threshold(&mut state, None);
+    }
+    if !options.warnings {
+        set_diagnostics_writer(&mut state, None);
+    }
+    set_ip_history(&mut state, options.ip_history);
+    set_loop_tracking(&mut state, options.loop_stack);
+    detect_infinite_loops(&mut state, options.detect_loops);
+    set_taint_tracking(&mut state, options.taint);
+    if !options.allowed_env.is_empty() {
+        let allowlist: Vec<&str> = options.allowed_env.iter().map(String::as_str).collect();
+        enable_env_extension(&mut state, &allowlist);
+    }
+    if options.decimal_io {
+        enable_decimal_extension(&mut state);
+    }
+    if options.deterministic {
+        set_determinism(&mut state, DeterminismLevel::Deterministic);
+        if let Err(error) = check_determinism(&state, options.limits.wall_timeout) {
+            eprintln!("{error}");
+            return ExitCode::from(EXIT_FAILURE);
+        }
+    }
+    // Set ahead of the run, so packaged tapes and checkpoints are checked against them
+    apply_limits(&mut state, &options.limits);
+    if let Some(package) = &package
+        && let Err(error) = apply_package(&mut state, package) {
+        eprintln!("{error}");
+        return ExitCode::from(EXIT_FAILURE);
+    }
+    if let Some(filename) = &options.tape_init {
+        match std::fs::read(filename) {
+            // A tape the limit can't hold stops the run like the program overflowing it would
+            Ok(bytes) => if let Err(error) = load_tape(&mut state, &bytes, options.tape_origin) {
+                eprintln!("{error}");
+                return ExitCode::from(EXIT_TAPE_LIMIT);
+            },
+            Err(error) => {
+                eprintln!("Failed to read file `{filename}` {error}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+        }
+    }
+    if !options.program_args.is_empty() {
+        push_input(&mut state, &serialize_args(&options.program_args, &options.args_separator));
+    }
+    let mut resumed_steps = 0;
+    if let Some(filename) = &options.resume {
+        match std::fs::read(filename).map_err(BfError::from).and_then(|bytes| restore_checkpoint(&mut state, &bytes)) {
+            Ok(steps) => resumed_steps = steps,
+            Err(error) => {
+                eprintln!("Failed to resume from `{filename}` {error}");
+                return ExitCode::from(EXIT_FAILURE);
+            }
+        }
+        // Fuel counts the steps run before the checkpoint too
+        options.limits.max_steps = options.limits.max_steps.map(|fuel| fuel.saturating_sub(resumed_steps));
+    }
+
+    if options.explain {
+        set_step_observer(&mut state, Some(explainer(options.explain_window, options.explain_rate, options.radix)));
+    }
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    set_interrupt_flag(&mut state, interrupted.clone());
+    let handler_flag = interrupted.clone();
+    if let Err(error) = ctrlc::set_handler(move || {
+        if handler_flag.swap(true, Ordering::Relaxed) {
+            // Second Ctrl-C, the program didn't stop in time
+            std::process::exit(EXIT_INTERRUPTED.into());
+        }
+    }) && options.warnings {
+        eprintln!("warning: failed to install the Ctrl-C handler: {error}");
+    }
+
+    let env = if options.allowed_env.is_empty() { Extensions::NONE } else { Extensions::ENV };
+    let decimal = if options.decimal_io { Extensions::DECIMAL } else { Extensions::NONE };
+    let checked = match options.extensions {
+        extensions if options.strict_charset => program(&state).check_charset(extensions.unwrap_or(Extensions::NONE) | env | decimal),
+        Some(extensions) => program(&state).check_extensions(extensions | env | decimal),
+        None => Ok(()),
+    };
+    let hints = if options.hints && options.warnings {
+        let input_given = !options.program_args.is_empty() || package.as_ref().is_some_and(|package| !package.input.is_empty())
+            || !std::io::stdin().is_terminal();
+        program_hints(&program(&state), input_given)
+    }
+    else {
+        Vec::new()
+    };
+    print_hints(&hints, source_map.as_ref());
+    let result = checked.and_then(|()| run_records(&mut state, &options, resumed_steps, &next_line)).map(|report| report.outcome);
+    let halted = matches!(result, Ok(RunOutcome::Halted));
+    let unfinished = matches!(result, Ok(RunOutcome::FuelExhausted | RunOutcome::TimedOut | RunOutcome::LoopDetected | RunOutcome::Interrupted));
+    let mut exit_code = match result {
+        Ok(RunOutcome::Halted) => {
+            if options.exit_cell { get_cell(&state, 0) } else { EXIT_OK }
+        },
+        Ok(RunOutcome::FuelExhausted) => {
+            print_report(&state, "Fuel exhausted", source_map.as_ref());
+            EXIT_FUEL_EXHAUSTED
+        },
+        Ok(RunOutcome::TimedOut) => {
+            print_report(&state, "Timed out", source_map.as_ref());
+            EXIT_TIMEOUT
+        },
+        Ok(RunOutcome::OutputLimitExceeded) => {
+            print_report(&state, "Output limit exceeded", source_map.as_ref());
+            EXIT_OUTPUT_LIMIT
+        },
+        Ok(RunOutcome::TapeLimitExceeded) => {
+            print_report(&state, "Tape limit exceeded", source_map.as_ref());
+            EXIT_TAPE_LIMIT
+        },
+        Ok(RunOutcome::LoopDetected) => {
+            print_report(&state, "Infinite loop detected", source_map.as_ref());
+            EXIT_LOOP_DETECTED
+        },
+        Ok(RunOutcome::Interrupted | RunOutcome::Cancelled) => {
+            let _ = flush_output(&mut state);
+            print_report(&state, "Interrupted", source_map.as_ref());
+            if let Some(filename) = &options.dump_on_interrupt
+                && let Err(error) = std::fs::write(filename, format_state_snapshot(&state, options.radix)) {
+                eprintln!("Failed to write the state snapshot to `{filename}` {error}");
             }
+            EXIT_INTERRUPTED
+        },
+        Err(BfError::Syntax { position, message }) if let Some((file, offset)) = source_map.as_ref().and_then(|map| map.locate(position)) => {
+            eprintln!("Syntax error in `{}` at byte {offset}: {message}", file.display());
+            EXIT_SYNTAX_ERROR
+        },
+        Err(error @ BfError::Syntax { .. }) => {
+            eprintln!("{error}");
+            EXIT_SYNTAX_ERROR
+        },
+        Err(BfError::Io(error)) if error.kind() == std::io::ErrorKind::BrokenPipe => {
+            // Whoever was reading the output doesn't need more of it
+            EXIT_OK
+        },
+        Err(BfError::Runtime(message) | BfError::Format(message)) => {
+            print_report(&state, &message, source_map.as_ref());
+            EXIT_RUNTIME_ERROR
+        },
+        Err(BfError::Io(error)) => {
+            print_report(&state, &format!("I/O error: {error}"), source_map.as_ref());
+            EXIT_RUNTIME_ERROR
+        },
+        Err(error @ BfError::Host { .. }) => {
+            print_report(&state, &error.to_string(), source_map.as_ref());
+            EXIT_RUNTIME_ERROR
         },
     };
 
-    let code = code.as_str();
+    if unfinished && let Some(hint) = hints.iter().find(|hint| hint.kind == HintKind::EndlessLoop) {
+        let location = locate_hint(hint.position, source_map.as_ref());
+        eprintln!("Hint: the run may not have ended because of the loop {location}, see above");
+    }
+
+    if options.taint {
+        let _ = flush_output(&mut state);
+        print_taint(&state);
+    }
+
+    if options.tape_stats {
+        let _ = flush_output(&mut state);
+        print_tape_stats(&state);
+    }
+
+    if let Some(filename) = &options.dump_tape {
+        let (start, end) = options.dump_tape_range.unwrap_or_else(|| {
+            let (lowest, highest) = tape_extent(&state);
+            (lowest, highest + 1)
+        });
+        let cells = export_tape(&state, start..end);
+        if let Err(error) = std::fs::write(filename, format_tape(&cells, start, &options.dump_tape_format)) {
+            eprintln!("Failed to write the tape to `{filename}` {error}");
+            if exit_code == EXIT_OK {
+                exit_code = EXIT_FAILURE;
+            }
+        }
+    }
+
+    if let Some(directory) = cache_dir
+        && halted {
+        let program = program(&state);
+        drop(state); // Finishes encoded output
+        if let Err(error) = write_cached_output(directory, &program, &cache_key, &recorded.lock().unwrap()) {
+            eprintln!("Failed to cache the output in `{}` {error}", directory.display());
+        }
+    }
 
-    let mut state = new_bf_state(code);
-    while step_bf(&mut state) {};
+    return ExitCode::from(exit_code);
 }
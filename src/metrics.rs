@@ -0,0 +1,96 @@
+use crate::*;
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/*
+ * Counters for services running programs, rendered in the Prometheus text format.
+ * A service shares one `Metrics` between its workers, calls `record` after each run and serves `render`
+ * on its `/metrics` endpoint; `bf daemon` records the programs of its sessions and answers its `metrics` command with it.
+ */
+
+const DURATION_BUCKETS: [f64; 10] = [0.0001, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0]; // Seconds
+
+#[derive(Default)]
+pub struct Metrics {
+    runs: AtomicU64, // Runs that ended with a report
+    errors: AtomicU64, // Runs that ended with an error
+    fuel_exhausted: AtomicU64,
+    timeouts: AtomicU64,
+    output_limits: AtomicU64, // Runs stopped by `max_output_bytes`
+    tape_limits: AtomicU64, // Runs stopped by `max_tape_cells`
+    input_bytes: AtomicU64,
+    output_bytes: AtomicU64,
+    duration_buckets: [AtomicU64; DURATION_BUCKETS.len()], // Runs in each bucket only, summed up when rendered
+    duration_micros: AtomicU64, // Sum of the durations
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        return Metrics::default();
+    }
+
+    pub fn record(&self, state: &BFState, result: &Result<RunReport, BfError>) {
+        /*
+         * Counts a finished run of the state. The bytes are the totals of the state,
+         * so a state that is run again has to be recorded only after its last run.
+         */
+
+        self.input_bytes.fetch_add(input_bytes(state), Ordering::Relaxed);
+        self.output_bytes.fetch_add(output_bytes(state), Ordering::Relaxed);
+        let report = match result {
+            Ok(report) => report,
+            Err(_) => {
+                self.errors.fetch_add(1, Ordering::Relaxed);
+                return;
+            },
+        };
+        self.runs.fetch_add(1, Ordering::Relaxed);
+        let counter = match report.outcome {
+            RunOutcome::FuelExhausted => Some(&self.fuel_exhausted),
+            RunOutcome::TimedOut => Some(&self.timeouts),
+            RunOutcome::OutputLimitExceeded => Some(&self.output_limits),
+            RunOutcome::TapeLimitExceeded => Some(&self.tape_limits),
+            _ => None,
+        };
+        if let Some(counter) = counter {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+        let seconds = report.duration.as_secs_f64();
+        if let Some(bucket) = DURATION_BUCKETS.iter().position(|&bound| seconds <= bound) {
+            self.duration_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        let micros = u64::try_from(report.duration.as_micros()).unwrap_or(u64::MAX);
+        self.duration_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    pub fn render(&self) -> String {
+        // The exposition format, served as `text/plain; version=0.0.4`
+        let mut text = String::new();
+        let counters = [
+            ("bf_runs_total", "Runs that finished with a report", &self.runs),
+            ("bf_run_errors_total", "Runs that failed with an error", &self.errors),
+            ("bf_fuel_exhausted_total", "Runs that ran out of fuel", &self.fuel_exhausted),
+            ("bf_timeouts_total", "Runs that timed out", &self.timeouts),
+            ("bf_output_limits_total", "Runs that tried to print past the output limit", &self.output_limits),
+            ("bf_tape_limits_total", "Runs that tried to move past the tape limit", &self.tape_limits),
+            ("bf_input_bytes_total", "Bytes read by programs", &self.input_bytes),
+            ("bf_output_bytes_total", "Bytes printed by programs", &self.output_bytes),
+        ];
+        for (name, help, counter) in counters {
+            let _ = writeln!(text, "# HELP {name} {help}\n# TYPE {name} counter\n{name} {}", counter.load(Ordering::Relaxed));
+        }
+
+        let name = "bf_run_duration_seconds";
+        let _ = writeln!(text, "# HELP {name} Wall-clock time of runs\n# TYPE {name} histogram");
+        let mut cumulative = 0;
+        for (bound, bucket) in DURATION_BUCKETS.iter().zip(&self.duration_buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(text, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+        }
+        let runs = self.runs.load(Ordering::Relaxed);
+        let seconds = self.duration_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        let _ = writeln!(text, "{name}_bucket{{le=\"+Inf\"}} {runs}\n{name}_sum {seconds}\n{name}_count {runs}");
+        return text;
+    }
+}
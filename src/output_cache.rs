@@ -0,0 +1,68 @@
+use crate::*;
+
+use program::{normalized_source, stable_hash};
+
+use std::path::{Path, PathBuf};
+
+/*
+ * Output cache: a program that never reads input (and has no extension operations, which could do anything)
+ * prints the same thing every time it runs to completion, so its output can be saved once and printed
+ * right away on the next runs. Entries are files named after the digest of the program and a hash of a key
+ * (the options that change the output, chosen by the caller, and the files they read: see `file_key`),
+ * in the section format of packages:
+ * `source` (the code without comments), `key` and `output`. They are compared when reading,
+ * so a hash collision is only a miss, and editing the comments of a program keeps its entry.
+ */
+
+const CACHE_MAGIC: &[u8] = b"bfcache 1\n";
+
+pub fn is_output_only(program: &Program) -> bool {
+    // `$` only queues input, so it can't change anything without `,`
+    return !program.bytecode.ops.iter().any(|op| matches!(op, Op::Input | Op::Extension(_)));
+}
+
+pub fn file_key(key: &[u8], files: &[impl AsRef<Path>]) -> std::io::Result<Vec<u8>> {
+    // The key followed by the path and a hash of the contents of each file, so editing any of them misses the entry
+    let mut extended = key.to_vec();
+    for path in files {
+        let path = path.as_ref();
+        let contents = std::fs::read(path)?;
+        extended.extend(format!("\n{} {:016x}", path.display(), stable_hash(&contents)).as_bytes());
+    }
+    return Ok(extended);
+}
+
+pub fn read_cached_output(directory: &Path, program: &Program, key: &[u8]) -> Option<Vec<u8>> {
+    // The saved output, `None` if there is none or the entry can't be read
+    let bytes = std::fs::read(entry_path(directory, program, key)).ok()?;
+    let source = normalized_source(&program.code[program.code_start..]);
+    let sections = package::read_sections(bytes.strip_prefix(CACHE_MAGIC)?).ok()?;
+    let mut output = None;
+    for (header, data) in sections {
+        match header.split(' ').next() {
+            Some("source") if data != source => return None,
+            Some("key") if data != key => return None,
+            Some("output") => output = Some(data.to_vec()),
+            _ => {},
+        }
+    }
+    return output;
+}
+
+pub fn write_cached_output(directory: &Path, program: &Program, key: &[u8], output: &[u8]) -> Result<(), BfError> {
+    // Creates the directory if needed; the entry replaces any previous one atomically
+    std::fs::create_dir_all(directory)?;
+    let mut bytes = CACHE_MAGIC.to_vec();
+    package::write_section(&mut bytes, "source", &normalized_source(&program.code[program.code_start..]));
+    package::write_section(&mut bytes, "key", key);
+    package::write_section(&mut bytes, "output", output);
+    let path = entry_path(directory, program, key);
+    let temporary = path.with_extension("tmp");
+    std::fs::write(&temporary, bytes)?;
+    std::fs::rename(&temporary, &path)?;
+    return Ok(());
+}
+
+fn entry_path(directory: &Path, program: &Program, key: &[u8]) -> PathBuf {
+    return directory.join(format!("{:016x}-{:016x}.bfcache", program.digest(), stable_hash(key)));
+}
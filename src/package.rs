@@ -0,0 +1,122 @@
+use crate::*;
+
+/*
+ * A package (`.bfpkg`) bundles a program with everything it needs to run the same way again:
+ * the initial tape image, the input data and the interpreter options.
+ *
+ * The format is a magic line followed by sections, each one being a header line
+ * `<name> [<origin>] <length>` and exactly `<length>` bytes of data ending with a newline.
+ * Sections: `code` (exactly once), `input`, `tape` (with the index of its first cell)
+ * and `arg` (one interpreter option or option value each, in order).
+ */
+
+const PACKAGE_MAGIC: &[u8] = b"bfpkg 1\n";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Package {
+    pub code: Vec<u8>,
+    pub input: Vec<u8>, // Queued as input before anything is read from stdin
+    pub tape: Vec<u8>, // Initial values of the cells, starting from `tape_origin`
+    pub tape_origin: isize,
+    pub args: Vec<String>, // Interpreter options, written like they would be on the command line
+}
+
+pub fn write_package(package: &Package) -> Vec<u8> {
+    let mut bytes = PACKAGE_MAGIC.to_vec();
+    write_section(&mut bytes, "code", &package.code);
+    if !package.tape.is_empty() {
+        write_section(&mut bytes, &format!("tape {}", package.tape_origin), &package.tape);
+    }
+    if !package.input.is_empty() {
+        write_section(&mut bytes, "input", &package.input);
+    }
+    for arg in &package.args {
+        write_section(&mut bytes, "arg", arg.as_bytes());
+    }
+    return bytes;
+}
+
+pub(crate) fn write_section(bytes: &mut Vec<u8>, header: &str, data: &[u8]) {
+    bytes.extend(format!("{header} {}\n", data.len()).as_bytes());
+    bytes.extend(data);
+    bytes.push(NEWLINE);
+}
+
+pub fn read_package(bytes: &[u8]) -> Result<Package, BfError> {
+    let invalid = |message: &str| Err(BfError::Format(format!("Invalid package: {message}")));
+    let Some(rest) = bytes.strip_prefix(PACKAGE_MAGIC) else {
+        return invalid("missing the `bfpkg 1` header");
+    };
+
+    let mut package = Package::default();
+    let mut code: Option<Vec<u8>> = None;
+    for (header, data) in read_sections(rest).map_err(|message| BfError::Format(format!("Invalid package: {message}")))? {
+        let fields: Vec<&str> = header.split(' ').collect();
+        match fields[..] {
+            ["code", _] if code.is_none() => code = Some(data.to_vec()),
+            ["input", _] => package.input.extend(data),
+            ["tape", origin, _] => {
+                let Ok(origin) = origin.parse() else {
+                    return invalid(&format!("bad tape origin in `{header}`"));
+                };
+                package.tape = data.to_vec();
+                package.tape_origin = origin;
+            },
+            ["arg", _] => {
+                let Ok(arg) = String::from_utf8(data.to_vec()) else {
+                    return invalid("an option isn't UTF-8");
+                };
+                package.args.push(arg);
+            },
+            _ => return invalid(&format!("unexpected section `{header}`")),
+        }
+    }
+
+    let Some(code) = code else {
+        return invalid("missing the `code` section");
+    };
+    package.code = code;
+    return Ok(package);
+}
+
+pub(crate) fn read_sections(mut rest: &[u8]) -> Result<Vec<(&str, &[u8])>, String> {
+    // Headers and data of the sections after the magic line, checking that each one is complete
+    let mut sections: Vec<(&str, &[u8])> = Vec::new();
+    while !rest.is_empty() {
+        let Some(header_end) = rest.iter().position(|&c| c == NEWLINE) else {
+            return Err(String::from("unterminated section header"));
+        };
+        let Ok(header) = std::str::from_utf8(&rest[..header_end]) else {
+            return Err(String::from("section header isn't UTF-8"));
+        };
+        let Some(Ok(length)) = header.split(' ').next_back().map(|length| length.parse::<usize>()) else {
+            return Err(format!("bad length in `{header}`"));
+        };
+        let data_start = header_end + 1;
+        // Checked, a crafted length could overflow the end of the section
+        let Some(after) = data_start.checked_add(length).and_then(|end| end.checked_add(1)) else {
+            return Err(format!("section `{header}` is truncated"));
+        };
+        if rest.len() < after || rest[after - 1] != NEWLINE {
+            return Err(format!("section `{header}` is truncated"));
+        }
+        sections.push((header, &rest[data_start..after - 1]));
+        rest = &rest[after..];
+    }
+    return Ok(sections);
+}
+
+pub fn apply_package(state: &mut BFState, package: &Package) -> Result<(), BfError> {
+    /*
+     * Loads the tape image and queues the input, the options are up to the caller.
+     * The origin comes from the file, so a tape that doesn't fit in `max_tape_cells` is rejected before
+     * anything is allocated; the limits have to be set first.
+     */
+
+    if !tape_fits(state, package.tape_origin, package.tape.len()) {
+        return Err(BfError::Format(format!("The packaged tape at cell {} doesn't fit in the tape limit", package.tape_origin)));
+    }
+    load_tape(state, &package.tape, package.tape_origin)?;
+    push_input(state, &package.input);
+    return Ok(());
+}
@@ -0,0 +1,84 @@
+use crate::*;
+
+use std::sync::Mutex;
+
+/*
+ * Recycles states between runs, for services running many short programs.
+ * A returned state only has the cells its cursor could have reached cleared, and its buffers emptied,
+ * so the next checkout reuses its allocations instead of allocating and zeroing a new tape.
+ * Checked out states read nothing and print nowhere until they're given a reader and a writer
+ * (or capture their output), which also keeps a writer from outliving the run it was given for.
+ */
+
+const DEFAULT_MAX_IDLE: usize = 64;
+
+struct Idle {
+    ptape: Vec<u8>, // Cleared
+    ntape: Vec<u8>, // Empty, only its capacity is kept
+    input: VecDeque<u8>,
+    output: Vec<u8>,
+    warnings: HashSet<String>,
+    loop_traces: Vec<LoopTrace>,
+}
+
+pub struct StatePool {
+    states: Mutex<Vec<Idle>>, // What's left of the returned states, ready to be reused
+    max_idle: usize, // States beyond this many are dropped instead of being kept
+}
+
+pub type MachinePool = StatePool; // The name the pool was first asked for under, states being the machines it hands out
+
+impl Default for StatePool {
+    fn default() -> Self {
+        return StatePool::new(DEFAULT_MAX_IDLE);
+    }
+}
+
+impl StatePool {
+    pub fn new(max_idle: usize) -> Self {
+        return StatePool { states: Mutex::new(Vec::new()), max_idle };
+    }
+
+    pub fn checkout(&self, program: Arc<Program>) -> BFState {
+        // A state at the start of the program with the default options, reusing a returned one when there is one
+        let writer = Box::new(std::io::sink());
+        let reader = Box::new(std::io::empty());
+        let Some(idle) = self.states.lock().unwrap().pop() else {
+            return blank_state(program, vec![0; 3000], writer, reader);
+        };
+        let mut state = blank_state(program, idle.ptape, writer, reader);
+        state.ntape = idle.ntape;
+        state.input = idle.input;
+        state.output = idle.output;
+        state.warnings = idle.warnings;
+        state.loop_traces = idle.loop_traces;
+        return state;
+    }
+
+    pub fn recycle(&self, state: BFState) {
+        // Everything else (I/O, observers, hooks) is dropped with the state
+        let BFState { mut ptape, mut ntape, highest_cursor, loops, mut input, mut output, mut warnings, mut loop_traces, .. } = state;
+        let touched = if loops {
+            ptape.len()
+        }
+        else {
+            usize::try_from(highest_cursor + 1).unwrap_or(0).min(ptape.len())
+        };
+        ptape[..touched].fill(0);
+        // The negative tape only grows when its cells are written, its length is how far the program went
+        ntape.clear();
+        input.clear();
+        output.clear();
+        warnings.clear();
+        loop_traces.clear();
+
+        let mut states = self.states.lock().unwrap();
+        if states.len() < self.max_idle {
+            states.push(Idle { ptape, ntape, input, output, warnings, loop_traces });
+        }
+    }
+
+    pub fn idle(&self) -> usize {
+        return self.states.lock().unwrap().len();
+    }
+}
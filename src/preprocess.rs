@@ -0,0 +1,461 @@
+use crate::{BfError, NEWLINE};
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/*
+ * Source-level directives, expanded before compiling:
+ * `@include "file.b"` is replaced by the (preprocessed) contents of the file.
+ * Included files are looked up next to the including file first, then in the search paths.
+ *
+ * `@def name(a, b) { ... }` defines a macro and `@use name(3, >)` expands it.
+ * In the body, `%a` is replaced by the argument and `%a{...}` repeats the braces' contents
+ * as many times as the argument says. Macros can use other macros, up to a depth limit, and the number of uses
+ * is limited too: macros expanding to nothing can still nest exponentially many uses.
+ *
+ * `@if name` ... `@endif` keeps its contents only when `name` is defined (`@if !name` when it isn't),
+ * so one source can target different dialects or cell widths. Conditionals can be nested.
+ *
+ * A directive name has to end at a word boundary, and a directive that doesn't parse (`the @include directive`,
+ * a stray `@endif`) is copied like the rest, so `@` in the comments of existing programs keeps working.
+ * Everything else is copied as it is; the source map points every output byte back to its file,
+ * bytes coming from a macro point into the macro's body (or into the arguments of its use).
+ *
+ * With `literate`, the program file is Markdown (as are included files ending in `.md`): only its fenced code blocks
+ * without a language, or marked `brainfuck` or `bf`, are code, concatenated in order. The rest, prose and blocks in
+ * other languages, is left out before expanding directives, so the source map points into the Markdown.
+ */
+
+const INCLUDE_DIRECTIVE: &[u8] = b"@include";
+const DEF_DIRECTIVE: &[u8] = b"@def";
+const USE_DIRECTIVE: &[u8] = b"@use";
+const IF_DIRECTIVE: &[u8] = b"@if";
+const ENDIF_DIRECTIVE: &[u8] = b"@endif";
+const PARAMETER_SIGIL: u8 = b'%';
+const DEFAULT_MAX_MACRO_DEPTH: usize = 64;
+const DEFAULT_MAX_EXPANSION: usize = 1 << 24; // 16 MiB
+const DEFAULT_MAX_MACRO_USES: usize = 1 << 18;
+
+#[derive(Clone, Debug)]
+pub struct PreprocessOptions {
+    pub search_paths: Vec<PathBuf>, // Where included files are looked up after the including file's directory
+    pub max_macro_depth: usize, // How deeply macros can use other macros
+    pub max_expansion: usize, // Largest the preprocessed code can get in bytes, so repetitions like `%n{...}` stay bounded
+    pub max_macro_uses: usize, // How many macro uses a program can expand in total, nested ones included
+    pub defines: HashSet<String>, // Names that are defined for `@if`
+    pub literate: bool, // The program file is Markdown, whose fenced code blocks are the code
+}
+
+impl Default for PreprocessOptions {
+    fn default() -> Self {
+        return PreprocessOptions {
+            search_paths: Vec::new(),
+            max_macro_depth: DEFAULT_MAX_MACRO_DEPTH,
+            max_expansion: DEFAULT_MAX_EXPANSION,
+            max_macro_uses: DEFAULT_MAX_MACRO_USES,
+            defines: HashSet::new(),
+            literate: false,
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Segment {
+    start: usize, // Position in the output
+    length: usize,
+    file: usize, // Index in `SourceMap::files`
+    offset: usize, // Position in the file
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    files: Vec<PathBuf>,
+    segments: Vec<Segment>, // Sorted by `start`, without gaps
+}
+
+impl SourceMap {
+    pub fn files(&self) -> &[PathBuf] {
+        return &self.files;
+    }
+
+    pub fn locate(&self, position: usize) -> Option<(&Path, usize)> {
+        // File and position in that file of the given output position
+        let index = self.segments.partition_point(|segment| segment.start <= position).checked_sub(1)?;
+        let segment = self.segments[index];
+        if position >= segment.start + segment.length {
+            return None;
+        }
+        return Some((&self.files[segment.file], segment.offset + position - segment.start));
+    }
+
+    fn push(&mut self, start: usize, file: usize, offset: usize) {
+        // Maps one more output byte, extending the last segment when it continues it
+        if let Some(last) = self.segments.last_mut()
+            && last.file == file && last.start + last.length == start && last.offset + last.length == offset {
+            last.length += 1;
+            return;
+        }
+        self.segments.push(Segment { start, length: 1, file, offset });
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SourceByte {
+    value: u8,
+    file: usize,
+    offset: usize,
+}
+
+struct Macro {
+    parameters: Vec<String>,
+    body: Vec<SourceByte>,
+}
+
+struct Preprocessor<'a> {
+    options: &'a PreprocessOptions,
+    output: Vec<u8>,
+    map: SourceMap,
+    stack: Vec<PathBuf>, // Canonical paths of the files being expanded, for cycle detection
+    macros: HashMap<String, Macro>,
+    uses: usize, // Macro uses expanded so far
+}
+
+pub fn preprocess(path: &Path, options: &PreprocessOptions) -> Result<(Vec<u8>, SourceMap), BfError> {
+    trace_span!(DEBUG, "preprocess", path = %path.display());
+    let mut preprocessor = Preprocessor {
+        options,
+        output: Vec::new(),
+        map: SourceMap::default(),
+        stack: Vec::new(),
+        macros: HashMap::new(),
+        uses: 0,
+    };
+    preprocessor.expand_file(path)?;
+    let source = preprocessor.output;
+    trace_event!(DEBUG, bytes = source.len(), files = preprocessor.map.files().len(), "preprocessed");
+    return Ok((source, preprocessor.map));
+}
+
+impl Preprocessor<'_> {
+    fn error(&self, at: SourceByte, message: &str) -> BfError {
+        let path = self.map.files[at.file].display();
+        return BfError::Format(format!("Preprocessor error in `{path}` at byte {}: {message}", at.offset));
+    }
+
+    fn expand_file(&mut self, path: &Path) -> Result<(), BfError> {
+        let read_error = |error: std::io::Error| BfError::Format(format!("Failed to read file `{}` {error}", path.display()));
+        let canonical = path.canonicalize().map_err(read_error)?;
+        if self.stack.contains(&canonical) {
+            let cycle: Vec<String> = self.stack.iter().chain([&canonical]).map(|path| path.display().to_string()).collect();
+            return Err(BfError::Format(format!("Include cycle: {}", cycle.join(" -> "))));
+        }
+        let code = std::fs::read(path).map_err(read_error)?;
+
+        let file = self.map.files.len();
+        self.map.files.push(path.to_path_buf());
+        let mut text: Vec<SourceByte> = code.iter().enumerate().map(|(offset, &value)| SourceByte { value, file, offset }).collect();
+        if self.options.literate && (self.stack.is_empty() || path.extension().is_some_and(|extension| extension == "md")) {
+            text = code_blocks(&text);
+        }
+        self.stack.push(canonical);
+        self.expand(&text, 0)?;
+        self.stack.pop();
+        return Ok(());
+    }
+
+    fn expand(&mut self, text: &[SourceByte], depth: usize) -> Result<(), BfError> {
+        let raw: Vec<u8> = text.iter().map(|c| c.value).collect();
+        let mut i = 0;
+        while i < raw.len() {
+            let rest = &raw[i..];
+            if is_directive(rest, INCLUDE_DIRECTIVE) && let Some((name, end)) = parse_quoted_argument(&raw, i + INCLUDE_DIRECTIVE.len()) {
+                let including = self.map.files[text[i].file].clone();
+                let included = self.resolve(&including, &name).ok_or_else(|| self.error(text[i], &format!("can't find `{name}`")))?;
+                self.expand_file(&included)?;
+                i = end;
+            }
+            else if let Some((kept, end)) = self.conditional(&raw, i) {
+                if let Some(kept) = kept {
+                    self.expand(&text[kept], depth)?;
+                }
+                i = end;
+            }
+            else if let Some(end) = self.define(text, &raw, i) {
+                i = end;
+            }
+            else if let Some((expansion, end)) = self.use_macro(text, &raw, i, depth)? {
+                self.expand(&expansion, depth + 1)?;
+                i = end;
+            }
+            else {
+                if self.output.len() >= self.options.max_expansion {
+                    return Err(self.error(text[i], &format!("the code expands to more than {} bytes", self.options.max_expansion)));
+                }
+                self.map.push(self.output.len(), text[i].file, text[i].offset);
+                self.output.push(raw[i]);
+                i += 1;
+            }
+        }
+        return Ok(());
+    }
+
+    fn conditional(&self, raw: &[u8], start: usize) -> Option<(Option<std::ops::Range<usize>>, usize)> {
+        /*
+         * `@if name ... @endif`, returns the contents if they're kept and the position right after `@endif`.
+         * None when it isn't a complete conditional, then it's a comment.
+         */
+
+        let (negated, name, body_start) = if_condition(raw, start)?;
+        let mut depth = 1;
+        let mut i = body_start;
+        while i < raw.len() {
+            if if_condition(raw, i).is_some() {
+                depth += 1;
+            }
+            else if is_directive(&raw[i..], ENDIF_DIRECTIVE) {
+                depth -= 1;
+                if depth == 0 {
+                    let kept = self.options.defines.contains(&name) != negated;
+                    return Some((kept.then_some(body_start..i), i + ENDIF_DIRECTIVE.len()));
+                }
+            }
+            i += 1;
+        }
+        return None;
+    }
+
+    fn define(&mut self, text: &[SourceByte], raw: &[u8], start: usize) -> Option<usize> {
+        // `@def name(a, b) { body }`, returns the position right after the body, or None when it isn't a definition
+        if !is_directive(&raw[start..], DEF_DIRECTIVE) {
+            return None;
+        }
+        let (name, mut i) = parse_identifier(raw, skip_spaces(raw, start + DEF_DIRECTIVE.len()))?;
+        let mut parameters: Vec<String> = Vec::new();
+        if raw.get(i) == Some(&b'(') {
+            let close = find_matching(raw, i)?;
+            for parameter in split_arguments(raw, i + 1, close) {
+                let (parameter_name, _) = parse_identifier(raw, parameter.start).filter(|&(_, end)| end == parameter.end)?;
+                parameters.push(parameter_name);
+            }
+            i = close + 1;
+        }
+        let open = skip_whitespace(raw, i);
+        if raw.get(open) != Some(&b'{') {
+            return None;
+        }
+        let close = find_matching(raw, open)?;
+        self.macros.insert(name, Macro { parameters, body: text[open + 1..close].to_vec() });
+        return Some(close + 1);
+    }
+
+    fn use_macro(&mut self, text: &[SourceByte], raw: &[u8], start: usize, depth: usize) -> Result<Option<(Vec<SourceByte>, usize)>, BfError> {
+        /*
+         * `@use name(3, >)`, returns the substituted body and the position right after the use.
+         * None when it isn't a use (no name, unclosed arguments, a name that isn't a macro), then it's a comment.
+         */
+
+        if !is_directive(&raw[start..], USE_DIRECTIVE) {
+            return Ok(None);
+        }
+        let Some((name, mut end)) = parse_identifier(raw, skip_spaces(raw, start + USE_DIRECTIVE.len())) else {
+            return Ok(None);
+        };
+        let mut arguments: Vec<&[SourceByte]> = Vec::new();
+        if raw.get(end) == Some(&b'(') {
+            let Some(close) = find_matching(raw, end) else {
+                return Ok(None);
+            };
+            arguments = split_arguments(raw, end + 1, close).into_iter().map(|range| &text[range]).collect();
+            end = close + 1;
+        }
+        if !self.macros.contains_key(&name) {
+            return Ok(None);
+        }
+        if self.uses >= self.options.max_macro_uses {
+            return Err(self.error(text[start], &format!("macros are used more than {} times", self.options.max_macro_uses)));
+        }
+        self.uses += 1;
+
+        let invalid = |message: &str| self.error(text[start], message);
+        if depth >= self.options.max_macro_depth {
+            return Err(invalid(&format!("macros are nested more than {} levels deep", self.options.max_macro_depth)));
+        }
+        let definition = &self.macros[&name];
+        if arguments.len() != definition.parameters.len() {
+            return Err(invalid(&format!("`{name}` takes {} arguments, got {}", definition.parameters.len(), arguments.len())));
+        }
+        let expansion = self.substitute(&definition.body, &definition.parameters, &arguments)
+            .map_err(|message| invalid(&format!("in `{name}`: {message}")))?;
+        return Ok(Some((expansion, end)));
+    }
+
+    fn substitute(&self, body: &[SourceByte], parameters: &[String], arguments: &[&[SourceByte]]) -> Result<Vec<SourceByte>, String> {
+        let raw: Vec<u8> = body.iter().map(|c| c.value).collect();
+        let mut expansion: Vec<SourceByte> = Vec::new();
+        let mut i = 0;
+        while i < raw.len() {
+            let parameter = if raw[i] == PARAMETER_SIGIL { parse_identifier(&raw, i + 1) } else { None };
+            let Some(index) = parameter.as_ref().and_then(|(name, _)| parameters.iter().position(|parameter| parameter == name)) else {
+                expansion.push(body[i]);
+                i += 1;
+                continue;
+            };
+            let (name, end) = parameter.unwrap();
+            let argument = arguments[index];
+            if raw.get(end) != Some(&b'{') {
+                expansion.extend(argument);
+                i = end;
+                continue;
+            }
+            let close = find_matching(&raw, end).ok_or_else(|| format!("unclosed `%{name}{{`"))?;
+            let count_text: String = argument.iter().map(|c| c.value as char).collect();
+            let count: usize = count_text.parse().map_err(|_| format!("`%{name}{{...}}` needs a count, got `{count_text}`"))?;
+            let repeated = self.substitute(&body[end + 1..close], parameters, arguments)?;
+            if repeated.len().checked_mul(count).is_none_or(|length| expansion.len() + length > self.options.max_expansion) {
+                return Err(format!("`%{name}{{...}}` expands to more than {} bytes", self.options.max_expansion));
+            }
+            if !repeated.is_empty() {
+                for _ in 0..count {
+                    expansion.extend(&repeated);
+                }
+            }
+            i = close + 1;
+        }
+        return Ok(expansion);
+    }
+
+    fn resolve(&self, including: &Path, name: &str) -> Option<PathBuf> {
+        let directory = including.parent().unwrap_or(Path::new(""));
+        return std::iter::once(directory)
+            .chain(self.options.search_paths.iter().map(PathBuf::as_path))
+            .map(|directory| directory.join(name))
+            .find(|candidate| candidate.is_file());
+    }
+}
+
+fn is_directive(code: &[u8], directive: &[u8]) -> bool {
+    // The directive followed by something else than a letter, digit or `_`, so `@deferred` or `me@user.net` aren't directives
+    return code.starts_with(directive) && code.get(directive.len()).is_none_or(|c| !c.is_ascii_alphanumeric() && *c != b'_');
+}
+
+fn if_condition(code: &[u8], start: usize) -> Option<(bool, String, usize)> {
+    // `@if name` or `@if !name` followed by a space, returns whether it's negated, the name and the position after it
+    if !code[start..].starts_with(IF_DIRECTIVE) || !matches!(code.get(start + IF_DIRECTIVE.len()), Some(b' ' | b'\t')) {
+        return None;
+    }
+    let condition_start = skip_spaces(code, start + IF_DIRECTIVE.len());
+    let negated = code.get(condition_start) == Some(&b'!');
+    let (name, end) = parse_identifier(code, condition_start + usize::from(negated))?;
+    return Some((negated, name, end));
+}
+
+fn skip_spaces(code: &[u8], start: usize) -> usize {
+    return start + code[start.min(code.len())..].iter().take_while(|&&c| c == b' ' || c == b'\t').count();
+}
+
+fn skip_whitespace(code: &[u8], start: usize) -> usize {
+    return start + code[start.min(code.len())..].iter().take_while(|c| c.is_ascii_whitespace()).count();
+}
+
+fn find_matching(code: &[u8], open: usize) -> Option<usize> {
+    // Position of the `}` or `)` closing the `{` or `(` at `open`, skipping the pairs nested in between
+    let (opening, closing) = match code[open] {
+        b'(' => (b'(', b')'),
+        _ => (b'{', b'}'),
+    };
+    let mut depth = 0;
+    for (i, &c) in code.iter().enumerate().skip(open) {
+        if c == opening {
+            depth += 1;
+        }
+        else if c == closing {
+            depth -= 1;
+            if depth == 0 { return Some(i); }
+        }
+    }
+    return None;
+}
+
+fn parse_identifier(code: &[u8], start: usize) -> Option<(String, usize)> {
+    // ASCII letters, digits and `_`; returns the name and the position right after it
+    let length = code.get(start..)?.iter().take_while(|c| c.is_ascii_alphanumeric() || **c == b'_').count();
+    if length == 0 {
+        return None;
+    }
+    let name = String::from_utf8(code[start..start + length].to_vec()).unwrap();
+    return Some((name, start + length));
+}
+
+fn split_arguments(code: &[u8], start: usize, end: usize) -> Vec<std::ops::Range<usize>> {
+    // Comma separated, surrounding whitespace trimmed; `()` has no arguments, and commas in parentheses (like those of
+    // a nested `@use`) don't separate them
+    if code[start..end].iter().all(|c| c.is_ascii_whitespace()) {
+        return Vec::new();
+    }
+    let mut arguments: Vec<std::ops::Range<usize>> = Vec::new();
+    let mut argument_start = start;
+    let mut depth = 0;
+    for i in start..=end {
+        match code.get(i).filter(|_| i < end) {
+            Some(b'(') => depth += 1,
+            Some(b')') => depth -= 1,
+            _ => {},
+        }
+        if i == end || (code[i] == b',' && depth == 0) {
+            let mut range = argument_start..i;
+            while range.start < range.end && code[range.start].is_ascii_whitespace() { range.start += 1; }
+            while range.end > range.start && code[range.end - 1].is_ascii_whitespace() { range.end -= 1; }
+            arguments.push(range);
+            argument_start = i + 1;
+        }
+    }
+    return arguments;
+}
+
+fn parse_quoted_argument(code: &[u8], start: usize) -> Option<(String, usize)> {
+    // `"text"` after optional spaces; returns the text and the position right after the closing quote
+    let quote = skip_spaces(code, start);
+    if code.get(quote) != Some(&b'"') {
+        return None;
+    }
+    let length = code[quote + 1..].iter().position(|&c| c == b'"' || c == b'\n')?;
+    let end = quote + 1 + length;
+    if code[end] != b'"' || length == 0 {
+        return None;
+    }
+    let text = String::from_utf8(code[quote + 1..end].to_vec()).ok()?;
+    return Some((text, end + 1));
+}
+
+fn code_blocks(text: &[SourceByte]) -> Vec<SourceByte> {
+    /*
+     * The lines inside the brainfuck code blocks of a Markdown text. A fence is a line of at least three backticks
+     * or tildes, indented by up to three spaces; the block ends at a line of at least as many of the same character.
+     */
+
+    let mut code = Vec::new();
+    let mut fence: Option<(u8, usize, bool)> = None; // Character and length of the open fence, and whether the block is code
+    let mut start = 0;
+    while start < text.len() {
+        let end = text[start..].iter().position(|c| c.value == NEWLINE).map_or(text.len(), |i| start + i + 1);
+        let line = &text[start..end];
+        let raw: Vec<u8> = line.iter().map(|c| c.value).collect();
+        let indent = raw.iter().take_while(|&&c| c == b' ').count();
+        let marker = raw.get(indent).copied().filter(|&c| indent <= 3 && (c == b'`' || c == b'~'));
+        let length = marker.map_or(0, |marker| raw[indent..].iter().take_while(|&&c| c == marker).count());
+        let info = marker.map(|_| String::from_utf8_lossy(&raw[indent + length..]).trim().to_lowercase());
+        match (fence, marker, info) {
+            (None, Some(marker), Some(info)) if length >= 3 => {
+                let language = info.split_whitespace().next().unwrap_or("");
+                fence = Some((marker, length, matches!(language, "" | "brainfuck" | "bf")));
+            },
+            (Some((open, open_length, _)), Some(marker), Some(info)) if marker == open && length >= open_length && info.is_empty() => {
+                fence = None;
+            },
+            (Some((_, _, true)), _, _) => code.extend_from_slice(line),
+            _ => {},
+        }
+        start = end;
+    }
+    return code;
+}
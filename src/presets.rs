@@ -0,0 +1,118 @@
+use crate::*;
+
+/*
+ * Named bundles of the settings interpreters disagree on, so users pick a behavior instead of a flag combination:
+ * the cell width, the tape, what reading past the end of the input does, how the output is printed and how newlines
+ * are translated. Every preset spells out all of them. The interpreter only has 8-bit wrapping cells,
+ * so `cell_width` is 8 in each of them, and applying a preset with another width fails.
+ */
+
+const SUPPORTED_CELL_WIDTH: u32 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str, // One line, for `--preset` errors and help
+    pub cell_width: u32, // Bits per cell
+    pub max_tape_cells: Option<usize>, // Cells of both directions, unbounded if `None`
+    pub eof_behavior: EofBehavior,
+    pub output_format: OutputFormat,
+    pub newline_mode: NewlineMode,
+}
+
+pub trait PresetTarget {
+    // Whatever a preset can be applied to: the state in the library, the parsed options in the CLI
+    fn set_max_tape_cells(&mut self, limit: Option<usize>);
+    fn set_eof_behavior(&mut self, behavior: EofBehavior);
+    fn set_output_format(&mut self, format: OutputFormat);
+    fn set_newline_mode(&mut self, mode: NewlineMode);
+}
+
+impl PresetTarget for BFState {
+    fn set_max_tape_cells(&mut self, limit: Option<usize>) {
+        set_max_tape_cells(self, limit);
+    }
+
+    fn set_eof_behavior(&mut self, behavior: EofBehavior) {
+        set_eof_behavior(self, behavior);
+    }
+
+    fn set_output_format(&mut self, format: OutputFormat) {
+        set_output_format(self, format);
+    }
+
+    fn set_newline_mode(&mut self, mode: NewlineMode) {
+        set_newline_mode(self, mode);
+    }
+}
+
+const PRESETS: [Preset; 5] = [
+    Preset {
+        name: "classic-30k",
+        description: "30000 cells and the cell left unchanged at EOF like the original implementation, UTF-8 output",
+        cell_width: 8,
+        max_tape_cells: Some(30_000),
+        eof_behavior: EofBehavior::Unchanged,
+        output_format: OutputFormat::Utf8,
+        newline_mode: NewlineMode::Unchanged,
+    },
+    Preset {
+        name: "unbounded",
+        description: "as many cells as the program uses in both directions, 0 at EOF, UTF-8 output (the defaults)",
+        cell_width: 8,
+        max_tape_cells: None,
+        eof_behavior: EofBehavior::Zero,
+        output_format: OutputFormat::Utf8,
+        newline_mode: NewlineMode::Unchanged,
+    },
+    Preset {
+        name: "8bit-wrap-eof0",
+        description: "named after its cells and EOF, behaves exactly like `unbounded` (the defaults)",
+        cell_width: 8,
+        max_tape_cells: None,
+        eof_behavior: EofBehavior::Zero,
+        output_format: OutputFormat::Utf8,
+        newline_mode: NewlineMode::Unchanged,
+    },
+    Preset {
+        name: "dbfi-compatible",
+        description: "for self-interpreters reading a program from the input, CRs are dropped from it, 0 at EOF",
+        cell_width: 8,
+        max_tape_cells: None,
+        eof_behavior: EofBehavior::Zero,
+        output_format: OutputFormat::Raw,
+        newline_mode: NewlineMode::StripCr,
+    },
+    Preset {
+        name: "golf",
+        description: "answers are compared byte for byte: bytes printed as they come, nothing translated, 0 at EOF",
+        cell_width: 8,
+        max_tape_cells: None,
+        eof_behavior: EofBehavior::Zero,
+        output_format: OutputFormat::Raw,
+        newline_mode: NewlineMode::Unchanged,
+    },
+];
+
+impl Preset {
+    pub fn named(name: &str) -> Option<Self> {
+        return PRESETS.iter().find(|preset| preset.name == name).copied();
+    }
+
+    pub fn all() -> &'static [Preset] {
+        return &PRESETS;
+    }
+
+    pub fn apply(&self, target: &mut impl PresetTarget) -> Result<(), BfError> {
+        // On a state, this wraps the current reader and writer, so it's meant to be called after `set_input_reader`
+        // and `set_output_writer`
+        if self.cell_width != SUPPORTED_CELL_WIDTH {
+            return Err(BfError::Runtime(format!("only {SUPPORTED_CELL_WIDTH}-bit cells are supported")));
+        }
+        target.set_max_tape_cells(self.max_tape_cells);
+        target.set_eof_behavior(self.eof_behavior);
+        target.set_output_format(self.output_format);
+        target.set_newline_mode(self.newline_mode);
+        return Ok(());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::*;
+
+/*
+ * The immutable part of a run: the code and everything compiled from it.
+ * It's shared through an `Arc`, so many states (one per thread) can run one program
+ * without compiling it again; each state only owns its tape, cursor and I/O.
+ */
+
+#[derive(Clone, Debug)]
+pub struct Program {
+    pub(crate) code: Vec<u8>, // The brainfuck code
+    pub(crate) code_start: usize, // Index of the first instruction (skips the shebang line)
+    pub(crate) bytecode: Bytecode, // The compiled code that actually gets executed
+    pub(crate) labels: Vec<Label>, // `@label{name}` directives in the code, in source order
+    pub(crate) syntax_error: Option<(usize, String)>, // Position and message, checked once when compiling
+    pub(crate) assumes_zeroed_tape: bool, // The bytecode was optimized for a tape that is 0 everywhere at the start
+    pub(crate) resume_positions: Vec<usize>, // With `assumes_zeroed_tape`, where the program goes on from after each op without the pass
+    pub(crate) digest: u64, // See `digest`
+}
+
+impl Program {
+    pub(crate) fn compile(code: Vec<u8>, code_start: usize, extensions: &[u8], assumes_zeroed_tape: bool) -> Self {
+        // The known-zero analysis only runs with `assumes_zeroed_tape`, it can't tell what cells hold otherwise
+        return Self::compile_folding(code, code_start, extensions, assumes_zeroed_tape.then_some(known_zero::fold_known_zero));
+    }
+
+    pub(crate) fn compile_folding(code: Vec<u8>, code_start: usize, extensions: &[u8], fold: Option<impl FnOnce(&Bytecode) -> known_zero::Folded>) -> Self {
+        // `fold` runs the known-zero analysis, `None` if the tape may not be zeroed at the start
+        trace_span!(DEBUG, "compile", bytes = code.len() - code_start);
+        let syntax_error = match check_syntax(&code[code_start..]) {
+            Err(BfError::Syntax { position, message }) => Some((position + code_start, message)),
+            _ => None,
+        };
+        let digest = stable_hash(&normalized_source(&code[code_start..]));
+        let mut bytecode = compile_with_extensions(&code, code_start, true, extensions);
+        let assumes_zeroed_tape = fold.is_some();
+        let mut resume_positions = Vec::new();
+        if let Some(fold) = fold {
+            let folded = fold(&bytecode);
+            bytecode = folded.bytecode;
+            resume_positions = folded.resume_positions;
+        }
+        trace_event!(DEBUG, operations = bytecode.ops.len(), syntax_error = syntax_error.is_some(), "compiled");
+        return Program {
+            bytecode,
+            labels: parse_labels(&code, code_start),
+            code,
+            code_start,
+            syntax_error,
+            assumes_zeroed_tape,
+            resume_positions,
+            digest,
+        };
+    }
+
+    pub fn from_ops(ops: &[Op]) -> Self {
+        /*
+         * A program from operations compiled ahead of time, like the ones the `bf!` macro embeds.
+         * The operations have to come from `compile`; there's no code, so each operation's source position is its index.
+         */
+
+        return Program {
+            code: Vec::new(),
+            code_start: 0,
+            bytecode: Bytecode { ops: ops.to_vec(), positions: (0..ops.len()).collect() },
+            labels: Vec::new(),
+            syntax_error: None,
+            assumes_zeroed_tape: false,
+            resume_positions: Vec::new(),
+            digest: stable_hash(format!("{ops:?}").as_bytes()),
+        };
+    }
+
+    pub fn code(&self) -> &[u8] {
+        return &self.code;
+    }
+
+    pub fn bytecode(&self) -> &Bytecode {
+        return &self.bytecode;
+    }
+
+    pub fn digest(&self) -> u64 {
+        /*
+         * A hash of the commands (`+-<>.,[]` and `$`) of the code, which stays the same between builds and platforms:
+         * programs differing only in comments, whitespace or a shebang line have the same digest.
+         * Programs from `from_ops` have no code, their operations are hashed instead.
+         */
+
+        return self.digest;
+    }
+
+    pub fn check_syntax(&self) -> Result<(), BfError> {
+        return match &self.syntax_error {
+            Some((position, message)) => Err(BfError::Syntax { position: *position, message: message.clone() }),
+            None => Ok(()),
+        };
+    }
+
+    pub fn check_extensions(&self, enabled: Extensions) -> Result<(), BfError> {
+        // Rejects characters of extensions that aren't enabled instead of treating them as comments
+        return check_extensions(&self.code[self.code_start..], enabled).map_err(|error| match error {
+            BfError::Syntax { position, message } => BfError::Syntax { position: position + self.code_start, message },
+            error => error,
+        });
+    }
+
+    pub fn check_charset(&self, enabled: Extensions) -> Result<(), BfError> {
+        // Rejects anything but commands, whitespace and characters of enabled extensions
+        return check_charset(&self.code[self.code_start..], enabled).map_err(|error| match error {
+            BfError::Syntax { position, message } => BfError::Syntax { position: position + self.code_start, message },
+            error => error,
+        });
+    }
+
+    pub fn into_fn(&self) -> impl FnMut(&[u8]) -> Result<Vec<u8>, BfError> + Send + use<> {
+        /*
+         * Wraps the program as a function from its input to its output.
+         * Each call runs it from the start on a cleared tape (reused between calls), reading 0 after the input ends.
+         * There are no limits, so a program that doesn't halt never returns.
+         */
+
+        let program = Arc::new(self.clone());
+        let pool = StatePool::new(1);
+        return move |input: &[u8]| {
+            let mut state = pool.checkout(Arc::clone(&program));
+            push_input(&mut state, input);
+            capture_output(&mut state);
+            let report = run_bf(&mut state, None, None);
+            pool.recycle(state);
+            return Ok(report?.output);
+        };
+    }
+
+    pub fn run_batch(&self, inputs: &[&[u8]], limits: &ResourceLimits) -> Vec<Result<RunReport, BfError>> {
+        /*
+         * Runs the program once per input, each from the start on a cleared tape and reading 0 after its input ends,
+         * spread over rayon's thread pool with the `rayon` feature, or over one thread per available core otherwise.
+         * The reports are in the order of the inputs, with the output captured; `limits` apply to each run on its own.
+         * A run that panics gets a `BfError::Runtime` instead of its report, the other runs go on.
+         */
+
+        let program = Arc::new(self.clone());
+        let pool = StatePool::default();
+        let run = |input: &[u8]| {
+            let mut state = pool.checkout(Arc::clone(&program));
+            push_input(&mut state, input);
+            capture_output(&mut state);
+            let report = run_with_limits(&mut state, limits);
+            pool.recycle(state);
+            return report;
+        };
+        #[cfg(feature = "rayon")]
+        return batch_on_rayon(inputs, run);
+        #[cfg(not(feature = "rayon"))]
+        return batch_on_threads(inputs, run);
+    }
+}
+
+fn run_caught(run: &impl Fn(&[u8]) -> Result<RunReport, BfError>, input: &[u8]) -> Result<RunReport, BfError> {
+    // The report of the run, or an error if it panicked, so one bad run doesn't take the others down with it
+    return std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run(input)))
+        .unwrap_or_else(|_| Err(BfError::Runtime(String::from("The run panicked"))));
+}
+
+#[cfg(feature = "rayon")]
+fn batch_on_rayon(inputs: &[&[u8]], run: impl Fn(&[u8]) -> Result<RunReport, BfError> + Sync) -> Vec<Result<RunReport, BfError>> {
+    use rayon::prelude::*;
+    return inputs.par_iter()
+        .map(|input| run_caught(&run, input))
+        .collect();
+}
+
+#[cfg(not(feature = "rayon"))]
+fn batch_on_threads(inputs: &[&[u8]], run: impl Fn(&[u8]) -> Result<RunReport, BfError> + Sync) -> Vec<Result<RunReport, BfError>> {
+    // Each thread takes the next input no thread has taken yet, until there are none left
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let reports: Vec<std::sync::OnceLock<Result<RunReport, BfError>>> = inputs.iter().map(|_| std::sync::OnceLock::new()).collect();
+    let threads = std::thread::available_parallelism().map_or(1, usize::from).min(inputs.len());
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(input) = inputs.get(index) else {
+                    return;
+                };
+                let _ = reports[index].set(run_caught(&run, input));
+            });
+        }
+    });
+    return reports.into_iter().map(|report| report.into_inner().unwrap()).collect();
+}
+
+pub fn new_program(code: impl AsRef<[u8]>) -> Arc<Program> {
+    // A leading shebang line is a comment, like in `new_bf_state`; the code doesn't have to be UTF-8
+    let code = code.as_ref().to_vec();
+    let code_start = if code.starts_with(SHEBANG) { first_line_end(&code) } else { 0 };
+    return Arc::new(Program::compile(code, code_start, &[], true));
+}
+
+pub(crate) fn replace_program(state: &mut BFState, program: Program) {
+    // Everything indexed by bytecode position is dropped, the caller sets the instruction position
+    state.program = Arc::new(program);
+    state.loop_traces.clear();
+    if let Some((_, history)) = state.ip_history.as_mut() {
+        history.clear();
+    }
+    if let Some(iterations) = state.loop_iterations.as_mut() {
+        iterations.clear();
+    }
+}
+
+pub(crate) fn normalized_source(code: &[u8]) -> Vec<u8> {
+    // The code without comments, what `digest` hashes
+    return code.iter().copied().filter(|c| b"+-<>.,[]$".contains(c)).collect();
+}
+
+pub(crate) fn stable_hash(bytes: &[u8]) -> u64 {
+    // FNV-1a, which unlike the standard library's hasher stays the same between builds
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in bytes {
+        hash = (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3);
+    }
+    return hash;
+}
+
+pub(crate) fn first_line_end(code: &[u8]) -> usize {
+    return match code.iter().position(|&c| c == NEWLINE) {
+        Some(i) => i + 1,
+        None => code.len(),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_panicking_run_only_fails_itself() {
+        let program = new_program(",[.,]");
+        let run = |input: &[u8]| {
+            assert!(input != b"panic", "the run panicked");
+            let mut state = new_bf_state_from_program(Arc::clone(&program));
+            set_input_reader(&mut state, Box::new(std::io::empty()));
+            push_input(&mut state, input);
+            capture_output(&mut state);
+            return run_bf(&mut state, None, None);
+        };
+        let inputs: Vec<&[u8]> = vec![b"one", b"panic", b"two", b"three", b"panic", b"four", b"five", b"six", b"seven"];
+        #[cfg(feature = "rayon")]
+        let reports = batch_on_rayon(&inputs, run);
+        #[cfg(not(feature = "rayon"))]
+        let reports = batch_on_threads(&inputs, run);
+        for (input, report) in inputs.iter().zip(reports) {
+            match report {
+                Ok(report) => assert_eq!(report.output, *input),
+                Err(error) => assert!(*input == b"panic" && error.to_string().contains("panicked"), "{error}"),
+            }
+        }
+    }
+
+    #[test]
+    fn programs_from_ops_keep_the_invariants() {
+        // The same program the `bf!` macro would embed, run with the invariants checked after every step
+        let program = Arc::new(Program::from_ops(&compile(b"+++[>++<-]>[<+>-]<.,[.,]", 0).ops));
+        let mut state = new_bf_state_from_program(program);
+        set_check_invariants(&mut state, true);
+        set_input_reader(&mut state, Box::new(std::io::empty()));
+        push_input(&mut state, b"ok");
+        capture_output(&mut state);
+        assert_eq!(run_bf(&mut state, None, None).unwrap().output, b"\x06ok");
+    }
+}
@@ -0,0 +1,32 @@
+use crate::*;
+
+/*
+ * Reachability: whether a command can ever execute, for some input. It's a bounded model check with the symbolic
+ * executor (see `symbolic.rs`): every path is followed until it reaches the command, halts, or runs out of steps.
+ * A command is only unreachable when every path halted without reaching it, so programs that can loop forever
+ * (like any interactive one) always get `Unknown` for their dead code.
+ */
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reachability {
+    Reachable(Vec<u8>), // An input reaching it
+    Unreachable, // No run reaches it, whatever the input
+    Unknown, // No path reached it within the bound, but some were cut
+}
+
+pub fn reachable(program: &Program, source_offset: usize, bound: usize) -> Reachability {
+    /*
+     * Checks the command at `source_offset`, with at most `bound` steps per path, from a zeroed tape.
+     * Bytes that aren't commands never execute, so they're unreachable.
+     */
+
+    let options = SymbolicOptions { max_steps: bound, first_only: true, ..SymbolicOptions::default() };
+    let report = explore(program, &Target::Position(source_offset), &options);
+    if let Some(path) = report.paths.into_iter().next() {
+        return Reachability::Reachable(path.input);
+    }
+    if report.complete {
+        return Reachability::Unreachable;
+    }
+    return Reachability::Unknown;
+}
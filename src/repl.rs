@@ -0,0 +1,115 @@
+use brainfuckers::*;
+
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/*
+ * `bf repl`: every entered line runs against the same state, so the tape and the cursor carry over.
+ * Lines starting with `:` are meta-commands instead of code.
+ * Ctrl-C stops the running snippet without leaving the REPL.
+ */
+
+const TAPE_VIEW_RADIUS: isize = 8; // How many cells `:tape` shows on each side of the cursor
+
+const HELP: &str = "\
+:tape          show the cells around the cursor
+:radix <radix> show cell values as `dec`, `hex`, `bin` or `char`
+:reset         start over with an empty tape
+:save <file>   write the snippets entered so far to a file
+:load <file>   run the code of a file in this session
+:help          show this help
+:quit          leave the REPL";
+
+fn new_session(interrupted: &Arc<AtomicBool>) -> BFState {
+    let mut state = new_bf_state("");
+    // One byte at a time, so the program doesn't read ahead into the next lines of the REPL
+    set_input_reader(&mut state, Box::new(std::io::BufReader::with_capacity(1, std::io::stdin())));
+    set_interrupt_flag(&mut state, interrupted.clone());
+    return state;
+}
+
+fn run_snippet(state: &mut BFState, code: &str, interrupted: &AtomicBool) {
+    load_code(state, code);
+    match run_bf(state, None, None) {
+        Ok(report) if matches!(report.outcome, RunOutcome::Interrupted) => {
+            let _ = flush_output(state);
+            interrupted.store(false, Ordering::Relaxed);
+            eprintln!("Interrupted");
+        },
+        Ok(_) => {},
+        Err(error) => eprintln!("{error}"),
+    }
+}
+
+fn format_tape_view(state: &BFState, radix: DisplayRadix) -> String {
+    let cursor = cursor_position(state);
+    let mut view = String::new();
+    for index in cursor - TAPE_VIEW_RADIUS..=cursor + TAPE_VIEW_RADIUS {
+        let value = format_cell(get_cell(state, index), radix);
+        if index == cursor {
+            view.push_str(&format!("[{value}] "));
+        }
+        else {
+            view.push_str(&format!("{value} "));
+        }
+    }
+    return format!("cells {}..={}: {}", cursor - TAPE_VIEW_RADIUS, cursor + TAPE_VIEW_RADIUS, view.trim_end());
+}
+
+pub fn repl() -> Result<(), String> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler_flag = interrupted.clone();
+    if let Err(error) = ctrlc::set_handler(move || handler_flag.store(true, Ordering::Relaxed)) {
+        eprintln!("Failed to install the Ctrl-C handler: {error}");
+    }
+
+    let mut state = new_session(&interrupted);
+    let mut history: Vec<String> = Vec::new();
+    let mut radix = DisplayRadix::Decimal;
+    let stdin = std::io::stdin();
+    loop {
+        print!("bf> ");
+        std::io::stdout().flush().map_err(|error| error.to_string())?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).map_err(|error| error.to_string())? == 0 {
+            println!();
+            return Ok(());
+        }
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            ":quit" | ":q" => return Ok(()),
+            ":help" => println!("{HELP}"),
+            ":tape" => println!("{}", format_tape_view(&state, radix)),
+            ":radix" => match argument.parse() {
+                Ok(new_radix) => radix = new_radix,
+                Err(message) => eprintln!("{message}"),
+            },
+            ":reset" => {
+                state = new_session(&interrupted);
+                history.clear();
+            },
+            ":save" if !argument.is_empty() => {
+                let mut code = history.join("\n");
+                code.push('\n');
+                if let Err(error) = std::fs::write(argument, code) {
+                    eprintln!("Failed to write file `{argument}` {error}");
+                }
+            },
+            ":load" if !argument.is_empty() => match std::fs::read_to_string(argument) {
+                Ok(code) => {
+                    run_snippet(&mut state, &code, &interrupted);
+                    history.push(code.trim_end().to_string());
+                },
+                Err(error) => eprintln!("Failed to read file `{argument}` {error}"),
+            },
+            _ if command.starts_with(':') => eprintln!("Unknown command `{line}`, try `:help`"),
+            _ => {
+                run_snippet(&mut state, line, &interrupted);
+                history.push(String::from(line));
+            },
+        }
+    }
+}
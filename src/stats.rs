@@ -0,0 +1,70 @@
+use crate::*;
+
+/*
+ * Static statistics of a program, for catalogs of programs: nothing is run.
+ * The tape extent is a lower bound, the cells the program reaches before anything can make it stop or move by an amount
+ * only known at runtime: top-level code is followed, through balanced loops (whose bodies may not run at all),
+ * up to the first unbalanced loop or scan.
+ */
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProgramStats {
+    pub command_counts: [(u8, usize); 8], // Each of `+-<>.,[]` with its number of occurrences
+    pub loops: usize,
+    pub max_depth: usize, // Deepest nesting of loops, 0 without loops
+    pub code_bytes: usize, // Bytes of the code, without the shebang line
+    pub comment_bytes: usize, // Bytes that aren't commands, whitespace included
+    pub tape_extent: (isize, isize), // Lowest and highest cells the program surely reaches
+}
+
+impl ProgramStats {
+    pub fn commands(&self) -> usize {
+        return self.code_bytes - self.comment_bytes;
+    }
+}
+
+pub fn program_stats(program: &Program) -> ProgramStats {
+    let code = &program.code[program.code_start..];
+    let mut stats = ProgramStats { command_counts: COMMANDS.map(|c| (c, 0)), code_bytes: code.len(), ..ProgramStats::default() };
+    let mut depth: usize = 0;
+    for &c in code {
+        let Some(index) = COMMANDS.iter().position(|&command| command == c) else {
+            stats.comment_bytes += 1;
+            continue;
+        };
+        stats.command_counts[index].1 += 1;
+        match c {
+            b'[' => {
+                stats.loops += 1;
+                depth += 1;
+                stats.max_depth = stats.max_depth.max(depth);
+            },
+            b']' => depth = depth.saturating_sub(1),
+            _ => {},
+        }
+    }
+
+    let ops = compile(&program.code, program.code_start).ops;
+    let (mut cursor, mut lowest, mut highest) = (0, 0, 0);
+    let mut i = 0;
+    while i < ops.len() {
+        let reached = match ops[i] {
+            Op::Add(offset, _) => cursor + offset,
+            Op::Move(delta) => {
+                cursor += delta;
+                cursor
+            },
+            Op::JumpIfZero(after) if balance::body_effect(&ops, i + 1..after - 1, &mut |_| {}) == Some(0) => {
+                i = after;
+                continue;
+            },
+            Op::JumpIfZero(_) | Op::Scan(_) | Op::Extension(_) => break,
+            _ => cursor,
+        };
+        lowest = lowest.min(reached);
+        highest = highest.max(reached);
+        i += 1;
+    }
+    stats.tape_extent = (lowest, highest);
+    return stats;
+}
@@ -0,0 +1,265 @@
+use crate::*;
+
+use std::collections::HashMap;
+use std::fmt;
+
+/*
+ * Symbolic execution: the input bytes are variables (`x0` is the first byte read, `x1` the second, ...)
+ * instead of values. Commands only add constants to cells, and copies happen through loops, so a cell always holds
+ * either a constant or an input byte plus a constant, modulo 256 like the cells themselves.
+ * A loop whose condition depends on the input forks the path: one side assumes the cell is 0, the other that it isn't,
+ * and sides whose constraints can't all hold are dropped. Every constraint is then `xN == value` or `xN != value`,
+ * so whether they can hold is exact. The paths reaching the target are reported with their constraints
+ * and the smallest input satisfying them.
+ *
+ * The search is bounded (steps per path, paths explored, input bytes), so it's meant for small programs:
+ * every iteration of a loop over an input-dependent counter is a fork of its own.
+ */
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Expr {
+    pub variable: Option<usize>, // The input byte added to the constant, if any
+    pub constant: u8,
+}
+
+impl Expr {
+    pub fn evaluate(&self, input: &[u8]) -> u8 {
+        // Variables past the end of `input` are 0
+        let value = self.variable.and_then(|variable| input.get(variable).copied()).unwrap_or(0);
+        return value.wrapping_add(self.constant);
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match (self.variable, self.constant) {
+            (Some(variable), 0) => write!(f, "x{variable}"),
+            (Some(variable), constant) => write!(f, "x{variable} + {constant}"),
+            (None, constant) => write!(f, "{constant}"),
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Constraint {
+    pub variable: usize,
+    pub value: u8,
+    pub equal: bool, // Whether the input byte has to be `value`, or anything else
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "x{} {} {}", self.variable, if self.equal { "==" } else { "!=" }, self.value);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Target {
+    Position(usize), // Reaching the instruction at this source position
+    Output(Vec<u8>), // Printing these bytes (as the start of the output)
+}
+
+#[derive(Clone, Debug)]
+pub struct SymbolicOptions {
+    pub max_steps: usize, // Instructions per path, longer paths are cut
+    pub max_paths: usize, // Paths explored in total, forks past it are dropped
+    pub max_inputs: usize, // Input bytes, reading past them gives 0 like EOF
+    pub first_only: bool, // Whether to stop at the first path reaching the target
+}
+
+impl Default for SymbolicOptions {
+    fn default() -> Self {
+        return SymbolicOptions { max_steps: 100_000, max_paths: 1000, max_inputs: 8, first_only: false };
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReachingPath {
+    pub constraints: Vec<Constraint>, // What the input has to satisfy, in the order the branches were taken
+    pub input: Vec<u8>, // The smallest input satisfying them, as long as the number of bytes the path read
+    pub steps: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SymbolicReport {
+    pub paths: Vec<ReachingPath>, // The paths reaching the target, in the order they were found
+    pub explored: usize, // Paths explored, including the ones that halted or failed before the target
+    pub complete: bool, // Whether every path was followed to its end; if not, other paths may reach the target
+}
+
+#[derive(Clone)]
+struct Path {
+    instruction: usize,
+    cursor: isize,
+    tape: HashMap<isize, Expr>, // Cells that aren't 0
+    inputs: usize, // Variables created so far
+    constraints: Vec<Constraint>,
+    printed: usize, // Bytes printed so far
+    steps: usize,
+}
+
+pub fn explore(program: &Program, target: &Target, options: &SymbolicOptions) -> SymbolicReport {
+    /*
+     * Explores the paths of the program from the start, on a zeroed tape, until they reach the target.
+     * Every command is its own instruction, so any source position of a command can be a target.
+     * Extension operations (`$` included) do nothing here.
+     */
+
+    let bytecode = compile_with(&program.code, program.code_start, false);
+    let mut report = SymbolicReport { complete: true, ..SymbolicReport::default() };
+    let start = Path { instruction: 0, cursor: 0, tape: HashMap::new(), inputs: 0, constraints: Vec::new(), printed: 0, steps: 0 };
+    report.explored = 1;
+    if let Target::Output(expected) = target
+        && expected.is_empty() {
+        report.paths.push(reaching(start));
+        return report;
+    }
+    let mut pending = vec![start];
+    while let Some(mut path) = pending.pop() {
+        if options.first_only && !report.paths.is_empty() {
+            report.complete = false;
+            break;
+        }
+        loop {
+            if path.steps >= options.max_steps {
+                report.complete = false;
+                break;
+            }
+            let Some(&op) = bytecode.ops.get(path.instruction) else {
+                break; // Halted without reaching the target
+            };
+            if let Target::Position(position) = target
+                && bytecode.positions[path.instruction] == *position {
+                report.paths.push(reaching(path));
+                break;
+            }
+            path.steps += 1;
+            let cell = path.tape.get(&path.cursor).copied().unwrap_or_default();
+            match op {
+                Op::Add(_, value) => {
+                    let mut sum = cell;
+                    sum.constant = sum.constant.wrapping_add(value);
+                    path.tape.insert(path.cursor, sum);
+                },
+                Op::Move(delta) => path.cursor += delta,
+                Op::Input if path.inputs < options.max_inputs => {
+                    path.tape.insert(path.cursor, Expr { variable: Some(path.inputs), constant: 0 });
+                    path.inputs += 1;
+                },
+                Op::Input => {
+                    // Followed as if the input had ended, but longer inputs may go other ways
+                    path.tape.remove(&path.cursor);
+                    report.complete = false;
+                },
+                Op::Print => {
+                    let Target::Output(expected) = target else {
+                        path.instruction += 1;
+                        continue;
+                    };
+                    let mut difference = cell;
+                    difference.constant = difference.constant.wrapping_sub(expected[path.printed]);
+                    if !assume(&mut path, difference, true) {
+                        break;
+                    }
+                    path.printed += 1;
+                    if path.printed == expected.len() {
+                        report.paths.push(reaching(path));
+                        break;
+                    }
+                },
+                Op::JumpIfZero(after) | Op::JumpIfNotZero(after) => {
+                    // Jumps when the cell is 0 for `[`, and when it isn't for `]`
+                    let jumps_on_zero = matches!(op, Op::JumpIfZero(_));
+                    if cell.variable.is_some() {
+                        // Both ways are possible, the jump is explored later
+                        if report.explored < options.max_paths {
+                            let mut jumped = path.clone();
+                            if assume(&mut jumped, cell, jumps_on_zero) {
+                                jumped.instruction = after;
+                                report.explored += 1;
+                                pending.push(jumped);
+                            }
+                        }
+                        else {
+                            report.complete = false;
+                        }
+                        if !assume(&mut path, cell, !jumps_on_zero) {
+                            break;
+                        }
+                    }
+                    else if (cell.constant == 0) == jumps_on_zero {
+                        path.instruction = after;
+                        continue;
+                    }
+                },
+                Op::GetEnv | Op::Extension(_) | Op::Clear | Op::Scan(_) => {},
+            }
+            path.instruction += 1;
+        }
+    }
+    return report;
+}
+
+fn assume(path: &mut Path, expr: Expr, zero: bool) -> bool {
+    /*
+     * Adds the constraint that the expression is 0 (or isn't), returns false if the path is impossible then.
+     * An input byte known exactly becomes a constant in every cell, so later checks of it don't fork or repeat.
+     */
+
+    let Some(variable) = expr.variable else {
+        return (expr.constant == 0) == zero;
+    };
+    let constraint = Constraint { variable, value: expr.constant.wrapping_neg(), equal: zero };
+    if path.constraints.contains(&constraint) {
+        return true;
+    }
+    path.constraints.push(constraint);
+    if solve(&path.constraints, path.inputs).is_none() {
+        return false;
+    }
+    if zero {
+        for cell in path.tape.values_mut() {
+            if cell.variable == Some(variable) {
+                *cell = Expr { variable: None, constant: cell.constant.wrapping_add(constraint.value) };
+            }
+        }
+    }
+    return true;
+}
+
+fn reaching(path: Path) -> ReachingPath {
+    let input = solve(&path.constraints, path.inputs).unwrap_or_default();
+    return ReachingPath { constraints: path.constraints, input, steps: path.steps };
+}
+
+fn solve(constraints: &[Constraint], variables: usize) -> Option<Vec<u8>> {
+    // The smallest value of each variable satisfying every constraint on it, `None` if one has no such value
+    let mut allowed = vec![[true; 256]; variables];
+    for constraint in constraints {
+        let values = &mut allowed[constraint.variable];
+        for (value, allowed) in values.iter_mut().enumerate() {
+            *allowed &= (value == constraint.value as usize) == constraint.equal;
+        }
+    }
+    return allowed.iter().map(|values| values.iter().position(|&allowed| allowed).map(|value| value as u8)).collect();
+}
+
+pub fn solve_input(program: &Program, target: &[u8], options: &SymbolicOptions) -> Option<Vec<u8>> {
+    /*
+     * The shortest input (the smallest one among those) making the program print `target` first, `None` if no
+     * explored path does. Each candidate is checked by running the program on it, within `max_steps` steps,
+     * since extension operations may have changed what a path does.
+     */
+
+    let options = SymbolicOptions { first_only: false, ..options.clone() };
+    let mut inputs: Vec<Vec<u8>> = explore(program, &Target::Output(target.to_vec()), &options).paths.into_iter().map(|path| path.input).collect();
+    inputs.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    inputs.dedup();
+    return inputs.into_iter().find(|input| {
+        let mut state = new_bf_state_from_program(Arc::new(program.clone()));
+        set_input_reader(&mut state, Box::new(std::io::Cursor::new(input.clone())));
+        capture_output(&mut state);
+        let limits = ResourceLimits { max_steps: Some(options.max_steps as u64), ..ResourceLimits::default() };
+        return run_with_limits(&mut state, &limits).is_ok_and(|report| report.output.starts_with(target));
+    });
+}
@@ -0,0 +1,97 @@
+use crate::*;
+
+use std::collections::HashSet;
+
+/*
+ * Taint tracking: which cells hold values derived from the input, and which printed bytes depend on it.
+ * A cell gets tainted by reading input into it, and by any change made while a loop whose condition depended
+ * on a tainted cell is running, since how often the change happens then depends on the input
+ * (that's how `[->+<]` copies a value). Clearing a cell outside of such loops makes it clean again,
+ * and so does leaving a loop on it, which always happens at 0.
+ * The cursor isn't tracked, so cells reached through a scan of tainted cells aren't tainted by that alone,
+ * and extension operations don't taint anything.
+ */
+
+#[derive(Default)]
+pub(crate) struct Taint {
+    cells: HashSet<isize>, // Tainted cells
+    control: Vec<bool>, // For each loop being run, whether its condition depended on a tainted cell so far
+    printed: u64, // Bytes printed so far
+    output: Vec<u64>, // Indices of the printed bytes that depend on input
+}
+
+pub fn set_taint_tracking(state: &mut BFState, enabled: bool) {
+    /*
+     * Starts tracking taint from the current step on, or stops and forgets it.
+     * Every step goes through the interpreter while it's on, without loop tracing.
+     */
+
+    state.taint = enabled.then(Taint::default);
+}
+
+pub(crate) fn forget_tainted_cells(state: &mut BFState) {
+    // The tape was cleared, what was printed stays tainted
+    if let Some(taint) = &mut state.taint {
+        taint.cells.clear();
+        taint.control.clear();
+    }
+}
+
+pub fn is_tainted(state: &BFState, index: isize) -> bool {
+    return state.taint.as_ref().is_some_and(|taint| taint.cells.contains(&index));
+}
+
+pub fn tainted_cells(state: &BFState) -> Vec<isize> {
+    // In increasing order
+    let mut cells: Vec<isize> = state.taint.as_ref().map(|taint| taint.cells.iter().copied().collect()).unwrap_or_default();
+    cells.sort_unstable();
+    return cells;
+}
+
+pub fn tainted_output(state: &BFState) -> Vec<u64> {
+    // Indices of the printed bytes that depend on input, counting every `.` since tracking started
+    return state.taint.as_ref().map(|taint| taint.output.clone()).unwrap_or_default();
+}
+
+pub(crate) fn track(state: &mut BFState) {
+    // Called by `step_bf` before the step runs, since the cell values decide what it does
+    let Some(mut taint) = state.taint.take() else {
+        return;
+    };
+    let cursor = state.cursor_position;
+    let controlled = taint.control.contains(&true);
+    match state.program.bytecode.ops.get(state.instruction_position) {
+        Some(Op::Add(offset, _)) if controlled => {
+            taint.cells.insert(cursor + offset);
+        },
+        Some(Op::Input) => {
+            taint.cells.insert(cursor);
+        },
+        Some(Op::Clear) if controlled => {
+            taint.cells.insert(cursor);
+        },
+        Some(Op::Clear) => {
+            taint.cells.remove(&cursor);
+        },
+        Some(Op::Print) => {
+            if controlled || taint.cells.contains(&cursor) {
+                taint.output.push(taint.printed);
+            }
+            taint.printed += 1;
+        },
+        Some(Op::JumpIfZero(_)) if get_value_at(state, cursor) != 0 => taint.control.push(taint.cells.contains(&cursor)),
+        Some(Op::JumpIfNotZero(_)) if get_value_at(state, cursor) != 0 => {
+            if let Some(tainted) = taint.control.last_mut() {
+                *tainted |= taint.cells.contains(&cursor);
+            }
+        },
+        Some(Op::JumpIfNotZero(_)) => {
+            taint.control.pop();
+            if !taint.control.contains(&true) {
+                taint.cells.remove(&cursor);
+            }
+        },
+        _ => {},
+    }
+    state.taint = Some(taint);
+}
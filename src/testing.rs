@@ -0,0 +1,187 @@
+use crate::*;
+
+use proptest::prelude::*;
+use std::collections::BTreeMap;
+
+/*
+ * Differential testing against a deliberately simple reference interpreter (`testing` feature).
+ * The reference executes the source byte by byte without any compilation,
+ * so a difference in the output or the final memory points at a bug in the faster interpreter,
+ * or in an extension that was supposed to keep the original semantics.
+ */
+
+pub struct ReferenceRun {
+    pub output: Vec<u8>,
+    pub cursor: isize, // Index of the current memory cell when the run stopped
+    pub cells: BTreeMap<isize, u8>, // Memory cells that aren't 0
+    pub halted: bool, // False when the step limit was reached first
+}
+
+pub fn reference_run(code: &[u8], input: &[u8], max_steps: u64) -> ReferenceRun {
+    /*
+     * Same semantics as the default options:
+     * unbounded tape in both directions, wrapping cells, 0 on EOF,
+     * a leading shebang line and unmatched brackets are comments.
+     */
+
+    let mut run = ReferenceRun { output: Vec::new(), cursor: 0, cells: BTreeMap::new(), halted: false };
+    let mut ip = if code.starts_with(b"#!") {
+        code.iter().position(|&c| c == b'\n').map_or(code.len(), |i| i + 1)
+    } else {
+        0
+    };
+    let mut input = input.iter();
+    let mut steps: u64 = 0;
+
+    while ip < code.len() {
+        if steps >= max_steps {
+            return run;
+        }
+        let value = *run.cells.get(&run.cursor).unwrap_or(&0);
+        let mut new_value = None;
+        match code[ip] {
+            b'+' => new_value = Some(value.wrapping_add(1)),
+            b'-' => new_value = Some(value.wrapping_sub(1)),
+            b'>' => run.cursor += 1,
+            b'<' => run.cursor -= 1,
+            b'.' => run.output.push(value),
+            b',' => new_value = Some(*input.next().unwrap_or(&0)),
+            b'[' if value == 0 => {
+                if let Some(end) = find_block_end(code, ip) {
+                    ip = end;
+                }
+            },
+            b']' if value != 0 => {
+                if let Some(begin) = find_block_begin(code, ip) {
+                    ip = begin;
+                }
+            },
+            _ => {}
+        }
+        match new_value {
+            Some(0) => { run.cells.remove(&run.cursor); },
+            Some(new_value) => { run.cells.insert(run.cursor, new_value); },
+            None => {}
+        }
+        ip += 1;
+        steps += 1;
+    }
+
+    run.halted = true;
+    return run;
+}
+
+fn find_block_end(code: &[u8], begin: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &c) in code.iter().enumerate().skip(begin) {
+        match c {
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 { return Some(i); }
+            },
+            _ => {}
+        }
+    }
+    return None;
+}
+
+fn find_block_begin(code: &[u8], end: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &c) in code.iter().enumerate().take(end + 1).rev() {
+        match c {
+            b']' => depth += 1,
+            b'[' => {
+                depth -= 1;
+                if depth == 0 { return Some(i); }
+            },
+            _ => {}
+        }
+    }
+    return None;
+}
+
+pub fn compare_with_reference<F>(code: &str, input: &[u8], max_steps: u64, subject: F) -> Result<bool, String>
+where
+    F: FnOnce(&str, &[u8]) -> Vec<u8>,
+{
+    /*
+     * Compares the output of `subject` with the reference.
+     * Returns `Ok(false)` when the reference didn't halt in `max_steps`, so nothing was compared.
+     */
+
+    let reference = reference_run(code.as_bytes(), input, max_steps);
+    if !reference.halted {
+        return Ok(false);
+    }
+    let output = subject(code, input);
+    if output != reference.output {
+        return Err(format!("Output differs for {code:?}: expected {:?}, got {output:?}", reference.output));
+    }
+    return Ok(true);
+}
+
+pub fn check_interpreter(code: &str, input: &[u8], max_steps: u64) -> Result<bool, String> {
+    /*
+     * Compares the output, the cursor and the memory of this crate's interpreter with the reference.
+     * Code with unmatched brackets isn't compared, since `run_bf` refuses to run it.
+     */
+
+    let reference = reference_run(code.as_bytes(), input, max_steps);
+    if !reference.halted {
+        return Ok(false);
+    }
+
+    let mut state = new_bf_state(code);
+    set_input_reader(&mut state, Box::new(std::io::Cursor::new(input.to_vec())));
+    capture_output(&mut state);
+    // Every operation covers at least one instruction, so the same limit is enough
+    let report = match run_bf(&mut state, Some(max_steps), None) {
+        Ok(report) => report,
+        Err(BfError::Syntax { .. }) => return Ok(false), // `run_bf` rejects unmatched brackets
+        Err(error) => return Err(format!("{error} for {code:?}")),
+    };
+
+    if !matches!(report.outcome, RunOutcome::Halted) {
+        return Err(format!("Didn't halt for {code:?}"));
+    }
+    if report.output != reference.output {
+        return Err(format!("Output differs for {code:?}: expected {:?}, got {:?}", reference.output, report.output));
+    }
+    if report.final_cursor != reference.cursor {
+        return Err(format!("Cursor differs for {code:?}: expected {}, got {}", reference.cursor, report.final_cursor));
+    }
+    let (lowest, highest) = report.tape_extent;
+    for (&index, &value) in &reference.cells {
+        if index < lowest || index > highest || get_cell(&state, index) != value {
+            return Err(format!("Cell {index} differs for {code:?}: expected {value}, got {}", get_cell(&state, index)));
+        }
+    }
+    for index in lowest..=highest {
+        if get_cell(&state, index) != 0 && !reference.cells.contains_key(&index) {
+            return Err(format!("Cell {index} differs for {code:?}: expected 0, got {}", get_cell(&state, index)));
+        }
+    }
+    return Ok(true);
+}
+
+pub fn program_strategy(max_depth: u32, max_length: usize) -> impl Strategy<Value = String> {
+    // Programs with balanced brackets and no comments
+    let straight = prop::collection::vec(prop::sample::select(vec!['+', '-', '<', '>', '.', ',']), 0..max_length)
+        .prop_map(|ops| ops.into_iter().collect::<String>());
+    return straight.prop_recursive(max_depth, 256, 4, |inner| {
+        prop::collection::vec(prop_oneof![inner.clone(), inner.prop_map(|body| format!("[{body}]"))], 1..4)
+            .prop_map(|parts| parts.concat())
+    });
+}
+
+pub fn noisy_program_strategy(max_length: usize) -> impl Strategy<Value = String> {
+    // Any mix of instructions and comments, brackets don't have to be balanced
+    let alphabet = vec!['+', '-', '<', '>', '.', ',', '[', ']', ' ', '\n', 'a', '#', '!', '$'];
+    return prop::collection::vec(prop::sample::select(alphabet), 0..max_length)
+        .prop_map(|chars| chars.into_iter().collect::<String>());
+}
+
+pub fn input_strategy(max_length: usize) -> impl Strategy<Value = Vec<u8>> {
+    return prop::collection::vec(any::<u8>(), 0..max_length);
+}
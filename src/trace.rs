@@ -0,0 +1,147 @@
+use crate::*;
+
+/*
+ * Hot loop tracing: loops are interpreted as usual until they have repeated often enough,
+ * then their body is analyzed once. Linear loops, ones that only add to cells and end up
+ * where they started with the first cell counting down or up by 1 (`[->+>++<<]`),
+ * are from then on run in a single step by multiplying each addition by the number of iterations left.
+ * Their body may also clear other cells (`[->[-]+<]`), which then hold what the last iteration added after the clear.
+ * Anything else is marked as untraceable and keeps being interpreted.
+ */
+
+pub const DEFAULT_HOT_LOOP_THRESHOLD: u32 = 16;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) enum LoopTrace {
+    #[default]
+    Cold, // Not entered yet, or not a loop at all
+    Counting(u32), // Iterations seen so far
+    Linear { step: u8, additions: Vec<(isize, u8)>, sets: Vec<(isize, u8)>, low: isize, reach: isize }, // Counter step, added and final values by cell offset, lowest and highest visited offsets
+    Untraceable, // The body does more than adding and moving, always interpreted
+}
+
+pub fn set_hot_loop_threshold(state: &mut BFState, threshold: Option<u32>) {
+    /*
+     * Number of iterations after which a loop gets traced, `None` turns tracing off.
+     * A traced loop counts as a single step, like clear and scan loops do.
+     * Nothing is traced while an observer is set, so it still sees every iteration.
+     */
+
+    state.hot_loop_threshold = threshold.map(|threshold| threshold.max(1));
+    state.loop_traces.clear();
+}
+
+#[inline]
+pub(crate) fn run_hot_loop(state: &mut BFState, begin: usize) -> bool {
+    /*
+     * Called whenever the loop starting at `begin` is about to run its body.
+     * Returns true if the trace ran all the remaining iterations, the current cell is 0 then.
+     */
+
+    return match state.loop_traces.get(begin) {
+        Some(LoopTrace::Untraceable) => false,
+        Some(LoopTrace::Linear { .. }) if !state.loops && !is_observed(state) => run_linear_loop(state, begin),
+        Some(LoopTrace::Linear { .. }) => false,
+        _ => count_iteration(state, begin),
+    };
+}
+
+fn count_iteration(state: &mut BFState, begin: usize) -> bool {
+    // Traces the loop once it's hot, and runs it right away if it's linear
+    let Some(threshold) = state.hot_loop_threshold else {
+        return false;
+    };
+    if state.loops || is_observed(state) {
+        return false;
+    }
+    if state.loop_traces.len() != state.program.bytecode.ops.len() {
+        state.loop_traces.resize(state.program.bytecode.ops.len(), LoopTrace::Cold);
+    }
+
+    let iterations = match state.loop_traces[begin] {
+        LoopTrace::Counting(iterations) => iterations + 1,
+        _ => 1,
+    };
+    if iterations < threshold {
+        state.loop_traces[begin] = LoopTrace::Counting(iterations);
+        return false;
+    }
+    let Op::JumpIfZero(after) = state.program.bytecode.ops[begin] else {
+        return false;
+    };
+    state.loop_traces[begin] = analyze_loop(&state.program.bytecode.ops[begin + 1..after - 1]);
+    trace_event!(DEBUG, begin, trace = ?state.loop_traces[begin], "hot loop analyzed");
+    return matches!(state.loop_traces[begin], LoopTrace::Linear { .. }) && run_linear_loop(state, begin);
+}
+
+fn run_linear_loop(state: &mut BFState, begin: usize) -> bool {
+    let trace = std::mem::take(&mut state.loop_traces[begin]);
+    let LoopTrace::Linear { step, additions, sets, low, reach } = &trace else {
+        state.loop_traces[begin] = trace;
+        return false;
+    };
+    // The counter reaches 0 after `-counter / step` iterations, with wrapping arithmetic
+    if !cells_within_tape_limit(state, state.cursor_position + low, state.cursor_position + reach) {
+        // Interpreted instead, so the limit stops the program at the right step
+        state.loop_traces[begin] = trace;
+        return false;
+    }
+    let counter = current_value(state);
+    let iterations = if *step == 1 { counter.wrapping_neg() } else { counter };
+    for &(offset, value) in additions {
+        let index = state.cursor_position + offset;
+        let cell = get_value_at(state, index);
+        set_value_at(state, index, cell.wrapping_add(value.wrapping_mul(iterations)));
+    }
+    for &(offset, value) in sets {
+        // There's at least one iteration left, the counter isn't 0
+        set_value_at(state, state.cursor_position + offset, value);
+    }
+    set_value_at(state, state.cursor_position, 0);
+    state.highest_cursor = state.highest_cursor.max(state.cursor_position + reach);
+    state.lowest_cursor = state.lowest_cursor.min(state.cursor_position + low);
+    state.loop_traces[begin] = trace;
+    return true;
+}
+
+fn analyze_loop(body: &[Op]) -> LoopTrace {
+    let mut offset: isize = 0;
+    let (mut low, mut reach): (isize, isize) = (0, 0);
+    let mut additions: Vec<(isize, u8)> = Vec::new();
+    let mut sets: Vec<(isize, u8)> = Vec::new(); // Cells cleared in the body, with what is added to them afterwards
+    for op in body {
+        match *op {
+            Op::Add(cell_offset, value) => {
+                let cell = offset + cell_offset;
+                (low, reach) = (low.min(cell), reach.max(cell));
+                match sets.iter_mut().chain(additions.iter_mut()).find(|(added, _)| *added == cell) {
+                    Some((_, total)) => *total = total.wrapping_add(value),
+                    None => additions.push((cell, value)),
+                }
+            },
+            Op::Clear if offset != 0 => {
+                // Whatever was added to the cell so far in the iteration is lost
+                additions.retain(|&(added, _)| added != offset);
+                sets.retain(|&(set, _)| set != offset);
+                sets.push((offset, 0));
+            },
+            Op::Move(delta) => {
+                offset += delta;
+                (low, reach) = (low.min(offset), reach.max(offset));
+            },
+            _ => return LoopTrace::Untraceable,
+        }
+    }
+    if offset != 0 {
+        return LoopTrace::Untraceable;
+    }
+
+    let Some(counter) = additions.iter().position(|(cell, _)| *cell == 0) else {
+        return LoopTrace::Untraceable;
+    };
+    let (_, step) = additions.remove(counter);
+    if step != 1 && step != 255 {
+        return LoopTrace::Untraceable;
+    }
+    return LoopTrace::Linear { step, additions, sets, low, reach };
+}
@@ -0,0 +1,208 @@
+use crate::{Bytecode, Op};
+
+/*
+ * Turns compiled code into source code of other languages.
+ * The generated programs have a fixed-size tape with the cursor starting in the middle,
+ * and read 0 on EOF like the interpreter. The `$` extension is left out.
+ */
+
+const C_TAPE_SIZE: usize = 1 << 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssemblyTarget {
+    Linux, // ELF, for ARM servers and boards
+    MacOs, // Mach-O, for Apple Silicon
+}
+
+pub fn to_c(bytecode: &Bytecode) -> String {
+    let mut c = String::from("#include <stdio.h>\n\n");
+    c.push_str(&format!("static unsigned char tape[{C_TAPE_SIZE}];\n\n"));
+    c.push_str("int main(void) {\n");
+    c.push_str(&format!("    unsigned char *p = tape + {};\n", C_TAPE_SIZE / 2));
+    c.push_str("    int c;\n");
+    let mut depth = 1;
+    for op in &bytecode.ops {
+        if let Op::JumpIfNotZero(_) = op {
+            depth -= 1;
+        }
+        let statement = match *op {
+            Op::Add(0, value) => format!("*p += {value};"),
+            Op::Add(offset, value) => format!("p[{offset}] += {value};"),
+            Op::Move(delta) => format!("p += {delta};"),
+            Op::Print => String::from("putchar(*p);"),
+            Op::Input => String::from("c = getchar(); *p = c == EOF ? 0 : c;"),
+            Op::Clear => String::from("*p = 0;"),
+            Op::Scan(delta) => format!("while (*p) p += {delta};"),
+            Op::JumpIfZero(_) => String::from("while (*p) {"),
+            Op::JumpIfNotZero(_) => String::from("}"),
+            Op::GetEnv | Op::Extension(_) => continue,
+        };
+        c.push_str(&"    ".repeat(depth));
+        c.push_str(&statement);
+        c.push('\n');
+        if let Op::JumpIfZero(_) = op {
+            depth += 1;
+        }
+    }
+    c.push_str("    return 0;\n}\n");
+    return c;
+}
+
+pub fn to_aarch64(bytecode: &Bytecode, target: AssemblyTarget) -> String {
+    /*
+     * GNU-style AArch64 assembly calling `putchar` and `getchar` from the C library,
+     * to be assembled and linked with `cc`. The tape is as big as the C one,
+     * and `x19` holds the cursor since it's preserved across calls.
+     * This is for building ahead of time; `--jit` compiles for AArch64 at runtime through Cranelift instead.
+     */
+
+    let (symbol, local, tape_page, tape_offset) = match target {
+        AssemblyTarget::Linux => ("", ".L", "tape", ":lo12:tape"),
+        AssemblyTarget::MacOs => ("_", "L", "tape@PAGE", "tape@PAGEOFF"),
+    };
+    let mut asm = String::new();
+    match target {
+        AssemblyTarget::Linux => asm.push_str(&format!("    .bss\n    .p2align 4\ntape:\n    .zero {C_TAPE_SIZE}\n\n")),
+        AssemblyTarget::MacOs => asm.push_str(&format!("    .zerofill __DATA,__bss,tape,{C_TAPE_SIZE},4\n\n")),
+    }
+    asm.push_str(&format!("    .text\n    .globl {symbol}main\n    .p2align 2\n{symbol}main:\n"));
+    asm.push_str("    stp x29, x30, [sp, #-16]!\n");
+    asm.push_str("    mov x29, sp\n");
+    asm.push_str("    stp x19, x20, [sp, #-16]!\n");
+    asm.push_str(&format!("    adrp x19, {tape_page}\n    add x19, x19, {tape_offset}\n"));
+    asm.push_str(&aarch64_move(isize::try_from(C_TAPE_SIZE / 2).unwrap()));
+    for (i, op) in bytecode.ops.iter().enumerate() {
+        match *op {
+            Op::Add(offset, value) => {
+                // Offsets up to 4095 fit in the load and store, others are added to the cursor in `x1`
+                let cell = match offset {
+                    0 => String::from("[x19]"),
+                    1..4096 => format!("[x19, #{offset}]"),
+                    _ => {
+                        asm.push_str(&aarch64_add("x1", "x19", offset));
+                        String::from("[x1]")
+                    },
+                };
+                asm.push_str(&format!("    ldrb w0, {cell}\n    add w0, w0, #{value}\n    strb w0, {cell}\n"));
+            },
+            Op::Move(delta) => asm.push_str(&aarch64_move(delta)),
+            Op::Print => asm.push_str(&format!("    ldrb w0, [x19]\n    bl {symbol}putchar\n")),
+            Op::Input => {
+                asm.push_str(&format!("    bl {symbol}getchar\n"));
+                asm.push_str("    cmn w0, #1\n    csel w0, wzr, w0, eq\n    strb w0, [x19]\n");
+            },
+            Op::Clear => asm.push_str("    strb wzr, [x19]\n"),
+            Op::Scan(delta) => {
+                asm.push_str(&format!("{local}scan{i}:\n    ldrb w0, [x19]\n    cbz w0, {local}after{i}\n"));
+                asm.push_str(&aarch64_move(delta));
+                asm.push_str(&format!("    b {local}scan{i}\n{local}after{i}:\n"));
+            },
+            Op::JumpIfZero(_) => asm.push_str(&format!("    ldrb w0, [x19]\n    cbz w0, {local}after{i}\n{local}body{i}:\n")),
+            Op::JumpIfNotZero(target) => {
+                let begin = target - 1;
+                asm.push_str(&format!("    ldrb w0, [x19]\n    cbnz w0, {local}body{begin}\n{local}after{begin}:\n"));
+            },
+            Op::GetEnv | Op::Extension(_) => {},
+        }
+    }
+    asm.push_str("    mov w0, #0\n");
+    asm.push_str("    ldp x19, x20, [sp], #16\n");
+    asm.push_str("    ldp x29, x30, [sp], #16\n");
+    asm.push_str("    ret\n");
+    return asm;
+}
+
+fn aarch64_move(delta: isize) -> String {
+    return aarch64_add("x19", "x19", delta);
+}
+
+fn aarch64_add(destination: &str, source: &str, delta: isize) -> String {
+    // Immediates only have 12 bits, optionally shifted by 12, which covers any move within the tape
+    let instruction = if delta < 0 { "sub" } else { "add" };
+    let distance = delta.unsigned_abs();
+    let mut asm = String::new();
+    let mut source = source;
+    if distance >> 12 != 0 {
+        asm.push_str(&format!("    {instruction} {destination}, {source}, #{}, lsl #12\n", distance >> 12 & 0xfff));
+        source = destination;
+    }
+    if distance & 0xfff != 0 || source != destination {
+        asm.push_str(&format!("    {instruction} {destination}, {source}, #{}\n", distance & 0xfff));
+    }
+    return asm;
+}
+
+pub fn to_llvm(bytecode: &Bytecode) -> String {
+    /*
+     * LLVM IR calling `putchar` and `getchar`, meant to be compiled with `clang -O3`.
+     * The cursor lives in a stack slot so the IR doesn't need phi nodes, LLVM promotes it to a register.
+     */
+
+    let mut ir = format!("@tape = internal global [{C_TAPE_SIZE} x i8] zeroinitializer\n\n");
+    ir.push_str("declare i32 @putchar(i32)\ndeclare i32 @getchar()\n\n");
+    ir.push_str("define i32 @main() {\nentry:\n");
+    ir.push_str("  %p = alloca ptr\n");
+    ir.push_str(&format!("  %start = getelementptr inbounds [{C_TAPE_SIZE} x i8], ptr @tape, i64 0, i64 {}\n", C_TAPE_SIZE / 2));
+    ir.push_str("  store ptr %start, ptr %p\n");
+    for (i, op) in bytecode.ops.iter().enumerate() {
+        match *op {
+            Op::Add(offset, value) => {
+                ir.push_str(&format!("  %cursor{i} = load ptr, ptr %p\n  %cell{i} = getelementptr i8, ptr %cursor{i}, i64 {offset}\n"));
+                ir.push_str(&format!("  %value{i} = load i8, ptr %cell{i}\n"));
+                ir.push_str(&format!("  %sum{i} = add i8 %value{i}, {value}\n  store i8 %sum{i}, ptr %cell{i}\n"));
+            },
+            Op::Move(delta) => ir.push_str(&llvm_move(i, delta)),
+            Op::Print => {
+                ir.push_str(&format!("  %cell{i} = load ptr, ptr %p\n  %value{i} = load i8, ptr %cell{i}\n"));
+                ir.push_str(&format!("  %char{i} = zext i8 %value{i} to i32\n  call i32 @putchar(i32 %char{i})\n"));
+            },
+            Op::Input => {
+                ir.push_str(&format!("  %char{i} = call i32 @getchar()\n  %eof{i} = icmp eq i32 %char{i}, -1\n"));
+                ir.push_str(&format!("  %read{i} = select i1 %eof{i}, i32 0, i32 %char{i}\n  %value{i} = trunc i32 %read{i} to i8\n"));
+                ir.push_str(&format!("  %cell{i} = load ptr, ptr %p\n  store i8 %value{i}, ptr %cell{i}\n"));
+            },
+            Op::Clear => ir.push_str(&format!("  %cell{i} = load ptr, ptr %p\n  store i8 0, ptr %cell{i}\n")),
+            Op::Scan(delta) => {
+                ir.push_str(&format!("  br label %scan{i}\nscan{i}:\n"));
+                ir.push_str(&llvm_branch(i, &format!("step{i}"), &format!("after{i}")));
+                ir.push_str(&format!("step{i}:\n"));
+                ir.push_str(&llvm_move(i, delta));
+                ir.push_str(&format!("  br label %scan{i}\nafter{i}:\n"));
+            },
+            Op::JumpIfZero(_) => {
+                ir.push_str(&llvm_branch(i, &format!("body{i}"), &format!("after{i}")));
+                ir.push_str(&format!("body{i}:\n"));
+            },
+            Op::JumpIfNotZero(target) => {
+                let begin = target - 1;
+                ir.push_str(&llvm_branch(i, &format!("body{begin}"), &format!("after{begin}")));
+                ir.push_str(&format!("after{begin}:\n"));
+            },
+            Op::GetEnv | Op::Extension(_) => {},
+        }
+    }
+    ir.push_str("  ret i32 0\n}\n");
+    return ir;
+}
+
+fn llvm_move(i: usize, delta: isize) -> String {
+    let mut ir = format!("  %from{i} = load ptr, ptr %p\n");
+    ir.push_str(&format!("  %to{i} = getelementptr i8, ptr %from{i}, i64 {delta}\n  store ptr %to{i}, ptr %p\n"));
+    return ir;
+}
+
+fn llvm_branch(i: usize, nonzero: &str, zero: &str) -> String {
+    // Branches on the current cell, `i` is the index of the operation, to keep the names unique
+    let mut ir = format!("  %test_cell{i} = load ptr, ptr %p\n  %test_value{i} = load i8, ptr %test_cell{i}\n");
+    ir.push_str(&format!("  %test{i} = icmp ne i8 %test_value{i}, 0\n  br i1 %test{i}, label %{nonzero}, label %{zero}\n"));
+    return ir;
+}
+
+pub fn to_listing(bytecode: &Bytecode) -> String {
+    // One operation per line, with the source position it was compiled from
+    let mut listing = String::new();
+    for (i, (op, position)) in bytecode.ops.iter().zip(&bytecode.positions).enumerate() {
+        listing.push_str(&format!("{i}\t{position}\t{op:?}\n"));
+    }
+    return listing;
+}
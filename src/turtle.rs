@@ -0,0 +1,202 @@
+use crate::*;
+
+/*
+ * Turtle graphics driven by the output: the program prints commands, and the drawing is rendered once it ends.
+ * A command is a letter, optionally followed by a decimal number; anything else separates commands.
+ *
+ * `F<n>` moves forward n units (10 by default), drawing a line while the pen is down
+ * `B<n>` moves backward the same way
+ * `R<n>` and `L<n>` turn right and left by n degrees (90 by default)
+ * `U` and `D` lift and lower the pen (it starts down)
+ * `C<n>` picks color n of the palette (black, red, green, blue, orange, purple, cyan and gray)
+ *
+ * The turtle starts at the origin heading up; the canvas is fitted around whatever was drawn.
+ */
+
+const PALETTE: [[u8; 3]; 8] = [
+    [0, 0, 0],
+    [220, 40, 40],
+    [40, 160, 60],
+    [40, 80, 220],
+    [240, 150, 20],
+    [140, 60, 180],
+    [20, 170, 190],
+    [128, 128, 128],
+];
+const MARGIN: f64 = 10.0; // Empty space around the drawing, in units
+#[cfg(feature = "images")]
+const MAX_PNG_SIDE: f64 = 16384.0; // Pixels, larger drawings only fit in an SVG
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CanvasFormat {
+    Svg,
+    Png, // Needs the `images` feature
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Line {
+    pub from: (f64, f64),
+    pub to: (f64, f64), // Y grows downwards, like in images
+    pub color: [u8; 3],
+}
+
+#[derive(Clone, Debug)]
+pub struct Turtle {
+    position: (f64, f64),
+    heading: f64, // Degrees clockwise from up
+    pen_down: bool,
+    color: [u8; 3],
+    lines: Vec<Line>,
+}
+
+impl Default for Turtle {
+    fn default() -> Self {
+        return Turtle { position: (0.0, 0.0), heading: 0.0, pen_down: true, color: PALETTE[0], lines: Vec::new() };
+    }
+}
+
+impl Turtle {
+    pub fn command(&mut self, letter: u8, argument: Option<u32>) {
+        // Runs a single command, unknown letters are ignored
+        let argument = argument.map(f64::from);
+        match letter {
+            b'F' => self.forward(argument.unwrap_or(10.0)),
+            b'B' => self.forward(-argument.unwrap_or(10.0)),
+            b'R' => self.heading = (self.heading + argument.unwrap_or(90.0)) % 360.0,
+            b'L' => self.heading = (self.heading - argument.unwrap_or(90.0)).rem_euclid(360.0),
+            b'U' => self.pen_down = false,
+            b'D' => self.pen_down = true,
+            b'C' => self.color = PALETTE[argument.unwrap_or(0.0) as usize % PALETTE.len()],
+            _ => {},
+        }
+    }
+
+    fn forward(&mut self, distance: f64) {
+        let (sin, cos) = self.heading.to_radians().sin_cos();
+        let to = (self.position.0 + distance * sin, self.position.1 - distance * cos);
+        if self.pen_down && distance != 0.0 {
+            self.lines.push(Line { from: self.position, to, color: self.color });
+        }
+        self.position = to;
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        return &self.lines;
+    }
+
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        // Left, top, width and height of the canvas, with the margin
+        let points = self.lines.iter().flat_map(|line| [line.from, line.to]);
+        let (mut left, mut top, mut right, mut bottom) = (0.0f64, 0.0f64, 0.0f64, 0.0f64);
+        for (x, y) in points {
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x);
+            bottom = bottom.max(y);
+        }
+        return (left - MARGIN, top - MARGIN, right - left + 2.0 * MARGIN, bottom - top + 2.0 * MARGIN);
+    }
+
+    pub fn to_svg(&self) -> String {
+        let (left, top, width, height) = self.bounds();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{left:.2} {top:.2} {width:.2} {height:.2}\" \
+             width=\"{width:.0}\" height=\"{height:.0}\">\n"
+        );
+        svg.push_str(&format!("<rect x=\"{left:.2}\" y=\"{top:.2}\" width=\"{width:.2}\" height=\"{height:.2}\" fill=\"white\"/>\n"));
+        for line in &self.lines {
+            let [red, green, blue] = line.color;
+            svg.push_str(&format!(
+                "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"#{red:02x}{green:02x}{blue:02x}\" stroke-linecap=\"round\"/>\n",
+                line.from.0, line.from.1, line.to.0, line.to.1,
+            ));
+        }
+        svg.push_str("</svg>\n");
+        return svg;
+    }
+
+    #[cfg(feature = "images")]
+    pub fn to_png(&self) -> Result<Vec<u8>, BfError> {
+        // One pixel per unit, lines are drawn a pixel wide
+        let (left, top, width, height) = self.bounds();
+        if width > MAX_PNG_SIDE || height > MAX_PNG_SIDE {
+            return Err(BfError::Format(format!("The drawing is too large for a PNG ({width:.0}x{height:.0} pixels)")));
+        }
+        let (width, height) = (width.ceil() as usize, height.ceil() as usize);
+        let mut pixels = vec![[255; 3]; width * height];
+        for line in &self.lines {
+            let (x0, y0) = (line.from.0 - left, line.from.1 - top);
+            let (x1, y1) = (line.to.0 - left, line.to.1 - top);
+            let steps = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0) as usize;
+            for step in 0..=steps {
+                let t = step as f64 / steps as f64;
+                let (x, y) = ((x0 + (x1 - x0) * t).round() as usize, (y0 + (y1 - y0) * t).round() as usize);
+                if x < width && y < height {
+                    pixels[y * width + x] = line.color;
+                }
+            }
+        }
+        return image::write_pixels(width, height, &pixels);
+    }
+}
+
+pub struct TurtleWriter {
+    inner: Box<dyn Write + Send>,
+    format: CanvasFormat,
+    turtle: Turtle,
+    pending: Option<(u8, Option<u32>)>, // The command being printed, its number can still continue
+    finished: bool,
+}
+
+impl TurtleWriter {
+    pub fn new(inner: Box<dyn Write + Send>, format: CanvasFormat) -> Self {
+        return TurtleWriter { inner, format, turtle: Turtle::default(), pending: None, finished: false };
+    }
+
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        // Runs the last command and writes the drawing, called when dropped as well
+        if let Some((letter, argument)) = self.pending.take() {
+            self.turtle.command(letter, argument);
+        }
+        if !std::mem::replace(&mut self.finished, true) {
+            let canvas = match self.format {
+                CanvasFormat::Svg => self.turtle.to_svg().into_bytes(),
+                #[cfg(feature = "images")]
+                CanvasFormat::Png => self.turtle.to_png().map_err(std::io::Error::other)?,
+                #[cfg(not(feature = "images"))]
+                CanvasFormat::Png => return Err(std::io::Error::other("PNG canvases need the `images` feature")),
+            };
+            self.inner.write_all(&canvas)?;
+        }
+        return self.inner.flush();
+    }
+}
+
+impl Write for TurtleWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for &c in buf {
+            if c.is_ascii_digit() && let Some((_, argument)) = &mut self.pending {
+                let digit = u32::from(c - b'0');
+                *argument = Some(argument.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                continue;
+            }
+            if let Some((letter, argument)) = self.pending.take() {
+                self.turtle.command(letter, argument);
+            }
+            if c.is_ascii_alphabetic() {
+                self.pending = Some((c, None));
+            }
+        }
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return Ok(());
+    }
+}
+
+impl Drop for TurtleWriter {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
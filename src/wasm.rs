@@ -0,0 +1,206 @@
+use crate::{Bytecode, Op};
+
+/*
+ * Standalone WebAssembly modules, as the binary format or as text (WAT).
+ * The module imports its I/O from the host and exports its memory and a `run` function:
+ *
+ * (import "env" "putchar" (func (param i32)))   ; prints a byte
+ * (import "env" "getchar" (func (result i32)))  ; reads a byte, -1 on EOF (stored as 0)
+ *
+ * The memory is the tape (1 MiB, the cursor starting in the middle); leaving it traps.
+ * The `$` extension is left out.
+ */
+
+const WASM_PAGES: u32 = 16; // 64 KiB each
+const WASM_TAPE_START: i32 = 1 << 19;
+
+// Local 0 is the cursor, local 1 holds the byte that was read
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Instruction {
+    Block,
+    Loop,
+    End,
+    Br(u32),
+    BrIf(u32),
+    Call(u32),
+    Select,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    Load8,
+    Store8,
+    Const(i32),
+    Eqz,
+    Ne,
+    Add,
+}
+
+fn lower(bytecode: &Bytecode) -> Vec<Instruction> {
+    use Instruction::*;
+
+    let move_cursor = |delta: isize| [LocalGet(0), Const(i32::try_from(delta).unwrap_or(i32::MAX)), Add, LocalSet(0)];
+    let mut instructions = vec![Const(WASM_TAPE_START), LocalSet(0)];
+    for op in &bytecode.ops {
+        match *op {
+            Op::Add(0, value) => instructions.extend([LocalGet(0), LocalGet(0), Load8, Const(i32::from(value)), Add, Store8]),
+            Op::Add(offset, value) => {
+                let offset = Const(i32::try_from(offset).unwrap_or(i32::MAX));
+                instructions.extend([LocalGet(0), offset, Add, LocalGet(0), offset, Add, Load8, Const(i32::from(value)), Add, Store8]);
+            },
+            Op::Move(delta) => instructions.extend(move_cursor(delta)),
+            Op::Print => instructions.extend([LocalGet(0), Load8, Call(0)]),
+            Op::Input => instructions.extend([LocalGet(0), Call(1), LocalTee(1), Const(0), LocalGet(1), Const(-1), Ne, Select, Store8]),
+            Op::Clear => instructions.extend([LocalGet(0), Const(0), Store8]),
+            Op::Scan(delta) => {
+                instructions.extend([Block, Loop, LocalGet(0), Load8, Eqz, BrIf(1)]);
+                instructions.extend(move_cursor(delta));
+                instructions.extend([Br(0), End, End]);
+            },
+            Op::JumpIfZero(_) => instructions.extend([Block, Loop, LocalGet(0), Load8, Eqz, BrIf(1)]),
+            Op::JumpIfNotZero(_) => instructions.extend([Br(0), End, End]),
+            Op::GetEnv | Op::Extension(_) => {},
+        }
+    }
+    return instructions;
+}
+
+pub fn to_wat(bytecode: &Bytecode) -> String {
+    let mut wat = String::from("(module\n");
+    wat.push_str("  (import \"env\" \"putchar\" (func $putchar (param i32)))\n");
+    wat.push_str("  (import \"env\" \"getchar\" (func $getchar (result i32)))\n");
+    wat.push_str(&format!("  (memory (export \"memory\") {WASM_PAGES})\n"));
+    wat.push_str("  (func (export \"run\") (local $cursor i32) (local $read i32)\n");
+    let mut depth = 2;
+    for instruction in lower(bytecode) {
+        if instruction == Instruction::End {
+            depth -= 1;
+        }
+        let text = match instruction {
+            Instruction::Block => String::from("block"),
+            Instruction::Loop => String::from("loop"),
+            Instruction::End => String::from("end"),
+            Instruction::Br(depth) => format!("br {depth}"),
+            Instruction::BrIf(depth) => format!("br_if {depth}"),
+            Instruction::Call(0) => String::from("call $putchar"),
+            Instruction::Call(_) => String::from("call $getchar"),
+            Instruction::Select => String::from("select"),
+            Instruction::LocalGet(local) => format!("local.get {}", wat_local(local)),
+            Instruction::LocalSet(local) => format!("local.set {}", wat_local(local)),
+            Instruction::LocalTee(local) => format!("local.tee {}", wat_local(local)),
+            Instruction::Load8 => String::from("i32.load8_u"),
+            Instruction::Store8 => String::from("i32.store8"),
+            Instruction::Const(value) => format!("i32.const {value}"),
+            Instruction::Eqz => String::from("i32.eqz"),
+            Instruction::Ne => String::from("i32.ne"),
+            Instruction::Add => String::from("i32.add"),
+        };
+        wat.push_str(&"  ".repeat(depth));
+        wat.push_str(&text);
+        wat.push('\n');
+        if matches!(instruction, Instruction::Block | Instruction::Loop) {
+            depth += 1;
+        }
+    }
+    wat.push_str("  )\n)\n");
+    return wat;
+}
+
+fn wat_local(local: u32) -> &'static str {
+    return if local == 0 { "$cursor" } else { "$read" };
+}
+
+pub fn to_wasm(bytecode: &Bytecode) -> Vec<u8> {
+    let mut code: Vec<u8> = vec![1, 2, 0x7f]; // One group of locals: 2 x i32
+    for instruction in lower(bytecode) {
+        match instruction {
+            Instruction::Block => code.extend([0x02, 0x40]),
+            Instruction::Loop => code.extend([0x03, 0x40]),
+            Instruction::End => code.push(0x0b),
+            Instruction::Br(depth) => push_instruction(&mut code, 0x0c, depth),
+            Instruction::BrIf(depth) => push_instruction(&mut code, 0x0d, depth),
+            Instruction::Call(function) => push_instruction(&mut code, 0x10, function),
+            Instruction::Select => code.push(0x1b),
+            Instruction::LocalGet(local) => push_instruction(&mut code, 0x20, local),
+            Instruction::LocalSet(local) => push_instruction(&mut code, 0x21, local),
+            Instruction::LocalTee(local) => push_instruction(&mut code, 0x22, local),
+            Instruction::Load8 => code.extend([0x2d, 0, 0]), // Alignment and offset
+            Instruction::Store8 => code.extend([0x3a, 0, 0]),
+            Instruction::Const(value) => {
+                code.push(0x41);
+                push_signed(&mut code, value);
+            },
+            Instruction::Eqz => code.push(0x45),
+            Instruction::Ne => code.push(0x47),
+            Instruction::Add => code.push(0x6a),
+        }
+    }
+    code.push(0x0b);
+
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+    // Types: putchar, getchar and run
+    push_section(&mut module, 1, &[3, 0x60, 1, 0x7f, 0, 0x60, 0, 1, 0x7f, 0x60, 0, 0]);
+    let mut imports = vec![2];
+    for (name, signature) in [("putchar", 0), ("getchar", 1)] {
+        push_name(&mut imports, "env");
+        push_name(&mut imports, name);
+        imports.extend([0, signature]);
+    }
+    push_section(&mut module, 2, &imports);
+    push_section(&mut module, 3, &[1, 2]);
+    let mut memory = vec![1, 0];
+    push_unsigned(&mut memory, WASM_PAGES);
+    push_section(&mut module, 5, &memory);
+    let mut exports = vec![2];
+    push_name(&mut exports, "memory");
+    exports.extend([2, 0]);
+    push_name(&mut exports, "run");
+    exports.extend([0, 2]); // The imported functions come first
+    push_section(&mut module, 7, &exports);
+    let mut body = vec![1];
+    push_unsigned(&mut body, u32::try_from(code.len()).unwrap());
+    body.extend(code);
+    push_section(&mut module, 10, &body);
+    return module;
+}
+
+fn push_instruction(code: &mut Vec<u8>, opcode: u8, immediate: u32) {
+    code.push(opcode);
+    push_unsigned(code, immediate);
+}
+
+fn push_section(module: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    module.push(id);
+    push_unsigned(module, u32::try_from(contents.len()).unwrap());
+    module.extend(contents);
+}
+
+fn push_name(bytes: &mut Vec<u8>, name: &str) {
+    push_unsigned(bytes, u32::try_from(name.len()).unwrap());
+    bytes.extend(name.as_bytes());
+}
+
+fn push_unsigned(bytes: &mut Vec<u8>, mut value: u32) {
+    // LEB128
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn push_signed(bytes: &mut Vec<u8>, mut value: i32) {
+    // Signed LEB128, done when the remaining bits are all copies of the sign bit
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
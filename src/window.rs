@@ -0,0 +1,30 @@
+use crate::*;
+
+/*
+ * Memory passed between a host and a program, for games and simulations running a script every frame:
+ * the host keeps its own buffer (a sprite's state, a frame of input...) and gives it to each run as a fixed window
+ * of the tape, without packing the cells itself. The tape is an owned `Vec`, so the buffer can't be shared with it:
+ * it's copied into the cells before the run and back after it,
+ * so between runs the window and the buffer always agree; each copy is a single `memcpy` for windows
+ * at non-negative cells. The rest of the tape, the cursor and the input carry over from one run to the next.
+ */
+
+pub fn run_with_window(state: &mut BFState, window: &mut [u8], origin: isize, limits: &ResourceLimits) -> Result<RunReport, BfError> {
+    /*
+     * Runs the program with `window` as the cells from `origin` on. Afterwards (even when the run failed) the buffer
+     * holds what the program left in those cells. To run the script again for the next frame, `load_code` it again.
+     * The limits are applied before the window is loaded, so a window past `max_tape_cells` fails without running.
+     */
+
+    let Some(end) = isize::try_from(window.len()).ok().and_then(|length| origin.checked_add(length)) else {
+        return Err(BfError::Runtime(String::from("The window doesn't fit in the tape")));
+    };
+    apply_limits(state, limits);
+    load_tape(state, window, origin)?;
+    let result = run_with_limits(state, limits);
+    match usize::try_from(origin).ok().and_then(|start| state.ptape.get(start..start + window.len())) {
+        Some(cells) => window.copy_from_slice(cells),
+        None => window.copy_from_slice(&export_tape(state, origin..end)),
+    }
+    return result;
+}
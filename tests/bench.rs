@@ -0,0 +1,9 @@
+use brainfuckers::bench::*;
+
+#[test]
+fn instruction_count_counts_source_instructions() {
+    // 4 additions, the first `[`, then 4 iterations of `>+++<-` and `]`; the optimizer turns the loop into one operation
+    assert_eq!(instruction_count("++++[>+++<-]", b"").unwrap(), 4 + 1 + 4 * 7);
+    assert_eq!(instruction_count("#!/usr/bin/env bf\n,[.,] comments aren't counted", b"ab").unwrap(), 1 + 2 * 3 + 1);
+    assert!(instruction_count("+]", b"").is_err());
+}
@@ -0,0 +1,128 @@
+use brainfuckers::*;
+
+const CODE: &str = "++>+++>+<[-]";
+const PRINTING: &str = "+++++[>++++++++++<-]>.+.+.<,[>.<-]"; // Prints `234`, then `4` as many times as the input byte
+
+fn checkpoint_after(steps: u64) -> Vec<u8> {
+    let mut state = new_bf_state(CODE);
+    capture_output(&mut state);
+    run_bf(&mut state, Some(steps), None).unwrap();
+    return save_checkpoint(&mut state, steps).unwrap();
+}
+
+fn with_position(bytes: &[u8], cursor: &str, highest: &str) -> Vec<u8> {
+    // The checkpoint with another cursor in its `position` section
+    let text = String::from_utf8(bytes.to_vec()).unwrap();
+    let start = text.find("position ").unwrap();
+    let end = start + text[start..].find('\n').unwrap();
+    let fields: Vec<&str> = text[start..end].split(' ').collect();
+    let position = format!("position {} {cursor} {highest} 0", fields[1]);
+    return format!("{}{position}{}", &text[..start], &text[end..]).into_bytes();
+}
+
+#[test]
+fn rejects_cursors_outside_of_the_tape_limit() {
+    let bytes = checkpoint_after(7);
+    let mut state = new_bf_state(CODE);
+    set_max_tape_cells(&mut state, Some(1000));
+    restore_checkpoint(&mut state, &with_position(&bytes, "2", "2")).unwrap();
+    for (cursor, highest) in [("-100000000000", "2"), ("2", "100000000000"), ("5", "2"), ("0", "9223372036854775807")] {
+        let mut state = new_bf_state(CODE);
+        set_max_tape_cells(&mut state, Some(1000));
+        let error = restore_checkpoint(&mut state, &with_position(&bytes, cursor, highest)).unwrap_err();
+        assert!(error.to_string().starts_with("Invalid checkpoint"), "cursor {cursor}, highest {highest}: {error}");
+    }
+}
+
+fn run_to_end(state: &mut BFState) -> Vec<u8> {
+    let report = run_bf(state, Some(1_000_000), None).unwrap();
+    assert!(matches!(report.outcome, RunOutcome::Halted));
+    return report.output;
+}
+
+fn replace_section(bytes: &[u8], name: &str, section: &str) -> Vec<u8> {
+    // The checkpoint with the section (header and data) of that name replaced by the given one
+    let text = String::from_utf8(bytes.to_vec()).unwrap();
+    let start = text.find(&format!("\n{name} ")).unwrap() + 1;
+    let header_end = start + text[start..].find('\n').unwrap();
+    let length: usize = text[start..header_end].rsplit(' ').next().unwrap().parse().unwrap();
+    let end = header_end + 1 + length + 1;
+    return format!("{}{section}{}", &text[..start], &text[end..]).into_bytes();
+}
+
+#[test]
+fn round_trips_a_running_state() {
+    let mut expected = new_bf_state(PRINTING);
+    capture_output(&mut expected);
+    push_input(&mut expected, b"\x03");
+    let expected_output = run_to_end(&mut expected);
+
+    for steps in [0, 1, 20, 60, 75] {
+        let mut state = new_bf_state(PRINTING);
+        capture_output(&mut state);
+        push_input(&mut state, b"\x03");
+        let report = run_bf(&mut state, Some(steps), None).unwrap();
+        let bytes = save_checkpoint(&mut state, report.steps).unwrap();
+
+        let mut restored = new_bf_state(PRINTING);
+        capture_output(&mut restored);
+        assert_eq!(restore_checkpoint(&mut restored, &bytes).unwrap(), report.steps);
+        assert_eq!(cursor_position(&restored), cursor_position(&state));
+        assert_eq!(input_bytes(&restored), input_bytes(&state));
+        let mut output = report.output;
+        output.extend(run_to_end(&mut restored));
+        assert_eq!(output, expected_output, "checkpoint after {steps} steps");
+        assert_eq!(export_tape(&restored, -4..4), export_tape(&expected, -4..4), "checkpoint after {steps} steps");
+    }
+}
+
+#[test]
+fn rejects_corrupt_checkpoints_without_changing_the_state() {
+    let bytes = checkpoint_after(7);
+    let duplicated = replace_section(&bytes, "input", "ntape 0\n\n");
+    let corrupt: [(&str, Vec<u8>); 6] = [
+        ("header", bytes[1..].to_vec()),
+        ("truncated", bytes[..bytes.len() - 3].to_vec()),
+        ("missing", replace_section(&bytes, "output", "")),
+        ("duplicated", duplicated),
+        ("number", replace_section(&bytes, "counters", "counters 7 x 0 0\n\n")),
+        ("program", replace_section(&bytes, "code", "code 1\n+\n")),
+    ];
+    for (name, corrupt) in corrupt {
+        let mut state = new_bf_state(CODE);
+        capture_output(&mut state);
+        run_bf(&mut state, Some(3), None).unwrap();
+        let before = (cursor_position(&state), source_position(&state), export_tape(&state, -4..4));
+        assert!(matches!(restore_checkpoint(&mut state, &corrupt), Err(BfError::Format(_))), "{name}");
+        assert_eq!((cursor_position(&state), source_position(&state), export_tape(&state, -4..4)), before, "{name}");
+    }
+}
+
+#[test]
+fn resumes_after_additions_merged_across_a_removed_loop() {
+    // Without `[.]`, the two `+` are one addition, which the restored state mustn't run again,
+    // whether the tape was changed before saving or the restore is what switches the program
+    for code in [">+<[.]>+<", ">+<[.]>+<.>.", "+[-]>+<[.]>+>[.]<."] {
+        let mut expected = new_bf_state(code);
+        capture_output(&mut expected);
+        let expected_output = run_to_end(&mut expected);
+        set_cell(&mut expected, 5, 1);
+        for (steps, change) in (0..code.len()).flat_map(|steps| [(steps, false), (steps, true)]) {
+            let mut state = new_bf_state(code);
+            capture_output(&mut state);
+            let output = run_bf(&mut state, Some(steps as u64), None).unwrap().output;
+            if change {
+                set_cell(&mut state, 5, 1);
+            }
+            let bytes = save_checkpoint(&mut state, steps as u64).unwrap();
+
+            let mut restored = new_bf_state(code);
+            capture_output(&mut restored);
+            restore_checkpoint(&mut restored, &bytes).unwrap();
+            let output = [output, run_to_end(&mut restored)].concat();
+            set_cell(&mut restored, 5, 1);
+            assert_eq!(output, expected_output, "{code} after {steps} steps");
+            assert_eq!(export_tape(&restored, -4..8), export_tape(&expected, -4..8), "{code} after {steps} steps");
+        }
+    }
+}
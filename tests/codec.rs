@@ -0,0 +1,144 @@
+use brainfuckers::*;
+
+use std::collections::VecDeque;
+use std::io::{BufRead, Read, Write};
+use std::sync::{Arc, Mutex};
+
+// Hands out the bytes in the given chunks, one per `fill_buf`
+struct Chunked(VecDeque<Vec<u8>>);
+
+impl Read for Chunked {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let length = available.len().min(buf.len());
+        buf[..length].copy_from_slice(&available[..length]);
+        self.consume(length);
+        return Ok(length);
+    }
+}
+
+impl BufRead for Chunked {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        while self.0.front().is_some_and(Vec::is_empty) {
+            self.0.pop_front();
+        }
+        return Ok(self.0.front().map_or(&[], Vec::as_slice));
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if let Some(chunk) = self.0.front_mut() {
+            chunk.drain(..amount);
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend(buf);
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return Ok(());
+    }
+}
+
+fn decode(chunks: &[&[u8]], format: InputFormat) -> std::io::Result<Vec<u8>> {
+    let inner = Chunked(chunks.iter().map(|chunk| chunk.to_vec()).collect());
+    let mut decoded = Vec::new();
+    DecodingReader::new(Box::new(inner), format).read_to_end(&mut decoded)?;
+    return Ok(decoded);
+}
+
+fn encode(bytes: &[u8], format: OutputFormat) -> Vec<u8> {
+    let output = Shared::default();
+    let mut writer = EncodingWriter::new(Box::new(output.clone()), format);
+    // Written one byte at a time, so base64 groups are completed across writes
+    for &c in bytes {
+        writer.write_all(&[c]).unwrap();
+    }
+    writer.finish().unwrap();
+    return output.0.lock().unwrap().clone();
+}
+
+fn all_bytes() -> Vec<u8> {
+    return (0..=255).collect();
+}
+
+#[test]
+fn hex_round_trips() {
+    let bytes = all_bytes();
+    let encoded = encode(&bytes, OutputFormat::Hex);
+    assert!(encoded.starts_with(b"000102") && encoded.ends_with(b"feff\n"));
+    assert_eq!(decode(&[&encoded], InputFormat::Hex).unwrap(), bytes);
+    assert_eq!(decode(&[b"4A 6b\n"], InputFormat::Hex).unwrap(), b"Jk");
+    assert!(decode(&[b"4a6"], InputFormat::Hex).is_err());
+    assert!(decode(&[b"4g"], InputFormat::Hex).is_err());
+}
+
+#[test]
+fn base64_round_trips_with_padding() {
+    for length in 0..8 {
+        let bytes = &all_bytes()[100..100 + length];
+        let encoded = encode(bytes, OutputFormat::Base64);
+        assert_eq!(encoded.len(), if length == 0 { 0 } else { length.div_ceil(3) * 4 + 1 }, "{length} bytes");
+        assert_eq!(decode(&[&encoded], InputFormat::Base64).unwrap(), bytes, "{length} bytes");
+    }
+    assert_eq!(decode(&[b"aGk="], InputFormat::Base64).unwrap(), b"hi");
+    assert_eq!(decode(&[b"aA=="], InputFormat::Base64).unwrap(), b"h");
+}
+
+#[test]
+fn base64_padding_is_optional() {
+    assert_eq!(decode(&[b"aGk"], InputFormat::Base64).unwrap(), b"hi");
+    assert_eq!(decode(&[b"aA"], InputFormat::Base64).unwrap(), b"h");
+    assert_eq!(decode(&[b"aGVsbG8"], InputFormat::Base64).unwrap(), b"hello");
+    let bytes = all_bytes();
+    let encoded = encode(&bytes, OutputFormat::Base64);
+    let unpadded: Vec<u8> = encoded.iter().copied().filter(|&c| c != b'=').collect();
+    assert_eq!(decode(&[&unpadded], InputFormat::Base64).unwrap(), bytes);
+}
+
+#[test]
+fn base64_rejects_bad_groups() {
+    for bad in [&b"AB=C"[..], b"A=BC", b"AB=CDEFG", b"====", b"A", b"AB!C"] {
+        assert!(decode(&[bad], InputFormat::Base64).is_err(), "{}", bad.escape_ascii());
+    }
+}
+
+#[test]
+fn groups_split_across_chunks_are_decoded() {
+    assert_eq!(decode(&[b"4", b"a6", b"", b"b"], InputFormat::Hex).unwrap(), b"Jk");
+    assert_eq!(decode(&[b"aG", b"VsbG", b"8", b"="], InputFormat::Base64).unwrap(), b"hello");
+    let encoded = encode(&all_bytes(), OutputFormat::Base64);
+    let chunks: Vec<&[u8]> = encoded.chunks(7).collect();
+    assert_eq!(decode(&chunks, InputFormat::Base64).unwrap(), all_bytes());
+}
+
+fn translate(chunks: &[&[u8]], mode: NewlineMode) -> Vec<u8> {
+    let inner = Chunked(chunks.iter().map(|chunk| chunk.to_vec()).collect());
+    let mut translated = Vec::new();
+    NewlineReader::new(Box::new(inner), mode).read_to_end(&mut translated).unwrap();
+    return translated;
+}
+
+#[test]
+fn crlf_reads_as_lf_across_chunks() {
+    assert_eq!(translate(&[b"a\r\nb\r\n"], NewlineMode::Crlf), b"a\nb\n");
+    assert_eq!(translate(&[b"a\r", b"\nb"], NewlineMode::Crlf), b"a\nb");
+    // A CR not followed by LF is kept, even when the chunk or the input ends on it
+    assert_eq!(translate(&[b"a\r", b"b\r"], NewlineMode::Crlf), b"a\rb\r");
+    assert_eq!(translate(&[b"\r", b"\r", b"\n"], NewlineMode::Crlf), b"\r\n");
+    assert_eq!(translate(&[b"a\r", b"\nb\r"], NewlineMode::StripCr), b"a\nb");
+}
+
+#[test]
+fn lf_prints_as_crlf() {
+    let output = Shared::default();
+    let mut writer = NewlineWriter::new(Box::new(output.clone()), NewlineMode::Crlf);
+    writer.write_all(b"a\nb\n").unwrap();
+    assert_eq!(*output.0.lock().unwrap(), b"a\r\nb\r\n");
+}
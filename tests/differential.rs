@@ -0,0 +1,12 @@
+use brainfuckers::testing::*;
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn interpreter_matches_the_reference(code in program_strategy(4, 24), input in input_strategy(8)) {
+        // Programs that don't halt within the step limit aren't compared
+        if let Err(message) = check_interpreter(&code, &input, 10_000) {
+            prop_assert!(false, "{}", message);
+        }
+    }
+}
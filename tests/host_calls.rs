@@ -0,0 +1,30 @@
+use brainfuckers::*;
+
+fn run(code: &str) -> Result<RunReport, BfError> {
+    let mut functions = HostFunctions::new();
+    functions.register(1, 2, |arguments| {
+        arguments[1] = arguments[0].wrapping_mul(2);
+        return Ok(());
+    });
+    functions.register(2, 0, |_| Err(String::from("the door is locked")));
+    let mut state = new_bf_state(code);
+    enable_syscall_extension(&mut state, functions);
+    capture_output(&mut state);
+    return run_bf(&mut state, Some(1000), None);
+}
+
+#[test]
+fn host_functions_write_their_results_to_the_tape() {
+    let report = run("+>++++++++++++++++++++++++++++++++<!>>.").unwrap();
+    assert_eq!(report.output, b"@");
+}
+
+#[test]
+fn failing_host_functions_report_their_own_error() {
+    match run("++!") {
+        Err(BfError::Host { number, message }) => assert_eq!((number, message.as_str()), (2, "the door is locked")),
+        Err(error) => panic!("{error}"),
+        Ok(_) => panic!("the run didn't fail"),
+    }
+    assert!(matches!(run("+++!"), Err(BfError::Runtime(message)) if message.contains("No host function is registered as 3")));
+}
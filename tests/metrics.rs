@@ -0,0 +1,53 @@
+use brainfuckers::*;
+
+use std::time::Duration;
+
+fn report(outcome: RunOutcome, duration: Duration) -> Result<RunReport, BfError> {
+    return Ok(RunReport { outcome, steps: 0, output: Vec::new(), duration, final_cursor: 0, tape_extent: (0, 0) });
+}
+
+fn value<'a>(text: &'a str, series: &str) -> &'a str {
+    // The value of the line of the series, which has to be there
+    let line = text.lines().find(|line| line.strip_prefix(series).is_some_and(|rest| rest.starts_with(' ')));
+    return line.unwrap_or_else(|| panic!("no `{series}` in\n{text}")).rsplit(' ').next().unwrap();
+}
+
+#[test]
+fn renders_cumulative_duration_buckets() {
+    let metrics = Metrics::new();
+    let state = new_bf_state("");
+    metrics.record(&state, &report(RunOutcome::Halted, Duration::from_micros(50)));
+    metrics.record(&state, &report(RunOutcome::Halted, Duration::from_millis(3)));
+    metrics.record(&state, &report(RunOutcome::TimedOut, Duration::from_secs(2)));
+    metrics.record(&state, &report(RunOutcome::Halted, Duration::from_secs(60))); // Past the last bound
+    metrics.record(&state, &Err(BfError::Runtime(String::from("failed")))); // Not a duration
+
+    let text = metrics.render();
+    let bucket = |bound: &str| value(&text, &format!("bf_run_duration_seconds_bucket{{le=\"{bound}\"}}")).parse::<u64>().unwrap();
+    assert_eq!(bucket("0.0001"), 1);
+    assert_eq!(bucket("0.001"), 1);
+    assert_eq!(bucket("0.005"), 2);
+    assert_eq!(bucket("1"), 2);
+    assert_eq!(bucket("5"), 3);
+    assert_eq!(bucket("10"), 3);
+    assert_eq!(bucket("+Inf"), 4);
+    assert_eq!(value(&text, "bf_run_duration_seconds_count"), "4");
+    assert_eq!(value(&text, "bf_runs_total"), "4");
+    assert_eq!(value(&text, "bf_timeouts_total"), "1");
+    assert_eq!(value(&text, "bf_run_errors_total"), "1");
+    assert_eq!(value(&text, "bf_run_duration_seconds_sum").parse::<f64>().unwrap(), 62.00305);
+}
+
+#[test]
+fn counts_the_bytes_of_runs() {
+    let metrics = Metrics::new();
+    let mut state = new_bf_state(",.,.");
+    capture_output(&mut state);
+    push_input(&mut state, b"ab");
+    let result = run_bf(&mut state, None, None);
+    metrics.record(&state, &result);
+    let text = metrics.render();
+    assert_eq!(value(&text, "bf_input_bytes_total"), "2");
+    assert_eq!(value(&text, "bf_output_bytes_total"), "2");
+    assert_eq!(value(&text, "bf_runs_total"), "1");
+}
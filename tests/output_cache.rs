@@ -0,0 +1,73 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("brainfuckers-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    return directory;
+}
+
+fn bf_output(args: &[&str], input: &[u8]) -> std::process::Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_brainfuckers"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "bf {args:?} failed");
+    return output;
+}
+
+fn bf(args: &[&str], input: &[u8]) -> Vec<u8> {
+    return bf_output(args, input).stdout;
+}
+
+#[test]
+fn per_line_runs_dont_reuse_cached_output() {
+    // The program never reads, but `--per-line` runs it once per line of stdin
+    let directory = scratch_dir("per-line-cache");
+    let program = directory.join("a.b");
+    std::fs::write(&program, "++++++++[>++++++++<-]>+.").unwrap();
+    let cache = directory.join("cache");
+    let args = ["--per-line", "--cache-dir", cache.to_str().unwrap(), program.to_str().unwrap()];
+    assert_eq!(bf(&args, b"x\ny\nz\n"), b"AAA");
+    assert_eq!(bf(&args, b"x\n"), b"A");
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn programs_without_input_still_use_the_cache() {
+    let directory = scratch_dir("plain-cache");
+    let program = directory.join("a.b");
+    std::fs::write(&program, "++++++++[>++++++++<-]>+.").unwrap();
+    let cache = directory.join("cache");
+    let args = ["--cache-dir", cache.to_str().unwrap(), program.to_str().unwrap()];
+    assert_eq!(bf(&args, b""), b"A");
+    assert!(std::fs::read_dir(&cache).unwrap().next().is_some());
+    assert_eq!(bf(&args, b""), b"A");
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn runs_reporting_on_stderr_skip_the_cache() {
+    // A cached run would only print the output, not the reports about the run
+    let directory = scratch_dir("report-cache");
+    let program = directory.join("a.b");
+    std::fs::write(&program, "++++++++[>++++++++<-]>+.").unwrap();
+    let cache = directory.join("cache");
+    for (report, expected) in [("--tape-stats", "Tape used"), ("--taint", "Cells depending on input")] {
+        let args = [report, "--cache-dir", cache.to_str().unwrap(), program.to_str().unwrap()];
+        for _ in 0..2 {
+            let output = bf_output(&args, b"");
+            assert_eq!(output.stdout, b"A");
+            assert!(String::from_utf8(output.stderr).unwrap().contains(expected), "{report}");
+        }
+    }
+    assert!(!cache.exists() || std::fs::read_dir(&cache).unwrap().next().is_none());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,40 @@
+use brainfuckers::*;
+
+fn package() -> Package {
+    return Package {
+        code: b",[.,]".to_vec(),
+        input: b"hi".to_vec(),
+        tape: vec![1, 2, 3],
+        tape_origin: -1,
+        args: vec![String::from("--eof"), String::from("0")],
+    };
+}
+
+#[test]
+fn round_trips_through_the_file_format() {
+    let package = package();
+    assert_eq!(read_package(&write_package(&package)).unwrap(), package);
+}
+
+#[test]
+fn rejects_sections_longer_than_the_file() {
+    for length in ["18446744073709551615", "18446744073709551614", "100"] {
+        let bytes = format!("bfpkg 1\ncode {length}\n+\n");
+        let error = read_package(bytes.as_bytes()).unwrap_err().to_string();
+        assert!(error.contains("is truncated"), "{length}: {error}");
+    }
+}
+
+#[test]
+fn rejects_tapes_past_the_tape_limit() {
+    for origin in [100_000_000_000, -100_000_000_000, 998] {
+        let package = Package { tape_origin: origin, ..package() };
+        let mut state = new_bf_state(&package.code);
+        set_max_tape_cells(&mut state, Some(1000));
+        assert!(apply_package(&mut state, &package).is_err(), "tape at {origin}");
+    }
+    let mut state = new_bf_state(package().code);
+    set_max_tape_cells(&mut state, Some(1000));
+    apply_package(&mut state, &package()).unwrap();
+    assert_eq!(export_tape(&state, -1..2), [1, 2, 3]);
+}
@@ -0,0 +1,31 @@
+use brainfuckers::*;
+
+#[test]
+fn returned_states_are_reused() {
+    // The second run starts on the tape the first one grew, so it doesn't have to grow it again
+    let far = new_program(format!("{}+.", ">".repeat(5000)));
+    let pool = StatePool::new(1);
+    let mut state = pool.checkout(far.clone());
+    capture_output(&mut state);
+    assert_eq!(run_bf(&mut state, None, None).unwrap().output, [1]);
+    assert_eq!(tape_stats(&state).ptape_resizes, 1);
+    pool.recycle(state);
+    assert_eq!(pool.idle(), 1);
+
+    let mut state = pool.checkout(far);
+    assert_eq!(pool.idle(), 0);
+    capture_output(&mut state);
+    assert_eq!(run_bf(&mut state, None, None).unwrap().output, [1]);
+    assert_eq!(tape_stats(&state).ptape_resizes, 0);
+    pool.recycle(state);
+
+    // Another program gets a cleared tape and empty buffers, and reads nothing until it's given input
+    let mut state = pool.checkout(new_program("<+>,[.,]>>>>>[.]"));
+    push_input(&mut state, b"hi");
+    capture_output(&mut state);
+    let report = run_bf(&mut state, Some(100_000), None).unwrap();
+    assert!(matches!(report.outcome, RunOutcome::Halted));
+    assert_eq!(report.output, b"hi");
+    assert_eq!(tape_stats(&state).lowest, -1);
+    assert_eq!(export_tape(&state, -1..6000).iter().filter(|&&cell| cell != 0).count(), 1);
+}
@@ -0,0 +1,116 @@
+use brainfuckers::*;
+
+use std::path::{Path, PathBuf};
+
+fn write_program(name: &str, code: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("brainfuckers-preprocess-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    std::fs::write(&path, code).unwrap();
+    return path;
+}
+
+fn expand(name: &str, code: &str, options: &PreprocessOptions) -> Result<(String, SourceMap, PathBuf), BfError> {
+    let path = write_program(name, code);
+    let preprocessed = preprocess(&path, options);
+    std::fs::remove_file(&path).unwrap();
+    let (source, map) = preprocessed?;
+    return Ok((String::from_utf8(source).unwrap(), map, path));
+}
+
+fn commands(source: &str) -> String {
+    return source.chars().filter(|c| "+-<>.,[]".contains(*c)).collect();
+}
+
+#[test]
+fn arguments_can_hold_parentheses() {
+    let code = "@def add(n) {%n{+}} @def twice(x) {%x%x} @def pair(a, b) {%a %b}\n\
+                @use twice(@use add(3))\n\
+                @use twice(> (one more cell) )\n\
+                @use pair(@use pair(-, <), .)";
+    let (source, _, _) = expand("parentheses.b", code, &PreprocessOptions::default()).unwrap();
+    assert_eq!(commands(&source), "++++++>>-<.");
+    assert!(source.contains("(one more cell)"));
+    // Without its closing parenthesis, the use is only a comment
+    let (source, _, _) = expand("unclosed.b", "@def twice(x) {%x%x} @use twice(+ (oops)", &PreprocessOptions::default()).unwrap();
+    assert_eq!(source, " @use twice(+ (oops)");
+}
+
+#[test]
+fn comments_can_mention_directives() {
+    let comments = ["write to me@user.net", "@deferred until later", "a stray @endif", "the @include directive",
+                    "@def is how macros start", "@use it like this", "@if you want", "@ifdef isn't one", "@usefully"];
+    for comment in comments {
+        let code = format!("{comment}\n+[-].");
+        let (source, _, _) = expand("comment.b", &code, &PreprocessOptions::default()).unwrap();
+        assert_eq!(source, code);
+    }
+    let error = expand("arguments.b", "@def twice(x) {%x%x} see @use twice(+, -)", &PreprocessOptions::default()).unwrap_err();
+    assert!(error.to_string().contains("takes 1 arguments, got 2"), "{error}");
+}
+
+#[test]
+fn macros_nest_up_to_the_depth_limit() {
+    let options = PreprocessOptions { max_macro_depth: 3, ..PreprocessOptions::default() };
+    let code = "@def a() {+} @def b() {@use a()} @def c() {@use b()} @def d() {@use c()}\n";
+    let (source, _, _) = expand("depth.b", &format!("{code}@use c()"), &options).unwrap();
+    assert_eq!(commands(&source), "+");
+    let error = expand("too-deep.b", &format!("{code}@use d()"), &options).unwrap_err();
+    assert!(error.to_string().contains("nested more than 3 levels deep"), "{error}");
+    let error = expand("recursive.b", "@def forever() {+@use forever()} @use forever()", &PreprocessOptions::default()).unwrap_err();
+    assert!(error.to_string().contains("nested more than 64 levels deep"), "{error}");
+}
+
+#[test]
+fn expansion_is_capped() {
+    let options = PreprocessOptions { max_expansion: 100, ..PreprocessOptions::default() };
+    let (source, _, _) = expand("small.b", "@def many(n) {%n{+}} @use many(90)", &options).unwrap();
+    assert_eq!(commands(&source).len(), 90);
+    for code in ["@def many(n) {%n{+}} @use many(101)", "@def many(n) {%n{+++}} @use many(18446744073709551615)",
+                 "@def many(n) {%n{+}} @def more() {@use many(60) @use many(60)} @use more()"] {
+        let error = expand("big.b", code, &options).unwrap_err();
+        assert!(error.to_string().contains("more than 100 bytes"), "{code}: {error}");
+    }
+}
+
+fn doubling_chain(length: usize) -> String {
+    // Macros each using the previous one twice and expanding to nothing, `2^length` uses in total
+    let mut code = String::from("@def m0() {}\n");
+    for n in 1..=length {
+        code.push_str(&format!("@def m{n}() {{@use m{}()@use m{}()}}\n", n - 1, n - 1));
+    }
+    code.push_str(&format!("@use m{length}() +"));
+    return code;
+}
+
+#[test]
+fn macro_uses_are_capped() {
+    let options = PreprocessOptions { max_macro_uses: 1000, ..PreprocessOptions::default() };
+    let (source, _, _) = expand("few-uses.b", &doubling_chain(8), &options).unwrap();
+    assert_eq!(commands(&source), "+");
+    let error = expand("many-uses.b", &doubling_chain(9), &options).unwrap_err();
+    assert!(error.to_string().contains("macros are used more than 1000 times"), "{error}");
+
+    // With the default limit, a chain that would take ages to expand fails quickly instead
+    let started = std::time::Instant::now();
+    let error = expand("exponential.b", &doubling_chain(60), &PreprocessOptions::default()).unwrap_err();
+    assert!(error.to_string().contains("macros are used more than"), "{error}");
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+    let (source, _, _) = expand("empty-repeat.b", "@def many(n) {%n{}+} @use many(18446744073709551615)", &options).unwrap();
+    assert_eq!(commands(&source), "+");
+}
+
+#[test]
+fn source_map_points_into_macro_arguments() {
+    let code = "@def twice(x) {%x-%x}\n@use twice(>.)";
+    let (source, map, path) = expand("map.b", code, &PreprocessOptions::default()).unwrap();
+    assert_eq!(source, "\n>.->.");
+    let argument = code.find(">.").unwrap();
+    let minus = code.find('-').unwrap();
+    let expected = [code.find('\n').unwrap(), argument, argument + 1, minus, argument, argument + 1];
+    for (position, offset) in expected.into_iter().enumerate() {
+        assert_eq!(map.locate(position), Some((path.as_path(), offset)), "output byte {position}");
+    }
+    assert_eq!(map.locate(source.len()), None::<(&Path, usize)>);
+}
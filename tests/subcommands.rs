@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+fn write_program(name: &str, code: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("brainfuckers-subcommands-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    std::fs::write(&path, code).unwrap();
+    return path;
+}
+
+fn bf(args: &[&str]) -> (bool, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_brainfuckers")).args(args).output().unwrap();
+    return (output.status.success(), String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap());
+}
+
+#[test]
+fn subcommands_take_the_preprocessing_options() {
+    let program = write_program("many.b", "@def many(n) {%n{+}} @use many(200)\n@if loud\n.\n@endif");
+    let program = program.to_str().unwrap();
+    for subcommand in ["analyze", "stats", "hash", "emit-wat"] {
+        let (ok, _, stderr) = bf(&[subcommand, "--max-expansion", "100", program]);
+        assert!(!ok && stderr.contains("more than 100 bytes"), "{subcommand}: {stderr}");
+        let (ok, _, stderr) = bf(&[subcommand, program, "--max-expansion", "1000"]);
+        assert!(ok, "{subcommand}: {stderr}");
+    }
+    let (_, quiet, _) = bf(&["stats", program]);
+    let (_, loud, _) = bf(&["stats", "--define", "loud", program]);
+    assert!(quiet.contains("  .  0\n") && loud.contains("  .  1\n"), "{quiet}\n{loud}");
+    let (ok, _, stderr) = bf(&["diff", "--max-expansion", "100", program, program]);
+    assert!(!ok && stderr.contains("more than 100 bytes"), "diff: {stderr}");
+}
+
+#[test]
+fn subcommands_locate_syntax_errors_in_included_files() {
+    let included = write_program("unbalanced.b", "+[");
+    let program = write_program("includes.b", &format!("++\n@include \"{}\"\n", included.display()));
+    for subcommand in ["analyze", "stats", "hash"] {
+        let (ok, _, stderr) = bf(&[subcommand, program.to_str().unwrap()]);
+        assert!(!ok && stderr.contains(&format!("Syntax error in `{}` at byte 1", included.display())), "{subcommand}: {stderr}");
+    }
+}
+
+#[test]
+fn packages_only_hold_limits_and_io_modes() {
+    let program = write_program("packed.b", "@if loud\n++++++++[>++++++++<-]>+.\n@endif");
+    let bundle = program.with_extension("bfpkg");
+    let (program, bundle) = (program.to_str().unwrap(), bundle.to_str().unwrap());
+    let (ok, _, stderr) = bf(&["pack", bundle, "--define", "loud", "--fuel", "1000", "--eof", "0", program]);
+    assert!(ok, "{stderr}");
+    let (ok, stdout, stderr) = bf(&["run", bundle]);
+    assert!(ok && stdout == "A", "{stderr}");
+    let stored = brainfuckers::read_package(&std::fs::read(bundle).unwrap()).unwrap();
+    assert_eq!(stored.args, ["--fuel", "1000", "--eof", "0"]);
+
+    let dump = PathBuf::from(bundle).with_extension("dump");
+    let (ok, _, stderr) = bf(&["pack", bundle, "--dump-tape", dump.to_str().unwrap(), program]);
+    assert!(!ok && stderr.contains("`--dump-tape` can't be packaged"), "{stderr}");
+
+    // A package made by hand with an option writing a file is rejected before anything runs
+    let crafted = brainfuckers::Package { args: vec![String::from("--dump-tape"), dump.display().to_string()], ..stored };
+    std::fs::write(bundle, brainfuckers::write_package(&crafted)).unwrap();
+    let (ok, _, stderr) = bf(&["run", bundle]);
+    assert!(!ok && stderr.contains("packages can't set `--dump-tape`"), "{stderr}");
+    assert!(!dump.exists());
+}
+
+#[test]
+fn tape_images_have_to_fit_in_the_tape_limit() {
+    let program = write_program("print-two.b", ".>.");
+    let image = write_program("image.bin", "AB");
+    let (program, image) = (program.to_str().unwrap(), image.to_str().unwrap());
+    let (ok, stdout, _) = bf(&["--max-tape-cells", "2", "--tape-init", image, program]);
+    assert!(ok && stdout == "AB", "{stdout}");
+    let output = Command::new(env!("CARGO_BIN_EXE_brainfuckers"))
+        .args(["--max-tape-cells", "2", "--tape-init", image, "--tape-origin", "1", program]).output().unwrap();
+    // Same exit code as a program moving past the limit
+    assert_eq!(output.status.code(), Some(7));
+    assert!(output.stdout.is_empty() && String::from_utf8_lossy(&output.stderr).contains("Tape limit exceeded"));
+}
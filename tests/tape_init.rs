@@ -0,0 +1,55 @@
+use brainfuckers::*;
+
+fn limited(cells: usize) -> BFState {
+    let mut state = new_bf_state("+");
+    set_max_tape_cells(&mut state, Some(cells));
+    return state;
+}
+
+#[test]
+fn loading_past_the_tape_limit_fails_without_writing() {
+    let mut state = limited(4);
+    load_tape(&mut state, &[1, 2, 3, 4], 0).unwrap();
+    load_tape(&mut state, &[5], -1).unwrap_err();
+    load_tape(&mut state, &[5, 6], 3).unwrap_err();
+    write_u32_at(&mut state, 1, u32::MAX, Endianness::Little).unwrap_err();
+    assert_eq!(export_tape(&state, -2..6), [0, 0, 1, 2, 3, 4, 0, 0]);
+    assert_eq!((tape_stats(&state).lowest, tape_stats(&state).highest), (0, 3));
+
+    let mut state = limited(4);
+    load_tape(&mut state, &[1, 2], -2).unwrap();
+    write_u32_at(&mut state, -2, 0x01020304, Endianness::Big).unwrap();
+    assert!(load_tape(&mut state, &[7; 1000], 1_000_000).unwrap_err().to_string().contains("Tape limit exceeded"));
+}
+
+#[test]
+fn adopting_past_the_tape_limit_keeps_the_old_tape() {
+    let mut state = limited(4);
+    load_tape(&mut state, &[9, 8], -2).unwrap();
+    adopt_tape(&mut state, vec![1; 5], 0).unwrap_err();
+    adopt_tape(&mut state, vec![1; 2], 3).unwrap_err();
+    assert_eq!(export_tape(&state, -2..2), [9, 8, 0, 0]);
+    // The negative cells are replaced, so they leave room for the whole buffer
+    adopt_tape(&mut state, vec![1; 4], 0).unwrap();
+    assert_eq!(export_tape(&state, -2..5), [0, 0, 1, 1, 1, 1, 0]);
+}
+
+#[test]
+fn windows_past_the_tape_limit_fail_before_running() {
+    let mut state = new_bf_state("[-]");
+    let limits = ResourceLimits { max_tape_cells: Some(8), ..ResourceLimits::default() };
+    let mut window = [3; 4];
+    assert!(run_with_window(&mut state, &mut window, 6, &limits).is_err());
+    assert_eq!(window, [3; 4]);
+    run_with_window(&mut state, &mut window, 4, &limits).unwrap();
+    assert_eq!(window, [3; 4]);
+}
+
+#[test]
+fn reserving_past_the_tape_limit_fails() {
+    let mut state = limited(3000);
+    reserve_tape(&mut state, 2000, 1000).unwrap();
+    reserve_tape(&mut state, 2001, 1000).unwrap_err();
+    reserve_tape(&mut state, usize::MAX, 1).unwrap_err();
+    reserve_tape(&mut new_bf_state("+"), 4000, 1000).unwrap();
+}
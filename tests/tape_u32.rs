@@ -0,0 +1,58 @@
+use brainfuckers::*;
+
+// Offsets around the start of the tape: entirely on the negative side, crossing cell 0 from either side, positive
+const OFFSETS: [isize; 6] = [-100, -4, -3, -1, 0, 7];
+
+#[test]
+fn round_trips_in_both_byte_orders() {
+    for endianness in [Endianness::Little, Endianness::Big] {
+        for index in OFFSETS {
+            let mut state = new_bf_state("");
+            write_u32_at(&mut state, index, 0x12345678, endianness).unwrap();
+            assert_eq!(read_u32_at(&state, index, endianness), 0x12345678, "{endianness:?} at {index}");
+        }
+    }
+}
+
+#[test]
+fn lays_out_the_bytes_in_order() {
+    for index in OFFSETS {
+        let mut state = new_bf_state("");
+        write_u32_at(&mut state, index, 0x12345678, Endianness::Little).unwrap();
+        assert_eq!(export_tape(&state, index..index + 4), [0x78, 0x56, 0x34, 0x12], "little endian at {index}");
+
+        let mut state = new_bf_state("");
+        write_u32_at(&mut state, index, 0x12345678, Endianness::Big).unwrap();
+        assert_eq!(export_tape(&state, index..index + 4), [0x12, 0x34, 0x56, 0x78], "big endian at {index}");
+    }
+}
+
+#[test]
+fn crossing_cell_zero_leaves_the_neighbours_alone() {
+    let mut state = new_bf_state("");
+    load_tape(&mut state, &[0xaa; 8], -4).unwrap();
+    write_u32_at(&mut state, -2, 0x01020304, Endianness::Big).unwrap();
+    assert_eq!(export_tape(&state, -4..4), [0xaa, 0xaa, 0x01, 0x02, 0x03, 0x04, 0xaa, 0xaa]);
+    assert_eq!(get_cell(&state, -1), 0x02);
+    assert_eq!(get_cell(&state, 0), 0x03);
+}
+
+#[test]
+fn reads_cells_set_one_by_one() {
+    let mut state = new_bf_state("");
+    for (i, value) in [0xde, 0xad, 0xbe, 0xef].into_iter().enumerate() {
+        set_cell(&mut state, -3 + i as isize, value);
+    }
+    assert_eq!(read_u32_at(&state, -3, Endianness::Little), 0xefbeadde);
+    assert_eq!(read_u32_at(&state, -3, Endianness::Big), 0xdeadbeef);
+}
+
+#[test]
+fn unallocated_cells_read_as_zero() {
+    let mut state = new_bf_state("");
+    assert_eq!(read_u32_at(&state, -1000, Endianness::Little), 0);
+    write_u32_at(&mut state, 0, 0xffffffff, Endianness::Little).unwrap();
+    assert_eq!(read_u32_at(&state, -2, Endianness::Little), 0xffff0000);
+    assert_eq!(read_u32_at(&state, -2, Endianness::Big), 0x0000ffff);
+    assert_eq!(read_u32_at(&state, 3, Endianness::Big), 0xff000000);
+}
@@ -0,0 +1,29 @@
+use brainfuckers::*;
+
+fn run(code: &str, limit: Option<usize>, traced: bool) -> (String, Vec<u8>, TapeStats) {
+    let mut state = new_bf_state(code);
+    set_max_tape_cells(&mut state, limit);
+    if !traced {
+        set_hot_loop_threshold(&mut state, None);
+    }
+    let report = run_bf(&mut state, Some(1_000_000), None).unwrap();
+    return (format!("{:?}", report.outcome), export_tape(&state, -8..8), tape_stats(&state));
+}
+
+#[test]
+fn traced_loops_keep_to_the_tape_limit() {
+    // Linear loops reaching left, right and both ways, entered on cells further and further out
+    let programs = [
+        "++++++++++[>++++++++++<-]>[-<<<+>>>]",
+        ">>>>++++++++++[>++++++++++<-]>[-<<<<+>>>>]<<<<[->>>>>>+<<<<<<]",
+        ">>>>++++++++++[>++++++++++<-]>[-<<+>>>+<]",
+        "++++++++++++++++++++>>++++++++++++++++++++>>++++++++++++++++++++[[-<+>]<<]",
+    ];
+    for code in programs {
+        for limit in [None, Some(3), Some(4), Some(5), Some(6), Some(7), Some(8), Some(9)] {
+            assert_eq!(run(code, limit, true), run(code, limit, false), "{code} with {limit:?} cells");
+        }
+    }
+    let (_, _, stats) = run(programs[0], None, true);
+    assert_eq!((stats.lowest, stats.highest), (-2, 1));
+}